@@ -0,0 +1,76 @@
+use codotaku_engine_rs::app::engine::{Engine, Event};
+use codotaku_engine_rs::assets::obj::ObjModel;
+use codotaku_engine_rs::core::camera::Camera;
+use codotaku_engine_rs::core::lights::{Light, Lights};
+use codotaku_engine_rs::core::renderer::{RenderParams, RenderPath, Renderer};
+use glam::{Mat4, Vec3};
+use winit::window::WindowAttributes;
+
+struct Scene {
+    model: ObjModel,
+    lights: Lights,
+    renderer: Renderer,
+}
+
+fn main() -> anyhow::Result<()> {
+    let Some(path) = std::env::args().nth(1) else {
+        anyhow::bail!("usage: spinning_model <model.obj>");
+    };
+    let mut scene = None;
+    Engine::builder()
+        .window(WindowAttributes::default().with_title("Spinning model"))
+        .run(|ctx, event| {
+            match event {
+                Event::Setup => {
+                    let gpu = ctx.gpu().clone();
+                    let window_id = ctx.windows.primary().unwrap();
+                    let image_format = ctx.windows.image_format(window_id).unwrap();
+                    let renderer = Renderer::lit(gpu.clone(), image_format, RenderPath::Forward)?;
+                    let model = ObjModel::load(gpu, &path)?;
+
+                    let mut lights = Lights::new();
+                    lights.add(Light::Directional {
+                        direction: Vec3::new(-0.5, -1.0, -0.3),
+                        color: Vec3::ONE,
+                        intensity: 3.0,
+                    })?;
+
+                    scene = Some(Scene {
+                        model,
+                        lights,
+                        renderer,
+                    });
+                }
+                Event::Render(window_id) => {
+                    let Some(scene) = scene.as_ref() else {
+                        return Ok(());
+                    };
+                    let size = ctx.windows.window_size(window_id).unwrap();
+                    let aspect_ratio = size.width as f32 / size.height.max(1) as f32;
+                    let camera = Camera::perspective(
+                        Vec3::new(0.0, 1.0, 3.0),
+                        Vec3::ZERO,
+                        60f32.to_radians(),
+                        aspect_ratio,
+                        0.1,
+                        100.0,
+                    );
+                    let angle = ctx.time.elapsed_secs();
+                    ctx.windows.redraw(
+                        window_id,
+                        &scene.renderer,
+                        RenderParams {
+                            clear_color: [0.1, 0.1, 0.1, 1.0],
+                            draws: scene.model.draws(Mat4::from_rotation_y(angle)),
+                            camera,
+                            lights: scene.lights.clone(),
+                            ..Default::default()
+                        },
+                    )?;
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+    Ok(())
+}
@@ -0,0 +1,339 @@
+use crate::app::tasks::TaskPool;
+use crate::app::time::Time;
+use crate::assets::server::AssetServer;
+use crate::core::config::EngineConfig;
+use crate::core::error::{EngineError, Result};
+use crate::core::frame_stats::{CpuTimings, FrameTimingHistory, FrameTimingStats};
+use crate::core::gpu::Gpu;
+use crate::graphics::windows::Windows;
+use crate::input::manager::Input;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use winit::application::ApplicationHandler;
+use winit::event::{DeviceEvent, DeviceId, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::{WindowAttributes, WindowId};
+
+pub enum Event<'a> {
+    Setup,
+    Update,
+    Render(WindowId),
+    Window(WindowId, &'a WindowEvent),
+    Suspended,
+    Resumed,
+    Exiting,
+}
+
+pub struct Context<'a> {
+    pub windows: &'a mut Windows,
+    pub input: &'a mut Input,
+    pub time: &'a Time,
+    pub config: &'a EngineConfig,
+    pub tasks: &'a mut TaskPool,
+    pub assets: &'a Arc<AssetServer>,
+    frame_timings: &'a FrameTimingHistory,
+    event_loop: &'a ActiveEventLoop,
+}
+
+impl Context<'_> {
+    pub fn gpu(&self) -> &Arc<Gpu> {
+        &self.windows.gpu
+    }
+
+    pub fn event_loop(&self) -> &ActiveEventLoop {
+        self.event_loop
+    }
+
+    pub fn exit(&self) {
+        self.event_loop.exit();
+    }
+
+    pub fn frame_stats(&self) -> FrameTimingStats {
+        self.frame_timings.stats()
+    }
+}
+
+pub struct Engine {
+    windows: Windows,
+    input: Input,
+    time: Time,
+    config: EngineConfig,
+    tasks: TaskPool,
+    assets: Arc<AssetServer>,
+    timings: CpuTimings,
+    frame_timings: FrameTimingHistory,
+}
+
+impl Engine {
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
+    }
+
+    pub fn frame_stats(&self) -> FrameTimingStats {
+        self.frame_timings.stats()
+    }
+
+    fn context<'a>(&'a mut self, event_loop: &'a ActiveEventLoop) -> Context<'a> {
+        Context {
+            windows: &mut self.windows,
+            input: &mut self.input,
+            time: &self.time,
+            config: &self.config,
+            tasks: &mut self.tasks,
+            assets: &self.assets,
+            frame_timings: &self.frame_timings,
+            event_loop,
+        }
+    }
+
+    fn complete_tasks(&mut self, event_loop: &ActiveEventLoop) -> Result<()> {
+        for (completion, output) in self.tasks.drain_completed() {
+            completion(&mut self.context(event_loop), output)?;
+        }
+        Ok(())
+    }
+
+    fn end_frame(&mut self) {
+        let mut timings = std::mem::take(&mut self.timings);
+        let windows = self.windows.take_cpu_timings();
+        timings.record = windows.record;
+        timings.submit = windows.submit;
+        timings.present_wait = windows.present_wait;
+        timings.frame = self.time.frame_delta();
+        if self.time.frame() > 1 {
+            self.frame_timings.push(timings);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct EngineBuilder {
+    windows: Vec<WindowAttributes>,
+    fixed_step: Option<Duration>,
+    max_catch_up_steps: Option<u32>,
+    worker_threads: Option<usize>,
+    config: EngineConfig,
+}
+
+impl EngineBuilder {
+    pub fn window(mut self, attributes: WindowAttributes) -> Self {
+        self.windows.push(attributes);
+        self
+    }
+
+    pub fn fixed_timestep(mut self, step: Duration) -> Self {
+        self.fixed_step = Some(step);
+        self
+    }
+
+    pub fn update_rate(self, hz: f64) -> Self {
+        self.fixed_timestep(Duration::from_secs_f64(1.0 / hz))
+    }
+
+    pub fn max_catch_up_steps(mut self, steps: u32) -> Self {
+        self.max_catch_up_steps = Some(steps);
+        self
+    }
+
+    pub fn config(mut self, config: EngineConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn worker_threads(mut self, threads: usize) -> Self {
+        self.worker_threads = Some(threads);
+        self
+    }
+
+    pub fn run<F>(self, callback: F) -> Result<()>
+    where
+        F: FnMut(&mut Context, Event) -> Result<()>,
+    {
+        let mut runner = Runner {
+            builder: self,
+            engine: None,
+            callback,
+            error: None,
+        };
+        EventLoop::new()?.run_app(&mut runner)?;
+        match runner.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Runner<F> {
+    builder: EngineBuilder,
+    engine: Option<Engine>,
+    callback: F,
+    error: Option<EngineError>,
+}
+
+impl<F> Runner<F>
+where
+    F: FnMut(&mut Context, Event) -> Result<()>,
+{
+    fn start(&mut self, event_loop: &ActiveEventLoop) -> Result<()> {
+        let config = std::mem::take(&mut self.builder.config);
+        let mut windows = Windows::with_config(event_loop, &config)?;
+        let attributes = std::mem::take(&mut self.builder.windows);
+        if attributes.is_empty() {
+            windows.add(event_loop, WindowAttributes::default())?;
+        }
+        for attributes in attributes {
+            windows.add(event_loop, attributes)?;
+        }
+        let mut time = Time::new();
+        time.set_fixed_step(self.builder.fixed_step);
+        if let Some(steps) = self.builder.max_catch_up_steps {
+            time.set_max_catch_up_steps(steps);
+        }
+        let tasks = self
+            .builder
+            .worker_threads
+            .map_or_else(TaskPool::default, TaskPool::new);
+        let assets = Arc::new(AssetServer::new(windows.gpu.clone()).with_task_pool(&tasks));
+        self.engine = Some(Engine {
+            windows,
+            input: Input::default(),
+            time,
+            config,
+            tasks,
+            assets,
+            timings: CpuTimings::default(),
+            frame_timings: FrameTimingHistory::default(),
+        });
+        self.emit(event_loop, Event::Setup)
+    }
+
+    fn resume(&mut self, event_loop: &ActiveEventLoop) -> Result<()> {
+        match self.engine.as_mut() {
+            Some(engine) => engine.windows.resume()?,
+            None => return self.start(event_loop),
+        }
+        self.emit(event_loop, Event::Resumed)
+    }
+
+    fn emit(&mut self, event_loop: &ActiveEventLoop, event: Event) -> Result<()> {
+        let Some(engine) = self.engine.as_mut() else {
+            return Ok(());
+        };
+        (self.callback)(&mut engine.context(event_loop), event)
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        id: WindowId,
+        event: WindowEvent,
+    ) -> Result<()> {
+        let start = Instant::now();
+        let redraw = matches!(event, WindowEvent::RedrawRequested);
+        let result = self.handle_window_event(event_loop, id, event);
+        if let Some(engine) = self.engine.as_mut()
+            && !redraw
+        {
+            engine.timings.events += start.elapsed();
+        }
+        result
+    }
+
+    fn handle_window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        id: WindowId,
+        event: WindowEvent,
+    ) -> Result<()> {
+        if let Some(engine) = self.engine.as_mut() {
+            engine.input.handle_window_event(id, &event);
+        }
+        self.emit(event_loop, Event::Window(id, &event))?;
+        if let WindowEvent::RedrawRequested = event {
+            self.emit(event_loop, Event::Render(id))?;
+        }
+        let Some(engine) = self.engine.as_mut() else {
+            return Ok(());
+        };
+        if engine.windows.contains(id) {
+            engine.windows.dispatch(event, id)?;
+        }
+        if engine.windows.len() == 0 {
+            event_loop.exit();
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, event_loop: &ActiveEventLoop) -> Result<()> {
+        let Some(engine) = self.engine.as_mut() else {
+            return Ok(());
+        };
+        engine.input.begin_frame();
+        let steps = engine.time.tick();
+        engine.end_frame();
+        let start = Instant::now();
+        engine.complete_tasks(event_loop)?;
+        for _ in 0..steps {
+            self.emit(event_loop, Event::Update)?;
+            if let Some(engine) = self.engine.as_mut() {
+                engine.time.step();
+            }
+        }
+        if let Some(engine) = self.engine.as_mut() {
+            engine.timings.update += start.elapsed();
+        }
+        if let Some(engine) = self.engine.as_ref() {
+            engine.windows.request_redraw();
+        }
+        Ok(())
+    }
+
+    fn check(&mut self, event_loop: &ActiveEventLoop, result: Result<()>) {
+        if let Err(error) = result {
+            log::error!("{error}");
+            self.error.get_or_insert(error);
+            event_loop.exit();
+        }
+    }
+}
+
+impl<F> ApplicationHandler for Runner<F>
+where
+    F: FnMut(&mut Context, Event) -> Result<()>,
+{
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let result = self.resume(event_loop);
+        self.check(event_loop, result);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        let result = self.window_event(event_loop, id, event);
+        self.check(event_loop, result);
+    }
+
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _id: DeviceId, event: DeviceEvent) {
+        if let Some(engine) = self.engine.as_mut() {
+            let start = Instant::now();
+            engine.input.handle_device_event(&event);
+            engine.timings.events += start.elapsed();
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let result = self.update(event_loop);
+        self.check(event_loop, result);
+    }
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(engine) = self.engine.as_mut() {
+            engine.windows.suspend();
+        }
+        let result = self.emit(event_loop, Event::Suspended);
+        self.check(event_loop, result);
+    }
+
+    fn exiting(&mut self, event_loop: &ActiveEventLoop) {
+        let result = self.emit(event_loop, Event::Exiting);
+        self.check(event_loop, result);
+    }
+}
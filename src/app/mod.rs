@@ -0,0 +1,5 @@
+pub mod engine;
+#[cfg(feature = "rhai")]
+pub mod scripting;
+pub mod tasks;
+pub mod time;
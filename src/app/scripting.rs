@@ -0,0 +1,300 @@
+use crate::app::engine::Context;
+use crate::core::error::{EngineError, Result};
+use crate::core::transform::Transform;
+use glam::{Quat, Vec3};
+use rhai::{Dynamic, Scope, AST};
+use serde::de::value::Error as ValueError;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+
+const MOUSE_BUTTONS: [MouseButton; 5] = [
+    MouseButton::Left,
+    MouseButton::Right,
+    MouseButton::Middle,
+    MouseButton::Back,
+    MouseButton::Forward,
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptDraw {
+    pub mesh: String,
+    pub transform: Transform,
+    pub layer: i32,
+}
+
+#[derive(Default)]
+struct ScriptState {
+    keys_down: HashSet<KeyCode>,
+    keys_pressed: HashSet<KeyCode>,
+    keys_released: HashSet<KeyCode>,
+    mouse_down: HashSet<MouseButton>,
+    mouse_pressed: HashSet<MouseButton>,
+    mouse_released: HashSet<MouseButton>,
+    cursor: [f32; 2],
+    scroll: [f32; 2],
+    mouse_delta: [f32; 2],
+    delta: f64,
+    elapsed: f64,
+    frame: i64,
+    window_size: [i64; 2],
+    draws: Vec<ScriptDraw>,
+    clear_color: Option<[f32; 4]>,
+    title: Option<String>,
+    exit: bool,
+}
+
+impl ScriptState {
+    fn capture(&mut self, ctx: &Context) {
+        let input = &*ctx.input;
+        self.keys_down = input.pressed_keys().collect();
+        self.keys_pressed = input.just_pressed_keys().collect();
+        self.keys_released = input.just_released_keys().collect();
+        let buttons = |filter: &dyn Fn(MouseButton) -> bool| {
+            MOUSE_BUTTONS
+                .into_iter()
+                .filter(|button| filter(*button))
+                .collect()
+        };
+        self.mouse_down = buttons(&|button| input.mouse_pressed(button));
+        self.mouse_pressed = buttons(&|button| input.mouse_just_pressed(button));
+        self.mouse_released = buttons(&|button| input.mouse_just_released(button));
+        let primary = ctx.windows.primary();
+        self.cursor = primary
+            .and_then(|id| input.cursor_position(id))
+            .unwrap_or_default();
+        self.scroll = input.scroll_delta();
+        self.mouse_delta = input.mouse_delta();
+        self.delta = ctx.time.delta().as_secs_f64();
+        self.elapsed = ctx.time.elapsed().as_secs_f64();
+        self.frame = ctx.time.frame() as i64;
+        self.window_size = primary
+            .and_then(|id| ctx.windows.window_size(id))
+            .map_or([0; 2], |size| [size.width as i64, size.height as i64]);
+    }
+}
+
+struct LoadedScript {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    ast: AST,
+    scope: Scope<'static>,
+}
+
+pub struct ScriptHost {
+    engine: rhai::Engine,
+    scripts: Vec<LoadedScript>,
+    state: Rc<RefCell<ScriptState>>,
+}
+
+impl Default for ScriptHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScriptHost {
+    pub fn new() -> Self {
+        let state = Rc::new(RefCell::new(ScriptState::default()));
+        let mut engine = rhai::Engine::new();
+        engine.on_print(|text| log::info!("{text}"));
+        engine.on_debug(|text, source, position| {
+            log::debug!("{}:{position}: {text}", source.unwrap_or("script"))
+        });
+        register_api(&mut engine, &state);
+        Self {
+            engine,
+            scripts: Vec::new(),
+            state,
+        }
+    }
+
+    pub fn engine_mut(&mut self) -> &mut rhai::Engine {
+        &mut self.engine
+    }
+
+    pub fn load(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+        let path = path.into();
+        let modified = modified(&path);
+        let (ast, scope) = self.compile(&path)?;
+        self.scripts.push(LoadedScript {
+            path,
+            modified,
+            ast,
+            scope,
+        });
+        Ok(())
+    }
+
+    pub fn poll(&mut self) -> Vec<EngineError> {
+        let mut errors = Vec::new();
+        for index in 0..self.scripts.len() {
+            let path = self.scripts[index].path.clone();
+            let modified = modified(&path);
+            if modified == self.scripts[index].modified {
+                continue;
+            }
+            self.scripts[index].modified = modified;
+            match self.compile(&path) {
+                Ok((ast, scope)) => {
+                    log::info!("reloaded {}", path.display());
+                    let script = &mut self.scripts[index];
+                    script.ast = ast;
+                    script.scope = scope;
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        errors
+    }
+
+    pub fn call(&mut self, ctx: &mut Context, function: &str) -> Result<()> {
+        {
+            let mut state = self.state.borrow_mut();
+            state.capture(ctx);
+            state.draws.clear();
+        }
+        for script in &mut self.scripts {
+            let defined = script
+                .ast
+                .iter_functions()
+                .any(|f| f.name == function && f.params.is_empty());
+            if !defined {
+                continue;
+            }
+            let _ = self
+                .engine
+                .call_fn::<Dynamic>(&mut script.scope, &script.ast, function, ())
+                .map_err(|e| script_error(&script.path, e))?;
+        }
+        let mut state = self.state.borrow_mut();
+        if let Some(title) = state.title.take()
+            && let Some(window) = ctx.windows.primary().and_then(|id| ctx.windows.get(id))
+        {
+            window.set_title(&title);
+        }
+        if std::mem::take(&mut state.exit) {
+            ctx.exit();
+        }
+        Ok(())
+    }
+
+    pub fn draws(&self) -> Vec<ScriptDraw> {
+        self.state.borrow().draws.clone()
+    }
+
+    pub fn clear_color(&self) -> Option<[f32; 4]> {
+        self.state.borrow().clear_color
+    }
+
+    fn compile(&self, path: &Path) -> Result<(AST, Scope<'static>)> {
+        let source = fs::read_to_string(path)?;
+        let mut ast = self
+            .engine
+            .compile(source)
+            .map_err(|e| script_error(path, e))?;
+        ast.set_source(path.display().to_string());
+        let mut scope = Scope::new();
+        self.engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| script_error(path, e))?;
+        Ok((ast, scope))
+    }
+}
+
+fn register_api(engine: &mut rhai::Engine, state: &Rc<RefCell<ScriptState>>) {
+    let keys = |select: fn(&ScriptState) -> &HashSet<KeyCode>| {
+        let state = state.clone();
+        move |name: &str| parse(name).is_some_and(|key| select(&state.borrow()).contains(&key))
+    };
+    engine.register_fn("key_down", keys(|state| &state.keys_down));
+    engine.register_fn("key_pressed", keys(|state| &state.keys_pressed));
+    engine.register_fn("key_released", keys(|state| &state.keys_released));
+
+    let buttons = |select: fn(&ScriptState) -> &HashSet<MouseButton>| {
+        let state = state.clone();
+        move |name: &str| {
+            parse(name).is_some_and(|button| select(&state.borrow()).contains(&button))
+        }
+    };
+    engine.register_fn("mouse_down", buttons(|state| &state.mouse_down));
+    engine.register_fn("mouse_pressed", buttons(|state| &state.mouse_pressed));
+    engine.register_fn("mouse_released", buttons(|state| &state.mouse_released));
+
+    let float = |get: fn(&ScriptState) -> f64| {
+        let state = state.clone();
+        move || get(&state.borrow())
+    };
+    engine.register_fn("cursor_x", float(|state| state.cursor[0] as f64));
+    engine.register_fn("cursor_y", float(|state| state.cursor[1] as f64));
+    engine.register_fn("scroll_x", float(|state| state.scroll[0] as f64));
+    engine.register_fn("scroll_y", float(|state| state.scroll[1] as f64));
+    engine.register_fn("mouse_dx", float(|state| state.mouse_delta[0] as f64));
+    engine.register_fn("mouse_dy", float(|state| state.mouse_delta[1] as f64));
+    engine.register_fn("dt", float(|state| state.delta));
+    engine.register_fn("elapsed", float(|state| state.elapsed));
+
+    let int = |get: fn(&ScriptState) -> i64| {
+        let state = state.clone();
+        move || get(&state.borrow())
+    };
+    engine.register_fn("frame", int(|state| state.frame));
+    engine.register_fn("window_width", int(|state| state.window_size[0]));
+    engine.register_fn("window_height", int(|state| state.window_size[1]));
+
+    let draw_state = state.clone();
+    engine.register_fn("draw", move |mesh: &str, x: f64, y: f64, z: f64| {
+        draw_state.borrow_mut().draws.push(ScriptDraw {
+            mesh: mesh.to_owned(),
+            transform: Transform::from_translation(Vec3::new(x as f32, y as f32, z as f32)),
+            layer: 0,
+        });
+    });
+    let draw_state = state.clone();
+    engine.register_fn(
+        "draw",
+        move |mesh: &str, x: f64, y: f64, z: f64, yaw: f64, scale: f64, layer: i64| {
+            draw_state.borrow_mut().draws.push(ScriptDraw {
+                mesh: mesh.to_owned(),
+                transform: Transform {
+                    translation: Vec3::new(x as f32, y as f32, z as f32),
+                    rotation: Quat::from_rotation_y(yaw as f32),
+                    scale: Vec3::splat(scale as f32),
+                },
+                layer: layer as i32,
+            });
+        },
+    );
+
+    let command_state = state.clone();
+    engine.register_fn("set_clear_color", move |r: f64, g: f64, b: f64, a: f64| {
+        command_state.borrow_mut().clear_color = Some([r as f32, g as f32, b as f32, a as f32]);
+    });
+    let command_state = state.clone();
+    engine.register_fn("set_title", move |title: &str| {
+        command_state.borrow_mut().title = Some(title.to_owned());
+    });
+    let command_state = state.clone();
+    engine.register_fn("exit", move || command_state.borrow_mut().exit = true);
+}
+
+fn parse<T: DeserializeOwned>(name: &str) -> Option<T> {
+    T::deserialize(IntoDeserializer::<ValueError>::into_deserializer(name)).ok()
+}
+
+fn script_error(path: &Path, error: impl std::fmt::Display) -> EngineError {
+    EngineError::Script {
+        path: path.to_owned(),
+        message: error.to_string(),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
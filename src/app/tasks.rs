@@ -0,0 +1,192 @@
+use crate::app::engine::Context;
+use crate::core::error::Result;
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+
+type Job = Box<dyn FnOnce() + Send>;
+type Output = std::thread::Result<Box<dyn Any + Send>>;
+type Completion = Box<dyn FnOnce(&mut Context, Box<dyn Any + Send>) -> Result<()>>;
+
+pub struct Task<T> {
+    receiver: Receiver<std::thread::Result<T>>,
+    result: Option<T>,
+}
+
+impl<T> Task<T> {
+    pub fn is_finished(&mut self) -> bool {
+        self.try_receive();
+        self.result.is_some()
+    }
+
+    pub fn poll(&mut self) -> Option<T> {
+        self.try_receive();
+        self.result.take()
+    }
+
+    pub fn wait(mut self) -> T {
+        if let Some(result) = self.result.take() {
+            return result;
+        }
+        match self.receiver.recv() {
+            Ok(Ok(result)) => result,
+            Ok(Err(panic)) => std::panic::resume_unwind(panic),
+            Err(_) => panic!("task was dropped before completion"),
+        }
+    }
+
+    fn try_receive(&mut self) {
+        if self.result.is_some() {
+            return;
+        }
+        match self.receiver.try_recv() {
+            Ok(Ok(result)) => self.result = Some(result),
+            Ok(Err(panic)) => std::panic::resume_unwind(panic),
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => panic!("task was dropped before completion"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Spawner {
+    jobs: Weak<Sender<Job>>,
+}
+
+impl Spawner {
+    pub(crate) fn spawn(&self, work: impl FnOnce() + Send + 'static) {
+        match self.jobs.upgrade() {
+            Some(jobs) => {
+                if let Err(job) = jobs.send(Box::new(work)) {
+                    (job.0)();
+                }
+            }
+            None => work(),
+        }
+    }
+}
+
+pub struct TaskPool {
+    jobs: Option<Arc<Sender<Job>>>,
+    workers: Vec<JoinHandle<()>>,
+    completed_sender: Sender<(u64, Output)>,
+    completed: Receiver<(u64, Output)>,
+    completions: HashMap<u64, Completion>,
+    next_id: u64,
+}
+
+impl TaskPool {
+    pub fn new(threads: usize) -> Self {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads.max(1))
+            .map(|index| {
+                let receiver = receiver.clone();
+                std::thread::Builder::new()
+                    .name(format!("task-pool-{index}"))
+                    .spawn(move || loop {
+                        let job = receiver.lock().unwrap().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("failed to spawn task pool worker")
+            })
+            .collect();
+        let (completed_sender, completed) = mpsc::channel();
+        Self {
+            jobs: Some(Arc::new(jobs)),
+            workers,
+            completed_sender,
+            completed,
+            completions: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn spawn<T: Send + 'static>(&self, work: impl FnOnce() -> T + Send + 'static) -> Task<T> {
+        let (sender, receiver) = mpsc::channel();
+        self.execute(Box::new(move || {
+            let _ = sender.send(std::panic::catch_unwind(AssertUnwindSafe(work)));
+        }));
+        Task {
+            receiver,
+            result: None,
+        }
+    }
+
+    pub fn spawn_then<T: Send + 'static>(
+        &mut self,
+        work: impl FnOnce() -> T + Send + 'static,
+        on_complete: impl FnOnce(&mut Context, T) -> Result<()> + 'static,
+    ) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.completions.insert(
+            id,
+            Box::new(move |context, output| on_complete(context, *output.downcast::<T>().unwrap())),
+        );
+        let sender = self.completed_sender.clone();
+        self.execute(Box::new(move || {
+            let output = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                Box::new(work()) as Box<dyn Any + Send>
+            }));
+            let _ = sender.send((id, output));
+        }));
+    }
+
+    pub fn pending(&self) -> usize {
+        self.completions.len()
+    }
+
+    pub(crate) fn spawner(&self) -> Spawner {
+        Spawner {
+            jobs: self.jobs.as_ref().map_or_else(Weak::new, Arc::downgrade),
+        }
+    }
+
+    pub(crate) fn drain_completed(&mut self) -> Vec<(Completion, Box<dyn Any + Send>)> {
+        let mut completed = Vec::new();
+        while let Ok((id, output)) = self.completed.try_recv() {
+            let Some(completion) = self.completions.remove(&id) else {
+                continue;
+            };
+            match output {
+                Ok(output) => completed.push((completion, output)),
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+        completed
+    }
+
+    fn execute(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            jobs.send(job).expect("task pool workers exited");
+        }
+    }
+}
+
+impl Default for TaskPool {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |threads| threads.get().saturating_sub(1));
+        Self::new(threads)
+    }
+}
+
+impl Drop for TaskPool {
+    fn drop(&mut self) {
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
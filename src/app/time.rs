@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_CATCH_UP_STEPS: u32 = 5;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Time {
+    start: Instant,
+    last: Instant,
+    frame_delta: Duration,
+    frame: u64,
+    fixed_step: Option<Duration>,
+    max_catch_up_steps: u32,
+    accumulator: Duration,
+    alpha: f32,
+    steps: u64,
+}
+
+impl Time {
+    pub(crate) fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last: now,
+            frame_delta: Duration::ZERO,
+            frame: 0,
+            fixed_step: None,
+            max_catch_up_steps: DEFAULT_MAX_CATCH_UP_STEPS,
+            accumulator: Duration::ZERO,
+            alpha: 1.0,
+            steps: 0,
+        }
+    }
+
+    pub(crate) fn set_fixed_step(&mut self, step: Option<Duration>) {
+        self.fixed_step = step.filter(|step| !step.is_zero());
+        self.accumulator = Duration::ZERO;
+    }
+
+    pub(crate) fn set_max_catch_up_steps(&mut self, steps: u32) {
+        self.max_catch_up_steps = steps.max(1);
+    }
+
+    pub(crate) fn tick(&mut self) -> u32 {
+        let now = Instant::now();
+        self.frame_delta = now - self.last;
+        self.last = now;
+        self.frame += 1;
+        let Some(step) = self.fixed_step else {
+            self.alpha = 1.0;
+            return 1;
+        };
+        self.accumulator += self.frame_delta;
+        let mut steps = 0;
+        while self.accumulator >= step && steps < self.max_catch_up_steps {
+            self.accumulator -= step;
+            steps += 1;
+        }
+        if self.accumulator >= step {
+            let remainder =
+                Duration::from_nanos((self.accumulator.as_nanos() % step.as_nanos()) as u64);
+            log::debug!(
+                "dropping {:?} of simulation time after {steps} catch-up steps",
+                self.accumulator - remainder
+            );
+            self.accumulator = remainder;
+        }
+        self.alpha = self.accumulator.as_secs_f32() / step.as_secs_f32();
+        steps
+    }
+
+    pub(crate) fn step(&mut self) {
+        self.steps += 1;
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.fixed_step.unwrap_or(self.frame_delta)
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta().as_secs_f32()
+    }
+
+    pub fn frame_delta(&self) -> Duration {
+        self.frame_delta
+    }
+
+    pub fn fixed_step(&self) -> Option<Duration> {
+        self.fixed_step
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.last - self.start
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed().as_secs_f32()
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+}
@@ -0,0 +1,714 @@
+use crate::core::animation::{
+    create_joint_buffer, AnimationClip, AnimationPlayer, Channel, Interpolation, Joint, Keyframes,
+    Skeleton,
+};
+use crate::core::camera::Camera;
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::lights::{Light, Lights};
+use crate::core::material::Material;
+use crate::core::morph::{MorphTarget, MorphedMesh};
+use crate::core::renderer::{Draw, Mesh, RenderParams};
+use crate::core::texture::{ColorSpace, Texture};
+use crate::core::transform::Transform;
+use crate::core::vertex::{SkinnedVertex3D, Vertex3D};
+use ::gltf::animation::util::ReadOutputs;
+use ::gltf::image::Format as ImageFormat;
+use glam::{Mat4, Quat, Vec3};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub enum GltfGeometry {
+    Static(Mesh<Vertex3D>),
+    Skinned(Mesh<SkinnedVertex3D>),
+    Morphed(MorphedMesh),
+}
+
+#[derive(Clone)]
+pub struct GltfPrimitive {
+    pub geometry: GltfGeometry,
+    pub material: Option<usize>,
+}
+
+#[derive(Clone)]
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub transform: Transform,
+    pub mesh: Option<usize>,
+    pub skin: Option<usize>,
+    pub camera: Option<usize>,
+    pub light: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum GltfProjection {
+    Perspective {
+        fov_y: f32,
+        near: f32,
+        far: Option<f32>,
+    },
+    Orthographic {
+        half_width: f32,
+        half_height: f32,
+        near: f32,
+        far: f32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct GltfCamera {
+    pub name: Option<String>,
+    pub projection: GltfProjection,
+}
+
+impl GltfCamera {
+    pub fn camera(&self, world: Mat4, aspect_ratio: f32) -> Camera {
+        let (_, rotation, position) = world.to_scale_rotation_translation();
+        let mut projection = match self.projection {
+            GltfProjection::Perspective { fov_y, near, far } => match far {
+                Some(far) => Mat4::perspective_rh(fov_y, aspect_ratio, near, far),
+                None => Mat4::perspective_infinite_rh(fov_y, aspect_ratio, near),
+            },
+            GltfProjection::Orthographic {
+                half_width,
+                half_height,
+                near,
+                far,
+            } => Mat4::orthographic_rh(
+                -half_width,
+                half_width,
+                -half_height,
+                half_height,
+                near,
+                far,
+            ),
+        };
+        projection.y_axis.y *= -1.0;
+        Camera {
+            view: Mat4::from_rotation_translation(rotation, position).inverse(),
+            projection,
+            position,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GltfLight {
+    pub name: Option<String>,
+    pub light: Light,
+}
+
+impl GltfLight {
+    pub fn light(&self, world: Mat4) -> Light {
+        let (_, rotation, position) = world.to_scale_rotation_translation();
+        let direction = rotation * Vec3::NEG_Z;
+        match self.light {
+            Light::Directional {
+                color, intensity, ..
+            } => Light::Directional {
+                direction,
+                color,
+                intensity,
+            },
+            Light::Point {
+                color,
+                intensity,
+                range,
+                ..
+            } => Light::Point {
+                position,
+                color,
+                intensity,
+                range,
+            },
+            Light::Spot {
+                color,
+                intensity,
+                range,
+                inner_angle,
+                outer_angle,
+                ..
+            } => Light::Spot {
+                position,
+                direction,
+                color,
+                intensity,
+                range,
+                inner_angle,
+                outer_angle,
+            },
+        }
+    }
+}
+
+pub struct GltfModel {
+    pub nodes: Vec<GltfNode>,
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<Material>,
+    pub cameras: Vec<GltfCamera>,
+    pub lights: Vec<GltfLight>,
+    pub skins: Vec<Arc<Skeleton>>,
+    pub animations: Vec<Arc<AnimationClip>>,
+    pub player: Option<AnimationPlayer>,
+    hierarchy: Skeleton,
+}
+
+impl GltfModel {
+    pub fn load(gpu: Arc<Gpu>, path: impl AsRef<Path>) -> Result<Self> {
+        let (document, buffers, images) = ::gltf::import(path)?;
+
+        let nodes = load_nodes(&document);
+        let hierarchy = Skeleton::new(
+            document
+                .nodes()
+                .zip(&nodes)
+                .map(|(node, loaded)| Joint {
+                    name: node.name().unwrap_or_default().to_owned(),
+                    parent: loaded.parent,
+                    inverse_bind: Mat4::IDENTITY,
+                    rest: loaded.transform,
+                })
+                .collect(),
+        )?;
+
+        let skins = document
+            .skins()
+            .map(|skin| {
+                let mut skeleton = hierarchy.clone();
+                let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+                let mut inverse_binds = reader.read_inverse_bind_matrices();
+                for joint in skin.joints() {
+                    let inverse_bind = inverse_binds
+                        .as_mut()
+                        .and_then(Iterator::next)
+                        .map(|m| Mat4::from_cols_array_2d(&m))
+                        .unwrap_or(Mat4::IDENTITY);
+                    skeleton.joints[joint.index()].inverse_bind = inverse_bind;
+                }
+                Arc::new(skeleton)
+            })
+            .collect();
+
+        let mut textures = TextureCache {
+            gpu: gpu.clone(),
+            images: &images,
+            loaded: HashMap::new(),
+        };
+        let materials = document
+            .materials()
+            .map(|material| load_material(&material, &mut textures))
+            .collect::<Result<Vec<_>>>()?;
+
+        let meshes = document
+            .meshes()
+            .map(|mesh| {
+                let skin = document
+                    .nodes()
+                    .find(|node| node.mesh().is_some_and(|m| m.index() == mesh.index()))
+                    .and_then(|node| node.skin());
+                let weights = mesh.weights().unwrap_or_default();
+                let primitives = mesh
+                    .primitives()
+                    .map(|primitive| {
+                        load_primitive(&gpu, &primitive, &buffers, skin.as_ref(), weights)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(GltfMesh {
+                    name: mesh.name().map(str::to_owned),
+                    primitives,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let animations = document
+            .animations()
+            .enumerate()
+            .map(|(index, animation)| {
+                let name = animation
+                    .name()
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| format!("animation{index}"));
+                let channels = animation
+                    .channels()
+                    .filter_map(|channel| load_channel(&channel, &buffers))
+                    .collect();
+                Arc::new(AnimationClip::new(name, channels))
+            })
+            .collect();
+
+        let cameras = document
+            .cameras()
+            .map(|camera| load_camera(&camera))
+            .collect();
+        let lights = document
+            .lights()
+            .into_iter()
+            .flatten()
+            .map(|light| load_light(&light))
+            .collect();
+
+        Ok(Self {
+            nodes,
+            meshes,
+            materials,
+            cameras,
+            lights,
+            skins,
+            animations,
+            player: None,
+            hierarchy,
+        })
+    }
+
+    pub fn find_animation(&self, name: &str) -> Option<Arc<AnimationClip>> {
+        self.animations
+            .iter()
+            .find(|clip| clip.name == name)
+            .cloned()
+    }
+
+    pub fn play(&mut self, clip: Arc<AnimationClip>) {
+        match &mut self.player {
+            Some(player) => player.play(clip),
+            None => self.player = Some(AnimationPlayer::new(clip)),
+        }
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        if let Some(player) = &mut self.player {
+            player.advance(delta_time);
+        }
+    }
+
+    pub fn pose(&self) -> Vec<Transform> {
+        match &self.player {
+            Some(player) => player.sample(&self.hierarchy),
+            None => self.hierarchy.rest_pose(),
+        }
+    }
+
+    pub fn world_transforms(&self, pose: &[Transform]) -> Result<Vec<Mat4>> {
+        self.hierarchy.joint_matrices(pose)
+    }
+
+    pub fn scene_cameras(&self, transform: Mat4, aspect_ratio: f32) -> Result<Vec<Camera>> {
+        let world_transforms = self.world_transforms(&self.pose())?;
+        Ok(self
+            .nodes
+            .iter()
+            .zip(world_transforms)
+            .filter_map(|(node, world)| {
+                let camera = self.cameras.get(node.camera?)?;
+                Some(camera.camera(transform * world, aspect_ratio))
+            })
+            .collect())
+    }
+
+    pub fn append_lights(&self, transform: Mat4, lights: &mut Lights) -> Result<()> {
+        let world_transforms = self.world_transforms(&self.pose())?;
+        for (node, world) in self.nodes.iter().zip(world_transforms) {
+            if let Some(light) = node.light.and_then(|light| self.lights.get(light)) {
+                lights.add(light.light(transform * world))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn append_draws(
+        &self,
+        gpu: &Gpu,
+        transform: Mat4,
+        render_params: &mut RenderParams<Vertex3D>,
+    ) -> Result<()> {
+        let pose = self.pose();
+        let world_transforms = self.world_transforms(&pose)?;
+        let joint_buffers = self
+            .skins
+            .iter()
+            .map(|skeleton| {
+                let matrices: Vec<_> = skeleton
+                    .joint_matrices(&pose)?
+                    .into_iter()
+                    .map(|matrix| transform * matrix)
+                    .collect();
+                create_joint_buffer(gpu, &matrices)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (node, world) in self.nodes.iter().zip(world_transforms) {
+            let Some(mesh) = node.mesh else {
+                continue;
+            };
+            for primitive in &self.meshes[mesh].primitives {
+                let material = primitive
+                    .material
+                    .and_then(|material| self.materials.get(material).cloned())
+                    .unwrap_or_default();
+                match &primitive.geometry {
+                    GltfGeometry::Static(mesh) => render_params.draws.push(
+                        Draw::new(mesh.clone(), 0)
+                            .with_transform(transform * world)
+                            .with_material(material),
+                    ),
+                    GltfGeometry::Morphed(morphed) => {
+                        render_params.draws.push(
+                            Draw::new(morphed.mesh(), 0)
+                                .with_transform(transform * world)
+                                .with_material(material),
+                        );
+                        render_params.morphs.push(morphed.clone());
+                    }
+                    GltfGeometry::Skinned(mesh) => {
+                        let Some(joints) = node.skin.and_then(|skin| joint_buffers.get(skin))
+                        else {
+                            return Err(EngineError::InvalidAsset(format!(
+                                "node {:?} has a skinned mesh but no skin",
+                                node.name
+                            )));
+                        };
+                        render_params.skinned_draws.push(
+                            Draw::new(mesh.clone(), 0)
+                                .with_material(material)
+                                .with_joints(joints.clone()),
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn load_camera(camera: &::gltf::Camera) -> GltfCamera {
+    let projection = match camera.projection() {
+        ::gltf::camera::Projection::Perspective(perspective) => GltfProjection::Perspective {
+            fov_y: perspective.yfov(),
+            near: perspective.znear(),
+            far: perspective.zfar(),
+        },
+        ::gltf::camera::Projection::Orthographic(orthographic) => GltfProjection::Orthographic {
+            half_width: orthographic.xmag(),
+            half_height: orthographic.ymag(),
+            near: orthographic.znear(),
+            far: orthographic.zfar(),
+        },
+    };
+    GltfCamera {
+        name: camera.name().map(str::to_owned),
+        projection,
+    }
+}
+
+fn load_light(light: &::gltf::khr_lights_punctual::Light) -> GltfLight {
+    let name = light.name().map(str::to_owned);
+    let color = Vec3::from(light.color());
+    let intensity = light.intensity();
+    let range = light.range().unwrap_or(f32::MAX);
+    let light = match light.kind() {
+        ::gltf::khr_lights_punctual::Kind::Directional => Light::Directional {
+            direction: Vec3::NEG_Z,
+            color,
+            intensity,
+        },
+        ::gltf::khr_lights_punctual::Kind::Point => Light::Point {
+            position: Vec3::ZERO,
+            color,
+            intensity,
+            range,
+        },
+        ::gltf::khr_lights_punctual::Kind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => Light::Spot {
+            position: Vec3::ZERO,
+            direction: Vec3::NEG_Z,
+            color,
+            intensity,
+            range,
+            inner_angle: inner_cone_angle,
+            outer_angle: outer_cone_angle,
+        },
+    };
+    GltfLight { name, light }
+}
+
+fn load_nodes(document: &::gltf::Document) -> Vec<GltfNode> {
+    let mut nodes: Vec<_> = document
+        .nodes()
+        .map(|node| {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            GltfNode {
+                name: node.name().map(str::to_owned),
+                parent: None,
+                children: node.children().map(|child| child.index()).collect(),
+                transform: Transform {
+                    translation: Vec3::from(translation),
+                    rotation: Quat::from_array(rotation),
+                    scale: Vec3::from(scale),
+                },
+                mesh: node.mesh().map(|mesh| mesh.index()),
+                skin: node.skin().map(|skin| skin.index()),
+                camera: node.camera().map(|camera| camera.index()),
+                light: node.light().map(|light| light.index()),
+            }
+        })
+        .collect();
+    for parent in 0..nodes.len() {
+        for child in nodes[parent].children.clone() {
+            nodes[child].parent = Some(parent);
+        }
+    }
+    nodes
+}
+
+struct TextureCache<'a> {
+    gpu: Arc<Gpu>,
+    images: &'a [::gltf::image::Data],
+    loaded: HashMap<(usize, bool), Texture>,
+}
+
+impl TextureCache<'_> {
+    fn get(&mut self, texture: ::gltf::Texture, srgb: bool) -> Result<Texture> {
+        let index = texture.source().index();
+        if let Some(texture) = self.loaded.get(&(index, srgb)) {
+            return Ok(texture.clone());
+        }
+        let image = &self.images[index];
+        let format = if srgb {
+            ColorSpace::Srgb
+        } else {
+            ColorSpace::Linear
+        }
+        .rgba8_format();
+        let texture = Texture::from_pixels(
+            self.gpu.clone(),
+            [image.width, image.height],
+            format,
+            &rgba8(image),
+        )?;
+        self.loaded.insert((index, srgb), texture.clone());
+        Ok(texture)
+    }
+}
+
+fn rgba8(image: &::gltf::image::Data) -> Vec<u8> {
+    let (channels, read): (usize, fn(&[u8]) -> u8) = match image.format {
+        ImageFormat::R8 => (1, |c| c[0]),
+        ImageFormat::R8G8 => (2, |c| c[0]),
+        ImageFormat::R8G8B8 => (3, |c| c[0]),
+        ImageFormat::R8G8B8A8 => (4, |c| c[0]),
+        ImageFormat::R16 => (1, |c| c[1]),
+        ImageFormat::R16G16 => (2, |c| c[1]),
+        ImageFormat::R16G16B16 => (3, |c| c[1]),
+        ImageFormat::R16G16B16A16 => (4, |c| c[1]),
+        ImageFormat::R32G32B32FLOAT => (3, float_to_u8),
+        ImageFormat::R32G32B32A32FLOAT => (4, float_to_u8),
+    };
+    let size = match image.format {
+        ImageFormat::R32G32B32FLOAT | ImageFormat::R32G32B32A32FLOAT => 4,
+        ImageFormat::R16
+        | ImageFormat::R16G16
+        | ImageFormat::R16G16B16
+        | ImageFormat::R16G16B16A16 => 2,
+        _ => 1,
+    };
+    image
+        .pixels
+        .chunks_exact(channels * size)
+        .flat_map(|pixel| {
+            let mut rgba = [0, 0, 0, 255];
+            for (channel, value) in pixel.chunks_exact(size).enumerate() {
+                rgba[channel] = read(value);
+            }
+            if channels == 1 {
+                rgba[1] = rgba[0];
+                rgba[2] = rgba[0];
+            }
+            rgba
+        })
+        .collect()
+}
+
+fn float_to_u8(bytes: &[u8]) -> u8 {
+    let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn load_material(material: &::gltf::Material, textures: &mut TextureCache) -> Result<Material> {
+    let pbr = material.pbr_metallic_roughness();
+    let base_color_map = pbr
+        .base_color_texture()
+        .map(|info| textures.get(info.texture(), true))
+        .transpose()?;
+    let metallic_roughness_map = pbr
+        .metallic_roughness_texture()
+        .map(|info| textures.get(info.texture(), false))
+        .transpose()?;
+    let normal = material.normal_texture();
+    let normal_map = normal
+        .as_ref()
+        .map(|normal| textures.get(normal.texture(), false))
+        .transpose()?;
+    Ok(Material {
+        base_color: pbr.base_color_factor(),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        base_color_map,
+        metallic_roughness_map,
+        normal_map,
+        normal_scale: normal.map_or(1.0, |normal| normal.scale()),
+    })
+}
+
+fn load_primitive(
+    gpu: &Arc<Gpu>,
+    primitive: &::gltf::Primitive,
+    buffers: &[::gltf::buffer::Data],
+    skin: Option<&::gltf::Skin>,
+    weights: &[f32],
+) -> Result<GltfPrimitive> {
+    if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+        return Err(EngineError::Unsupported(format!(
+            "unsupported primitive mode {:?}",
+            primitive.mode()
+        )));
+    }
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    let Some(positions) = reader.read_positions() else {
+        return Err(EngineError::InvalidAsset(
+            "primitive has no POSITION attribute".into(),
+        ));
+    };
+    let mut vertices: Vec<Vertex3D> = positions
+        .map(|position| Vertex3D {
+            position,
+            normal: [0.0, 0.0, 1.0],
+            uv: [0.0; 2],
+            tangent: [0.0; 4],
+        })
+        .collect();
+    if let Some(normals) = reader.read_normals() {
+        for (vertex, normal) in vertices.iter_mut().zip(normals) {
+            vertex.normal = normal;
+        }
+    }
+    if let Some(uvs) = reader.read_tex_coords(0) {
+        for (vertex, uv) in vertices.iter_mut().zip(uvs.into_f32()) {
+            vertex.uv = uv;
+        }
+    }
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..vertices.len() as u32).collect(),
+    };
+    match reader.read_tangents() {
+        Some(tangents) => {
+            for (vertex, tangent) in vertices.iter_mut().zip(tangents) {
+                vertex.tangent = tangent;
+            }
+        }
+        None => {
+            if !Vertex3D::generate_tangents(&mut vertices, &indices) {
+                return Err(EngineError::InvalidAsset(
+                    "cannot generate tangents for primitive".into(),
+                ));
+            }
+        }
+    }
+    let material = primitive.material().index();
+
+    if let (Some(skin), Some(joints), Some(joint_weights)) =
+        (skin, reader.read_joints(0), reader.read_weights(0))
+    {
+        let nodes: Vec<u32> = skin.joints().map(|joint| joint.index() as u32).collect();
+        let vertices = vertices
+            .into_iter()
+            .zip(joints.into_u16())
+            .zip(joint_weights.into_f32())
+            .map(|((vertex, joints), weights)| SkinnedVertex3D {
+                position: vertex.position,
+                normal: vertex.normal,
+                uv: vertex.uv,
+                tangent: vertex.tangent,
+                joints: joints.map(|joint| nodes.get(joint as usize).copied().unwrap_or(0)),
+                weights,
+            })
+            .collect();
+        return Ok(GltfPrimitive {
+            geometry: GltfGeometry::Skinned(Mesh::new(gpu.clone(), vertices, indices)?),
+            material,
+        });
+    }
+
+    let targets: Vec<MorphTarget> = reader
+        .read_morph_targets()
+        .enumerate()
+        .map(|(index, (positions, normals, tangents))| MorphTarget {
+            name: format!("target{index}"),
+            positions: positions
+                .map(Iterator::collect)
+                .unwrap_or_else(|| vec![[0.0; 3]; vertices.len()]),
+            normals: normals.map(Iterator::collect).unwrap_or_default(),
+            tangents: tangents.map(Iterator::collect).unwrap_or_default(),
+        })
+        .collect();
+    let geometry = if targets.is_empty() {
+        GltfGeometry::Static(Mesh::new(gpu.clone(), vertices, indices)?)
+    } else {
+        let mut morphed = MorphedMesh::new(gpu.clone(), vertices, indices, targets)?;
+        morphed.set_weights(weights);
+        GltfGeometry::Morphed(morphed)
+    };
+    Ok(GltfPrimitive { geometry, material })
+}
+
+fn load_channel(
+    channel: &::gltf::animation::Channel,
+    buffers: &[::gltf::buffer::Data],
+) -> Option<Channel> {
+    let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+    let times: Vec<f32> = reader.read_inputs()?.collect();
+    let sampler = channel.sampler();
+    let interpolation = match sampler.interpolation() {
+        ::gltf::animation::Interpolation::Step => Interpolation::Step,
+        _ => Interpolation::Linear,
+    };
+    let cubic = sampler.interpolation() == ::gltf::animation::Interpolation::CubicSpline;
+    let keyframes = match reader.read_outputs()? {
+        ReadOutputs::Translations(values) => {
+            Keyframes::Translation(spline_values(values.map(Vec3::from), cubic))
+        }
+        ReadOutputs::Rotations(values) => Keyframes::Rotation(spline_values(
+            values.into_f32().map(Quat::from_array),
+            cubic,
+        )),
+        ReadOutputs::Scales(values) => {
+            Keyframes::Scale(spline_values(values.map(Vec3::from), cubic))
+        }
+        ReadOutputs::MorphTargetWeights(_) => return None,
+    };
+    Some(Channel {
+        joint: channel.target().node().index(),
+        interpolation,
+        times,
+        keyframes,
+    })
+}
+
+fn spline_values<T>(values: impl Iterator<Item = T>, cubic: bool) -> Vec<T> {
+    if cubic {
+        values.skip(1).step_by(3).collect()
+    } else {
+        values.collect()
+    }
+}
@@ -0,0 +1,132 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::material::Material;
+use crate::core::renderer::{Draw, Mesh};
+use crate::core::texture::{ColorSpace, Texture};
+use crate::core::vertex::Vertex3D;
+use glam::{Mat4, Vec3};
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct ObjMesh {
+    pub name: String,
+    pub mesh: Mesh<Vertex3D>,
+    pub material: Option<usize>,
+}
+
+#[derive(Clone)]
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<Material>,
+}
+
+impl ObjModel {
+    pub fn load(gpu: Arc<Gpu>, path: impl AsRef<Path>) -> Result<Self> {
+        let (models, materials) = tobj::load_obj(
+            path.as_ref(),
+            &tobj::LoadOptions {
+                single_index: true,
+                triangulate: true,
+                ..Default::default()
+            },
+        )?;
+        let directory = path.as_ref().parent().unwrap_or(Path::new(""));
+        let materials = materials?
+            .iter()
+            .map(|material| load_material(&gpu, directory, material))
+            .collect::<Result<Vec<_>>>()?;
+
+        let meshes = models
+            .into_iter()
+            .map(|model| {
+                let mut vertices = load_vertices(&model.mesh);
+                let indices = model.mesh.indices;
+                if !Vertex3D::generate_tangents(&mut vertices, &indices) {
+                    return Err(EngineError::InvalidAsset(format!(
+                        "cannot generate tangents for {}",
+                        model.name
+                    )));
+                }
+                Ok(ObjMesh {
+                    name: model.name,
+                    mesh: Mesh::new(gpu.clone(), vertices, indices)?,
+                    material: model.mesh.material_id,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { meshes, materials })
+    }
+
+    pub fn draws(&self, transform: Mat4) -> Vec<Draw<Vertex3D>> {
+        self.meshes
+            .iter()
+            .map(|mesh| {
+                let material = mesh
+                    .material
+                    .and_then(|material| self.materials.get(material).cloned())
+                    .unwrap_or_default();
+                Draw::new(mesh.mesh.clone(), 0)
+                    .with_transform(transform)
+                    .with_material(material)
+            })
+            .collect()
+    }
+}
+
+fn load_vertices(mesh: &tobj::Mesh) -> Vec<Vertex3D> {
+    let mut vertices: Vec<Vertex3D> = mesh
+        .positions
+        .chunks_exact(3)
+        .enumerate()
+        .map(|(i, position)| Vertex3D {
+            position: [position[0], position[1], position[2]],
+            normal: mesh
+                .normals
+                .get(i * 3..i * 3 + 3)
+                .map_or([0.0; 3], |n| [n[0], n[1], n[2]]),
+            uv: mesh
+                .texcoords
+                .get(i * 2..i * 2 + 2)
+                .map_or([0.0; 2], |uv| [uv[0], 1.0 - uv[1]]),
+            tangent: [0.0; 4],
+        })
+        .collect();
+
+    if mesh.normals.is_empty() {
+        let mut normals = vec![Vec3::ZERO; vertices.len()];
+        for face in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [face[0], face[1], face[2]]
+                .map(|index| Vec3::from(vertices[index as usize].position));
+            let normal = (b - a).cross(c - a);
+            for &index in face {
+                normals[index as usize] += normal;
+            }
+        }
+        for (vertex, normal) in vertices.iter_mut().zip(normals) {
+            vertex.normal = normal.normalize_or(Vec3::Z).to_array();
+        }
+    }
+    vertices
+}
+
+fn load_material(gpu: &Arc<Gpu>, directory: &Path, material: &tobj::Material) -> Result<Material> {
+    let [r, g, b] = material.diffuse.unwrap_or([1.0; 3]);
+    let alpha = material.dissolve.unwrap_or(1.0);
+    let roughness = material
+        .shininess
+        .map_or(0.5, |shininess| (2.0 / (shininess.max(0.0) + 2.0)).sqrt());
+    let texture = |name: &Option<String>, color_space| {
+        name.as_ref()
+            .map(|name| Texture::from_path(gpu.clone(), directory.join(name), color_space))
+            .transpose()
+    };
+    Ok(Material {
+        base_color: [r, g, b, alpha],
+        roughness,
+        base_color_map: texture(&material.diffuse_texture, ColorSpace::Srgb)?,
+        normal_map: texture(&material.normal_texture, ColorSpace::Linear)?,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,463 @@
+use crate::app::tasks::{Spawner, TaskPool};
+use crate::assets::gltf::GltfModel;
+use crate::assets::obj::ObjModel;
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+#[cfg(feature = "shaderc")]
+use crate::core::shader_compiler::ShaderCompiler;
+use crate::core::texture::{ColorSpace, Texture};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use vulkano::shader::{ShaderModule, ShaderModuleCreateInfo};
+
+type AnyAsset = dyn Any + Send + Sync;
+type ErasedLoader = Arc<dyn Fn(&Arc<Gpu>, &Path) -> Result<Arc<AnyAsset>> + Send + Sync>;
+
+const PLACEHOLDER_TEXTURE: [u8; 16] = [
+    255, 0, 255, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 0, 255, 255,
+];
+
+pub trait AssetLoader: Send + Sync + 'static {
+    type Asset: Any + Send + Sync;
+
+    fn extensions(&self) -> &[&str];
+
+    fn load(&self, gpu: &Arc<Gpu>, path: &Path) -> Result<Self::Asset>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub requested: usize,
+    pub loaded: usize,
+    pub failed: usize,
+}
+
+impl LoadProgress {
+    pub fn pending(&self) -> usize {
+        self.requested.saturating_sub(self.loaded + self.failed)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending() == 0
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.requested == 0 {
+            return 1.0;
+        }
+        (self.loaded + self.failed) as f32 / self.requested as f32
+    }
+}
+
+struct Slot<T> {
+    asset: Mutex<Option<Arc<T>>>,
+    state: Mutex<LoadState>,
+}
+
+impl<T> Slot<T> {
+    fn finish(&self, result: Result<Arc<T>>, path: &Path, progress: &Mutex<LoadProgress>) {
+        match result {
+            Ok(asset) => {
+                *self.asset.lock().unwrap() = Some(asset);
+                *self.state.lock().unwrap() = LoadState::Loaded;
+                progress.lock().unwrap().loaded += 1;
+            }
+            Err(e) => {
+                log::error!("failed to load {}: {e}", path.display());
+                *self.state.lock().unwrap() = LoadState::Failed(e.to_string());
+                progress.lock().unwrap().failed += 1;
+            }
+        }
+    }
+}
+
+pub struct Handle<T> {
+    slot: Arc<Slot<T>>,
+    path: Arc<Path>,
+}
+
+impl<T> Handle<T> {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self) -> Option<Arc<T>> {
+        self.slot.asset.lock().unwrap().clone()
+    }
+
+    pub fn state(&self) -> LoadState {
+        self.slot.state.lock().unwrap().clone()
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        *self.slot.state.lock().unwrap() == LoadState::Loaded
+    }
+
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.slot, &other.slot)
+    }
+
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.slot)
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: self.slot.clone(),
+            path: self.path.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("path", &self.path)
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+pub struct AssetServer {
+    gpu: Arc<Gpu>,
+    root: PathBuf,
+    spawner: Option<Spawner>,
+    loaders: RwLock<HashMap<(TypeId, String), ErasedLoader>>,
+    placeholders: RwLock<HashMap<TypeId, Arc<AnyAsset>>>,
+    cache: Mutex<HashMap<(TypeId, PathBuf), Weak<AnyAsset>>>,
+    progress: Arc<Mutex<LoadProgress>>,
+}
+
+impl AssetServer {
+    pub fn new(gpu: Arc<Gpu>) -> Self {
+        let server = Self {
+            gpu,
+            root: PathBuf::new(),
+            spawner: None,
+            loaders: RwLock::new(HashMap::new()),
+            placeholders: RwLock::new(HashMap::new()),
+            cache: Mutex::new(HashMap::new()),
+            progress: Arc::default(),
+        };
+        server.register(TextureLoader);
+        server.register(ObjLoader);
+        server.register(GltfLoader);
+        server.register(SpirvLoader);
+        #[cfg(feature = "shaderc")]
+        if let Ok(compiler) = ShaderCompiler::new() {
+            server.register(GlslLoader(Mutex::new(compiler)));
+        }
+        match Texture::from_pixels(
+            server.gpu.clone(),
+            [2, 2],
+            ColorSpace::Srgb.rgba8_format(),
+            &PLACEHOLDER_TEXTURE,
+        ) {
+            Ok(texture) => server.set_placeholder(texture),
+            Err(e) => log::warn!("failed to create placeholder texture: {e}"),
+        }
+        server
+    }
+
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    pub fn with_task_pool(mut self, tasks: &TaskPool) -> Self {
+        self.spawner = Some(tasks.spawner());
+        self
+    }
+
+    pub fn gpu(&self) -> &Arc<Gpu> {
+        &self.gpu
+    }
+
+    pub fn register<L: AssetLoader>(&self, loader: L) {
+        let loader = Arc::new(loader);
+        let mut loaders = self.loaders.write().unwrap();
+        for extension in loader.extensions() {
+            let loader = loader.clone();
+            loaders.insert(
+                (TypeId::of::<L::Asset>(), extension.to_lowercase()),
+                Arc::new(move |gpu, path| Ok(Arc::new(loader.load(gpu, path)?) as Arc<AnyAsset>)),
+            );
+        }
+    }
+
+    pub fn set_placeholder<T: Any + Send + Sync>(&self, asset: T) {
+        self.placeholders
+            .write()
+            .unwrap()
+            .insert(TypeId::of::<T>(), Arc::new(asset));
+    }
+
+    pub fn load<T: Any + Send + Sync>(&self, path: impl AsRef<Path>) -> Handle<T> {
+        let path = self.resolve(path.as_ref());
+        let slot = {
+            let mut cache = self.cache.lock().unwrap();
+            let key = (TypeId::of::<T>(), path.clone());
+            if let Some(slot) = cache.get(&key).and_then(Weak::upgrade) {
+                return Handle {
+                    slot: slot.downcast().unwrap(),
+                    path: path.into(),
+                };
+            }
+            let slot = Arc::new(Slot {
+                asset: Mutex::new(self.placeholder::<T>()),
+                state: Mutex::new(LoadState::Loading),
+            });
+            cache.insert(key, Arc::downgrade(&slot) as Weak<AnyAsset>);
+            slot
+        };
+        self.progress.lock().unwrap().requested += 1;
+        let handle = Handle {
+            slot,
+            path: path.into(),
+        };
+        let loader = match self.loader::<T>(&handle.path) {
+            Ok(loader) => loader,
+            Err(e) => {
+                handle.slot.finish(Err(e), &handle.path, &self.progress);
+                return handle;
+            }
+        };
+        let (slot, path) = (handle.slot.clone(), handle.path.clone());
+        let (gpu, progress) = (self.gpu.clone(), self.progress.clone());
+        let work = move || {
+            let result = loader(&gpu, &path).map(|asset| asset.downcast().unwrap());
+            slot.finish(result, &path, &progress);
+        };
+        match &self.spawner {
+            Some(spawner) => spawner.spawn(work),
+            None => work(),
+        }
+        handle
+    }
+
+    pub fn load_blocking<T: Any + Send + Sync>(&self, path: impl AsRef<Path>) -> Result<Handle<T>> {
+        let path = self.resolve(path.as_ref());
+        if let Some(handle) = self.cached::<T>(&path)
+            && handle.is_loaded()
+        {
+            return Ok(handle);
+        }
+        let asset = self.loader::<T>(&path)?(&self.gpu, &path)?;
+        Ok(self.store(path, asset.downcast().unwrap()))
+    }
+
+    pub fn get<T: Any + Send + Sync>(&self, path: impl AsRef<Path>) -> Option<Handle<T>> {
+        self.cached(&self.resolve(path.as_ref()))
+    }
+
+    pub fn insert<T: Any + Send + Sync>(&self, path: impl AsRef<Path>, asset: T) -> Handle<T> {
+        self.store(self.resolve(path.as_ref()), Arc::new(asset))
+    }
+
+    pub fn progress(&self) -> LoadProgress {
+        *self.progress.lock().unwrap()
+    }
+
+    pub fn reset_progress(&self) {
+        let mut progress = self.progress.lock().unwrap();
+        *progress = LoadProgress {
+            requested: progress.pending(),
+            ..Default::default()
+        };
+    }
+
+    pub fn loaded(&self) -> usize {
+        self.cache
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|asset| asset.strong_count() > 0)
+            .count()
+    }
+
+    pub fn collect_garbage(&self) -> usize {
+        let mut cache = self.cache.lock().unwrap();
+        let before = cache.len();
+        cache.retain(|_, asset| asset.strong_count() > 0);
+        before - cache.len()
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        let path = self.root.join(path);
+        path.canonicalize().unwrap_or(path)
+    }
+
+    fn placeholder<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        let placeholder = self
+            .placeholders
+            .read()
+            .unwrap()
+            .get(&TypeId::of::<T>())?
+            .clone();
+        placeholder.downcast().ok()
+    }
+
+    fn cached<T: Any + Send + Sync>(&self, path: &Path) -> Option<Handle<T>> {
+        let slot = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(&(TypeId::of::<T>(), path.to_owned()))?
+            .upgrade()?;
+        Some(Handle {
+            slot: slot.downcast().ok()?,
+            path: path.into(),
+        })
+    }
+
+    fn loader<T: Any>(&self, path: &Path) -> Result<ErasedLoader> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        self.loaders
+            .read()
+            .unwrap()
+            .get(&(TypeId::of::<T>(), extension))
+            .cloned()
+            .ok_or_else(|| {
+                EngineError::Unsupported(format!(
+                    "no {} loader is registered for {}",
+                    std::any::type_name::<T>(),
+                    path.display()
+                ))
+            })
+    }
+
+    fn store<T: Any + Send + Sync>(&self, path: PathBuf, asset: Arc<T>) -> Handle<T> {
+        let mut cache = self.cache.lock().unwrap();
+        let key = (TypeId::of::<T>(), path.clone());
+        let slot = match cache.get(&key).and_then(Weak::upgrade) {
+            Some(slot) => {
+                let slot: Arc<Slot<T>> = slot.downcast().unwrap();
+                *slot.asset.lock().unwrap() = Some(asset);
+                *slot.state.lock().unwrap() = LoadState::Loaded;
+                slot
+            }
+            None => {
+                let slot = Arc::new(Slot {
+                    asset: Mutex::new(Some(asset)),
+                    state: Mutex::new(LoadState::Loaded),
+                });
+                cache.insert(key, Arc::downgrade(&slot) as Weak<AnyAsset>);
+                slot
+            }
+        };
+        Handle {
+            slot,
+            path: path.into(),
+        }
+    }
+}
+
+struct TextureLoader;
+
+impl AssetLoader for TextureLoader {
+    type Asset = Texture;
+
+    fn extensions(&self) -> &[&str] {
+        &["png", "jpg", "jpeg", "hdr", "ktx2"]
+    }
+
+    fn load(&self, gpu: &Arc<Gpu>, path: &Path) -> Result<Texture> {
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "ktx2")
+        {
+            Texture::from_ktx2_path(gpu.clone(), path)
+        } else {
+            Texture::from_path(gpu.clone(), path, ColorSpace::Srgb)
+        }
+    }
+}
+
+struct ObjLoader;
+
+impl AssetLoader for ObjLoader {
+    type Asset = ObjModel;
+
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+
+    fn load(&self, gpu: &Arc<Gpu>, path: &Path) -> Result<ObjModel> {
+        ObjModel::load(gpu.clone(), path)
+    }
+}
+
+struct GltfLoader;
+
+impl AssetLoader for GltfLoader {
+    type Asset = GltfModel;
+
+    fn extensions(&self) -> &[&str] {
+        &["gltf", "glb"]
+    }
+
+    fn load(&self, gpu: &Arc<Gpu>, path: &Path) -> Result<GltfModel> {
+        GltfModel::load(gpu.clone(), path)
+    }
+}
+
+struct SpirvLoader;
+
+impl AssetLoader for SpirvLoader {
+    type Asset = Arc<ShaderModule>;
+
+    fn extensions(&self) -> &[&str] {
+        &["spv"]
+    }
+
+    fn load(&self, gpu: &Arc<Gpu>, path: &Path) -> Result<Arc<ShaderModule>> {
+        let bytes = std::fs::read(path)?;
+        if bytes.len() % 4 != 0 {
+            return Err(EngineError::InvalidAsset(format!(
+                "{} is not a SPIR-V module",
+                path.display()
+            )));
+        }
+        let words: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_ne_bytes(word.try_into().unwrap()))
+            .collect();
+        Ok(
+            unsafe {
+                ShaderModule::new(gpu.device().clone(), ShaderModuleCreateInfo::new(&words))?
+            },
+        )
+    }
+}
+
+#[cfg(feature = "shaderc")]
+struct GlslLoader(Mutex<ShaderCompiler>);
+
+#[cfg(feature = "shaderc")]
+impl AssetLoader for GlslLoader {
+    type Asset = Arc<ShaderModule>;
+
+    fn extensions(&self) -> &[&str] {
+        &["vert", "frag", "comp", "geom", "tesc", "tese"]
+    }
+
+    fn load(&self, gpu: &Arc<Gpu>, path: &Path) -> Result<Arc<ShaderModule>> {
+        self.0.lock().unwrap().compile_file(gpu, path, None)
+    }
+}
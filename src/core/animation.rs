@@ -0,0 +1,292 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::transform::Transform;
+use glam::{Mat4, Quat, Vec3};
+use std::sync::Arc;
+use vulkano::buffer::{BufferUsage, Subbuffer};
+
+pub type JointBuffer = Subbuffer<[[[f32; 4]; 4]]>;
+
+#[derive(Clone, Debug)]
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub inverse_bind: Mat4,
+    pub rest: Transform,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+}
+
+impl Skeleton {
+    pub fn new(joints: Vec<Joint>) -> Result<Self> {
+        let skeleton = Self { joints };
+        skeleton.validate()?;
+        Ok(skeleton)
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+
+    pub fn rest_pose(&self) -> Vec<Transform> {
+        self.joints.iter().map(|joint| joint.rest).collect()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (index, joint) in self.joints.iter().enumerate() {
+            let mut parent = joint.parent;
+            for _ in 0..self.joints.len() {
+                let Some(current) = parent else {
+                    break;
+                };
+                let Some(next) = self.joints.get(current) else {
+                    return Err(EngineError::InvalidAsset(format!(
+                        "joint {index} has parent {current}, but the skeleton has only {} joints",
+                        self.joints.len()
+                    )));
+                };
+                parent = next.parent;
+            }
+            if parent.is_some() {
+                return Err(EngineError::InvalidAsset(format!(
+                    "joint {index} is part of a parent cycle"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn joint_matrices(&self, pose: &[Transform]) -> Result<Vec<Mat4>> {
+        if pose.len() != self.joints.len() {
+            return Err(EngineError::InvalidArgument(format!(
+                "pose has {} transforms, but the skeleton has {} joints",
+                pose.len(),
+                self.joints.len()
+            )));
+        }
+        let mut globals: Vec<Option<Mat4>> = vec![None; self.joints.len()];
+        let mut chain = Vec::new();
+        for index in 0..self.joints.len() {
+            let mut current = Some(index);
+            let mut global = Mat4::IDENTITY;
+            while let Some(joint) = current {
+                if let Some(known) = globals[joint] {
+                    global = known;
+                    break;
+                }
+                if chain.len() == self.joints.len() {
+                    return Err(EngineError::InvalidAsset(format!(
+                        "joint {index} is part of a parent cycle"
+                    )));
+                }
+                chain.push(joint);
+                current = self.joints[joint].parent;
+                if current.is_some_and(|parent| parent >= self.joints.len()) {
+                    return Err(EngineError::InvalidAsset(format!(
+                        "joint {joint} has an out-of-range parent"
+                    )));
+                }
+            }
+            for joint in chain.drain(..).rev() {
+                global *= pose[joint].matrix();
+                globals[joint] = Some(global);
+            }
+        }
+        Ok(self
+            .joints
+            .iter()
+            .zip(globals)
+            .map(|(joint, global)| global.unwrap() * joint.inverse_bind)
+            .collect())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interpolation {
+    Step,
+    Linear,
+}
+
+#[derive(Clone, Debug)]
+pub enum Keyframes {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Channel {
+    pub joint: usize,
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    pub keyframes: Keyframes,
+}
+
+impl Channel {
+    fn apply(&self, time: f32, transform: &mut Transform) {
+        if self.times.is_empty() {
+            return;
+        }
+        let next = self.times.partition_point(|&t| t <= time);
+        let (a, b, factor) = if next == 0 {
+            (0, 0, 0.0)
+        } else if next == self.times.len() {
+            (next - 1, next - 1, 0.0)
+        } else {
+            let (start, end) = (self.times[next - 1], self.times[next]);
+            let factor = match self.interpolation {
+                Interpolation::Step => 0.0,
+                Interpolation::Linear => (time - start) / (end - start),
+            };
+            (next - 1, next, factor)
+        };
+        match &self.keyframes {
+            Keyframes::Translation(values) => {
+                transform.translation = values[a].lerp(values[b], factor);
+            }
+            Keyframes::Rotation(values) => {
+                transform.rotation = values[a].slerp(values[b], factor).normalize();
+            }
+            Keyframes::Scale(values) => {
+                transform.scale = values[a].lerp(values[b], factor);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub name: String,
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+impl AnimationClip {
+    pub fn new(name: impl Into<String>, channels: Vec<Channel>) -> Self {
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.times.last().copied())
+            .fold(0.0, f32::max);
+        Self {
+            name: name.into(),
+            duration,
+            channels,
+        }
+    }
+
+    pub fn sample(&self, time: f32, pose: &mut [Transform]) {
+        for channel in &self.channels {
+            if let Some(transform) = pose.get_mut(channel.joint) {
+                channel.apply(time, transform);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+    clip: Arc<AnimationClip>,
+    time: f32,
+    pub speed: f32,
+    pub looping: bool,
+    pub paused: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(clip: Arc<AnimationClip>) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+            paused: false,
+        }
+    }
+
+    pub fn clip(&self) -> &Arc<AnimationClip> {
+        &self.clip
+    }
+
+    pub fn play(&mut self, clip: Arc<AnimationClip>) {
+        self.clip = clip;
+        self.time = 0.0;
+        self.paused = false;
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.clamp(0.0, self.clip.duration);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.clip.duration
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        if self.paused {
+            return;
+        }
+        let duration = self.clip.duration;
+        self.time += delta_time * self.speed;
+        if duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.clamp(0.0, duration);
+        }
+    }
+
+    pub fn sample(&self, skeleton: &Skeleton) -> Vec<Transform> {
+        let mut pose = skeleton.rest_pose();
+        self.clip.sample(self.time, &mut pose);
+        pose
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Skin {
+    pub skeleton: Arc<Skeleton>,
+    pub player: Option<AnimationPlayer>,
+}
+
+impl Skin {
+    pub fn new(skeleton: Arc<Skeleton>) -> Self {
+        Self {
+            skeleton,
+            player: None,
+        }
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        if let Some(player) = &mut self.player {
+            player.advance(delta_time);
+        }
+    }
+
+    pub fn joint_matrices(&self) -> Result<Vec<Mat4>> {
+        let pose = match &self.player {
+            Some(player) => player.sample(&self.skeleton),
+            None => self.skeleton.rest_pose(),
+        };
+        self.skeleton.joint_matrices(&pose)
+    }
+
+    pub fn joint_buffer(&self, gpu: &Gpu) -> Result<JointBuffer> {
+        create_joint_buffer(gpu, &self.joint_matrices()?)
+    }
+}
+
+pub(crate) fn create_joint_buffer(gpu: &Gpu, matrices: &[Mat4]) -> Result<JointBuffer> {
+    let buffer = gpu.create_buffer(
+        matrices.iter().map(Mat4::to_cols_array_2d),
+        BufferUsage::STORAGE_BUFFER,
+    )?;
+    Ok(buffer)
+}
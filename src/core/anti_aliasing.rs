@@ -0,0 +1,287 @@
+use crate::core::camera::Camera;
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::render_graph::{Attachment, RenderGraph, ResourceId};
+use crate::core::renderer::{create_pipeline, rendering_info};
+use crate::core::shaders::{fullscreen_vs, fxaa_fs, taa_fs};
+use glam::{Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use vulkano::buffer::BufferContents;
+use vulkano::descriptor_set::{DescriptorSet, WriteDescriptorSet};
+use vulkano::format::Format;
+use vulkano::image::sampler::{Sampler, SamplerAddressMode, SamplerCreateInfo};
+use vulkano::image::view::ImageView;
+use vulkano::image::ImageUsage;
+use vulkano::pipeline::graphics::vertex_input::VertexInputState;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint, PipelineLayout};
+
+const HISTORY_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+const JITTER_SAMPLES: u32 = 8;
+const HISTORY_BLEND: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum AntiAliasing {
+    #[default]
+    None,
+    Fxaa,
+    Taa,
+}
+
+fn clamp_sampler(gpu: &Gpu) -> Result<Arc<Sampler>> {
+    let sampler = Sampler::new(
+        gpu.device().clone(),
+        SamplerCreateInfo {
+            address_mode: [SamplerAddressMode::ClampToEdge; 3],
+            ..SamplerCreateInfo::simple_repeat_linear_no_mipmap()
+        },
+    )?;
+    Ok(sampler)
+}
+
+pub(crate) struct FxaaPipeline {
+    sampler: Arc<Sampler>,
+    pipeline: Arc<GraphicsPipeline>,
+    gpu: Arc<Gpu>,
+}
+
+impl FxaaPipeline {
+    pub(crate) fn new(gpu: Arc<Gpu>, image_format: Format) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = fullscreen_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = fxaa_fs::load(device)?.entry_point("main").unwrap();
+        let pipeline = create_pipeline(
+            &gpu,
+            vs,
+            Some(fs),
+            VertexInputState::new(),
+            rendering_info(&[image_format], None),
+            None,
+        )?;
+        gpu.set_debug_name(&*pipeline, "fxaa")?;
+        Ok(Self {
+            sampler: clamp_sampler(&gpu)?,
+            pipeline,
+            gpu,
+        })
+    }
+
+    pub(crate) fn add_pass<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        source: ResourceId,
+        target: ResourceId,
+    ) {
+        graph
+            .add_pass("fxaa")
+            .color_attachment(Attachment::clear(target, [0.0; 4]))
+            .sample(source)
+            .record(move |ctx| {
+                let layout = self.pipeline.layout().clone();
+                let set = self.gpu.create_frame_descriptor_set(
+                    layout.set_layouts()[0].clone(),
+                    [WriteDescriptorSet::image_view_sampler(
+                        0,
+                        ctx.image_view(source),
+                        self.sampler.clone(),
+                    )],
+                )?;
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?
+                    .bind_pipeline_graphics(self.pipeline.clone())?
+                    .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, set)?;
+                unsafe { ctx.builder.draw(3, 1, 0, 0) }?;
+                Ok(())
+            });
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct TaaConstants {
+    previous_view_projection: [[f32; 4]; 4],
+    blend: f32,
+    history_valid: f32,
+}
+
+#[derive(Default)]
+struct TaaState {
+    history: Vec<Arc<ImageView>>,
+    extent: [u32; 2],
+    current: usize,
+    frame: u32,
+    previous_view_projection: Option<Mat4>,
+}
+
+pub(crate) struct TaaFrame {
+    read: Arc<ImageView>,
+    write: Arc<ImageView>,
+    previous_view_projection: Mat4,
+    history_valid: bool,
+}
+
+pub(crate) struct TaaPipeline {
+    sampler: Arc<Sampler>,
+    depth_sampler: Arc<Sampler>,
+    pipeline: Arc<GraphicsPipeline>,
+    state: Mutex<TaaState>,
+    gpu: Arc<Gpu>,
+}
+
+impl TaaPipeline {
+    pub(crate) fn new(gpu: Arc<Gpu>, image_format: Format) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = fullscreen_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = taa_fs::load(device)?.entry_point("main").unwrap();
+        let pipeline = create_pipeline(
+            &gpu,
+            vs,
+            Some(fs),
+            VertexInputState::new(),
+            rendering_info(&[image_format, HISTORY_FORMAT], None),
+            None,
+        )?;
+        gpu.set_debug_name(&*pipeline, "taa")?;
+        let depth_sampler = Sampler::new(
+            gpu.device().clone(),
+            SamplerCreateInfo {
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )?;
+        Ok(Self {
+            sampler: clamp_sampler(&gpu)?,
+            depth_sampler,
+            pipeline,
+            state: Mutex::new(TaaState::default()),
+            gpu,
+        })
+    }
+
+    pub(crate) fn layout(&self) -> &Arc<PipelineLayout> {
+        self.pipeline.layout()
+    }
+
+    pub(crate) fn reset(&self) {
+        *self.state.lock().unwrap() = TaaState::default();
+    }
+
+    pub(crate) fn prepare(&self, camera: &mut Camera, extent: [u32; 2]) -> Result<TaaFrame> {
+        let mut state = self.state.lock().unwrap();
+        if state.history.is_empty() || state.extent != extent {
+            let history = (0..2)
+                .map(|_| {
+                    let image = self.gpu.create_image(
+                        HISTORY_FORMAT,
+                        [extent[0], extent[1], 1],
+                        1,
+                        ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+                    )?;
+                    Ok(ImageView::new_default(image)?)
+                })
+                .collect::<Result<_>>()?;
+            *state = TaaState {
+                history,
+                extent,
+                ..TaaState::default()
+            };
+        }
+
+        let view_projection = camera.view_projection();
+        let index = state.frame % JITTER_SAMPLES + 1;
+        let jitter = Vec3::new(
+            (halton(index, 2) - 0.5) * 2.0 / extent[0] as f32,
+            (halton(index, 3) - 0.5) * 2.0 / extent[1] as f32,
+            0.0,
+        );
+        camera.projection = Mat4::from_translation(jitter) * camera.projection;
+
+        let frame = TaaFrame {
+            read: state.history[state.current].clone(),
+            write: state.history[1 - state.current].clone(),
+            previous_view_projection: state.previous_view_projection.unwrap_or(view_projection),
+            history_valid: state.previous_view_projection.is_some(),
+        };
+        state.current = 1 - state.current;
+        state.frame = state.frame.wrapping_add(1);
+        state.previous_view_projection = Some(view_projection);
+        Ok(frame)
+    }
+
+    pub(crate) fn add_pass<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        frame: TaaFrame,
+        frame_set: Arc<DescriptorSet>,
+        source: ResourceId,
+        depth: ResourceId,
+        target: ResourceId,
+    ) {
+        let history = graph.import(frame.read);
+        let next_history = graph.import(frame.write);
+        let constants = TaaConstants {
+            previous_view_projection: frame.previous_view_projection.to_cols_array_2d(),
+            blend: HISTORY_BLEND,
+            history_valid: if frame.history_valid { 1.0 } else { 0.0 },
+        };
+        graph
+            .add_pass("taa")
+            .color_attachment(Attachment::clear(target, [0.0; 4]))
+            .color_attachment(Attachment::clear(next_history, [0.0; 4]))
+            .sample(source)
+            .sample(depth)
+            .sample(history)
+            .record(move |ctx| {
+                let layout = self.pipeline.layout().clone();
+                let inputs_set = self.gpu.create_frame_descriptor_set(
+                    layout.set_layouts()[1].clone(),
+                    [
+                        WriteDescriptorSet::image_view_sampler(
+                            0,
+                            ctx.image_view(source),
+                            self.sampler.clone(),
+                        ),
+                        WriteDescriptorSet::image_view_sampler(
+                            1,
+                            ctx.image_view(depth),
+                            self.depth_sampler.clone(),
+                        ),
+                        WriteDescriptorSet::image_view_sampler(
+                            2,
+                            ctx.image_view(history),
+                            self.sampler.clone(),
+                        ),
+                    ],
+                )?;
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?
+                    .bind_pipeline_graphics(self.pipeline.clone())?
+                    .bind_descriptor_sets(
+                        PipelineBindPoint::Graphics,
+                        layout.clone(),
+                        0,
+                        (frame_set, inputs_set),
+                    )?
+                    .push_constants(layout, 0, constants)?;
+                unsafe { ctx.builder.draw(3, 1, 0, 0) }?;
+                Ok(())
+            });
+    }
+}
+
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
@@ -0,0 +1,192 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::texture::{ColorSpace, Texture};
+use etagere::{size2, AllocId, AtlasAllocator};
+use std::collections::HashMap;
+use std::sync::Arc;
+use vulkano::command_buffer::{BufferImageCopy, CopyBufferToImageInfo};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AtlasId(AllocId);
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct UvRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl UvRect {
+    pub const FULL: Self = Self {
+        min: [0.0, 0.0],
+        max: [1.0, 1.0],
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AtlasRegion {
+    pub id: AtlasId,
+    pub offset: [u32; 2],
+    pub extent: [u32; 2],
+    pub uv: UvRect,
+}
+
+pub struct TextureAtlas {
+    allocator: AtlasAllocator,
+    extent: [u32; 2],
+    padding: u32,
+    color_space: ColorSpace,
+    pixels: Vec<u8>,
+    regions: HashMap<AtlasId, AtlasRegion>,
+    pending: Vec<AtlasRegion>,
+    texture: Option<Texture>,
+}
+
+impl TextureAtlas {
+    pub fn new(extent: [u32; 2], color_space: ColorSpace) -> Self {
+        Self {
+            allocator: AtlasAllocator::new(size2(extent[0] as i32, extent[1] as i32)),
+            extent,
+            padding: 1,
+            color_space,
+            pixels: vec![0; (extent[0] * extent[1] * 4) as usize],
+            regions: HashMap::new(),
+            pending: Vec::new(),
+            texture: None,
+        }
+    }
+
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn extent(&self) -> [u32; 2] {
+        self.extent
+    }
+
+    pub fn insert(&mut self, extent: [u32; 2], rgba: &[u8]) -> Result<AtlasRegion> {
+        let [width, height] = extent;
+        if width == 0 || height == 0 {
+            return Err(EngineError::InvalidArgument(
+                "cannot insert an empty image into a texture atlas".into(),
+            ));
+        }
+        if rgba.len() != (width * height * 4) as usize {
+            return Err(EngineError::InvalidArgument(format!(
+                "expected {width}x{height} RGBA8 pixels, got {} bytes",
+                rgba.len()
+            )));
+        }
+        let padded = size2(
+            (width + self.padding * 2) as i32,
+            (height + self.padding * 2) as i32,
+        );
+        let Some(allocation) = self.allocator.allocate(padded) else {
+            return Err(EngineError::InvalidArgument(format!(
+                "texture atlas has no room for a {width}x{height} image"
+            )));
+        };
+        let offset = [
+            allocation.rectangle.min.x as u32 + self.padding,
+            allocation.rectangle.min.y as u32 + self.padding,
+        ];
+        for row in 0..height {
+            let source = (row * width * 4) as usize;
+            let target = (((offset[1] + row) * self.extent[0] + offset[0]) * 4) as usize;
+            self.pixels[target..target + (width * 4) as usize]
+                .copy_from_slice(&rgba[source..source + (width * 4) as usize]);
+        }
+
+        let size = [self.extent[0] as f32, self.extent[1] as f32];
+        let region = AtlasRegion {
+            id: AtlasId(allocation.id),
+            offset,
+            extent,
+            uv: UvRect {
+                min: [offset[0] as f32 / size[0], offset[1] as f32 / size[1]],
+                max: [
+                    (offset[0] + width) as f32 / size[0],
+                    (offset[1] + height) as f32 / size[1],
+                ],
+            },
+        };
+        self.regions.insert(region.id, region);
+        self.pending.push(region);
+        Ok(region)
+    }
+
+    pub fn insert_image(&mut self, image: &image::RgbaImage) -> Result<AtlasRegion> {
+        self.insert([image.width(), image.height()], image.as_raw())
+    }
+
+    pub fn get(&self, id: AtlasId) -> Option<&AtlasRegion> {
+        self.regions.get(&id)
+    }
+
+    pub fn remove(&mut self, id: AtlasId) -> Option<AtlasRegion> {
+        let region = self.regions.remove(&id)?;
+        self.allocator.deallocate(id.0);
+        self.pending.retain(|pending| pending.id != id);
+        Some(region)
+    }
+
+    pub fn clear(&mut self) {
+        self.allocator.clear();
+        self.regions.clear();
+        self.pending.clear();
+        self.pixels.fill(0);
+        self.texture = None;
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn texture(&mut self, gpu: Arc<Gpu>) -> Result<Texture> {
+        let Some(texture) = &self.texture else {
+            let texture = Texture::from_pixels(
+                gpu,
+                self.extent,
+                self.color_space.rgba8_format(),
+                &self.pixels,
+            )?;
+            self.pending.clear();
+            self.texture = Some(texture.clone());
+            return Ok(texture);
+        };
+        if self.pending.is_empty() {
+            return Ok(texture.clone());
+        }
+
+        let mut staging = Vec::new();
+        let mut regions = Vec::with_capacity(self.pending.len());
+        for region in self.pending.drain(..) {
+            let [width, height] = region.extent;
+            regions.push(BufferImageCopy {
+                buffer_offset: staging.len() as u64,
+                image_subresource: texture.image_view().image().subresource_layers(),
+                image_offset: [region.offset[0], region.offset[1], 0],
+                image_extent: [width, height, 1],
+                ..Default::default()
+            });
+            for row in 0..height {
+                let start =
+                    (((region.offset[1] + row) * self.extent[0] + region.offset[0]) * 4) as usize;
+                staging.extend_from_slice(&self.pixels[start..start + (width * 4) as usize]);
+            }
+        }
+        let staging = gpu.create_staging_buffer(staging)?;
+        gpu.immediate(|builder| {
+            builder.copy_buffer_to_image(CopyBufferToImageInfo {
+                regions: regions.into(),
+                ..CopyBufferToImageInfo::buffer_image(staging, texture.image_view().image().clone())
+            })?;
+            Ok(())
+        })?;
+        Ok(texture.clone())
+    }
+}
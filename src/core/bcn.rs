@@ -0,0 +1,504 @@
+use vulkano::format::Format;
+
+pub(crate) fn decompress(format: Format, extent: [u32; 2], data: &[u8]) -> Option<Vec<u8>> {
+    let block_size = match format {
+        Format::BC1_RGB_UNORM_BLOCK
+        | Format::BC1_RGB_SRGB_BLOCK
+        | Format::BC1_RGBA_UNORM_BLOCK
+        | Format::BC1_RGBA_SRGB_BLOCK
+        | Format::BC4_UNORM_BLOCK => 8,
+        Format::BC2_UNORM_BLOCK
+        | Format::BC2_SRGB_BLOCK
+        | Format::BC3_UNORM_BLOCK
+        | Format::BC3_SRGB_BLOCK
+        | Format::BC5_UNORM_BLOCK
+        | Format::BC7_UNORM_BLOCK
+        | Format::BC7_SRGB_BLOCK => 16,
+        _ => return None,
+    };
+    let [width, height] = extent;
+    let blocks_x = width.div_ceil(4) as usize;
+    let blocks_y = height.div_ceil(4) as usize;
+    if data.len() < blocks_x * blocks_y * block_size {
+        return None;
+    }
+
+    let mut pixels = vec![0; (width * height * 4) as usize];
+    for (index, block) in data
+        .chunks_exact(block_size)
+        .take(blocks_x * blocks_y)
+        .enumerate()
+    {
+        let texels = decode_block(format, block);
+        let (block_x, block_y) = (index % blocks_x * 4, index / blocks_x * 4);
+        for (i, texel) in texels.iter().enumerate() {
+            let (x, y) = (block_x + i % 4, block_y + i / 4);
+            if x < width as usize && y < height as usize {
+                let offset = (y * width as usize + x) * 4;
+                pixels[offset..offset + 4].copy_from_slice(texel);
+            }
+        }
+    }
+    Some(pixels)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColorMode {
+    FourColor,
+    Opaque,
+    PunchThrough,
+}
+
+fn decode_block(format: Format, block: &[u8]) -> [[u8; 4]; 16] {
+    match format {
+        Format::BC1_RGBA_UNORM_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => {
+            decode_color(block, ColorMode::PunchThrough)
+        }
+        Format::BC2_UNORM_BLOCK | Format::BC2_SRGB_BLOCK => {
+            let mut texels = decode_color(&block[8..], ColorMode::FourColor);
+            for (i, texel) in texels.iter_mut().enumerate() {
+                let nibble = (block[i / 2] >> (i % 2 * 4)) & 0xf;
+                texel[3] = nibble * 17;
+            }
+            texels
+        }
+        Format::BC3_UNORM_BLOCK | Format::BC3_SRGB_BLOCK => {
+            let mut texels = decode_color(&block[8..], ColorMode::FourColor);
+            for (texel, alpha) in texels.iter_mut().zip(decode_alpha(block)) {
+                texel[3] = alpha;
+            }
+            texels
+        }
+        Format::BC4_UNORM_BLOCK => decode_alpha(block).map(|r| [r, r, r, 255]),
+        Format::BC5_UNORM_BLOCK => {
+            let (red, green) = (decode_alpha(block), decode_alpha(&block[8..]));
+            std::array::from_fn(|i| [red[i], green[i], 0, 255])
+        }
+        Format::BC7_UNORM_BLOCK | Format::BC7_SRGB_BLOCK => decode_bc7(block),
+        _ => decode_color(block, ColorMode::Opaque),
+    }
+}
+
+fn decode_color(block: &[u8], mode: ColorMode) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let rgb = |c: u16| {
+        let c = c as u32;
+        [
+            (c >> 11 & 31) * 255 / 31,
+            (c >> 5 & 63) * 255 / 63,
+            (c & 31) * 255 / 31,
+        ]
+    };
+    let (a, b) = (rgb(c0), rgb(c1));
+    let mix = |wa: u32, wb: u32| {
+        let [r, g, b] = std::array::from_fn(|i| ((a[i] * wa + b[i] * wb) / (wa + wb)) as u8);
+        [r, g, b, 255]
+    };
+    let palette = if mode == ColorMode::FourColor || c0 > c1 {
+        [mix(1, 0), mix(0, 1), mix(2, 1), mix(1, 2)]
+    } else if mode == ColorMode::PunchThrough {
+        [mix(1, 0), mix(0, 1), mix(1, 1), [0; 4]]
+    } else {
+        [mix(1, 0), mix(0, 1), mix(1, 1), [0, 0, 0, 255]]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    std::array::from_fn(|i| palette[(indices >> (i * 2) & 3) as usize])
+}
+
+fn decode_alpha(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let palette: [u8; 8] = std::array::from_fn(|i| {
+        let i = i as u32;
+        match i {
+            0 => a0 as u8,
+            1 => a1 as u8,
+            _ if a0 > a1 => (((8 - i) * a0 + (i - 1) * a1) / 7) as u8,
+            6 => 0,
+            7 => 255,
+            _ => (((6 - i) * a0 + (i - 1) * a1) / 5) as u8,
+        }
+    });
+    let bits = block[2..8]
+        .iter()
+        .rev()
+        .fold(0u64, |bits, &byte| bits << 8 | byte as u64);
+    std::array::from_fn(|i| palette[(bits >> (i * 3) & 7) as usize])
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+const fn bc7_mode(
+    subsets: usize,
+    [partition_bits, rotation_bits, index_selection_bits]: [u32; 3],
+    [color_bits, alpha_bits]: [u32; 2],
+    [endpoint_pbits, shared_pbits]: [bool; 2],
+    [index_bits, secondary_index_bits]: [u32; 2],
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_pbits,
+        shared_pbits,
+        index_bits,
+        secondary_index_bits,
+    }
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    bc7_mode(3, [4, 0, 0], [4, 0], [true, false], [3, 0]),
+    bc7_mode(2, [6, 0, 0], [6, 0], [false, true], [3, 0]),
+    bc7_mode(3, [6, 0, 0], [5, 0], [false, false], [2, 0]),
+    bc7_mode(2, [6, 0, 0], [7, 0], [true, false], [2, 0]),
+    bc7_mode(1, [0, 2, 1], [5, 6], [false, false], [2, 3]),
+    bc7_mode(1, [0, 2, 0], [7, 8], [false, false], [2, 2]),
+    bc7_mode(1, [0, 0, 0], [7, 7], [true, false], [4, 0]),
+    bc7_mode(2, [6, 0, 0], [5, 5], [true, false], [2, 0]),
+];
+
+const BC7_PARTITIONS2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80, 0xc800, 0xffec, 0xfe80, 0xe800,
+    0xffe8, 0xff00, 0xfff0, 0xf000, 0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c, 0xaaaa, 0xf0f0, 0x5a5a, 0x33cc,
+    0x3c3c, 0x55aa, 0x9696, 0xa55a, 0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c, 0x9336, 0x9cc6, 0x817e, 0xe718,
+    0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+const BC7_PARTITIONS3: [u32; 64] = [
+    0xaa685050, 0x6a5a5040, 0x5a5a4200, 0x5450a0a8, 0xa5a50000, 0xa0a05050, 0x5555a0a0, 0x5a5a5050,
+    0xaa550000, 0xaa555500, 0xaaaa5500, 0x90909090, 0x94949494, 0xa4a4a4a4, 0xa9a59450, 0x2a0a4250,
+    0xa5945040, 0x0a425054, 0xa5a5a500, 0x55a0a0a0, 0xa8a85454, 0x6a6a4040, 0xa4a45000, 0x1a1a0500,
+    0x0050a4a4, 0xaaa59090, 0x14696914, 0x69691400, 0xa08585a0, 0xaa821414, 0x50a4a450, 0x6a5a0200,
+    0xa9a58000, 0x5090a0a8, 0xa8a09050, 0x24242424, 0x00aa5500, 0x24924924, 0x24499224, 0x50a50a50,
+    0x500aa550, 0xaaaa4444, 0x66660000, 0xa5a0a5a0, 0x50a050a0, 0x69286928, 0x44aaaa44, 0x66666600,
+    0xaa444444, 0x54a854a8, 0x95809580, 0x96969600, 0xa85454a8, 0x80959580, 0xaa141414, 0x96960000,
+    0xaaaa1414, 0xa05050a0, 0xa0a5a5a0, 0x96000000, 0x40804080, 0xa9a8a9a8, 0xaaaaaa44, 0x2a4a5254,
+];
+
+const BC7_ANCHORS2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+const BC7_ANCHORS3: [[u8; 64]; 2] = [
+    [
+        3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6,
+        8, 5, 15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8,
+        5, 10, 5, 10, 8, 13, 15, 12, 3, 3,
+    ],
+    [
+        15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3,
+        15, 6, 10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15,
+        15, 15, 15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+    ],
+];
+
+const BC7_WEIGHTS2: [u32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+struct BitReader {
+    bits: u128,
+    offset: u32,
+}
+
+impl BitReader {
+    fn read(&mut self, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        let value = (self.bits >> self.offset) & ((1 << count) - 1);
+        self.offset += count;
+        value as u32
+    }
+}
+
+fn decode_bc7(block: &[u8]) -> [[u8; 4]; 16] {
+    let Some(mode) = (0..8).find(|&mode| block[0] >> mode & 1 == 1) else {
+        return [[0; 4]; 16];
+    };
+    let m = &BC7_MODES[mode as usize];
+    let mut bits = BitReader {
+        bits: u128::from_le_bytes(block[..16].try_into().unwrap()),
+        offset: mode + 1,
+    };
+    let partition = bits.read(m.partition_bits) as usize;
+    let rotation = bits.read(m.rotation_bits);
+    let index_selection = bits.read(m.index_selection_bits) == 1;
+
+    let endpoints = m.subsets * 2;
+    let mut colors = [[0u32; 4]; 6];
+    for channel in 0..4 {
+        let width = if channel == 3 {
+            m.alpha_bits
+        } else {
+            m.color_bits
+        };
+        for endpoint in &mut colors[..endpoints] {
+            endpoint[channel] = bits.read(width);
+        }
+    }
+    let pbits: Vec<u32> = if m.endpoint_pbits {
+        (0..endpoints).map(|_| bits.read(1)).collect()
+    } else if m.shared_pbits {
+        (0..m.subsets)
+            .flat_map(|_| {
+                let pbit = bits.read(1);
+                [pbit, pbit]
+            })
+            .collect()
+    } else {
+        vec![0; endpoints]
+    };
+    for (endpoint, pbit) in colors.iter_mut().zip(pbits) {
+        for (channel, value) in endpoint.iter_mut().enumerate() {
+            let width = if channel == 3 {
+                m.alpha_bits
+            } else {
+                m.color_bits
+            };
+            *value = if width == 0 {
+                255
+            } else if m.endpoint_pbits || m.shared_pbits {
+                unquantize(*value << 1 | pbit, width + 1)
+            } else {
+                unquantize(*value, width)
+            };
+        }
+    }
+
+    let subset = |i: usize| match m.subsets {
+        2 => (BC7_PARTITIONS2[partition] >> i & 1) as usize,
+        3 => (BC7_PARTITIONS3[partition] >> (i * 2) & 3) as usize,
+        _ => 0,
+    };
+    let is_anchor = |i: usize| match m.subsets {
+        _ if i == 0 => true,
+        2 => i == BC7_ANCHORS2[partition] as usize,
+        3 => BC7_ANCHORS3
+            .iter()
+            .any(|anchors| i == anchors[partition] as usize),
+        _ => false,
+    };
+    let primary: [u32; 16] = std::array::from_fn(|i| bits.read(m.index_bits - is_anchor(i) as u32));
+    let secondary: [u32; 16] = std::array::from_fn(|i| match m.secondary_index_bits {
+        0 => 0,
+        width => bits.read(width - (i == 0) as u32),
+    });
+
+    std::array::from_fn(|i| {
+        let s = subset(i);
+        let (e0, e1) = (colors[s * 2], colors[s * 2 + 1]);
+        let ((color_index, color_bits), (alpha_index, alpha_bits)) = match m.secondary_index_bits {
+            0 => ((primary[i], m.index_bits), (primary[i], m.index_bits)),
+            width if index_selection => ((secondary[i], width), (primary[i], m.index_bits)),
+            width => ((primary[i], m.index_bits), (secondary[i], width)),
+        };
+        let mut texel: [u8; 4] = std::array::from_fn(|channel| {
+            let (index, width) = if channel == 3 {
+                (alpha_index, alpha_bits)
+            } else {
+                (color_index, color_bits)
+            };
+            let weight = match width {
+                2 => BC7_WEIGHTS2[index as usize],
+                3 => BC7_WEIGHTS3[index as usize],
+                _ => BC7_WEIGHTS4[index as usize],
+            };
+            (((64 - weight) * e0[channel] + weight * e1[channel] + 32) >> 6) as u8
+        });
+        if rotation > 0 {
+            texel.swap(rotation as usize - 1, 3);
+        }
+        texel
+    })
+}
+
+fn unquantize(value: u32, width: u32) -> u32 {
+    value << (8 - width) | value >> (width * 2 - 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bc1_block(c0: u16, c1: u16, indices: u32) -> [u8; 8] {
+        let mut block = [0; 8];
+        block[0..2].copy_from_slice(&c0.to_le_bytes());
+        block[2..4].copy_from_slice(&c1.to_le_bytes());
+        block[4..8].copy_from_slice(&indices.to_le_bytes());
+        block
+    }
+
+    #[test]
+    fn bc1_four_color_interpolates_endpoints() {
+        let block = bc1_block(0xffff, 0x0000, 0b11_10_01_00);
+        let texels = decompress(Format::BC1_RGB_UNORM_BLOCK, [4, 4], &block).unwrap();
+        assert_eq!(&texels[0..4], &[255, 255, 255, 255]);
+        assert_eq!(&texels[4..8], &[0, 0, 0, 255]);
+        assert_eq!(&texels[8..12], &[170, 170, 170, 255]);
+        assert_eq!(&texels[12..16], &[85, 85, 85, 255]);
+    }
+
+    #[test]
+    fn bc1_rgb_three_color_index_is_opaque_black() {
+        let block = bc1_block(0x0000, 0xffff, 0b11_10);
+        let texels = decompress(Format::BC1_RGB_SRGB_BLOCK, [4, 4], &block).unwrap();
+        assert_eq!(&texels[0..4], &[127, 127, 127, 255]);
+        assert_eq!(&texels[4..8], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc1_rgba_three_color_index_is_transparent() {
+        let block = bc1_block(0x0000, 0xffff, 0b11);
+        let texels = decompress(Format::BC1_RGBA_UNORM_BLOCK, [4, 4], &block).unwrap();
+        assert_eq!(&texels[0..4], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bc4_uses_eight_and_six_value_palettes() {
+        let mut block = [0; 8];
+        block[0] = 255;
+        block[1] = 0;
+        block[2] = 0b010_001_000u16 as u8;
+        block[3] = (0b010_001_000u16 >> 8) as u8;
+        let texels = decompress(Format::BC4_UNORM_BLOCK, [4, 4], &block).unwrap();
+        assert_eq!(&texels[0..4], &[255, 255, 255, 255]);
+        assert_eq!(&texels[4..8], &[0, 0, 0, 255]);
+        assert_eq!(texels[8], 218);
+
+        block[0] = 0;
+        block[1] = 255;
+        block[2] = 0b111_110_000u16 as u8;
+        block[3] = (0b111_110_000u16 >> 8) as u8;
+        let texels = decompress(Format::BC4_UNORM_BLOCK, [4, 4], &block).unwrap();
+        assert_eq!(texels[4], 0);
+        assert_eq!(texels[8], 255);
+    }
+
+    #[test]
+    fn bc3_combines_alpha_and_color_blocks() {
+        let mut block = [0; 16];
+        block[0] = 128;
+        block[8..16].copy_from_slice(&bc1_block(0xf800, 0x0000, 0));
+        let texels = decompress(Format::BC3_UNORM_BLOCK, [4, 4], &block).unwrap();
+        assert_eq!(&texels[0..4], &[255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn partial_blocks_are_cropped_to_the_extent() {
+        let block = bc1_block(0xffff, 0x0000, 0);
+        let texels = decompress(Format::BC1_RGB_UNORM_BLOCK, [2, 3], &block).unwrap();
+        assert_eq!(texels.len(), 2 * 3 * 4);
+        assert!(texels.iter().all(|&channel| channel == 255));
+    }
+
+    #[test]
+    fn short_or_unknown_data_is_rejected() {
+        assert!(decompress(Format::BC1_RGB_UNORM_BLOCK, [8, 4], &[0; 8]).is_none());
+        assert!(decompress(Format::R8G8B8A8_UNORM, [4, 4], &[0; 64]).is_none());
+    }
+
+    #[derive(Default)]
+    struct BitWriter {
+        bits: u128,
+        offset: u32,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u32, count: u32) -> &mut Self {
+            self.bits |= (value as u128) << self.offset;
+            self.offset += count;
+            self
+        }
+
+        fn block(&self) -> [u8; 16] {
+            assert_eq!(self.offset, 128);
+            self.bits.to_le_bytes()
+        }
+    }
+
+    #[test]
+    fn bc7_mode6_interpolates_with_pbits() {
+        let mut bits = BitWriter::default();
+        bits.write(1 << 6, 7);
+        for _ in 0..4 {
+            bits.write(0, 7).write(127, 7);
+        }
+        bits.write(0, 1).write(1, 1);
+        bits.write(0, 3).write(15, 4).write(8, 4);
+        for _ in 3..16 {
+            bits.write(0, 4);
+        }
+        let texels = decompress(Format::BC7_UNORM_BLOCK, [4, 4], &bits.block()).unwrap();
+        assert_eq!(&texels[0..4], &[0, 0, 0, 0]);
+        assert_eq!(&texels[4..8], &[255, 255, 255, 255]);
+        assert_eq!(&texels[8..12], &[135, 135, 135, 135]);
+    }
+
+    #[test]
+    fn bc7_mode1_uses_partition_subsets() {
+        let mut bits = BitWriter::default();
+        bits.write(1 << 1, 2).write(0, 6);
+        for _ in 0..3 {
+            bits.write(0, 6).write(0, 6).write(63, 6).write(63, 6);
+        }
+        bits.write(0, 1).write(1, 1);
+        bits.write(0, 46);
+        let texels = decompress(Format::BC7_SRGB_BLOCK, [4, 4], &bits.block()).unwrap();
+        assert_eq!(&texels[0..4], &[0, 0, 0, 255]);
+        assert_eq!(&texels[8..12], &[255, 255, 255, 255]);
+        assert_eq!(&texels[16..20], &[0, 0, 0, 255]);
+        assert_eq!(&texels[24..28], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn bc7_mode5_rotates_alpha_into_red() {
+        let mut bits = BitWriter::default();
+        bits.write(1 << 5, 6).write(1, 2);
+        bits.write(127, 7).write(127, 7);
+        bits.write(0, 28);
+        bits.write(0, 8).write(0, 8);
+        bits.write(0, 62);
+        let texels = decompress(Format::BC7_UNORM_BLOCK, [4, 4], &bits.block()).unwrap();
+        assert_eq!(&texels[0..4], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc7_anchors_lie_in_their_subsets() {
+        for partition in 0..64 {
+            assert_eq!(BC7_PARTITIONS2[partition] & 1, 0);
+            assert_eq!(BC7_PARTITIONS2[partition] >> BC7_ANCHORS2[partition] & 1, 1);
+            assert_eq!(BC7_PARTITIONS3[partition] & 3, 0);
+            for (subset, anchors) in (1..).zip(BC7_ANCHORS3) {
+                assert_eq!(
+                    BC7_PARTITIONS3[partition] >> (anchors[partition] * 2) & 3,
+                    subset
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bc7_reserved_mode_decodes_to_transparent_black() {
+        let texels = decompress(Format::BC7_UNORM_BLOCK, [4, 4], &[0; 16]).unwrap();
+        assert!(texels.iter().all(|&channel| channel == 0));
+    }
+}
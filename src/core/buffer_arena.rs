@@ -0,0 +1,180 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use vulkano::buffer::{BufferContents, BufferUsage, Subbuffer};
+use vulkano::sync::HostAccessError;
+use vulkano::DeviceSize;
+
+const ALIGNMENT: DeviceSize = 16;
+const DEFAULT_BLOCK_SIZE: DeviceSize = 4 * 1024 * 1024;
+
+struct Block {
+    buffer: Subbuffer<[u8]>,
+    free: Vec<Range<DeviceSize>>,
+}
+
+#[derive(Default)]
+struct ArenaState {
+    blocks: Vec<Block>,
+}
+
+impl ArenaState {
+    fn release(&mut self, block: usize, range: Range<DeviceSize>) {
+        let free = &mut self.blocks[block].free;
+        let index = free.partition_point(|r| r.start < range.start);
+        free.insert(index, range);
+        if index + 1 < free.len() && free[index].end == free[index + 1].start {
+            free[index].end = free[index + 1].end;
+            free.remove(index + 1);
+        }
+        if index > 0 && free[index - 1].end == free[index].start {
+            free[index - 1].end = free[index].end;
+            free.remove(index);
+        }
+    }
+}
+
+pub(crate) struct ArenaRange {
+    state: Arc<Mutex<ArenaState>>,
+    block: usize,
+    range: Range<DeviceSize>,
+}
+
+impl Drop for ArenaRange {
+    fn drop(&mut self) {
+        self.state
+            .lock()
+            .unwrap()
+            .release(self.block, self.range.clone());
+    }
+}
+
+pub struct ArenaBuffer<T> {
+    buffer: Subbuffer<[T]>,
+    range: Arc<ArenaRange>,
+}
+
+impl<T> Clone for ArenaBuffer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer.clone(),
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl<T> ArenaBuffer<T> {
+    pub fn buffer(&self) -> &Subbuffer<[T]> {
+        &self.buffer
+    }
+
+    pub(crate) fn into_parts(self) -> (Subbuffer<[T]>, Arc<ArenaRange>) {
+        (self.buffer, self.range)
+    }
+}
+
+pub struct BufferArena {
+    usage: BufferUsage,
+    block_size: DeviceSize,
+    state: Arc<Mutex<ArenaState>>,
+    gpu: Arc<Gpu>,
+}
+
+impl BufferArena {
+    pub fn new(gpu: Arc<Gpu>, usage: BufferUsage) -> Self {
+        Self {
+            usage,
+            block_size: DEFAULT_BLOCK_SIZE,
+            state: Default::default(),
+            gpu,
+        }
+    }
+
+    pub fn with_block_size(mut self, block_size: DeviceSize) -> Self {
+        self.block_size = block_size.next_multiple_of(ALIGNMENT);
+        self
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.state.lock().unwrap().blocks.len()
+    }
+
+    pub fn allocate<T, I>(&self, data: I) -> Result<ArenaBuffer<T>>
+    where
+        T: BufferContents,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let data = data.into_iter();
+        let size = (data.len() * size_of::<T>()) as DeviceSize;
+        if size == 0 {
+            return Err(EngineError::InvalidArgument(
+                "arena allocations must not be empty".into(),
+            ));
+        }
+        let reserved = size.next_multiple_of(ALIGNMENT);
+        let mut state = self.state.lock().unwrap();
+        let (block, offset) = match Self::find_free::<T>(&state, size, reserved)? {
+            Some(found) => found,
+            None => {
+                let buffer = self
+                    .gpu
+                    .create_arena_block(self.block_size.max(reserved), self.usage)?;
+                let len = buffer.len();
+                state.blocks.push(Block {
+                    buffer,
+                    free: std::iter::once(0..len).collect(),
+                });
+                (state.blocks.len() - 1, 0)
+            }
+        };
+        let free = &mut state.blocks[block].free;
+        let index = free.iter().position(|r| r.start == offset).unwrap();
+        free[index].start += reserved;
+        if free[index].is_empty() {
+            free.remove(index);
+        }
+        let buffer = state.blocks[block]
+            .buffer
+            .clone()
+            .slice(offset..offset + size)
+            .reinterpret::<[T]>();
+        for (slot, value) in buffer.write()?.iter_mut().zip(data) {
+            *slot = value;
+        }
+        Ok(ArenaBuffer {
+            buffer,
+            range: Arc::new(ArenaRange {
+                state: self.state.clone(),
+                block,
+                range: offset..offset + reserved,
+            }),
+        })
+    }
+
+    fn find_free<T: BufferContents>(
+        state: &ArenaState,
+        size: DeviceSize,
+        reserved: DeviceSize,
+    ) -> Result<Option<(usize, DeviceSize)>> {
+        for (index, block) in state.blocks.iter().enumerate() {
+            for range in &block.free {
+                if range.end - range.start < reserved {
+                    continue;
+                }
+                let candidate = block
+                    .buffer
+                    .clone()
+                    .slice(range.start..range.start + size)
+                    .reinterpret::<[T]>();
+                match candidate.write() {
+                    Ok(_) => return Ok(Some((index, range.start))),
+                    Err(HostAccessError::AccessConflict(_)) => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        Ok(None)
+    }
+}
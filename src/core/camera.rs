@@ -0,0 +1,130 @@
+use glam::{Mat4, Vec2, Vec3};
+use vulkano::buffer::BufferContents;
+
+pub(crate) const MAX_VIEWS: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "ecs", derive(bevy_ecs::component::Component))]
+pub struct Camera {
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub position: Vec3,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            view: Mat4::IDENTITY,
+            projection: Mat4::IDENTITY,
+            position: Vec3::ZERO,
+        }
+    }
+}
+
+impl Camera {
+    pub fn perspective(
+        position: Vec3,
+        target: Vec3,
+        fov_y: f32,
+        aspect_ratio: f32,
+        near: f32,
+        far: f32,
+    ) -> Self {
+        let mut projection = Mat4::perspective_rh(fov_y, aspect_ratio, near, far);
+        projection.y_axis.y *= -1.0;
+        Self {
+            view: Mat4::look_at_rh(position, target, Vec3::Y),
+            projection,
+            position,
+        }
+    }
+
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        Self {
+            view: Mat4::IDENTITY,
+            projection: Mat4::orthographic_rh(left, right, top, bottom, 0.0, 1.0),
+            position: Vec3::ZERO,
+        }
+    }
+
+    pub fn screen(physical_size: [u32; 2], scale_factor: f64) -> Self {
+        let [width, height] = physical_size.map(|extent| (extent as f64 / scale_factor) as f32);
+        Self::orthographic(0.0, width, height, 0.0)
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection * self.view
+    }
+
+    pub(crate) fn uniform(&self) -> CameraUniform {
+        let view_projection = self.view_projection();
+        CameraUniform {
+            view_projection: view_projection.to_cols_array_2d(),
+            inverse_view_projection: view_projection.inverse().to_cols_array_2d(),
+            position: self.position.extend(1.0).to_array(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera2D {
+    pub center: Vec2,
+    pub zoom: f32,
+    pub viewport: Vec2,
+}
+
+impl Camera2D {
+    pub fn new(viewport: Vec2) -> Self {
+        Self {
+            center: viewport / 2.0,
+            zoom: 1.0,
+            viewport,
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        let half = self.viewport / (2.0 * self.zoom);
+        Camera::orthographic(
+            self.center.x - half.x,
+            self.center.x + half.x,
+            self.center.y + half.y,
+            self.center.y - half.y,
+        )
+    }
+
+    pub fn screen_to_world(&self, screen: Vec2) -> Vec2 {
+        self.center + (screen - self.viewport / 2.0) / self.zoom
+    }
+
+    pub fn pan(&mut self, screen_delta: Vec2) {
+        self.center -= screen_delta / self.zoom;
+    }
+
+    pub fn zoom_at(&mut self, factor: f32, screen: Vec2) {
+        let anchor = self.screen_to_world(screen);
+        self.zoom = (self.zoom * factor).max(f32::EPSILON);
+        self.center = anchor - (screen - self.viewport / 2.0) / self.zoom;
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct CameraUniform {
+    view_projection: [[f32; 4]; 4],
+    inverse_view_projection: [[f32; 4]; 4],
+    position: [f32; 4],
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct MultiviewCameraUniform {
+    views: [CameraUniform; MAX_VIEWS],
+}
+
+impl MultiviewCameraUniform {
+    pub(crate) fn new(views: &[Camera], fallback: &Camera) -> Self {
+        Self {
+            views: std::array::from_fn(|index| views.get(index).unwrap_or(fallback).uniform()),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::render_graph::{RenderGraph, ResourceId};
+use half::f16;
+use image::RgbaImage;
+use vulkano::buffer::Subbuffer;
+use vulkano::command_buffer::CopyImageToBufferInfo;
+use vulkano::format::Format;
+use vulkano::image::ImageUsage;
+
+pub(crate) struct PendingCapture {
+    buffer: Subbuffer<[u8]>,
+    extent: [u32; 2],
+    format: Format,
+}
+
+impl PendingCapture {
+    pub(crate) fn record(
+        gpu: &Gpu,
+        graph: &mut RenderGraph,
+        target: ResourceId,
+        format: Format,
+        usage: ImageUsage,
+    ) -> Result<Self> {
+        if !usage.intersects(ImageUsage::TRANSFER_SRC) {
+            return Err(EngineError::Unsupported(
+                "the surface does not support copying from swapchain images".into(),
+            ));
+        }
+        let Some(block_size) = pixel_size(format) else {
+            return Err(EngineError::Unsupported(format!(
+                "capturing {format:?} images is not supported"
+            )));
+        };
+        let extent = graph.extent(target);
+        let buffer =
+            gpu.create_readback_buffer(extent[0] as u64 * extent[1] as u64 * block_size)?;
+        let destination = buffer.clone();
+        graph
+            .add_pass("capture")
+            .transfer_src(target)
+            .record(move |ctx| {
+                let image = ctx.image_view(target).image().clone();
+                let mut copy = CopyImageToBufferInfo::image_buffer(image, destination);
+                copy.regions[0].image_subresource.array_layers = 0..1;
+                ctx.builder.copy_image_to_buffer(copy)?;
+                Ok(())
+            });
+        Ok(Self {
+            buffer,
+            extent,
+            format,
+        })
+    }
+
+    pub(crate) fn into_image(self) -> Result<RgbaImage> {
+        let data = self.buffer.read()?;
+        let pixels = match self.format {
+            Format::R8G8B8A8_UNORM
+            | Format::R8G8B8A8_SRGB
+            | Format::A8B8G8R8_UNORM_PACK32
+            | Format::A8B8G8R8_SRGB_PACK32 => data.to_vec(),
+            Format::B8G8R8A8_UNORM | Format::B8G8R8A8_SRGB => data
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+                .collect(),
+            Format::A2B10G10R10_UNORM_PACK32 => data
+                .chunks_exact(4)
+                .flat_map(|pixel| {
+                    let packed = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                    let channel = |shift: u32| ((packed >> shift & 0x3ff) >> 2) as u8;
+                    [
+                        channel(0),
+                        channel(10),
+                        channel(20),
+                        ((packed >> 30) * 85) as u8,
+                    ]
+                })
+                .collect(),
+            Format::R16G16B16A16_SFLOAT => data
+                .chunks_exact(8)
+                .flat_map(|pixel| {
+                    let channel = |index: usize| {
+                        f16::from_le_bytes([pixel[index * 2], pixel[index * 2 + 1]])
+                            .to_f32()
+                            .clamp(0.0, 1.0)
+                    };
+                    let encode = |value: f32| (value * 255.0).round() as u8;
+                    [
+                        encode(linear_to_srgb(channel(0))),
+                        encode(linear_to_srgb(channel(1))),
+                        encode(linear_to_srgb(channel(2))),
+                        encode(channel(3)),
+                    ]
+                })
+                .collect(),
+            format => {
+                return Err(EngineError::Unsupported(format!(
+                    "capturing {format:?} images is not supported"
+                )))
+            }
+        };
+        RgbaImage::from_raw(self.extent[0], self.extent[1], pixels).ok_or_else(|| {
+            EngineError::InvalidArgument("captured image has an unexpected size".into())
+        })
+    }
+}
+
+fn pixel_size(format: Format) -> Option<u64> {
+    match format {
+        Format::R8G8B8A8_UNORM
+        | Format::R8G8B8A8_SRGB
+        | Format::A8B8G8R8_UNORM_PACK32
+        | Format::A8B8G8R8_SRGB_PACK32
+        | Format::B8G8R8A8_UNORM
+        | Format::B8G8R8A8_SRGB
+        | Format::A2B10G10R10_UNORM_PACK32 => Some(4),
+        Format::R16G16B16A16_SFLOAT => Some(8),
+        _ => None,
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,159 @@
+use crate::core::anti_aliasing::AntiAliasing;
+use crate::core::device_selector::DeviceSelector;
+use crate::core::driver::DriverConfig;
+use crate::core::error::{EngineError, Result};
+use crate::core::renderer::Renderer;
+use crate::core::upscaling::RenderScale;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::Path;
+use std::str::FromStr;
+
+const ENV_PREFIX: &str = "CODOTAKU_";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    pub device: Option<String>,
+    pub vsync: bool,
+    pub frames_in_flight: Option<u32>,
+    pub validation: bool,
+    pub render_scale: f32,
+    pub anti_aliasing: AntiAliasing,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            device: None,
+            vsync: true,
+            frames_in_flight: None,
+            validation: false,
+            render_scale: 1.0,
+            anti_aliasing: AntiAliasing::None,
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn from_toml(source: &str) -> Result<Self> {
+        Ok(toml::from_str(source)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self> {
+        Ok(ron::from_str(source)?)
+    }
+
+    pub fn to_ron(&self) -> Result<String> {
+        Ok(ron::ser::to_string_pretty(self, Default::default())?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        if is_ron(path) {
+            Self::from_ron(&source)
+        } else {
+            Self::from_toml(&source)
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let source = if is_ron(path) {
+            self.to_ron()?
+        } else {
+            self.to_toml()?
+        };
+        Ok(std::fs::write(path, source)?)
+    }
+
+    pub fn with_env_overrides(mut self) -> Result<Self> {
+        if let Some(device) = var("ADAPTER") {
+            self.device = Some(device);
+        }
+        if let Some(validation) = var("VALIDATION") {
+            self.validation = validation != "0";
+        }
+        if let Some(vsync) = parse_var("VSYNC")? {
+            self.vsync = vsync;
+        }
+        if let Some(frames_in_flight) = parse_var("FRAMES_IN_FLIGHT")? {
+            self.frames_in_flight = Some(frames_in_flight);
+        }
+        if let Some(render_scale) = parse_var("RENDER_SCALE")? {
+            self.render_scale = render_scale;
+        }
+        if let Some(anti_aliasing) = var("ANTI_ALIASING") {
+            self.anti_aliasing = match anti_aliasing.to_lowercase().as_str() {
+                "none" => AntiAliasing::None,
+                "fxaa" => AntiAliasing::Fxaa,
+                "taa" => AntiAliasing::Taa,
+                _ => {
+                    return Err(EngineError::InvalidArgument(format!(
+                        "{ENV_PREFIX}ANTI_ALIASING must be none, fxaa or taa, got {anti_aliasing:?}"
+                    )));
+                }
+            };
+        }
+        Ok(self)
+    }
+
+    pub fn driver_config(&self) -> DriverConfig {
+        let config = DriverConfig::default();
+        if self.validation {
+            config.with_validation(true)
+        } else {
+            config
+        }
+    }
+
+    pub fn device_selector(&self) -> DeviceSelector {
+        match &self.device {
+            Some(device) => DeviceSelector::new().with_override(device),
+            None => DeviceSelector::new(),
+        }
+    }
+
+    pub fn image_count(&self) -> Option<u32> {
+        self.frames_in_flight
+            .map(|frames_in_flight| frames_in_flight.max(1) + 1)
+    }
+
+    pub fn render_scale(&self) -> Option<RenderScale> {
+        (self.render_scale < 1.0).then(|| RenderScale::new(self.render_scale))
+    }
+
+    pub fn apply(&self, renderer: &Renderer) -> Result<()> {
+        renderer.set_anti_aliasing(self.anti_aliasing)?;
+        if self.render_scale < 1.0 {
+            renderer.set_render_scale(self.render_scale())?;
+        }
+        Ok(())
+    }
+}
+
+fn is_ron(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "ron")
+}
+
+fn var(name: &str) -> Option<String> {
+    env::var(format!("{ENV_PREFIX}{name}"))
+        .ok()
+        .map(|value| value.trim().to_owned())
+        .filter(|value| !value.is_empty())
+}
+
+fn parse_var<T: FromStr>(name: &str) -> Result<Option<T>> {
+    var(name)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                EngineError::InvalidArgument(format!("invalid {ENV_PREFIX}{name}: {value:?}"))
+            })
+        })
+        .transpose()
+}
@@ -0,0 +1,175 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use glam::Vec3;
+use half::f16;
+use image::Rgba32FImage;
+use std::f32::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+use vulkano::command_buffer::CopyBufferToImageInfo;
+use vulkano::format::Format;
+use vulkano::image::view::{ImageView, ImageViewCreateInfo, ImageViewType};
+use vulkano::image::{Image, ImageUsage};
+
+pub const CUBEMAP_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+#[derive(Clone)]
+pub struct Cubemap {
+    image_view: Arc<ImageView>,
+}
+
+impl Cubemap {
+    pub fn from_faces(gpu: Arc<Gpu>, size: u32, format: Format, pixels: &[u8]) -> Result<Self> {
+        let image = gpu.create_cubemap(
+            format,
+            size,
+            1,
+            ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+        )?;
+        let staging = gpu.create_staging_buffer(pixels.iter().copied())?;
+        gpu.immediate(|builder| {
+            builder.copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
+                staging,
+                image.clone(),
+            ))?;
+            Ok(())
+        })?;
+        Self::from_image(image)
+    }
+
+    pub(crate) fn from_image(image: Arc<Image>) -> Result<Self> {
+        let image_view = ImageView::new(
+            image.clone(),
+            ImageViewCreateInfo {
+                view_type: ImageViewType::Cube,
+                ..ImageViewCreateInfo::from_image(&image)
+            },
+        )?;
+        Ok(Self { image_view })
+    }
+
+    pub fn from_equirectangular(gpu: Arc<Gpu>, image: &Rgba32FImage, size: u32) -> Result<Self> {
+        let pixels = faces(size, |direction| sample_equirectangular(image, direction));
+        Self::from_faces(gpu, size, CUBEMAP_FORMAT, &pixels)
+    }
+
+    pub fn from_cross(gpu: Arc<Gpu>, image: &Rgba32FImage) -> Result<Self> {
+        let (width, height) = image.dimensions();
+        let (size, layout) = if width * 3 == height * 4 {
+            (width / 4, &HORIZONTAL_CROSS)
+        } else if width * 4 == height * 3 {
+            (width / 3, &VERTICAL_CROSS)
+        } else {
+            return Err(EngineError::InvalidAsset(format!(
+                "{width}x{height} is not a 4:3 or 3:4 cubemap cross"
+            )));
+        };
+
+        let mut texels = Vec::with_capacity((size * size * 6) as usize);
+        for &(column, row, flip) in layout {
+            for y in 0..size {
+                for x in 0..size {
+                    let (x, y) = if flip {
+                        (size - 1 - x, size - 1 - y)
+                    } else {
+                        (x, y)
+                    };
+                    texels.push(image.get_pixel(column * size + x, row * size + y).0);
+                }
+            }
+        }
+        Self::from_faces(gpu, size, CUBEMAP_FORMAT, &encode(texels))
+    }
+
+    pub fn from_path(gpu: Arc<Gpu>, path: impl AsRef<Path>, size: u32) -> Result<Self> {
+        let image = image::open(path)?.into_rgba32f();
+        if image.width() == image.height() * 2 {
+            Self::from_equirectangular(gpu, &image, size)
+        } else {
+            Self::from_cross(gpu, &image)
+        }
+    }
+
+    pub fn image_view(&self) -> Arc<ImageView> {
+        self.image_view.clone()
+    }
+}
+
+const HORIZONTAL_CROSS: [(u32, u32, bool); 6] = [
+    (2, 1, false),
+    (0, 1, false),
+    (1, 0, false),
+    (1, 2, false),
+    (1, 1, false),
+    (3, 1, false),
+];
+
+const VERTICAL_CROSS: [(u32, u32, bool); 6] = [
+    (2, 1, false),
+    (0, 1, false),
+    (1, 0, false),
+    (1, 2, false),
+    (1, 1, false),
+    (1, 3, true),
+];
+
+pub(crate) fn face_direction(face: usize, u: f32, v: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1.0, -v, -u),
+        1 => Vec3::new(-1.0, -v, u),
+        2 => Vec3::new(u, 1.0, v),
+        3 => Vec3::new(u, -1.0, -v),
+        4 => Vec3::new(u, -v, 1.0),
+        _ => Vec3::new(-u, -v, -1.0),
+    }
+    .normalize()
+}
+
+fn faces(size: u32, mut sample: impl FnMut(Vec3) -> [f32; 4]) -> Vec<u8> {
+    let mut texels = Vec::with_capacity((size * size * 6) as usize);
+    for face in 0..6 {
+        for y in 0..size {
+            for x in 0..size {
+                let u = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+                let v = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+                texels.push(sample(face_direction(face, u, v)));
+            }
+        }
+    }
+    encode(texels)
+}
+
+fn encode(texels: Vec<[f32; 4]>) -> Vec<u8> {
+    let halves: Vec<u16> = texels
+        .into_iter()
+        .flatten()
+        .map(|c| f16::from_f32(c).to_bits())
+        .collect();
+    bytemuck::cast_slice(&halves).to_vec()
+}
+
+fn sample_equirectangular(image: &Rgba32FImage, direction: Vec3) -> [f32; 4] {
+    let (width, height) = image.dimensions();
+    let s = 0.5 + direction.z.atan2(direction.x) / (2.0 * PI);
+    let t = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    let x = s * width as f32 - 0.5;
+    let y = (t * height as f32 - 0.5).clamp(0.0, height as f32 - 1.0);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let texel = |x: f32, y: f32| {
+        let x = (x as i64).rem_euclid(width as i64) as u32;
+        let y = (y as u32).min(height - 1);
+        image.get_pixel(x, y).0
+    };
+    let (a, b, c, d) = (
+        texel(x0, y0),
+        texel(x0 + 1.0, y0),
+        texel(x0, y0 + 1.0),
+        texel(x0 + 1.0, y0 + 1.0),
+    );
+    std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * fx;
+        let bottom = c[i] + (d[i] - c[i]) * fx;
+        top + (bottom - top) * fy
+    })
+}
@@ -0,0 +1,205 @@
+use crate::core::camera::Camera;
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::material::Material;
+use crate::core::vertex::Vertex3D;
+use glam::{Mat4, Vec3, Vec4};
+use std::sync::Arc;
+use vulkano::buffer::{BufferContents, BufferUsage, Subbuffer};
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilder, DrawIndexedIndirectCommand, PrimaryAutoCommandBuffer,
+};
+use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::pipeline::{ComputePipeline, Pipeline, PipelineBindPoint, PipelineLayout};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct CullObject {
+    model: [[f32; 4]; 4],
+    bounds: [f32; 4],
+    range: [u32; 4],
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct CullConstants {
+    planes: [[f32; 4]; 6],
+    object_count: u32,
+}
+
+pub struct CulledMesh<'a> {
+    pub vertices: &'a [Vertex3D],
+    pub indices: &'a [u32],
+}
+
+#[derive(Clone)]
+pub struct CulledBatch {
+    vertex_buffer: Subbuffer<[Vertex3D]>,
+    index_buffer: Subbuffer<[u32]>,
+    objects: Subbuffer<[CullObject]>,
+    commands: Subbuffer<[DrawIndexedIndirectCommand]>,
+    pub material: Material,
+}
+
+impl CulledBatch {
+    pub fn new(
+        gpu: Arc<Gpu>,
+        meshes: &[CulledMesh],
+        instances: impl IntoIterator<Item = (usize, Mat4)>,
+        material: Material,
+    ) -> Result<Self> {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut ranges = Vec::with_capacity(meshes.len());
+        for mesh in meshes {
+            let (center, radius) = bounding_sphere(mesh.vertices);
+            ranges.push((
+                center.extend(radius).to_array(),
+                [
+                    mesh.indices.len() as u32,
+                    indices.len() as u32,
+                    vertices.len() as u32,
+                    0,
+                ],
+            ));
+            vertices.extend_from_slice(mesh.vertices);
+            indices.extend_from_slice(mesh.indices);
+        }
+        let mut objects = Vec::new();
+        for (mesh, transform) in instances {
+            let Some(&(bounds, range)) = ranges.get(mesh) else {
+                return Err(EngineError::InvalidArgument(format!(
+                    "instance references mesh {mesh} but the batch has {}",
+                    meshes.len()
+                )));
+            };
+            objects.push(CullObject {
+                model: transform.to_cols_array_2d(),
+                bounds,
+                range,
+            });
+        }
+        if vertices.is_empty() || objects.is_empty() {
+            return Err(EngineError::InvalidArgument(
+                "a culled batch needs at least one mesh and one instance".into(),
+            ));
+        }
+        let commands = vec![DrawIndexedIndirectCommand::default(); objects.len()];
+        Ok(Self {
+            vertex_buffer: gpu.create_device_local_buffer(vertices, BufferUsage::VERTEX_BUFFER)?,
+            index_buffer: gpu.create_device_local_buffer(indices, BufferUsage::INDEX_BUFFER)?,
+            objects: gpu.create_buffer(objects, BufferUsage::STORAGE_BUFFER)?,
+            commands: gpu.create_buffer(
+                commands,
+                BufferUsage::STORAGE_BUFFER | BufferUsage::INDIRECT_BUFFER,
+            )?,
+            material,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.len() == 0
+    }
+
+    pub fn set_transform(&self, index: usize, transform: Mat4) -> Result<()> {
+        let mut objects = self.objects.write()?;
+        let count = objects.len();
+        let Some(object) = objects.get_mut(index) else {
+            return Err(EngineError::InvalidArgument(format!(
+                "instance {index} is out of range, the batch has {count}"
+            )));
+        };
+        object.model = transform.to_cols_array_2d();
+        Ok(())
+    }
+
+    pub(crate) fn record_cull(
+        &self,
+        gpu: &Gpu,
+        pipeline: &Arc<ComputePipeline>,
+        camera: &Camera,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<()> {
+        let layout = pipeline.layout().clone();
+        let set = gpu.create_frame_descriptor_set(
+            layout.set_layouts()[0].clone(),
+            [
+                WriteDescriptorSet::buffer(0, self.objects.clone()),
+                WriteDescriptorSet::buffer(1, self.commands.clone()),
+            ],
+        )?;
+        let object_count = self.objects.len() as u32;
+        builder
+            .bind_pipeline_compute(pipeline.clone())?
+            .bind_descriptor_sets(PipelineBindPoint::Compute, layout.clone(), 0, set)?
+            .push_constants(
+                layout,
+                0,
+                CullConstants {
+                    planes: frustum_planes(camera.view_projection()),
+                    object_count,
+                },
+            )?;
+        unsafe { builder.dispatch([object_count.div_ceil(WORKGROUP_SIZE), 1, 1]) }?;
+        Ok(())
+    }
+
+    pub(crate) fn record_draw(
+        &self,
+        gpu: &Gpu,
+        layout: &Arc<PipelineLayout>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<()> {
+        let features = gpu.device().enabled_features();
+        if !features.draw_indirect_first_instance {
+            return Err(EngineError::Unsupported(
+                "culled batches require the drawIndirectFirstInstance feature".into(),
+            ));
+        }
+        let objects_set = gpu.create_frame_descriptor_set(
+            layout.set_layouts()[2].clone(),
+            [WriteDescriptorSet::buffer(0, self.objects.clone())],
+        )?;
+        builder
+            .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 2, objects_set)?
+            .bind_vertex_buffers(0, self.vertex_buffer.clone())?
+            .bind_index_buffer(self.index_buffer.clone())?;
+        if features.multi_draw_indirect {
+            unsafe { builder.draw_indexed_indirect(self.commands.clone()) }?;
+        } else {
+            for index in 0..self.commands.len() {
+                let command = self.commands.clone().slice(index..index + 1);
+                unsafe { builder.draw_indexed_indirect(command) }?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn bounding_sphere(vertices: &[Vertex3D]) -> (Vec3, f32) {
+    let (min, max) = vertices.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), vertex| {
+            let position = Vec3::from(vertex.position);
+            (min.min(position), max.max(position))
+        },
+    );
+    let center = (min + max) * 0.5;
+    let radius = vertices
+        .iter()
+        .map(|vertex| Vec3::from(vertex.position).distance(center))
+        .fold(0.0, f32::max);
+    (center, radius)
+}
+
+fn frustum_planes(view_projection: Mat4) -> [[f32; 4]; 6] {
+    let [x, y, z, w] = [0, 1, 2, 3].map(|i| view_projection.row(i));
+    let normalize = |plane: Vec4| (plane / plane.truncate().length()).to_array();
+    [w + x, w - x, w + y, w - y, z, w - z].map(normalize)
+}
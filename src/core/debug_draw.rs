@@ -0,0 +1,221 @@
+use crate::core::camera::Camera;
+use crate::core::error::Result;
+use crate::core::glyphs;
+use crate::core::gpu::Gpu;
+use crate::core::resource_tracker::GpuResourceKind;
+use crate::core::shaders::{debug_fs, debug_vs};
+use crate::core::vertex::DebugVertex;
+use glam::{Mat4, Vec2, Vec3};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use vulkano::buffer::BufferContents;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer};
+use vulkano::format::Format;
+use vulkano::pipeline::graphics::color_blend::{
+    AttachmentBlend, ColorBlendAttachmentState, ColorBlendState,
+};
+use vulkano::pipeline::graphics::depth_stencil::{CompareOp, DepthState, DepthStencilState};
+use vulkano::pipeline::graphics::input_assembly::{InputAssemblyState, PrimitiveTopology};
+use vulkano::pipeline::graphics::multisample::MultisampleState;
+use vulkano::pipeline::graphics::rasterization::RasterizationState;
+use vulkano::pipeline::graphics::subpass::PipelineRenderingCreateInfo;
+use vulkano::pipeline::graphics::vertex_input::{Vertex as VertexTrait, VertexDefinition};
+use vulkano::pipeline::graphics::viewport::ViewportState;
+use vulkano::pipeline::graphics::GraphicsPipelineCreateInfo;
+use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
+use vulkano::pipeline::{
+    DynamicState, GraphicsPipeline, Pipeline, PipelineLayout, PipelineShaderStageCreateInfo,
+};
+
+const CIRCLE_SEGMENTS: usize = 32;
+#[derive(Clone, Debug, Default)]
+pub struct DebugDraw {
+    vertices: Vec<DebugVertex>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn line(&mut self, start: Vec3, end: Vec3, color: [f32; 4]) {
+        self.vertices.extend([
+            DebugVertex {
+                position: start.to_array(),
+                color,
+            },
+            DebugVertex {
+                position: end.to_array(),
+                color,
+            },
+        ]);
+    }
+
+    pub fn rect(&mut self, min: Vec2, max: Vec2, color: [f32; 4]) {
+        let corners = [
+            Vec3::new(min.x, min.y, 0.0),
+            Vec3::new(max.x, min.y, 0.0),
+            Vec3::new(max.x, max.y, 0.0),
+            Vec3::new(min.x, max.y, 0.0),
+        ];
+        for i in 0..4 {
+            self.line(corners[i], corners[(i + 1) % 4], color);
+        }
+    }
+
+    pub fn circle(&mut self, center: Vec3, normal: Vec3, radius: f32, color: [f32; 4]) {
+        let (tangent, bitangent) = normal.normalize_or(Vec3::Z).any_orthonormal_pair();
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + (tangent * angle.cos() + bitangent * angle.sin()) * radius
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    pub fn aabb(&mut self, min: Vec3, max: Vec3, color: [f32; 4]) {
+        let corner = |i: usize| {
+            Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        for i in 0..8 {
+            for axis in [1, 2, 4] {
+                if i & axis == 0 {
+                    self.line(corner(i), corner(i | axis), color);
+                }
+            }
+        }
+    }
+
+    pub fn axis(&mut self, transform: Mat4, length: f32) {
+        let origin = transform.transform_point3(Vec3::ZERO);
+        for (axis, color) in [
+            (Vec3::X, [1.0, 0.0, 0.0, 1.0]),
+            (Vec3::Y, [0.0, 1.0, 0.0, 1.0]),
+            (Vec3::Z, [0.0, 0.0, 1.0, 1.0]),
+        ] {
+            self.line(origin, transform.transform_point3(axis * length), color);
+        }
+    }
+
+    pub fn text(&mut self, position: Vec3, size: f32, text: &str, color: [f32; 4]) {
+        for [start, end] in glyphs::segments(text, size) {
+            self.line(
+                position + Vec3::new(start[0], start[1], 0.0),
+                position + Vec3::new(end[0], end[1], 0.0),
+                color,
+            );
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct DebugConstants {
+    view_projection: [[f32; 4]; 4],
+}
+
+pub(crate) struct DebugDrawPipeline {
+    pipeline: Arc<GraphicsPipeline>,
+    gpu: Arc<Gpu>,
+}
+
+impl DebugDrawPipeline {
+    pub(crate) fn new(
+        gpu: Arc<Gpu>,
+        image_format: Format,
+        depth_format: Option<Format>,
+    ) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = debug_vs::load(device.clone())?.entry_point("main").unwrap();
+        let fs = debug_fs::load(device.clone())?.entry_point("main").unwrap();
+        let vertex_input_state = DebugVertex::per_vertex().definition(&vs)?;
+        let stages = [
+            PipelineShaderStageCreateInfo::new(vs),
+            PipelineShaderStageCreateInfo::new(fs),
+        ];
+        let layout = PipelineLayout::new(
+            device.clone(),
+            PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+                .into_pipeline_layout_create_info(device.clone())
+                .unwrap(),
+        )?;
+        let subpass = PipelineRenderingCreateInfo {
+            color_attachment_formats: vec![Some(image_format)],
+            depth_attachment_format: depth_format,
+            ..Default::default()
+        };
+        let pipeline = GraphicsPipeline::new(
+            device,
+            None,
+            GraphicsPipelineCreateInfo {
+                stages: stages.into_iter().collect(),
+                vertex_input_state: Some(vertex_input_state),
+                input_assembly_state: Some(InputAssemblyState {
+                    topology: PrimitiveTopology::LineList,
+                    ..Default::default()
+                }),
+                viewport_state: Some(ViewportState::default()),
+                rasterization_state: Some(RasterizationState::default()),
+                depth_stencil_state: depth_format.map(|_| DepthStencilState {
+                    depth: Some(DepthState {
+                        write_enable: false,
+                        compare_op: CompareOp::LessOrEqual,
+                    }),
+                    ..Default::default()
+                }),
+                multisample_state: Some(MultisampleState::default()),
+                color_blend_state: Some(ColorBlendState::with_attachment_states(
+                    1,
+                    ColorBlendAttachmentState {
+                        blend: Some(AttachmentBlend::alpha()),
+                        ..Default::default()
+                    },
+                )),
+                dynamic_state: [DynamicState::Viewport].into_iter().collect(),
+                subpass: Some(subpass.into()),
+                ..GraphicsPipelineCreateInfo::layout(layout)
+            },
+        )?;
+        gpu.track_resource(GpuResourceKind::Pipeline, &pipeline, "debug draw", 0);
+        Ok(Self { pipeline, gpu })
+    }
+
+    pub(crate) fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        camera: &Camera,
+        debug_draw: DebugDraw,
+    ) -> Result<()> {
+        if debug_draw.is_empty() {
+            return Ok(());
+        }
+        let vertex_count = debug_draw.vertices.len() as u32;
+        let vertex_buffer = self.gpu.frame_staging(debug_draw.vertices)?;
+        let layout = self.pipeline.layout().clone();
+        builder
+            .bind_pipeline_graphics(self.pipeline.clone())?
+            .push_constants(
+                layout,
+                0,
+                DebugConstants {
+                    view_projection: camera.view_projection().to_cols_array_2d(),
+                },
+            )?
+            .bind_vertex_buffers(0, vertex_buffer)?;
+        unsafe { builder.draw(vertex_count, 1, 0, 0) }?;
+        Ok(())
+    }
+}
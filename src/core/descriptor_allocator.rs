@@ -0,0 +1,114 @@
+use crate::core::error::Result;
+use crate::core::frame_pool::FRAMES_IN_FLIGHT;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use vulkano::descriptor_set::allocator::{
+    StandardDescriptorSetAllocator, StandardDescriptorSetAllocatorCreateInfo,
+};
+use vulkano::descriptor_set::layout::DescriptorSetLayout;
+use vulkano::descriptor_set::{DescriptorSet, WriteDescriptorSet};
+use vulkano::device::Device;
+
+const PERSISTENT_SETS_PER_POOL: usize = 256;
+const CACHE_LIFETIME: u64 = 120;
+
+struct CachedSet {
+    set: Arc<DescriptorSet>,
+    last_used: u64,
+}
+
+pub(crate) struct DescriptorAllocator {
+    persistent: Arc<StandardDescriptorSetAllocator>,
+    frames: Vec<Arc<StandardDescriptorSetAllocator>>,
+    cache: Mutex<HashMap<Vec<usize>, CachedSet>>,
+    frame: AtomicU64,
+}
+
+impl DescriptorAllocator {
+    pub(crate) fn new(device: &Arc<Device>) -> Self {
+        Self {
+            persistent: Arc::new(StandardDescriptorSetAllocator::new(
+                device.clone(),
+                StandardDescriptorSetAllocatorCreateInfo {
+                    set_count: PERSISTENT_SETS_PER_POOL,
+                    ..Default::default()
+                },
+            )),
+            frames: (0..FRAMES_IN_FLIGHT)
+                .map(|_| {
+                    Arc::new(StandardDescriptorSetAllocator::new(
+                        device.clone(),
+                        Default::default(),
+                    ))
+                })
+                .collect(),
+            cache: Mutex::new(HashMap::new()),
+            frame: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn persistent(
+        &self,
+        layout: Arc<DescriptorSetLayout>,
+        writes: impl IntoIterator<Item = WriteDescriptorSet>,
+    ) -> Result<Arc<DescriptorSet>> {
+        Ok(DescriptorSet::new(
+            self.persistent.clone(),
+            layout,
+            writes,
+            [],
+        )?)
+    }
+
+    pub(crate) fn transient(
+        &self,
+        layout: Arc<DescriptorSetLayout>,
+        writes: impl IntoIterator<Item = WriteDescriptorSet>,
+    ) -> Result<Arc<DescriptorSet>> {
+        let frame = self.frame.load(Ordering::Relaxed) as usize % self.frames.len();
+        Ok(DescriptorSet::new(
+            self.frames[frame].clone(),
+            layout,
+            writes,
+            [],
+        )?)
+    }
+
+    pub(crate) fn cached<I>(
+        &self,
+        layout: Arc<DescriptorSetLayout>,
+        resources: &[usize],
+        writes: impl FnOnce() -> I,
+    ) -> Result<Arc<DescriptorSet>>
+    where
+        I: IntoIterator<Item = WriteDescriptorSet>,
+    {
+        let frame = self.frame.load(Ordering::Relaxed);
+        let mut key = Vec::with_capacity(resources.len() + 1);
+        key.push(Arc::as_ptr(&layout) as usize);
+        key.extend_from_slice(resources);
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(cached) = cache.get_mut(&key) {
+            cached.last_used = frame;
+            return Ok(cached.set.clone());
+        }
+        let set = self.persistent(layout, writes())?;
+        cache.insert(
+            key,
+            CachedSet {
+                set: set.clone(),
+                last_used: frame,
+            },
+        );
+        Ok(set)
+    }
+
+    pub(crate) fn end_frame(&self) {
+        let frame = self.frame.fetch_add(1, Ordering::Relaxed) + 1;
+        self.cache
+            .lock()
+            .unwrap()
+            .retain(|_, cached| frame - cached.last_used < CACHE_LIFETIME);
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt::Write;
+use vulkano::device::physical::{PhysicalDevice, PhysicalDeviceType};
+use vulkano::device::DeviceFeatures;
+use vulkano::memory::MemoryHeapFlags;
+use vulkano::{DeviceSize, Version};
+
+const ADAPTER_VAR: &str = "CODOTAKU_ADAPTER";
+
+#[derive(Clone, Debug)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub device_type: PhysicalDeviceType,
+    pub api_version: Version,
+    pub driver_name: Option<String>,
+    pub device_uuid: Option<[u8; 16]>,
+    pub device_luid: Option<[u8; 8]>,
+    pub vram: DeviceSize,
+}
+
+impl AdapterInfo {
+    pub(crate) fn new(physical_device: &PhysicalDevice) -> Self {
+        let properties = physical_device.properties();
+        let vram = physical_device
+            .memory_properties()
+            .memory_heaps
+            .iter()
+            .filter(|heap| heap.flags.intersects(MemoryHeapFlags::DEVICE_LOCAL))
+            .map(|heap| heap.size)
+            .sum();
+        Self {
+            name: properties.device_name.clone(),
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            device_type: properties.device_type,
+            api_version: physical_device.api_version(),
+            driver_name: properties.driver_name.clone(),
+            device_uuid: properties.device_uuid,
+            device_luid: properties.device_luid,
+            vram,
+        }
+    }
+
+    pub fn uuid_string(&self) -> Option<String> {
+        self.device_uuid.map(|uuid| hex(&uuid))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DeviceSelector {
+    name: Option<String>,
+    vendor_id: Option<u32>,
+    device_uuid: Option<[u8; 16]>,
+    device_luid: Option<[u8; 8]>,
+    min_vram: DeviceSize,
+    required_features: DeviceFeatures,
+}
+
+impl DeviceSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into().to_lowercase());
+        self
+    }
+
+    pub fn with_vendor(mut self, vendor_id: u32) -> Self {
+        self.vendor_id = Some(vendor_id);
+        self
+    }
+
+    pub fn with_uuid(mut self, device_uuid: [u8; 16]) -> Self {
+        self.device_uuid = Some(device_uuid);
+        self
+    }
+
+    pub fn with_luid(mut self, device_luid: [u8; 8]) -> Self {
+        self.device_luid = Some(device_luid);
+        self
+    }
+
+    pub fn with_min_vram(mut self, min_vram: DeviceSize) -> Self {
+        self.min_vram = min_vram;
+        self
+    }
+
+    pub fn with_features(mut self, required_features: DeviceFeatures) -> Self {
+        self.required_features = required_features;
+        self
+    }
+
+    pub(crate) fn required_features(&self) -> &DeviceFeatures {
+        &self.required_features
+    }
+
+    pub fn with_env_override(self) -> Self {
+        match std::env::var(ADAPTER_VAR) {
+            Ok(value) if !value.trim().is_empty() => self.with_override(value.trim()),
+            _ => self,
+        }
+    }
+
+    pub(crate) fn with_override(self, value: &str) -> Self {
+        match parse_uuid(value) {
+            Some(device_uuid) => self.with_uuid(device_uuid),
+            None => self.with_name(value),
+        }
+    }
+
+    pub fn matches(&self, physical_device: &PhysicalDevice) -> bool {
+        let info = AdapterInfo::new(physical_device);
+        self.name
+            .as_ref()
+            .is_none_or(|name| info.name.to_lowercase().contains(name))
+            && self.vendor_id.is_none_or(|id| info.vendor_id == id)
+            && self
+                .device_uuid
+                .is_none_or(|uuid| info.device_uuid == Some(uuid))
+            && self
+                .device_luid
+                .is_none_or(|luid| info.device_luid == Some(luid))
+            && info.vram >= self.min_vram
+            && physical_device
+                .supported_features()
+                .contains(&self.required_features)
+    }
+}
+
+fn parse_uuid(value: &str) -> Option<[u8; 16]> {
+    let digits: String = value.chars().filter(|c| *c != '-').collect();
+    if digits.len() != 32 {
+        return None;
+    }
+    let mut uuid = [0; 16];
+    for (byte, chunk) in uuid.iter_mut().zip(digits.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+    }
+    Some(uuid)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
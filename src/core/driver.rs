@@ -1,29 +1,204 @@
+use crate::core::device_selector::{AdapterInfo, DeviceSelector};
+use crate::core::error::Result;
+use log::{Level, LevelFilter};
 use std::any::Any;
+use std::env;
 use std::sync::Arc;
 use vulkano::device::physical::{PhysicalDevice, PhysicalDeviceType};
 use vulkano::device::{
     Device, DeviceCreateInfo, DeviceExtensions, DeviceFeatures, Queue, QueueCreateInfo, QueueFlags,
 };
-use vulkano::instance::{Instance, InstanceCreateFlags, InstanceCreateInfo};
+use vulkano::instance::debug::{
+    DebugUtilsMessageSeverity, DebugUtilsMessageType, DebugUtilsMessenger,
+    DebugUtilsMessengerCallback, DebugUtilsMessengerCreateInfo,
+};
+use vulkano::instance::{Instance, InstanceCreateFlags, InstanceCreateInfo, InstanceExtensions};
 use vulkano::swapchain::{FromWindowError, Surface};
 use vulkano::{Validated, Version, VulkanError, VulkanLibrary};
 use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
+const VALIDATION_LAYER: &str = "VK_LAYER_KHRONOS_validation";
+const VALIDATION_ENV: &str = "CODOTAKU_VALIDATION";
+
+#[derive(Clone, Copy, Debug)]
+pub struct DriverConfig {
+    validation: bool,
+    debug_utils: bool,
+    min_level: LevelFilter,
+    instance_extensions: InstanceExtensions,
+    device_extensions: DeviceExtensions,
+}
+
+impl Default for DriverConfig {
+    fn default() -> Self {
+        let validation = env::var(VALIDATION_ENV).is_ok_and(|value| value != "0");
+        Self {
+            validation,
+            debug_utils: validation,
+            min_level: LevelFilter::Warn,
+            instance_extensions: InstanceExtensions::empty(),
+            device_extensions: DeviceExtensions::empty(),
+        }
+    }
+}
+
+impl DriverConfig {
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self.debug_utils |= validation;
+        self
+    }
+
+    pub fn with_debug_utils(mut self, debug_utils: bool) -> Self {
+        self.debug_utils = debug_utils;
+        self
+    }
+
+    pub fn with_min_level(mut self, min_level: LevelFilter) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    pub fn with_instance_extensions(mut self, extensions: InstanceExtensions) -> Self {
+        self.instance_extensions |= extensions;
+        self
+    }
+
+    pub fn with_device_extensions(mut self, extensions: DeviceExtensions) -> Self {
+        self.device_extensions |= extensions;
+        self
+    }
+
+    fn message_severity(&self) -> DebugUtilsMessageSeverity {
+        let mut severity = DebugUtilsMessageSeverity::empty();
+        if self.min_level >= LevelFilter::Error {
+            severity |= DebugUtilsMessageSeverity::ERROR;
+        }
+        if self.min_level >= LevelFilter::Warn {
+            severity |= DebugUtilsMessageSeverity::WARNING;
+        }
+        if self.min_level >= LevelFilter::Info {
+            severity |= DebugUtilsMessageSeverity::INFO;
+        }
+        if self.min_level >= LevelFilter::Debug {
+            severity |= DebugUtilsMessageSeverity::VERBOSE;
+        }
+        severity
+    }
+
+    fn wants_messenger(&self) -> bool {
+        self.validation && !self.message_severity().is_empty()
+    }
+
+    fn messenger_create_info(&self) -> DebugUtilsMessengerCreateInfo {
+        let callback = unsafe {
+            DebugUtilsMessengerCallback::new(|severity, ty, data| {
+                let level = if severity.intersects(DebugUtilsMessageSeverity::ERROR) {
+                    Level::Error
+                } else if severity.intersects(DebugUtilsMessageSeverity::WARNING) {
+                    Level::Warn
+                } else if severity.intersects(DebugUtilsMessageSeverity::INFO) {
+                    Level::Info
+                } else {
+                    Level::Debug
+                };
+                log::log!(
+                    target: "vulkan",
+                    level,
+                    "[{ty:?}] {}: {}",
+                    data.message_id_name.unwrap_or("unknown"),
+                    data.message
+                );
+            })
+        };
+        DebugUtilsMessengerCreateInfo {
+            message_severity: self.message_severity(),
+            message_type: DebugUtilsMessageType::GENERAL
+                | DebugUtilsMessageType::VALIDATION
+                | DebugUtilsMessageType::PERFORMANCE,
+            ..DebugUtilsMessengerCreateInfo::user_callback(callback)
+        }
+    }
+}
+
 pub struct Driver {
     pub(crate) instance: Arc<Instance>,
+    device_extensions: DeviceExtensions,
+    _messenger: Option<DebugUtilsMessenger>,
 }
 
 impl Driver {
-    pub fn new(display: impl HasDisplayHandle) -> anyhow::Result<Self> {
+    pub fn new(display: impl HasDisplayHandle) -> Result<Self> {
+        Self::with_config(display, DriverConfig::default())
+    }
+
+    pub fn with_config(display: impl HasDisplayHandle, config: DriverConfig) -> Result<Self> {
+        Self::create(Surface::required_extensions(&display)?, config)
+    }
+
+    pub fn headless() -> Result<Self> {
+        Self::headless_with_config(DriverConfig::default())
+    }
+
+    pub fn headless_with_config(config: DriverConfig) -> Result<Self> {
+        Self::create(InstanceExtensions::empty(), config)
+    }
+
+    fn create(mut enabled_extensions: InstanceExtensions, config: DriverConfig) -> Result<Self> {
+        let library = VulkanLibrary::new()?;
+        enabled_extensions |= config.instance_extensions;
+        let mut enabled_layers = Vec::new();
+        let mut debug_utils_messengers = Vec::new();
+        if config.validation {
+            if library
+                .layer_properties()?
+                .any(|layer| layer.name() == VALIDATION_LAYER)
+            {
+                enabled_layers.push(VALIDATION_LAYER.to_owned());
+            } else {
+                log::warn!("{VALIDATION_LAYER} is not installed, validation is disabled");
+            }
+        }
+        if (config.validation || config.debug_utils)
+            && library.supported_extensions().ext_debug_utils
+        {
+            enabled_extensions.ext_debug_utils = true;
+            if config.wants_messenger() {
+                debug_utils_messengers.push(config.messenger_create_info());
+            }
+        }
+        if enabled_extensions.khr_surface && library.supported_extensions().ext_swapchain_colorspace
+        {
+            enabled_extensions.ext_swapchain_colorspace = true;
+        }
         let instance = Instance::new(
-            VulkanLibrary::new()?,
+            library,
             InstanceCreateInfo {
                 flags: InstanceCreateFlags::ENUMERATE_PORTABILITY,
-                enabled_extensions: Surface::required_extensions(&display)?,
+                enabled_layers,
+                enabled_extensions,
+                debug_utils_messengers,
                 ..Default::default()
             },
         )?;
-        Ok(Self { instance })
+        let messenger = if config.wants_messenger() && instance.enabled_extensions().ext_debug_utils
+        {
+            Some(DebugUtilsMessenger::new(
+                instance.clone(),
+                config.messenger_create_info(),
+            )?)
+        } else {
+            None
+        };
+        Ok(Self {
+            instance,
+            device_extensions: config.device_extensions,
+            _messenger: messenger,
+        })
+    }
+
+    pub fn instance(&self) -> &Arc<Instance> {
+        &self.instance
     }
 
     pub fn enumerate_physical_devices(
@@ -43,50 +218,172 @@ impl Driver {
         &self,
         physical_device: Arc<PhysicalDevice>,
         queue_family_index: u32,
+        required_features: &DeviceFeatures,
     ) -> Result<(Arc<Device>, impl ExactSizeIterator<Item = Arc<Queue>>), Validated<VulkanError>>
     {
+        let supported_features = physical_device.supported_features();
+        let fragment_shading_rate = physical_device.api_version() >= Version::V1_2
+            && physical_device
+                .supported_extensions()
+                .khr_fragment_shading_rate;
+        let memory_budget = self.instance.api_version() >= Version::V1_1
+            && physical_device.supported_extensions().ext_memory_budget;
+        let external_handles = physical_device.api_version() >= Version::V1_1;
+        let external_memory_fd = external_handles
+            && cfg!(unix)
+            && physical_device
+                .supported_extensions()
+                .khr_external_memory_fd;
+        let external_memory_win32 = external_handles
+            && cfg!(windows)
+            && physical_device
+                .supported_extensions()
+                .khr_external_memory_win32;
+        let external_semaphore_fd = external_handles
+            && cfg!(unix)
+            && physical_device
+                .supported_extensions()
+                .khr_external_semaphore_fd;
+        let external_semaphore_win32 = external_handles
+            && cfg!(windows)
+            && physical_device
+                .supported_extensions()
+                .khr_external_semaphore_win32;
+        let enabled_features = DeviceFeatures {
+            dynamic_rendering: true,
+            fill_mode_non_solid: true,
+            multi_draw_indirect: supported_features.multi_draw_indirect,
+            draw_indirect_first_instance: supported_features.draw_indirect_first_instance,
+            pipeline_statistics_query: supported_features.pipeline_statistics_query,
+            occlusion_query_precise: supported_features.occlusion_query_precise,
+            multiview: physical_device.api_version() >= Version::V1_1
+                && supported_features.multiview,
+            timeline_semaphore: physical_device.api_version() >= Version::V1_2
+                && supported_features.timeline_semaphore,
+            pipeline_fragment_shading_rate: fragment_shading_rate
+                && supported_features.pipeline_fragment_shading_rate,
+            ..DeviceFeatures::empty()
+        } | *required_features;
+        let mut queue_create_infos = vec![QueueCreateInfo {
+            queue_family_index,
+            ..Default::default()
+        }];
+        let dedicated_families = [
+            Self::compute_queue_family(&physical_device),
+            Self::transfer_queue_family(&physical_device),
+        ];
+        for queue_family_index in dedicated_families.into_iter().flatten() {
+            queue_create_infos.push(QueueCreateInfo {
+                queue_family_index,
+                ..Default::default()
+            });
+        }
         Device::new(
             physical_device,
             DeviceCreateInfo {
-                queue_create_infos: vec![QueueCreateInfo {
-                    queue_family_index,
-                    ..Default::default()
-                }],
+                queue_create_infos,
                 enabled_extensions: DeviceExtensions {
-                    khr_swapchain: true,
+                    khr_swapchain: self.instance.enabled_extensions().khr_surface,
+                    khr_fragment_shading_rate: fragment_shading_rate,
+                    ext_memory_budget: memory_budget,
+                    khr_external_memory_fd: external_memory_fd,
+                    khr_external_memory_win32: external_memory_win32,
+                    khr_external_semaphore_fd: external_semaphore_fd,
+                    khr_external_semaphore_win32: external_semaphore_win32,
                     ..DeviceExtensions::empty()
-                },
-                enabled_features: DeviceFeatures {
-                    dynamic_rendering: true,
-                    fill_mode_non_solid: true,
-                    ..DeviceFeatures::empty()
-                },
+                } | self.device_extensions,
+                enabled_features,
                 ..Default::default()
             },
         )
     }
 
+    pub(crate) fn compute_queue_family(physical_device: &PhysicalDevice) -> Option<u32> {
+        physical_device
+            .queue_family_properties()
+            .iter()
+            .position(|q| {
+                q.queue_flags.intersects(QueueFlags::COMPUTE)
+                    && !q.queue_flags.intersects(QueueFlags::GRAPHICS)
+            })
+            .map(|i| i as u32)
+    }
+
+    pub(crate) fn transfer_queue_family(physical_device: &PhysicalDevice) -> Option<u32> {
+        physical_device
+            .queue_family_properties()
+            .iter()
+            .position(|q| {
+                q.queue_flags.intersects(QueueFlags::TRANSFER)
+                    && !q
+                        .queue_flags
+                        .intersects(QueueFlags::GRAPHICS | QueueFlags::COMPUTE)
+                    && q.min_image_transfer_granularity == [1, 1, 1]
+            })
+            .map(|i| i as u32)
+    }
+
+    pub fn enumerate_adapters(&self) -> Vec<AdapterInfo> {
+        self.enumerate_physical_devices()
+            .map(|devices| devices.map(|p| AdapterInfo::new(&p)).collect())
+            .unwrap_or_default()
+    }
+
     pub fn request_device(
         &self,
         display: &impl HasDisplayHandle,
+    ) -> Option<(Arc<PhysicalDevice>, u32)> {
+        self.request_device_with(display, &DeviceSelector::default())
+    }
+
+    pub fn request_device_with(
+        &self,
+        display: &impl HasDisplayHandle,
+        selector: &DeviceSelector,
     ) -> Option<(Arc<PhysicalDevice>, u32)> {
         let device_extensions = DeviceExtensions {
             khr_swapchain: true,
             ..DeviceExtensions::empty()
         };
+        self.select_device(device_extensions, selector, |p, i| {
+            p.presentation_support(i, display).unwrap()
+        })
+    }
+
+    pub fn request_headless_device(&self) -> Option<(Arc<PhysicalDevice>, u32)> {
+        self.request_headless_device_with(&DeviceSelector::default())
+    }
+
+    pub fn request_headless_device_with(
+        &self,
+        selector: &DeviceSelector,
+    ) -> Option<(Arc<PhysicalDevice>, u32)> {
+        self.select_device(DeviceExtensions::empty(), selector, |_, _| true)
+    }
+
+    fn select_device(
+        &self,
+        device_extensions: DeviceExtensions,
+        selector: &DeviceSelector,
+        queue_filter: impl Fn(&PhysicalDevice, u32) -> bool,
+    ) -> Option<(Arc<PhysicalDevice>, u32)> {
+        let selector = selector.clone().with_env_override();
         self.enumerate_physical_devices()
             .ok()?
             .filter(|p| {
                 p.api_version() >= Version::V1_3 || p.supported_extensions().khr_dynamic_rendering
             })
-            .filter(|p| p.supported_extensions().contains(&device_extensions))
+            .filter(|p| {
+                p.supported_extensions()
+                    .contains(&(device_extensions | self.device_extensions))
+            })
+            .filter(|p| selector.matches(p))
             .filter_map(|p| {
                 p.queue_family_properties()
                     .iter()
                     .enumerate()
                     .position(|(i, q)| {
-                        q.queue_flags.intersects(QueueFlags::GRAPHICS)
-                            && p.presentation_support(i as u32, display).unwrap()
+                        q.queue_flags.intersects(QueueFlags::GRAPHICS) && queue_filter(&p, i as u32)
                     })
                     .map(|i| (p, i as u32))
             })
@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::path::PathBuf;
+use thiserror::Error;
+use vulkano::{Validated, ValidationError, VulkanError};
+use winit::window::WindowId;
+
+pub type Result<T, E = EngineError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum EngineError {
+    #[error("the swapchain is out of date")]
+    SwapchainOutOfDate,
+    #[error("the window surface was lost")]
+    SurfaceLost,
+    #[error("window {0:?} no longer exists")]
+    WindowGone(WindowId),
+    #[error("the device was lost")]
+    DeviceLost,
+    #[error("out of memory: {0}")]
+    OutOfMemory(VulkanError),
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("{0}")]
+    InvalidAsset(String),
+    #[error("{0}")]
+    ImageMismatch(String),
+    #[error("failed to compile {}", path.display())]
+    Shader {
+        path: PathBuf,
+        #[source]
+        source: Box<EngineError>,
+    },
+    #[error("script {} failed: {message}", path.display())]
+    Script { path: PathBuf, message: String },
+    #[error("pass `{name}` failed")]
+    Pass {
+        name: String,
+        #[source]
+        source: Box<EngineError>,
+    },
+    #[error(transparent)]
+    Vulkan(VulkanError),
+    #[error(transparent)]
+    Validation(Box<ValidationError>),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+    #[error(transparent)]
+    Other(Box<dyn Error + Send + Sync>),
+}
+
+impl EngineError {
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            Self::SwapchainOutOfDate | Self::SurfaceLost | Self::WindowGone(_) => true,
+            Self::Shader { source, .. } | Self::Pass { source, .. } => source.is_recoverable(),
+            _ => false,
+        }
+    }
+
+    pub fn other(error: impl Error + Send + Sync + 'static) -> Self {
+        Self::Other(Box::new(error))
+    }
+}
+
+impl From<VulkanError> for EngineError {
+    fn from(error: VulkanError) -> Self {
+        match error {
+            VulkanError::OutOfDate => Self::SwapchainOutOfDate,
+            VulkanError::SurfaceLost => Self::SurfaceLost,
+            VulkanError::DeviceLost => Self::DeviceLost,
+            VulkanError::OutOfHostMemory | VulkanError::OutOfDeviceMemory => {
+                Self::OutOfMemory(error)
+            }
+            error => Self::Vulkan(error),
+        }
+    }
+}
+
+impl From<Box<ValidationError>> for EngineError {
+    fn from(error: Box<ValidationError>) -> Self {
+        Self::Validation(error)
+    }
+}
+
+impl<E: Into<EngineError>> From<Validated<E>> for EngineError {
+    fn from(error: Validated<E>) -> Self {
+        match error {
+            Validated::Error(error) => error.into(),
+            Validated::ValidationError(error) => Self::Validation(error),
+        }
+    }
+}
+
+macro_rules! other_errors {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl From<$ty> for EngineError {
+                fn from(error: $ty) -> Self {
+                    Self::other(error)
+                }
+            }
+        )*
+    };
+}
+
+other_errors!(
+    gltf::Error,
+    ktx2::ParseError,
+    lyon::tessellation::TessellationError,
+    ron::Error,
+    ron::error::SpannedError,
+    serde_json::Error,
+    taffy::TaffyError,
+    tobj::LoadError,
+    toml::de::Error,
+    toml::ser::Error,
+    vulkano::LoadingError,
+    vulkano::buffer::AllocateBufferError,
+    vulkano::command_buffer::CommandBufferExecError,
+    vulkano::image::AllocateImageError,
+    vulkano::memory::allocator::MemoryAllocatorError,
+    vulkano::shader::spirv::SpirvError,
+    vulkano::swapchain::FromWindowError,
+    vulkano::sync::HostAccessError,
+    winit::error::EventLoopError,
+    winit::error::ExternalError,
+    winit::error::OsError,
+    winit::raw_window_handle::HandleError,
+);
+
+#[cfg(feature = "shaderc")]
+other_errors!(shaderc::Error);
@@ -0,0 +1,242 @@
+use crate::core::error::{EngineError, Result};
+use ash::vk;
+use std::fs::File;
+use std::sync::Arc;
+use vulkano::buffer::{Buffer, BufferCreateInfo, BufferMemory, RawBuffer};
+use vulkano::device::{Device, DeviceOwned};
+use vulkano::image::sys::RawImage;
+use vulkano::image::{Image, ImageCreateInfo, ImageMemory};
+use vulkano::memory::{
+    DedicatedAllocation, DeviceMemory, ExternalMemoryHandleType, MemoryAllocateInfo,
+    MemoryImportInfo, MemoryPropertyFlags, MemoryRequirements, ResourceMemory,
+};
+use vulkano::{DeviceSize, VulkanError, VulkanObject};
+
+#[derive(Debug)]
+pub enum ExternalHandle {
+    Fd(File),
+    Win32(vk::HANDLE),
+}
+
+#[derive(Debug)]
+pub struct ExportedMemory {
+    pub handle: ExternalHandle,
+    pub handle_type: ExternalMemoryHandleType,
+    pub allocation_size: DeviceSize,
+    pub memory_type_index: u32,
+}
+
+pub(crate) fn platform_handle_type(device: &Device) -> Option<ExternalMemoryHandleType> {
+    let extensions = device.enabled_extensions();
+    if extensions.khr_external_memory_fd {
+        Some(ExternalMemoryHandleType::OpaqueFd)
+    } else if extensions.khr_external_memory_win32 {
+        Some(ExternalMemoryHandleType::OpaqueWin32)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn require_handle_type(device: &Device) -> Result<ExternalMemoryHandleType> {
+    platform_handle_type(device).ok_or_else(|| {
+        EngineError::Unsupported("the device does not support exporting memory".into())
+    })
+}
+
+pub(crate) unsafe fn create_image(
+    device: &Arc<Device>,
+    create_info: ImageCreateInfo,
+    import: Option<ExportedMemory>,
+) -> Result<Arc<Image>> {
+    let handle_type = require_handle_type(device)?;
+    let raw_image = RawImage::new(
+        device.clone(),
+        ImageCreateInfo {
+            external_memory_handle_types: handle_type.into(),
+            ..create_info
+        },
+    )?;
+    let memory = unsafe {
+        allocate(
+            device,
+            raw_image.memory_requirements()[0],
+            DedicatedAllocation::Image(&raw_image),
+            handle_type,
+            import,
+        )
+    }?;
+    let image = raw_image
+        .bind_memory([ResourceMemory::new_dedicated(memory)])
+        .map_err(|(error, _, _)| error)?;
+    Ok(Arc::new(image))
+}
+
+pub(crate) unsafe fn create_buffer(
+    device: &Arc<Device>,
+    create_info: BufferCreateInfo,
+    import: Option<ExportedMemory>,
+) -> Result<Arc<Buffer>> {
+    let handle_type = require_handle_type(device)?;
+    let raw_buffer = RawBuffer::new(
+        device.clone(),
+        BufferCreateInfo {
+            external_memory_handle_types: handle_type.into(),
+            ..create_info
+        },
+    )?;
+    let memory = unsafe {
+        allocate(
+            device,
+            *raw_buffer.memory_requirements(),
+            DedicatedAllocation::Buffer(&raw_buffer),
+            handle_type,
+            import,
+        )
+    }?;
+    let buffer = raw_buffer
+        .bind_memory(ResourceMemory::new_dedicated(memory))
+        .map_err(|(error, _, _)| error)?;
+    Ok(Arc::new(buffer))
+}
+
+unsafe fn allocate(
+    device: &Arc<Device>,
+    requirements: MemoryRequirements,
+    dedicated_allocation: DedicatedAllocation<'_>,
+    handle_type: ExternalMemoryHandleType,
+    import: Option<ExportedMemory>,
+) -> Result<DeviceMemory> {
+    let Some(import) = import else {
+        let Some(memory_type_index) = device_local_memory_type(device, requirements) else {
+            return Err(EngineError::Unsupported(
+                "no memory type can back an exportable resource".into(),
+            ));
+        };
+        return Ok(DeviceMemory::allocate(
+            device.clone(),
+            MemoryAllocateInfo {
+                allocation_size: requirements.layout.size(),
+                memory_type_index,
+                dedicated_allocation: Some(dedicated_allocation),
+                export_handle_types: handle_type.into(),
+                ..Default::default()
+            },
+        )?);
+    };
+    if import.handle_type != handle_type {
+        return Err(EngineError::InvalidArgument(format!(
+            "cannot import a {:?} handle, the device uses {handle_type:?}",
+            import.handle_type
+        )));
+    }
+    if requirements.memory_type_bits & (1 << import.memory_type_index) == 0 {
+        return Err(EngineError::InvalidArgument(format!(
+            "memory type {} cannot back the imported resource",
+            import.memory_type_index
+        )));
+    }
+    if import.allocation_size < requirements.layout.size() {
+        return Err(EngineError::InvalidArgument(format!(
+            "imported memory of {} bytes is smaller than the {} bytes required",
+            import.allocation_size,
+            requirements.layout.size()
+        )));
+    }
+    let import_info = match import.handle {
+        ExternalHandle::Fd(file) => MemoryImportInfo::Fd { handle_type, file },
+        ExternalHandle::Win32(handle) => MemoryImportInfo::Win32 {
+            handle_type,
+            handle,
+        },
+    };
+    Ok(unsafe {
+        DeviceMemory::import(
+            device.clone(),
+            MemoryAllocateInfo {
+                allocation_size: import.allocation_size,
+                memory_type_index: import.memory_type_index,
+                dedicated_allocation: Some(dedicated_allocation),
+                ..Default::default()
+            },
+            import_info,
+        )
+    }?)
+}
+
+fn device_local_memory_type(device: &Device, requirements: MemoryRequirements) -> Option<u32> {
+    let memory_types = &device.physical_device().memory_properties().memory_types;
+    let candidates = || {
+        (0..memory_types.len() as u32)
+            .filter(|&index| requirements.memory_type_bits & (1 << index) != 0)
+    };
+    candidates()
+        .find(|&index| {
+            memory_types[index as usize]
+                .property_flags
+                .intersects(MemoryPropertyFlags::DEVICE_LOCAL)
+        })
+        .or_else(|| candidates().next())
+}
+
+pub(crate) fn export_image(image: &Image) -> Result<ExportedMemory> {
+    match image.memory() {
+        ImageMemory::Normal(memory) if memory.len() == 1 => export(&memory[0]),
+        _ => Err(EngineError::InvalidArgument(
+            "only images bound to a single allocation can be exported".into(),
+        )),
+    }
+}
+
+pub(crate) fn export_buffer(buffer: &Buffer) -> Result<ExportedMemory> {
+    match buffer.memory() {
+        BufferMemory::Normal(memory) => export(memory),
+        _ => Err(EngineError::InvalidArgument(
+            "only buffers bound to normal memory can be exported".into(),
+        )),
+    }
+}
+
+fn export(memory: &ResourceMemory) -> Result<ExportedMemory> {
+    let device_memory = memory.device_memory();
+    let Some(handle_type) = platform_handle_type(device_memory.device()).filter(|&handle_type| {
+        device_memory
+            .export_handle_types()
+            .contains_enum(handle_type)
+    }) else {
+        return Err(EngineError::InvalidArgument(
+            "the resource was not created as exportable".into(),
+        ));
+    };
+    let handle = match handle_type {
+        ExternalMemoryHandleType::OpaqueFd => {
+            ExternalHandle::Fd(device_memory.export_fd(handle_type)?)
+        }
+        _ => ExternalHandle::Win32(export_win32(device_memory, handle_type)?),
+    };
+    Ok(ExportedMemory {
+        handle,
+        handle_type,
+        allocation_size: device_memory.allocation_size(),
+        memory_type_index: device_memory.memory_type_index(),
+    })
+}
+
+fn export_win32(
+    memory: &DeviceMemory,
+    handle_type: ExternalMemoryHandleType,
+) -> Result<vk::HANDLE, VulkanError> {
+    let device = memory.device();
+    let info = vk::MemoryGetWin32HandleInfoKHR::default()
+        .memory(memory.handle())
+        .handle_type(handle_type.into());
+    let mut handle = 0;
+    unsafe {
+        (device
+            .fns()
+            .khr_external_memory_win32
+            .get_memory_win32_handle_khr)(device.handle(), &info, &mut handle)
+    }
+    .result()
+    .map_err(VulkanError::from)?;
+    Ok(handle)
+}
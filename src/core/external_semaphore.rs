@@ -0,0 +1,201 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::external_memory::ExternalHandle;
+use crate::core::gpu::Gpu;
+use std::ops::Range;
+use std::sync::Arc;
+use vulkano::buffer::Buffer;
+use vulkano::command_buffer::{SemaphoreSubmitInfo, SubmitInfo};
+use vulkano::device::{Device, DeviceOwned, Queue};
+use vulkano::image::{Image, ImageLayout};
+use vulkano::swapchain::Swapchain;
+use vulkano::sync::future::{AccessCheckError, SubmitAnyBuilder};
+use vulkano::sync::semaphore::{
+    ExternalSemaphoreHandleType, ImportSemaphoreFdInfo, ImportSemaphoreWin32HandleInfo, Semaphore,
+    SemaphoreCreateInfo,
+};
+use vulkano::sync::GpuFuture;
+use vulkano::{DeviceSize, Validated, VulkanError};
+
+#[derive(Debug)]
+pub struct ExportedSemaphore {
+    pub handle: ExternalHandle,
+    pub handle_type: ExternalSemaphoreHandleType,
+}
+
+pub struct ExternalSemaphore {
+    semaphore: Arc<Semaphore>,
+    handle_type: ExternalSemaphoreHandleType,
+}
+
+impl ExternalSemaphore {
+    pub fn new(gpu: &Gpu) -> Result<Self> {
+        let handle_type = require_handle_type(gpu.device())?;
+        let semaphore = Semaphore::new(
+            gpu.device().clone(),
+            SemaphoreCreateInfo {
+                export_handle_types: handle_type.into(),
+                ..Default::default()
+            },
+        )?;
+        Ok(Self {
+            semaphore: Arc::new(semaphore),
+            handle_type,
+        })
+    }
+
+    /// # Safety
+    ///
+    /// `exported` must hold a valid semaphore handle of the given type. File descriptors are
+    /// owned by Vulkan once imported, Windows handles remain owned by the caller.
+    pub unsafe fn import(gpu: &Gpu, exported: ExportedSemaphore) -> Result<Self> {
+        let handle_type = require_handle_type(gpu.device())?;
+        if exported.handle_type != handle_type {
+            return Err(EngineError::InvalidArgument(format!(
+                "cannot import a {:?} handle, the device uses {handle_type:?}",
+                exported.handle_type
+            )));
+        }
+        let semaphore = Semaphore::new(gpu.device().clone(), SemaphoreCreateInfo::default())?;
+        match exported.handle {
+            ExternalHandle::Fd(file) => unsafe {
+                semaphore.import_fd(ImportSemaphoreFdInfo {
+                    file: Some(file),
+                    ..ImportSemaphoreFdInfo::handle_type(handle_type)
+                })
+            }?,
+            ExternalHandle::Win32(handle) => unsafe {
+                semaphore.import_win32_handle(ImportSemaphoreWin32HandleInfo {
+                    handle,
+                    ..ImportSemaphoreWin32HandleInfo::handle_type(handle_type)
+                })
+            }?,
+        }
+        Ok(Self {
+            semaphore: Arc::new(semaphore),
+            handle_type,
+        })
+    }
+
+    pub fn export(&self) -> Result<ExportedSemaphore> {
+        let handle = match self.handle_type {
+            ExternalSemaphoreHandleType::OpaqueFd => {
+                ExternalHandle::Fd(unsafe { self.semaphore.export_fd(self.handle_type) }?)
+            }
+            _ => ExternalHandle::Win32(self.semaphore.export_win32_handle(self.handle_type)?),
+        };
+        Ok(ExportedSemaphore {
+            handle,
+            handle_type: self.handle_type,
+        })
+    }
+
+    pub fn semaphore(&self) -> &Arc<Semaphore> {
+        &self.semaphore
+    }
+}
+
+pub(crate) fn platform_handle_type(device: &Device) -> Option<ExternalSemaphoreHandleType> {
+    let extensions = device.enabled_extensions();
+    if extensions.khr_external_semaphore_fd {
+        Some(ExternalSemaphoreHandleType::OpaqueFd)
+    } else if extensions.khr_external_semaphore_win32 {
+        Some(ExternalSemaphoreHandleType::OpaqueWin32)
+    } else {
+        None
+    }
+}
+
+fn require_handle_type(device: &Device) -> Result<ExternalSemaphoreHandleType> {
+    platform_handle_type(device).ok_or_else(|| {
+        EngineError::Unsupported("the device does not support external semaphores".into())
+    })
+}
+
+pub(crate) fn signal(queue: &Arc<Queue>, semaphores: Vec<Arc<Semaphore>>) -> Result<()> {
+    if semaphores.is_empty() {
+        return Ok(());
+    }
+    let submit_info = SubmitInfo {
+        signal_semaphores: semaphores
+            .into_iter()
+            .map(SemaphoreSubmitInfo::new)
+            .collect(),
+        ..Default::default()
+    };
+    queue.with(|mut queue| unsafe { queue.submit(&[submit_info], None) })?;
+    Ok(())
+}
+
+pub(crate) struct SemaphoreWaitFuture {
+    semaphores: Vec<Arc<Semaphore>>,
+    device: Arc<Device>,
+}
+
+impl SemaphoreWaitFuture {
+    pub(crate) fn new(device: Arc<Device>, semaphores: Vec<Arc<Semaphore>>) -> Self {
+        Self { semaphores, device }
+    }
+}
+
+unsafe impl DeviceOwned for SemaphoreWaitFuture {
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+unsafe impl GpuFuture for SemaphoreWaitFuture {
+    fn cleanup_finished(&mut self) {}
+
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Validated<VulkanError>> {
+        if self.semaphores.is_empty() {
+            return Ok(SubmitAnyBuilder::Empty);
+        }
+        Ok(SubmitAnyBuilder::SemaphoresWait(
+            self.semaphores.iter().cloned().collect(),
+        ))
+    }
+
+    fn flush(&self) -> Result<(), Validated<VulkanError>> {
+        Ok(())
+    }
+
+    unsafe fn signal_finished(&self) {}
+
+    fn queue(&self) -> Option<Arc<Queue>> {
+        None
+    }
+
+    fn queue_change_allowed(&self) -> bool {
+        true
+    }
+
+    fn check_buffer_access(
+        &self,
+        _buffer: &Buffer,
+        _range: Range<DeviceSize>,
+        _exclusive: bool,
+        _queue: &Queue,
+    ) -> Result<(), AccessCheckError> {
+        Err(AccessCheckError::Unknown)
+    }
+
+    fn check_image_access(
+        &self,
+        _image: &Image,
+        _range: Range<DeviceSize>,
+        _exclusive: bool,
+        _expected_layout: ImageLayout,
+        _queue: &Queue,
+    ) -> Result<(), AccessCheckError> {
+        Err(AccessCheckError::Unknown)
+    }
+
+    fn check_swapchain_image_acquired(
+        &self,
+        _swapchain: &Swapchain,
+        _image_index: u32,
+        _before: bool,
+    ) -> Result<(), AccessCheckError> {
+        Err(AccessCheckError::Unknown)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::core::error::{EngineError, Result};
+use renderdoc::{RenderDoc, V141};
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::Mutex;
+
+pub use renderdoc::InputButton;
+
+pub(crate) struct FrameCapture {
+    api: Mutex<RenderDoc<V141>>,
+}
+
+impl FrameCapture {
+    pub(crate) fn attach() -> Option<Self> {
+        let api = RenderDoc::new().ok()?;
+        log::info!("RenderDoc is attached, frame captures are available");
+        Some(Self {
+            api: Mutex::new(api),
+        })
+    }
+
+    pub(crate) fn trigger(&self, frames: u32) {
+        let mut api = self.api.lock().unwrap();
+        match frames {
+            1 => api.trigger_capture(),
+            frames => api.trigger_multi_frame_capture(frames),
+        }
+    }
+
+    pub(crate) fn set_keys(&self, keys: &[InputButton]) {
+        self.api.lock().unwrap().set_capture_keys(keys);
+    }
+
+    pub(crate) fn start(&self) {
+        self.api
+            .lock()
+            .unwrap()
+            .start_frame_capture(ptr::null::<c_void>(), ptr::null());
+    }
+
+    pub(crate) fn end(&self) -> Result<()> {
+        let mut api = self.api.lock().unwrap();
+        if !api.is_frame_capturing() {
+            return Err(EngineError::InvalidArgument(
+                "no RenderDoc capture is in progress".into(),
+            ));
+        }
+        api.end_frame_capture(ptr::null::<c_void>(), ptr::null());
+        Ok(())
+    }
+}
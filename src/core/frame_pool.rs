@@ -0,0 +1,148 @@
+use crate::core::error::Result;
+use crate::core::frame_stats::PresentFence;
+use crate::core::timeline::Timeline;
+use std::sync::{Arc, Mutex};
+use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
+use vulkano::buffer::{BufferContents, BufferUsage, Subbuffer};
+use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
+use vulkano::device::Device;
+use vulkano::memory::allocator::{MemoryTypeFilter, StandardMemoryAllocator};
+use vulkano::DeviceSize;
+
+pub(crate) const FRAMES_IN_FLIGHT: usize = 2;
+const UNIFORM_ARENA_SIZE: DeviceSize = 64 * 1024;
+const STAGING_ARENA_SIZE: DeviceSize = 4 * 1024 * 1024;
+
+pub(crate) enum FrameFence {
+    Present(PresentFence),
+    Timeline(u64),
+}
+
+struct FrameSlot {
+    uniforms: SubbufferAllocator,
+    staging: SubbufferAllocator,
+    command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    fence: Option<FrameFence>,
+}
+
+impl FrameSlot {
+    fn new(device: &Arc<Device>, memory_allocator: &Arc<StandardMemoryAllocator>) -> Self {
+        let subbuffer_allocator = |arena_size, buffer_usage, memory_type_filter| {
+            SubbufferAllocator::new(
+                memory_allocator.clone(),
+                SubbufferAllocatorCreateInfo {
+                    arena_size,
+                    buffer_usage,
+                    memory_type_filter,
+                    ..Default::default()
+                },
+            )
+        };
+        Self {
+            uniforms: subbuffer_allocator(
+                UNIFORM_ARENA_SIZE,
+                BufferUsage::UNIFORM_BUFFER,
+                MemoryTypeFilter::PREFER_DEVICE | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ),
+            staging: subbuffer_allocator(
+                STAGING_ARENA_SIZE,
+                BufferUsage::TRANSFER_SRC
+                    | BufferUsage::VERTEX_BUFFER
+                    | BufferUsage::INDEX_BUFFER
+                    | BufferUsage::STORAGE_BUFFER,
+                MemoryTypeFilter::PREFER_HOST | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ),
+            command_buffer_allocator: Arc::new(StandardCommandBufferAllocator::new(
+                device.clone(),
+                Default::default(),
+            )),
+            fence: None,
+        }
+    }
+
+    fn reset(&mut self, timeline: Option<&Timeline>) -> Result<()> {
+        match (self.fence.take(), timeline) {
+            (Some(FrameFence::Present(fence)), _) => fence.wait(None)?,
+            (Some(FrameFence::Timeline(value)), Some(timeline)) => timeline.wait(value, None)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+struct FramePoolState {
+    slots: Vec<FrameSlot>,
+    current: usize,
+    frame: u64,
+}
+
+pub(crate) struct FramePool {
+    state: Mutex<FramePoolState>,
+}
+
+impl FramePool {
+    pub(crate) fn new(
+        device: &Arc<Device>,
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+    ) -> Self {
+        let slots = (0..FRAMES_IN_FLIGHT)
+            .map(|_| FrameSlot::new(device, memory_allocator))
+            .collect();
+        Self {
+            state: Mutex::new(FramePoolState {
+                slots,
+                current: 0,
+                frame: 0,
+            }),
+        }
+    }
+
+    fn with_slot<R>(&self, f: impl FnOnce(&mut FrameSlot) -> R) -> R {
+        let mut state = self.state.lock().unwrap();
+        let current = state.current;
+        f(&mut state.slots[current])
+    }
+
+    pub(crate) fn uniform<T: BufferContents>(&self, data: T) -> Result<Subbuffer<T>> {
+        let buffer = self.with_slot(|slot| slot.uniforms.allocate_sized::<T>())?;
+        *buffer.write()? = data;
+        Ok(buffer)
+    }
+
+    pub(crate) fn staging<T, I>(&self, data: I) -> Result<Subbuffer<[T]>>
+    where
+        T: BufferContents,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let data = data.into_iter();
+        let buffer =
+            self.with_slot(|slot| slot.staging.allocate_slice::<T>(data.len() as DeviceSize))?;
+        for (slot, value) in buffer.write()?.iter_mut().zip(data) {
+            *slot = value;
+        }
+        Ok(buffer)
+    }
+
+    pub(crate) fn command_buffer_allocator(&self) -> Arc<StandardCommandBufferAllocator> {
+        self.with_slot(|slot| slot.command_buffer_allocator.clone())
+    }
+
+    pub(crate) fn frame(&self) -> u64 {
+        self.state.lock().unwrap().frame
+    }
+
+    pub(crate) fn end_frame(
+        &self,
+        fence: Option<FrameFence>,
+        timeline: Option<&Timeline>,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let current = state.current;
+        state.slots[current].fence = fence;
+        state.current = (current + 1) % state.slots.len();
+        state.frame += 1;
+        let next = state.current;
+        state.slots[next].reset(timeline)
+    }
+}
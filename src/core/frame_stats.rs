@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use vulkano::sync::future::FenceSignalFuture;
+use vulkano::sync::GpuFuture;
+
+const FRAME_HISTORY: usize = 120;
+const MAX_IN_FLIGHT: usize = 8;
+
+pub(crate) type PresentFence = Arc<FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>>;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+    pub frames_presented: u64,
+    pub frame_time: Duration,
+    pub average_frame_time: Duration,
+    pub max_frame_time: Duration,
+    pub acquire_wait: Duration,
+    pub present_wait: Duration,
+    pub submit_to_present: Option<Duration>,
+    pub swapchain_recreations: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct FrameStatsTracker {
+    stats: FrameStats,
+    last_present: Option<Instant>,
+    frame_times: VecDeque<Duration>,
+    in_flight: VecDeque<(PresentFence, Instant)>,
+}
+
+impl FrameStatsTracker {
+    pub(crate) fn stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    pub(crate) fn record_acquire(&mut self, wait: Duration) {
+        self.stats.acquire_wait = wait;
+        self.poll();
+    }
+
+    pub(crate) fn record_recreation(&mut self) {
+        self.stats.swapchain_recreations += 1;
+    }
+
+    pub(crate) fn record_present(
+        &mut self,
+        fence: Option<PresentFence>,
+        submitted: Instant,
+        present_wait: Duration,
+    ) {
+        let now = Instant::now();
+        self.stats.frames_presented += 1;
+        self.stats.present_wait = present_wait;
+        if let Some(last_present) = self.last_present.replace(now) {
+            let frame_time = now - last_present;
+            if self.frame_times.len() == FRAME_HISTORY {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(frame_time);
+            self.stats.frame_time = frame_time;
+            self.stats.max_frame_time = self.frame_times.iter().copied().max().unwrap_or_default();
+            self.stats.average_frame_time =
+                self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32;
+        }
+        if let Some(fence) = fence {
+            if self.in_flight.len() == MAX_IN_FLIGHT {
+                self.in_flight.pop_front();
+            }
+            self.in_flight.push_back((fence, submitted));
+        }
+        self.poll();
+    }
+
+    fn poll(&mut self) {
+        while let Some((fence, submitted)) = self.in_flight.front() {
+            if !fence.is_signaled().unwrap_or(true) {
+                break;
+            }
+            self.stats.submit_to_present = Some(submitted.elapsed());
+            self.in_flight.pop_front();
+        }
+    }
+}
+
+const TIMING_HISTORY: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuTimings {
+    pub events: Duration,
+    pub update: Duration,
+    pub record: Duration,
+    pub submit: Duration,
+    pub present_wait: Duration,
+    pub frame: Duration,
+}
+
+impl CpuTimings {
+    fn accumulate(&mut self, other: &CpuTimings) {
+        self.events += other.events;
+        self.update += other.update;
+        self.record += other.record;
+        self.submit += other.submit;
+        self.present_wait += other.present_wait;
+        self.frame += other.frame;
+    }
+
+    fn divide(&mut self, count: u32) {
+        self.events /= count;
+        self.update /= count;
+        self.record /= count;
+        self.submit /= count;
+        self.present_wait /= count;
+        self.frame /= count;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameTimingStats {
+    pub frames: u64,
+    pub last: CpuTimings,
+    pub average: CpuTimings,
+    pub fps: f32,
+    pub low_1_percent: f32,
+    pub low_0_1_percent: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct FrameTimingHistory {
+    frames: u64,
+    history: VecDeque<CpuTimings>,
+}
+
+impl FrameTimingHistory {
+    pub(crate) fn push(&mut self, timings: CpuTimings) {
+        if self.history.len() == TIMING_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(timings);
+        self.frames += 1;
+    }
+
+    pub(crate) fn stats(&self) -> FrameTimingStats {
+        let mut stats = FrameTimingStats {
+            frames: self.frames,
+            last: self.history.back().copied().unwrap_or_default(),
+            ..Default::default()
+        };
+        if self.history.is_empty() {
+            return stats;
+        }
+        for timings in &self.history {
+            stats.average.accumulate(timings);
+        }
+        stats.average.divide(self.history.len() as u32);
+        stats.fps = fps(stats.average.frame);
+
+        let mut frame_times: Vec<_> = self.history.iter().map(|timings| timings.frame).collect();
+        frame_times.sort_unstable_by(|a, b| b.cmp(a));
+        let low = |fraction: f32| {
+            let index = (frame_times.len() as f32 * fraction).ceil() as usize;
+            fps(frame_times[index.clamp(1, frame_times.len()) - 1])
+        };
+        stats.low_1_percent = low(0.01);
+        stats.low_0_1_percent = low(0.001);
+        stats
+    }
+}
+
+fn fps(frame_time: Duration) -> f32 {
+    if frame_time.is_zero() {
+        0.0
+    } else {
+        1.0 / frame_time.as_secs_f32()
+    }
+}
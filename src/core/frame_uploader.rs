@@ -0,0 +1,232 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::frame_pool::FRAMES_IN_FLIGHT;
+use crate::core::gpu::{Gpu, QueueKind};
+use bytemuck::Pod;
+use std::collections::VecDeque;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use vulkano::buffer::{BufferUsage, Subbuffer};
+use vulkano::DeviceSize;
+
+const DEFAULT_CAPACITY: DeviceSize = 1024 * 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Overflow {
+    /// Frames the GPU may still be reading hold the space.
+    InFlight,
+    /// The current frame alone has used up the ring.
+    Frame,
+}
+
+/// Offset bookkeeping for the ring, `frames` holds the first offset each in-flight frame wrote.
+struct Ring {
+    capacity: DeviceSize,
+    alignment: DeviceSize,
+    head: DeviceSize,
+    frames: VecDeque<(u64, DeviceSize)>,
+}
+
+impl Ring {
+    fn new(capacity: DeviceSize, alignment: DeviceSize) -> Self {
+        Self {
+            capacity,
+            alignment,
+            head: 0,
+            frames: VecDeque::new(),
+        }
+    }
+
+    fn reserve(&mut self, frame: u64, size: DeviceSize) -> Result<DeviceSize, Overflow> {
+        self.retire(frame);
+        let start = self.head.next_multiple_of(self.alignment);
+        let offset = if start + size <= self.capacity {
+            start
+        } else {
+            0
+        };
+        if !self.is_free(offset, size) {
+            let in_flight = self
+                .frames
+                .front()
+                .is_some_and(|&(oldest, _)| oldest != frame);
+            return Err(if in_flight {
+                Overflow::InFlight
+            } else {
+                Overflow::Frame
+            });
+        }
+        if self
+            .frames
+            .back()
+            .is_none_or(|&(written, _)| written != frame)
+        {
+            self.frames.push_back((frame, offset));
+        }
+        self.head = offset + size;
+        Ok(offset)
+    }
+
+    /// Forgets every frame but `frame`, once the GPU is known to be done with them.
+    fn release_in_flight(&mut self, frame: u64) {
+        self.frames.retain(|&(written, _)| written == frame);
+    }
+
+    fn retire(&mut self, frame: u64) {
+        while let Some(&(written, _)) = self.frames.front() {
+            if written + FRAMES_IN_FLIGHT as u64 > frame {
+                break;
+            }
+            self.frames.pop_front();
+        }
+    }
+
+    fn is_free(&self, offset: DeviceSize, size: DeviceSize) -> bool {
+        let Some(&(_, tail)) = self.frames.front() else {
+            return true;
+        };
+        let head = self.head;
+        if tail < head {
+            offset >= head || offset + size <= tail
+        } else {
+            offset >= head && offset + size <= tail
+        }
+    }
+}
+
+pub struct FrameUploader {
+    buffer: Subbuffer<[u8]>,
+    mapped: NonNull<[u8]>,
+    ring: Ring,
+    gpu: Arc<Gpu>,
+}
+
+unsafe impl Send for FrameUploader {}
+unsafe impl Sync for FrameUploader {}
+
+impl FrameUploader {
+    pub fn new(gpu: Arc<Gpu>) -> Result<Self> {
+        Self::with_capacity(gpu, DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(gpu: Arc<Gpu>, capacity: DeviceSize) -> Result<Self> {
+        let alignment = gpu
+            .device()
+            .physical_device()
+            .properties()
+            .min_uniform_buffer_offset_alignment
+            .as_devicesize();
+        let buffer = gpu.create_mapped_buffer(
+            capacity.next_multiple_of(alignment),
+            BufferUsage::UNIFORM_BUFFER,
+        )?;
+        let mapped = buffer.mapped_slice()?;
+        let ring = Ring::new(buffer.len(), alignment);
+        Ok(Self {
+            buffer,
+            mapped,
+            ring,
+            gpu,
+        })
+    }
+
+    pub fn buffer(&self) -> &Subbuffer<[u8]> {
+        &self.buffer
+    }
+
+    pub fn capacity(&self) -> DeviceSize {
+        self.buffer.len()
+    }
+
+    pub fn alignment(&self) -> DeviceSize {
+        self.ring.alignment
+    }
+
+    pub fn push<T: Pod>(&mut self, data: &T) -> Result<(Subbuffer<[u8]>, u32)> {
+        let bytes = bytemuck::bytes_of(data);
+        let size = bytes.len() as DeviceSize;
+        let offset = self.reserve(size)?;
+        unsafe {
+            self.mapped
+                .cast::<u8>()
+                .add(offset as usize)
+                .copy_from_nonoverlapping(NonNull::from(bytes).cast(), bytes.len());
+        }
+        Ok((self.buffer.clone(), offset as u32))
+    }
+
+    fn reserve(&mut self, size: DeviceSize) -> Result<DeviceSize> {
+        let capacity = self.capacity();
+        if size > capacity {
+            return Err(EngineError::InvalidArgument(format!(
+                "cannot push {size} bytes into a {capacity} byte frame uploader"
+            )));
+        }
+        let frame = self.gpu.frame_index();
+        let offset = match self.ring.reserve(frame, size) {
+            Err(Overflow::InFlight) => {
+                self.gpu.wait_idle(QueueKind::Graphics)?;
+                self.ring.release_in_flight(frame);
+                self.ring.reserve(frame, size)
+            }
+            result => result,
+        };
+        offset.map_err(|_| {
+            EngineError::InvalidArgument(format!(
+                "frame uploader capacity of {capacity} bytes exceeded within a single frame"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_are_aligned() {
+        let mut ring = Ring::new(1024, 256);
+        assert_eq!(ring.reserve(0, 10), Ok(0));
+        assert_eq!(ring.reserve(0, 10), Ok(256));
+        assert_eq!(ring.reserve(0, 256), Ok(512));
+        assert_eq!(ring.reserve(0, 1), Ok(768));
+    }
+
+    #[test]
+    fn wraps_to_the_start_once_old_frames_retire() {
+        let mut ring = Ring::new(1024, 256);
+        assert_eq!(ring.reserve(0, 512), Ok(0));
+        assert_eq!(ring.reserve(1, 256), Ok(512));
+        assert_eq!(ring.reserve(2, 256), Ok(768));
+        assert_eq!(ring.reserve(2, 256), Ok(0));
+        assert_eq!(ring.reserve(3, 256), Ok(256));
+    }
+
+    #[test]
+    fn does_not_overwrite_an_in_flight_frame() {
+        let mut ring = Ring::new(1024, 256);
+        assert_eq!(ring.reserve(0, 256), Ok(0));
+        assert_eq!(ring.reserve(1, 768), Ok(256));
+        assert_eq!(ring.reserve(1, 256), Err(Overflow::InFlight));
+        ring.release_in_flight(1);
+        assert_eq!(ring.reserve(1, 256), Ok(0));
+    }
+
+    #[test]
+    fn keeps_the_gap_between_head_and_an_in_flight_tail() {
+        let mut ring = Ring::new(1024, 256);
+        assert_eq!(ring.reserve(0, 768), Ok(0));
+        assert_eq!(ring.reserve(1, 256), Ok(768));
+        assert_eq!(ring.reserve(2, 256), Ok(0));
+        assert_eq!(ring.reserve(2, 768), Err(Overflow::InFlight));
+        assert_eq!(ring.reserve(2, 512), Ok(256));
+    }
+
+    #[test]
+    fn reports_a_single_frame_overflow() {
+        let mut ring = Ring::new(1024, 256);
+        assert_eq!(ring.reserve(0, 768), Ok(0));
+        assert_eq!(ring.reserve(0, 512), Err(Overflow::Frame));
+        assert_eq!(ring.reserve(0, 256), Ok(768));
+        assert_eq!(ring.reserve(0, 1), Err(Overflow::Frame));
+    }
+}
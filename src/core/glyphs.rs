@@ -0,0 +1,122 @@
+const GLYPH_SPACING: f32 = 0.25;
+const LINE_SPACING: f32 = 0.75;
+
+// Glyphs are drawn from the segments of a 16-segment display inside a 1x2 cell,
+// plus a dot. Each character in a glyph string names one segment.
+const SEGMENTS: [(char, [f32; 2], [f32; 2]); 17] = [
+    ('a', [0.0, 2.0], [0.5, 2.0]),
+    ('b', [0.5, 2.0], [1.0, 2.0]),
+    ('c', [1.0, 2.0], [1.0, 1.0]),
+    ('d', [1.0, 1.0], [1.0, 0.0]),
+    ('e', [0.5, 0.0], [1.0, 0.0]),
+    ('f', [0.0, 0.0], [0.5, 0.0]),
+    ('g', [0.0, 1.0], [0.0, 0.0]),
+    ('h', [0.0, 2.0], [0.0, 1.0]),
+    ('i', [0.0, 1.0], [0.5, 1.0]),
+    ('j', [0.5, 1.0], [1.0, 1.0]),
+    ('k', [0.0, 2.0], [0.5, 1.0]),
+    ('l', [0.5, 2.0], [0.5, 1.0]),
+    ('m', [1.0, 2.0], [0.5, 1.0]),
+    ('n', [0.5, 1.0], [1.0, 0.0]),
+    ('o', [0.5, 1.0], [0.5, 0.0]),
+    ('p', [0.5, 1.0], [0.0, 0.0]),
+    ('q', [0.4, 0.0], [0.6, 0.0]),
+];
+
+fn glyph(character: char) -> &'static str {
+    match character.to_ascii_uppercase() {
+        '0' => "abcdefghmp",
+        '1' => "cdm",
+        '2' => "abcijgef",
+        '3' => "abcdefj",
+        '4' => "hijcd",
+        '5' | 'S' => "abhijdef",
+        '6' => "abhgefdij",
+        '7' => "abcd",
+        '8' => "abcdefghij",
+        '9' => "abcdefhij",
+        'A' => "abcdghij",
+        'B' => "abcdefloj",
+        'C' => "abefgh",
+        'D' => "abcdeflo",
+        'E' => "abefghi",
+        'F' => "abghi",
+        'G' => "abefghdj",
+        'H' => "ghcdij",
+        'I' => "abeflo",
+        'J' => "cdefg",
+        'K' => "ghimn",
+        'L' => "ghef",
+        'M' => "ghcdkm",
+        'N' => "ghcdkn",
+        'O' => "abcdefgh",
+        'P' => "abghcij",
+        'Q' => "abcdefghn",
+        'R' => "abghcijn",
+        'T' => "ablo",
+        'U' => "ghcdef",
+        'V' => "ghmp",
+        'W' => "ghcdnp",
+        'X' => "kmnp",
+        'Y' => "kmo",
+        'Z' => "abmpef",
+        '-' => "ij",
+        '+' => "ijlo",
+        '=' => "ijef",
+        '_' => "ef",
+        '/' => "mp",
+        '\\' => "kn",
+        '*' => "ijklmnop",
+        '|' => "lo",
+        '(' | '<' => "mn",
+        ')' | '>' => "kp",
+        '\'' => "l",
+        '"' => "hl",
+        ':' => "lq",
+        '.' | ',' => "q",
+        _ => "",
+    }
+}
+
+pub(crate) fn advance(size: f32) -> f32 {
+    (1.0 + GLYPH_SPACING) * size / 2.0
+}
+
+pub(crate) fn line_height(size: f32) -> f32 {
+    (1.0 + LINE_SPACING) * size
+}
+
+pub(crate) fn extent(text: &str, size: f32) -> [f32; 2] {
+    let lines = text.lines().count();
+    let columns = text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    if lines == 0 || columns == 0 {
+        return [0.0, 0.0];
+    }
+    [
+        columns as f32 * advance(size) - GLYPH_SPACING * size / 2.0,
+        (lines - 1) as f32 * line_height(size) + size,
+    ]
+}
+
+pub(crate) fn segments(text: &str, size: f32) -> Vec<[[f32; 2]; 2]> {
+    let scale = size / 2.0;
+    let mut segments = Vec::new();
+    for (row, line) in text.lines().enumerate() {
+        let y = -(row as f32) * line_height(size);
+        for (column, character) in line.chars().enumerate() {
+            let x = column as f32 * advance(size);
+            for segment in glyph(character).chars() {
+                let (_, start, end) = SEGMENTS.iter().find(|(name, ..)| *name == segment).unwrap();
+                segments.push([
+                    [x + start[0] * scale, y + start[1] * scale],
+                    [x + end[0] * scale, y + end[1] * scale],
+                ]);
+            }
+        }
+    }
+    segments
+}
@@ -0,0 +1,195 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::offscreen_target::OffscreenTarget;
+use crate::core::renderer::{RenderParams, Renderer};
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+const UPDATE_VAR: &str = "UPDATE_GOLDEN";
+
+pub struct GoldenImage {
+    path: PathBuf,
+    tolerance: u8,
+    max_mismatch: f32,
+}
+
+impl GoldenImage {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            tolerance: 2,
+            max_mismatch: 0.0,
+        }
+    }
+
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_max_mismatch(mut self, max_mismatch: f32) -> Self {
+        self.max_mismatch = max_mismatch;
+        self
+    }
+
+    pub fn check<Vertex>(
+        &self,
+        target: &OffscreenTarget,
+        renderer: &Renderer,
+        render_params: RenderParams<Vertex>,
+    ) -> Result<()> {
+        let actual = target.capture(renderer, render_params)?;
+        self.compare(&actual)
+    }
+
+    pub fn compare(&self, actual: &RgbaImage) -> Result<()> {
+        if std::env::var_os(UPDATE_VAR).is_some() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            actual.save(&self.path)?;
+            return Ok(());
+        }
+        if !self.path.exists() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            actual.save(self.sibling("actual"))?;
+            return Err(EngineError::ImageMismatch(format!(
+                "{}: reference image is missing, rerun with {UPDATE_VAR}=1 to create it from {}",
+                self.path.display(),
+                self.sibling("actual").display()
+            )));
+        }
+
+        let expected = image::open(&self.path)?.into_rgba8();
+        if expected.dimensions() != actual.dimensions() {
+            actual.save(self.sibling("actual"))?;
+            return Err(EngineError::ImageMismatch(format!(
+                "{}: expected {:?} pixels, got {:?}",
+                self.path.display(),
+                expected.dimensions(),
+                actual.dimensions()
+            )));
+        }
+
+        let mut mismatched = 0;
+        let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+            let actual = actual.get_pixel(x, y).0;
+            let expected = expected.get_pixel(x, y).0;
+            let delta = actual
+                .iter()
+                .zip(expected)
+                .map(|(&a, b)| a.abs_diff(b))
+                .max()
+                .unwrap();
+            if delta > self.tolerance {
+                mismatched += 1;
+                Rgba([255, 0, 255, 255])
+            } else {
+                let [r, g, b, _] = expected;
+                let luma = ((r as u32 * 2 + g as u32 * 5 + b as u32) / 8 / 4) as u8;
+                Rgba([luma, luma, luma, 255])
+            }
+        });
+
+        let total = (actual.width() * actual.height()).max(1);
+        let ratio = mismatched as f32 / total as f32;
+        if ratio > self.max_mismatch {
+            actual.save(self.sibling("actual"))?;
+            diff.save(self.sibling("diff"))?;
+            return Err(EngineError::ImageMismatch(format!(
+                "{}: {mismatched} of {total} pixels differ by more than {} ({:.3}%), see {}",
+                self.path.display(),
+                self.tolerance,
+                ratio * 100.0,
+                self.sibling("diff").display()
+            )));
+        }
+        Ok(())
+    }
+
+    fn sibling(&self, suffix: &str) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.path.with_file_name(format!("{stem}.{suffix}.png"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("golden-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("reference.png")
+    }
+
+    fn solid(width: u32, height: u32, value: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([value, value, value, 255]))
+    }
+
+    #[test]
+    fn differences_within_tolerance_pass() {
+        let path = scratch("tolerance");
+        solid(4, 4, 100).save(&path).unwrap();
+        let golden = GoldenImage::new(&path).with_tolerance(3);
+        assert!(golden.compare(&solid(4, 4, 103)).is_ok());
+        assert!(!golden.sibling("diff").exists());
+        assert!(matches!(
+            golden.compare(&solid(4, 4, 104)),
+            Err(EngineError::ImageMismatch(_))
+        ));
+        assert!(golden.sibling("diff").exists());
+        assert!(golden.sibling("actual").exists());
+    }
+
+    #[test]
+    fn mismatch_ratio_is_bounded_by_max_mismatch() {
+        let path = scratch("ratio");
+        solid(4, 4, 0).save(&path).unwrap();
+        let mut actual = solid(4, 4, 0);
+        actual.put_pixel(0, 0, Rgba([255, 255, 255, 255]));
+        let golden = GoldenImage::new(&path).with_max_mismatch(1.0 / 16.0);
+        assert!(golden.compare(&actual).is_ok());
+        actual.put_pixel(1, 0, Rgba([255, 255, 255, 255]));
+        assert!(golden.compare(&actual).is_err());
+
+        let diff = image::open(golden.sibling("diff")).unwrap().into_rgba8();
+        assert_eq!(diff.get_pixel(0, 0), &Rgba([255, 0, 255, 255]));
+        assert_eq!(diff.get_pixel(2, 0), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn dimension_mismatch_fails_and_saves_actual() {
+        let path = scratch("dimensions");
+        solid(4, 4, 0).save(&path).unwrap();
+        let golden = GoldenImage::new(&path);
+        assert!(matches!(
+            golden.compare(&solid(2, 4, 0)),
+            Err(EngineError::ImageMismatch(_))
+        ));
+        let actual = image::open(golden.sibling("actual")).unwrap();
+        assert_eq!((actual.width(), actual.height()), (2, 4));
+        assert!(!golden.sibling("diff").exists());
+    }
+
+    #[test]
+    fn missing_reference_fails_without_blessing() {
+        if std::env::var_os(UPDATE_VAR).is_some() {
+            return;
+        }
+        let path = scratch("missing");
+        let golden = GoldenImage::new(&path);
+        assert!(matches!(
+            golden.compare(&solid(4, 4, 0)),
+            Err(EngineError::ImageMismatch(_))
+        ));
+        assert!(!path.exists());
+        assert!(golden.sibling("actual").exists());
+    }
+}
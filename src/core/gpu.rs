@@ -1,26 +1,117 @@
+use crate::core::descriptor_allocator::DescriptorAllocator;
+use crate::core::device_selector::{AdapterInfo, DeviceSelector};
 use crate::core::driver::Driver;
+use crate::core::error::{EngineError, Result};
+use crate::core::external_memory::{self, ExportedMemory};
+#[cfg(feature = "renderdoc")]
+use crate::core::frame_capture::{FrameCapture, InputButton};
+use crate::core::frame_pool::{FrameFence, FramePool};
+use crate::core::frame_stats::PresentFence;
+use crate::core::memory_budget::{MemoryBudgetWatcher, MemoryReport};
+use crate::core::readback::Readback;
+use crate::core::resource_tracker::{GpuResourceKind, ResourceReport, ResourceTracker};
+use crate::core::submission::Submission;
+use crate::core::timeline::Timeline;
+use crate::core::upload_queue::UploadQueue;
 use std::any::Any;
 use std::sync::Arc;
+use std::time::Duration;
 use vulkano::buffer::{
     AllocateBufferError, Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer,
 };
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
 use vulkano::command_buffer::{
-    AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer,
+    AutoCommandBufferBuilder, CommandBuffer, CommandBufferBeginInfo, CommandBufferLevel,
+    CommandBufferUsage, CopyBufferInfo, CopyImageToBufferInfo, PrimaryAutoCommandBuffer,
+    PrimaryCommandBufferAbstract, RecordingCommandBuffer,
 };
-use vulkano::device::physical::PhysicalDevice;
-use vulkano::device::Queue;
-use vulkano::image::{Image, ImageUsage};
-use vulkano::memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator};
+use vulkano::descriptor_set::layout::DescriptorSetLayout;
+use vulkano::descriptor_set::{DescriptorSet, WriteDescriptorSet};
+use vulkano::device::physical::{PhysicalDevice, PhysicalDeviceType};
+use vulkano::device::{Device, DeviceOwned, Queue};
+use vulkano::format::Format;
+use vulkano::image::{
+    AllocateImageError, Image, ImageCreateFlags, ImageCreateInfo, ImageType, ImageUsage,
+};
+use vulkano::memory::allocator::{
+    AllocationCreateInfo, DeviceLayout, MemoryTypeFilter, StandardMemoryAllocator,
+};
+use vulkano::memory::{ExternalMemoryHandleType, MemoryPropertyFlags};
+use vulkano::pipeline::compute::ComputePipelineCreateInfo;
+use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
+use vulkano::pipeline::{ComputePipeline, PipelineLayout, PipelineShaderStageCreateInfo};
+use vulkano::shader::EntryPoint;
 use vulkano::swapchain::{FromWindowError, Surface, SurfaceInfo, Swapchain, SwapchainCreateInfo};
 use vulkano::sync::GpuFuture;
-use vulkano::{sync, Validated, VulkanError};
+use vulkano::sync::{
+    AccessFlags, BufferMemoryBarrier, DependencyInfo, PipelineStages, QueueFamilyOwnershipTransfer,
+};
+use vulkano::{sync, DeviceSize, Validated, VulkanError, VulkanObject};
 use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
-pub struct Gpu {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QueueKind {
+    Graphics,
+    Compute,
+    Transfer,
+}
+
+struct GpuQueue {
+    queue: Arc<Queue>,
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    timeline: Option<Timeline>,
+}
+
+impl GpuQueue {
+    fn new(queue: Arc<Queue>) -> Result<Self> {
+        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
+            queue.device().clone(),
+            Default::default(),
+        ));
+        let timeline = Timeline::new(&queue)?;
+        Ok(Self {
+            queue,
+            command_buffer_allocator,
+            timeline,
+        })
+    }
+
+    fn timeline(&self) -> Result<&Timeline> {
+        self.timeline.as_ref().ok_or_else(|| {
+            EngineError::Unsupported("the device does not support timeline semaphores".into())
+        })
+    }
+
+    fn record(
+        &self,
+        record: impl FnOnce(&mut RecordingCommandBuffer) -> Result<()>,
+    ) -> Result<CommandBuffer> {
+        let mut recorder = RecordingCommandBuffer::new(
+            self.command_buffer_allocator.clone(),
+            self.queue.queue_family_index(),
+            CommandBufferLevel::Primary,
+            CommandBufferBeginInfo {
+                usage: CommandBufferUsage::OneTimeSubmit,
+                ..Default::default()
+            },
+        )?;
+        record(&mut recorder)?;
+        Ok(unsafe { recorder.end() }?)
+    }
+}
+
+pub struct Gpu {
+    descriptor_allocator: DescriptorAllocator,
     memory_allocator: Arc<StandardMemoryAllocator>,
-    pub queue: Arc<Queue>,
+    frame_pool: FramePool,
+    graphics: GpuQueue,
+    compute: Option<GpuQueue>,
+    transfer: Option<GpuQueue>,
+    uploads: UploadQueue,
+    memory_budget: MemoryBudgetWatcher,
+    resource_tracker: ResourceTracker,
+    #[cfg(feature = "renderdoc")]
+    frame_capture: Option<FrameCapture>,
     driver: Arc<Driver>,
 }
 
@@ -29,24 +120,82 @@ impl Gpu {
         driver: Arc<Driver>,
         physical_device: Arc<PhysicalDevice>,
         queue_family_index: u32,
-    ) -> anyhow::Result<Self> {
+    ) -> Result<Self> {
+        Self::with_selector(
+            driver,
+            physical_device,
+            queue_family_index,
+            &DeviceSelector::default(),
+        )
+    }
+
+    pub fn with_selector(
+        driver: Arc<Driver>,
+        physical_device: Arc<PhysicalDevice>,
+        queue_family_index: u32,
+        selector: &DeviceSelector,
+    ) -> Result<Self> {
         let d = driver.clone();
-        let (device, mut queues) = d.create_device(physical_device, queue_family_index)?;
-        let queue = queues.next().unwrap();
+        let compute_family = Driver::compute_queue_family(&physical_device);
+        let transfer_family = Driver::transfer_queue_family(&physical_device);
+        let (device, queues) = d.create_device(
+            physical_device,
+            queue_family_index,
+            selector.required_features(),
+        )?;
+        let (mut graphics, mut compute, mut transfer) = (None, None, None);
+        for queue in queues {
+            let family = Some(queue.queue_family_index());
+            let slot = if graphics.is_none() {
+                &mut graphics
+            } else if family == compute_family {
+                &mut compute
+            } else if family == transfer_family {
+                &mut transfer
+            } else {
+                continue;
+            };
+            *slot = Some(GpuQueue::new(queue)?);
+        }
         let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
-        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
-            device.clone(),
-            Default::default(),
-        ));
+
+        let descriptor_allocator = DescriptorAllocator::new(&device);
+
+        let frame_pool = FramePool::new(&device, &memory_allocator);
+
+        tracing::info!(
+            adapter = %device.physical_device().properties().device_name,
+            compute_queue = compute.is_some(),
+            transfer_queue = transfer.is_some(),
+            "created device"
+        );
 
         Ok(Gpu {
-            command_buffer_allocator,
+            descriptor_allocator,
             memory_allocator,
-            queue,
+            frame_pool,
+            graphics: graphics.unwrap(),
+            compute,
+            transfer,
+            uploads: UploadQueue::default(),
+            memory_budget: MemoryBudgetWatcher::default(),
+            resource_tracker: ResourceTracker::default(),
+            #[cfg(feature = "renderdoc")]
+            frame_capture: FrameCapture::attach(),
             driver,
         })
     }
 
+    pub fn headless() -> Result<Self> {
+        let driver = Arc::new(Driver::headless()?);
+        let Some((physical_device, queue_family_index)) = driver.request_headless_device() else {
+            return Err(EngineError::Unsupported(
+                "no suitable physical device found".into(),
+            ));
+        };
+        Self::new(driver, physical_device, queue_family_index)
+    }
+
     pub(crate) fn create_surface(
         &self,
         window: Arc<impl HasWindowHandle + HasDisplayHandle + Any + Send + Sync>,
@@ -57,54 +206,352 @@ impl Gpu {
     pub(crate) fn create_swapchain(
         &self,
         surface: Arc<Surface>,
-        image_extent: [u32; 2],
-        image_usage: ImageUsage,
+        create_info: SwapchainCreateInfo,
     ) -> Result<(Arc<Swapchain>, Vec<Arc<Image>>), Validated<VulkanError>> {
         let surface_capabilities = self
-            .queue
             .device()
             .physical_device()
             .surface_capabilities(&surface, SurfaceInfo::default())?;
 
-        let (image_format, _) = self
-            .queue
-            .device()
-            .physical_device()
-            .surface_formats(&surface, Default::default())?[0];
-
         Swapchain::new(
-            self.queue.device().clone(),
+            self.device().clone(),
             surface,
             SwapchainCreateInfo {
-                min_image_count: surface_capabilities.min_image_count.max(2),
-                image_format,
-                image_extent,
-                image_usage,
-                composite_alpha: surface_capabilities
-                    .supported_composite_alpha
-                    .into_iter()
-                    .next()
-                    .unwrap(),
-
-                ..Default::default()
+                image_usage: create_info.image_usage & surface_capabilities.supported_usage_flags,
+                ..create_info
             },
         )
     }
 
-    pub(crate) fn now(&self) -> Box<dyn GpuFuture> {
-        sync::now(self.queue.device().clone()).boxed()
+    pub fn device(&self) -> &Arc<Device> {
+        self.graphics.queue.device()
+    }
+
+    pub fn driver(&self) -> &Arc<Driver> {
+        &self.driver
+    }
+
+    pub fn adapter_info(&self) -> AdapterInfo {
+        AdapterInfo::new(self.device().physical_device())
+    }
+
+    fn gpu_queue(&self, kind: QueueKind) -> &GpuQueue {
+        match kind {
+            QueueKind::Graphics => None,
+            QueueKind::Compute => self.compute.as_ref(),
+            QueueKind::Transfer => self.transfer.as_ref(),
+        }
+        .unwrap_or(&self.graphics)
+    }
+
+    pub fn queue(&self, kind: QueueKind) -> &Arc<Queue> {
+        &self.gpu_queue(kind).queue
+    }
+
+    pub fn has_dedicated_queue(&self, kind: QueueKind) -> bool {
+        match kind {
+            QueueKind::Graphics => true,
+            QueueKind::Compute => self.compute.is_some(),
+            QueueKind::Transfer => self.transfer.is_some(),
+        }
+    }
+
+    pub fn debug_utils_enabled(&self) -> bool {
+        self.device()
+            .instance()
+            .enabled_extensions()
+            .ext_debug_utils
+    }
+
+    pub fn set_debug_name<T: VulkanObject + DeviceOwned>(
+        &self,
+        object: &T,
+        name: &str,
+    ) -> Result<()> {
+        self.resource_tracker.rename(object, name);
+        if self.debug_utils_enabled() {
+            self.device()
+                .set_debug_utils_object_name(object, Some(name))?;
+        }
+        Ok(())
+    }
+
+    pub fn resource_tracking_enabled(&self) -> bool {
+        self.resource_tracker.is_enabled()
+    }
+
+    pub fn set_resource_tracking(&self, enabled: bool) {
+        self.resource_tracker.set_enabled(enabled);
+    }
+
+    pub fn track_resource<T: Any + Send + Sync>(
+        &self,
+        kind: GpuResourceKind,
+        resource: &Arc<T>,
+        label: &str,
+        size: DeviceSize,
+    ) {
+        self.resource_tracker.track(kind, resource, label, size);
+    }
+
+    pub fn dump_resources(&self) -> Result<ResourceReport> {
+        if !self.resource_tracker.is_enabled() {
+            return Err(EngineError::Unsupported(
+                "resource tracking is disabled, enable it with Gpu::set_resource_tracking".into(),
+            ));
+        }
+        Ok(self.resource_tracker.report())
+    }
+
+    fn track_buffer<T: ?Sized>(&self, buffer: &Subbuffer<T>, label: &str) {
+        self.resource_tracker.track(
+            GpuResourceKind::Buffer,
+            buffer.buffer(),
+            label,
+            buffer.buffer().size(),
+        );
+    }
+
+    fn track_image(&self, image: &Arc<Image>, label: &str) {
+        let size = image
+            .memory_requirements()
+            .iter()
+            .map(|requirements| requirements.layout.size())
+            .sum();
+        self.resource_tracker
+            .track(GpuResourceKind::Image, image, label, size);
+    }
+
+    #[cfg(feature = "renderdoc")]
+    pub fn renderdoc_attached(&self) -> bool {
+        self.frame_capture.is_some()
+    }
+
+    #[cfg(feature = "renderdoc")]
+    fn frame_capture(&self) -> Result<&FrameCapture> {
+        match &self.frame_capture {
+            Some(frame_capture) => Ok(frame_capture),
+            None => Err(EngineError::Unsupported(
+                "RenderDoc is not attached, launch the application from RenderDoc".into(),
+            )),
+        }
+    }
+
+    #[cfg(feature = "renderdoc")]
+    pub fn trigger_capture(&self) -> Result<()> {
+        self.trigger_multi_frame_capture(1)
+    }
+
+    #[cfg(feature = "renderdoc")]
+    pub fn trigger_multi_frame_capture(&self, frames: u32) -> Result<()> {
+        if frames == 0 {
+            return Err(EngineError::InvalidArgument(
+                "a capture must span at least one frame".into(),
+            ));
+        }
+        self.frame_capture()?.trigger(frames);
+        Ok(())
+    }
+
+    #[cfg(feature = "renderdoc")]
+    pub fn set_capture_keys(&self, keys: &[InputButton]) -> Result<()> {
+        self.frame_capture()?.set_keys(keys);
+        Ok(())
+    }
+
+    #[cfg(feature = "renderdoc")]
+    pub fn start_capture(&self) -> Result<()> {
+        self.frame_capture()?.start();
+        Ok(())
+    }
+
+    #[cfg(feature = "renderdoc")]
+    pub fn end_capture(&self) -> Result<()> {
+        self.frame_capture()?.end()
+    }
+
+    pub fn memory_report(&self) -> Option<MemoryReport> {
+        MemoryReport::query(self.device())
+    }
+
+    pub fn set_memory_budget_callback(
+        &self,
+        threshold: f32,
+        callback: impl Fn(&MemoryReport) + Send + Sync + 'static,
+    ) -> Result<()> {
+        if !self.device().enabled_extensions().ext_memory_budget {
+            return Err(EngineError::Unsupported(
+                "the device does not support VK_EXT_memory_budget".into(),
+            ));
+        }
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(EngineError::InvalidArgument(format!(
+                "memory budget threshold {threshold} is not in (0, 1]"
+            )));
+        }
+        self.memory_budget.set(threshold, Box::new(callback));
+        Ok(())
+    }
+
+    pub fn clear_memory_budget_callback(&self) {
+        self.memory_budget.clear();
+    }
+
+    pub(crate) fn poll_memory_budget(&self) {
+        self.memory_budget.poll(self.device());
+    }
+
+    pub(crate) fn now(&self) -> Box<dyn GpuFuture + Send + Sync> {
+        sync::now(self.device().clone()).boxed_send_sync()
     }
 
     pub(crate) fn create_command_buffer_builder(
         &self,
+        kind: QueueKind,
     ) -> Result<AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>, Validated<VulkanError>> {
+        let gpu_queue = self.gpu_queue(kind);
         AutoCommandBufferBuilder::primary(
-            self.command_buffer_allocator.clone(),
-            self.queue.queue_family_index(),
+            gpu_queue.command_buffer_allocator.clone(),
+            gpu_queue.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+    }
+
+    pub(crate) fn create_frame_command_buffer_builder(
+        &self,
+        kind: QueueKind,
+    ) -> Result<AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>, Validated<VulkanError>> {
+        AutoCommandBufferBuilder::primary(
+            self.frame_pool.command_buffer_allocator(),
+            self.queue(kind).queue_family_index(),
             CommandBufferUsage::OneTimeSubmit,
         )
     }
 
+    pub(crate) fn frame_index(&self) -> u64 {
+        self.frame_pool.frame()
+    }
+
+    pub(crate) fn end_frame(&self, fence: Option<PresentFence>) -> Result<()> {
+        self.descriptor_allocator.end_frame();
+        let Some(timeline) = &self.graphics.timeline else {
+            return self
+                .frame_pool
+                .end_frame(fence.map(FrameFence::Present), None);
+        };
+        self.uploads.retire(timeline)?;
+        let value = timeline.signal(&self.graphics.queue)?;
+        self.frame_pool
+            .end_frame(Some(FrameFence::Timeline(value)), Some(timeline))
+    }
+
+    pub(crate) fn submit_and_wait(
+        &self,
+        kind: QueueKind,
+        command_buffer: Arc<PrimaryAutoCommandBuffer>,
+    ) -> Result<()> {
+        let gpu_queue = self.gpu_queue(kind);
+        let Some(timeline) = &gpu_queue.timeline else {
+            command_buffer
+                .execute(gpu_queue.queue.clone())?
+                .then_signal_fence_and_flush()?
+                .wait(None)?;
+            return Ok(());
+        };
+        let future = command_buffer.execute(gpu_queue.queue.clone())?;
+        future.flush()?;
+        let value = timeline.signal(&gpu_queue.queue)?;
+        timeline.wait(value, None)?;
+        unsafe { future.signal_finished() };
+        Ok(())
+    }
+
+    pub fn immediate(
+        &self,
+        record: impl FnOnce(&mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) -> Result<()>,
+    ) -> Result<()> {
+        self.immediate_on(QueueKind::Graphics, record)
+    }
+
+    pub fn immediate_on(
+        &self,
+        kind: QueueKind,
+        record: impl FnOnce(&mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) -> Result<()>,
+    ) -> Result<()> {
+        let mut builder = self.create_command_buffer_builder(kind)?;
+        record(&mut builder)?;
+        self.submit_and_wait(kind, builder.build()?)
+    }
+
+    pub fn immediate_async(
+        &self,
+        kind: QueueKind,
+        record: impl FnOnce(&mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) -> Result<()>,
+    ) -> Result<Submission> {
+        let mut builder = self.create_command_buffer_builder(kind)?;
+        record(&mut builder)?;
+        let future = builder
+            .build()?
+            .execute(self.queue(kind).clone())?
+            .boxed_send_sync()
+            .then_signal_fence_and_flush()?;
+        Ok(Submission::new(future))
+    }
+
+    pub fn timeline_semaphores_enabled(&self) -> bool {
+        self.graphics.timeline.is_some()
+    }
+
+    pub fn signal_timeline(&self, kind: QueueKind) -> Result<u64> {
+        let gpu_queue = self.gpu_queue(kind);
+        gpu_queue.timeline()?.signal(&gpu_queue.queue)
+    }
+
+    pub fn signaled_timeline_value(&self, kind: QueueKind) -> Result<u64> {
+        Ok(self.gpu_queue(kind).timeline()?.signaled())
+    }
+
+    pub fn completed_timeline_value(&self, kind: QueueKind) -> Result<u64> {
+        self.gpu_queue(kind).timeline()?.completed()
+    }
+
+    pub fn wait_timeline_value(
+        &self,
+        kind: QueueKind,
+        value: u64,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        let timeline = self.gpu_queue(kind).timeline()?;
+        if value > timeline.signaled() {
+            return Err(EngineError::InvalidArgument(format!(
+                "timeline value {value} has not been signaled yet"
+            )));
+        }
+        timeline.wait(value, timeout)
+    }
+
+    pub fn wait_idle(&self, kind: QueueKind) -> Result<()> {
+        let gpu_queue = self.gpu_queue(kind);
+        match &gpu_queue.timeline {
+            Some(timeline) => timeline.wait(timeline.signal(&gpu_queue.queue)?, None),
+            None => Ok(gpu_queue.queue.with(|mut queue| queue.wait_idle())?),
+        }
+    }
+
+    fn image_create_info(&self, usage: ImageUsage) -> ImageCreateInfo {
+        ImageCreateInfo {
+            usage,
+            ..Default::default()
+        }
+    }
+
+    fn prefers_staging(&self) -> bool {
+        !matches!(
+            self.device().physical_device().properties().device_type,
+            PhysicalDeviceType::IntegratedGpu | PhysicalDeviceType::Cpu
+        )
+    }
+
     pub(crate) fn create_buffer<T, I>(
         &self,
         data: I,
@@ -115,7 +562,7 @@ impl Gpu {
         I: IntoIterator<Item = T>,
         I::IntoIter: ExactSizeIterator,
     {
-        Buffer::from_iter(
+        let buffer = Buffer::from_iter(
             self.memory_allocator.clone(),
             BufferCreateInfo {
                 usage,
@@ -127,6 +574,526 @@ impl Gpu {
                 ..Default::default()
             },
             data,
-        )
+        )?;
+        self.track_buffer(&buffer, "buffer");
+        Ok(buffer)
+    }
+
+    pub(crate) fn create_staging_buffer<T, I>(
+        &self,
+        data: I,
+    ) -> Result<Subbuffer<[T]>, Validated<AllocateBufferError>>
+    where
+        T: BufferContents,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let buffer = Buffer::from_iter(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+            data,
+        )?;
+        self.track_buffer(&buffer, "staging buffer");
+        Ok(buffer)
+    }
+
+    pub(crate) fn create_device_local_buffer<T, I>(
+        &self,
+        data: I,
+        usage: BufferUsage,
+    ) -> Result<Subbuffer<[T]>>
+    where
+        T: BufferContents,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        if !self.prefers_staging() {
+            return Ok(self.create_buffer(data, usage)?);
+        }
+        let staging = self.create_staging_buffer(data)?;
+        let buffer = Buffer::new_slice(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage: usage | BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
+                ..Default::default()
+            },
+            staging.len(),
+        )?;
+        self.track_buffer(&buffer, "device local buffer");
+        self.upload_buffer(staging, buffer.clone())?;
+        Ok(buffer)
+    }
+
+    /// Copies `staging` into `buffer` on the transfer queue and hands ownership to the graphics
+    /// queue, returning the graphics timeline value after which the buffer is ready. Graphics work
+    /// submitted afterwards is ordered behind the acquire, so callers only need the value to wait
+    /// from the CPU. Without a transfer queue or timeline semaphores the copy runs synchronously on
+    /// the graphics queue and `None` is returned.
+    pub(crate) fn upload_buffer<T: BufferContents>(
+        &self,
+        staging: Subbuffer<[T]>,
+        buffer: Subbuffer<[T]>,
+    ) -> Result<Option<u64>> {
+        let (Some(transfer), Some(timeline)) = (&self.transfer, &self.graphics.timeline) else {
+            self.immediate(|builder| {
+                builder.copy_buffer(CopyBufferInfo::buffers(staging, buffer))?;
+                Ok(())
+            })?;
+            return Ok(None);
+        };
+        let transfer_timeline = transfer.timeline()?;
+        let barrier = BufferMemoryBarrier {
+            queue_family_ownership_transfer: Some(
+                QueueFamilyOwnershipTransfer::ExclusiveBetweenLocal {
+                    src_index: transfer.queue.queue_family_index(),
+                    dst_index: self.graphics.queue.queue_family_index(),
+                },
+            ),
+            range: buffer.offset()..buffer.offset() + buffer.size(),
+            ..BufferMemoryBarrier::buffer(buffer.buffer().clone())
+        };
+        let release = transfer.record(|recorder| unsafe {
+            recorder.copy_buffer(&CopyBufferInfo::buffers(staging.clone(), buffer.clone()))?;
+            recorder.pipeline_barrier(&DependencyInfo {
+                buffer_memory_barriers: vec![BufferMemoryBarrier {
+                    src_stages: PipelineStages::ALL_TRANSFER,
+                    src_access: AccessFlags::TRANSFER_WRITE,
+                    ..barrier.clone()
+                }]
+                .into(),
+                ..Default::default()
+            })?;
+            Ok(())
+        })?;
+        let acquire = self.graphics.record(|recorder| unsafe {
+            recorder.pipeline_barrier(&DependencyInfo {
+                buffer_memory_barriers: vec![BufferMemoryBarrier {
+                    dst_stages: PipelineStages::ALL_COMMANDS,
+                    dst_access: AccessFlags::MEMORY_READ,
+                    ..barrier
+                }]
+                .into(),
+                ..Default::default()
+            })?;
+            Ok(())
+        })?;
+        let value = unsafe {
+            let copied = transfer_timeline.submit(&transfer.queue, &release, None)?;
+            timeline.submit(
+                &self.graphics.queue,
+                &acquire,
+                Some((transfer_timeline, copied)),
+            )?
+        };
+        self.uploads.retire(timeline)?;
+        self.uploads.push(
+            value,
+            [staging.buffer().clone(), buffer.buffer().clone()],
+            [release, acquire],
+        );
+        Ok(Some(value))
+    }
+
+    pub(crate) fn create_arena_block(
+        &self,
+        size: DeviceSize,
+        usage: BufferUsage,
+    ) -> Result<Subbuffer<[u8]>, Validated<AllocateBufferError>> {
+        let buffer = Buffer::new(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+            DeviceLayout::from_size_alignment(size, 256).unwrap(),
+        )?;
+        let buffer = Subbuffer::new(buffer);
+        self.track_buffer(&buffer, "arena block");
+        Ok(buffer)
+    }
+
+    pub(crate) fn create_mapped_buffer(
+        &self,
+        size: DeviceSize,
+        usage: BufferUsage,
+    ) -> Result<Subbuffer<[u8]>, Validated<AllocateBufferError>> {
+        let mut memory_type_filter =
+            MemoryTypeFilter::PREFER_DEVICE | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE;
+        memory_type_filter.required_flags |= MemoryPropertyFlags::HOST_COHERENT;
+        let buffer = Buffer::new(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter,
+                ..Default::default()
+            },
+            DeviceLayout::from_size_alignment(size, 256).unwrap(),
+        )?;
+        let buffer = Subbuffer::new(buffer);
+        self.track_buffer(&buffer, "mapped buffer");
+        Ok(buffer)
+    }
+
+    pub(crate) fn create_readback_buffer<T: BufferContents>(
+        &self,
+        len: DeviceSize,
+    ) -> Result<Subbuffer<[T]>, Validated<AllocateBufferError>> {
+        let buffer = Buffer::new_slice(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                    | MemoryTypeFilter::HOST_RANDOM_ACCESS,
+                ..Default::default()
+            },
+            len,
+        )?;
+        self.track_buffer(&buffer, "readback buffer");
+        Ok(buffer)
+    }
+
+    pub fn read_buffer<T: BufferContents + Copy>(&self, buffer: &Subbuffer<[T]>) -> Result<Vec<T>> {
+        self.read_buffer_async(buffer)?.wait()
+    }
+
+    pub fn read_buffer_async<T: BufferContents + Copy>(
+        &self,
+        buffer: &Subbuffer<[T]>,
+    ) -> Result<Readback<T>> {
+        if !buffer
+            .buffer()
+            .usage()
+            .intersects(BufferUsage::TRANSFER_SRC)
+        {
+            return Err(EngineError::InvalidArgument(
+                "buffers must be created with TRANSFER_SRC usage to be read back".into(),
+            ));
+        }
+        let staging = self.create_readback_buffer(buffer.len())?;
+        let mut builder = self.create_command_buffer_builder(QueueKind::Graphics)?;
+        builder.copy_buffer(CopyBufferInfo::buffers(buffer.clone(), staging.clone()))?;
+        self.submit_readback(builder, staging)
+    }
+
+    pub fn read_image(&self, image: &Arc<Image>) -> Result<Vec<u8>> {
+        self.read_image_async(image)?.wait()
+    }
+
+    pub fn read_image_async(&self, image: &Arc<Image>) -> Result<Readback<u8>> {
+        if !image.usage().intersects(ImageUsage::TRANSFER_SRC) {
+            return Err(EngineError::InvalidArgument(
+                "images must be created with TRANSFER_SRC usage to be read back".into(),
+            ));
+        }
+        let format = image.format();
+        if format.aspects().count() > 1 {
+            return Err(EngineError::Unsupported(format!(
+                "reading back {format:?} images is not supported"
+            )));
+        }
+        let extent = image.extent();
+        let blocks: DeviceSize = extent
+            .iter()
+            .zip(format.block_extent())
+            .map(|(&size, block)| size.div_ceil(block) as DeviceSize)
+            .product();
+        let staging = self.create_readback_buffer(blocks * format.block_size())?;
+        let mut copy = CopyImageToBufferInfo::image_buffer(image.clone(), staging.clone());
+        copy.regions[0].image_subresource.array_layers = 0..1;
+        let mut builder = self.create_command_buffer_builder(QueueKind::Graphics)?;
+        builder.copy_image_to_buffer(copy)?;
+        self.submit_readback(builder, staging)
+    }
+
+    fn submit_readback<T: BufferContents + Copy>(
+        &self,
+        builder: AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        staging: Subbuffer<[T]>,
+    ) -> Result<Readback<T>> {
+        let future = builder
+            .build()?
+            .execute(self.queue(QueueKind::Graphics).clone())?
+            .boxed_send_sync()
+            .then_signal_fence_and_flush()?;
+        Ok(Readback::new(staging, future))
+    }
+
+    pub(crate) fn create_uniform_buffer<T: BufferContents>(
+        &self,
+        data: T,
+    ) -> Result<Subbuffer<T>, Validated<AllocateBufferError>> {
+        let buffer = Buffer::from_data(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::UNIFORM_BUFFER,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+            data,
+        )?;
+        self.track_buffer(&buffer, "uniform buffer");
+        Ok(buffer)
+    }
+
+    pub(crate) fn create_descriptor_set(
+        &self,
+        layout: Arc<DescriptorSetLayout>,
+        writes: impl IntoIterator<Item = WriteDescriptorSet>,
+    ) -> Result<Arc<DescriptorSet>> {
+        self.descriptor_allocator.persistent(layout, writes)
+    }
+
+    pub(crate) fn create_frame_descriptor_set(
+        &self,
+        layout: Arc<DescriptorSetLayout>,
+        writes: impl IntoIterator<Item = WriteDescriptorSet>,
+    ) -> Result<Arc<DescriptorSet>> {
+        self.descriptor_allocator.transient(layout, writes)
+    }
+
+    pub(crate) fn cached_descriptor_set<I>(
+        &self,
+        layout: Arc<DescriptorSetLayout>,
+        resources: &[usize],
+        writes: impl FnOnce() -> I,
+    ) -> Result<Arc<DescriptorSet>>
+    where
+        I: IntoIterator<Item = WriteDescriptorSet>,
+    {
+        self.descriptor_allocator.cached(layout, resources, writes)
+    }
+
+    pub(crate) fn frame_uniform<T: BufferContents>(&self, data: T) -> Result<Subbuffer<T>> {
+        self.frame_pool.uniform(data)
+    }
+
+    pub(crate) fn frame_staging<T, I>(&self, data: I) -> Result<Subbuffer<[T]>>
+    where
+        T: BufferContents,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.frame_pool.staging(data)
+    }
+
+    pub(crate) fn create_image(
+        &self,
+        format: Format,
+        extent: [u32; 3],
+        mip_levels: u32,
+        usage: ImageUsage,
+    ) -> Result<Arc<Image>, Validated<AllocateImageError>> {
+        let image = Image::new(
+            self.memory_allocator.clone(),
+            ImageCreateInfo {
+                image_type: ImageType::Dim2d,
+                format,
+                extent,
+                mip_levels,
+                ..self.image_create_info(usage)
+            },
+            AllocationCreateInfo::default(),
+        )?;
+        self.track_image(&image, "image");
+        Ok(image)
+    }
+
+    pub(crate) fn create_layered_image(
+        &self,
+        format: Format,
+        extent: [u32; 2],
+        array_layers: u32,
+        usage: ImageUsage,
+    ) -> Result<Arc<Image>, Validated<AllocateImageError>> {
+        let image = Image::new(
+            self.memory_allocator.clone(),
+            ImageCreateInfo {
+                image_type: ImageType::Dim2d,
+                format,
+                extent: [extent[0], extent[1], 1],
+                array_layers,
+                ..self.image_create_info(usage)
+            },
+            AllocationCreateInfo::default(),
+        )?;
+        self.track_image(&image, "layered image");
+        Ok(image)
+    }
+
+    pub(crate) fn create_cubemap(
+        &self,
+        format: Format,
+        size: u32,
+        mip_levels: u32,
+        usage: ImageUsage,
+    ) -> Result<Arc<Image>, Validated<AllocateImageError>> {
+        let image = Image::new(
+            self.memory_allocator.clone(),
+            ImageCreateInfo {
+                flags: ImageCreateFlags::CUBE_COMPATIBLE,
+                image_type: ImageType::Dim2d,
+                format,
+                extent: [size, size, 1],
+                array_layers: 6,
+                mip_levels,
+                ..self.image_create_info(usage)
+            },
+            AllocationCreateInfo::default(),
+        )?;
+        self.track_image(&image, "cubemap");
+        Ok(image)
+    }
+
+    pub fn external_memory_handle_type(&self) -> Option<ExternalMemoryHandleType> {
+        external_memory::platform_handle_type(self.device())
+    }
+
+    fn external_image_create_info(
+        &self,
+        format: Format,
+        extent: [u32; 2],
+        usage: ImageUsage,
+    ) -> ImageCreateInfo {
+        ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format,
+            extent: [extent[0], extent[1], 1],
+            usage,
+            ..Default::default()
+        }
+    }
+
+    pub fn create_exportable_image(
+        &self,
+        format: Format,
+        extent: [u32; 2],
+        usage: ImageUsage,
+    ) -> Result<Arc<Image>> {
+        let create_info = self.external_image_create_info(format, extent, usage);
+        let image = unsafe { external_memory::create_image(self.device(), create_info, None) }?;
+        self.track_image(&image, "exportable image");
+        Ok(image)
+    }
+
+    /// # Safety
+    ///
+    /// `memory` must hold a valid handle to memory that was allocated for a resource created
+    /// with identical parameters, and it must not be used through the handle afterwards.
+    pub unsafe fn import_image(
+        &self,
+        format: Format,
+        extent: [u32; 2],
+        usage: ImageUsage,
+        memory: ExportedMemory,
+    ) -> Result<Arc<Image>> {
+        let create_info = self.external_image_create_info(format, extent, usage);
+        let image =
+            unsafe { external_memory::create_image(self.device(), create_info, Some(memory)) }?;
+        self.track_image(&image, "imported image");
+        Ok(image)
+    }
+
+    pub fn create_exportable_buffer(
+        &self,
+        size: DeviceSize,
+        usage: BufferUsage,
+    ) -> Result<Subbuffer<[u8]>> {
+        let create_info = BufferCreateInfo {
+            size,
+            usage,
+            ..Default::default()
+        };
+        let buffer = unsafe { external_memory::create_buffer(self.device(), create_info, None) }?;
+        let buffer = Subbuffer::new(buffer);
+        self.track_buffer(&buffer, "exportable buffer");
+        Ok(buffer)
+    }
+
+    /// # Safety
+    ///
+    /// `memory` must hold a valid handle to memory that was allocated for a resource created
+    /// with identical parameters, and it must not be used through the handle afterwards.
+    pub unsafe fn import_buffer(
+        &self,
+        size: DeviceSize,
+        usage: BufferUsage,
+        memory: ExportedMemory,
+    ) -> Result<Subbuffer<[u8]>> {
+        let create_info = BufferCreateInfo {
+            size,
+            usage,
+            ..Default::default()
+        };
+        let buffer =
+            unsafe { external_memory::create_buffer(self.device(), create_info, Some(memory)) }?;
+        let buffer = Subbuffer::new(buffer);
+        self.track_buffer(&buffer, "imported buffer");
+        Ok(buffer)
+    }
+
+    pub fn export_image(&self, image: &Image) -> Result<ExportedMemory> {
+        external_memory::export_image(image)
+    }
+
+    pub fn export_buffer<T: ?Sized>(&self, buffer: &Subbuffer<T>) -> Result<ExportedMemory> {
+        external_memory::export_buffer(buffer.buffer())
+    }
+
+    pub(crate) fn create_compute_pipeline(
+        &self,
+        entry_point: EntryPoint,
+    ) -> Result<Arc<ComputePipeline>, Validated<VulkanError>> {
+        let device = self.device().clone();
+        let stage = PipelineShaderStageCreateInfo::new(entry_point);
+        let layout = PipelineLayout::new(
+            device.clone(),
+            PipelineDescriptorSetLayoutCreateInfo::from_stages([&stage])
+                .into_pipeline_layout_create_info(device.clone())
+                .unwrap(),
+        )?;
+        let pipeline = ComputePipeline::new(
+            device,
+            None,
+            ComputePipelineCreateInfo::stage_layout(stage, layout),
+        )?;
+        self.track_resource(GpuResourceKind::Pipeline, &pipeline, "compute pipeline", 0);
+        Ok(pipeline)
+    }
+}
+
+impl Drop for Gpu {
+    fn drop(&mut self) {
+        if let Some(timeline) = &self.graphics.timeline
+            && let Err(e) = self.uploads.drain(timeline)
+        {
+            tracing::error!(error = %e, "failed to wait for pending uploads");
+        }
     }
 }
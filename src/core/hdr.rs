@@ -0,0 +1,154 @@
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::render_graph::{Attachment, RenderGraph, ResourceId};
+use crate::core::renderer::{create_pipeline, rendering_info};
+use crate::core::shaders::{fullscreen_vs, hdr_output_fs};
+use std::sync::Arc;
+use vulkano::buffer::BufferContents;
+use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::format::Format;
+use vulkano::image::sampler::{Sampler, SamplerCreateInfo};
+use vulkano::pipeline::graphics::vertex_input::VertexInputState;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint};
+use vulkano::swapchain::ColorSpace;
+
+pub(crate) const HDR_SCENE_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OutputColorSpace {
+    #[default]
+    Srgb,
+    Hdr10,
+    ScRgb,
+}
+
+impl OutputColorSpace {
+    pub fn is_hdr(self) -> bool {
+        self != Self::Srgb
+    }
+
+    pub(crate) fn surface_format(self) -> Option<(Format, ColorSpace)> {
+        match self {
+            Self::Srgb => None,
+            Self::Hdr10 => Some((Format::A2B10G10R10_UNORM_PACK32, ColorSpace::Hdr10St2084)),
+            Self::ScRgb => Some((Format::R16G16B16A16_SFLOAT, ColorSpace::ExtendedSrgbLinear)),
+        }
+    }
+
+    pub(crate) fn from_surface_format(format: Format, color_space: ColorSpace) -> Option<Self> {
+        [Self::Hdr10, Self::ScRgb]
+            .into_iter()
+            .find(|output| output.surface_format() == Some((format, color_space)))
+            .or((color_space == ColorSpace::SrgbNonLinear).then_some(Self::Srgb))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HdrSettings {
+    pub paper_white_nits: f32,
+    pub max_nits: f32,
+}
+
+impl Default for HdrSettings {
+    fn default() -> Self {
+        Self {
+            paper_white_nits: 203.0,
+            max_nits: 1000.0,
+        }
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct HdrOutputConstants {
+    color_space: u32,
+    paper_white_nits: f32,
+    max_nits: f32,
+}
+
+pub(crate) struct HdrOutputPipeline {
+    color_space: OutputColorSpace,
+    sampler: Arc<Sampler>,
+    pipeline: Arc<GraphicsPipeline>,
+    gpu: Arc<Gpu>,
+}
+
+impl HdrOutputPipeline {
+    pub(crate) fn new(
+        gpu: Arc<Gpu>,
+        image_format: Format,
+        color_space: OutputColorSpace,
+    ) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = fullscreen_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = hdr_output_fs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let pipeline = create_pipeline(
+            &gpu,
+            vs,
+            Some(fs),
+            VertexInputState::new(),
+            rendering_info(&[image_format], None),
+            None,
+        )?;
+        gpu.set_debug_name(&*pipeline, "hdr output")?;
+        let sampler = Sampler::new(device, SamplerCreateInfo::default())?;
+        Ok(Self {
+            color_space,
+            sampler,
+            pipeline,
+            gpu,
+        })
+    }
+
+    pub(crate) fn color_space(&self) -> OutputColorSpace {
+        self.color_space
+    }
+
+    pub(crate) fn add_pass<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        scene: ResourceId,
+        target: ResourceId,
+        settings: HdrSettings,
+    ) {
+        let color_space = match self.color_space {
+            OutputColorSpace::Hdr10 => 0,
+            _ => 1,
+        };
+        graph
+            .add_pass("hdr output")
+            .color_attachment(Attachment::clear(target, [0.0; 4]))
+            .sample(scene)
+            .record(move |ctx| {
+                let layout = self.pipeline.layout().clone();
+                let set = self.gpu.create_frame_descriptor_set(
+                    layout.set_layouts()[0].clone(),
+                    [WriteDescriptorSet::image_view_sampler(
+                        0,
+                        ctx.image_view(scene),
+                        self.sampler.clone(),
+                    )],
+                )?;
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?
+                    .bind_pipeline_graphics(self.pipeline.clone())?
+                    .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 0, set)?
+                    .push_constants(
+                        layout,
+                        0,
+                        HdrOutputConstants {
+                            color_space,
+                            paper_white_nits: settings.paper_white_nits.max(1.0),
+                            max_nits: settings.max_nits.max(settings.paper_white_nits),
+                        },
+                    )?;
+                unsafe { ctx.builder.draw(3, 1, 0, 0) }?;
+                Ok(())
+            });
+    }
+}
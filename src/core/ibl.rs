@@ -0,0 +1,162 @@
+use crate::core::cubemap::{Cubemap, CUBEMAP_FORMAT};
+use crate::core::error::Result;
+use crate::core::gpu::{Gpu, QueueKind};
+use crate::core::shaders::{brdf_lut_cs, irradiance_cs, prefilter_cs};
+use crate::core::texture::Texture;
+use std::sync::Arc;
+use vulkano::buffer::BufferContents;
+use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::format::Format;
+use vulkano::image::sampler::{Sampler, SamplerAddressMode, SamplerCreateInfo};
+use vulkano::image::view::{ImageView, ImageViewCreateInfo, ImageViewType};
+use vulkano::image::{Image, ImageAspects, ImageSubresourceRange, ImageUsage};
+use vulkano::pipeline::{ComputePipeline, Pipeline, PipelineBindPoint};
+
+pub const IRRADIANCE_SIZE: u32 = 32;
+pub const PREFILTERED_SIZE: u32 = 128;
+pub const PREFILTERED_MIP_LEVELS: u32 = 5;
+pub const BRDF_LUT_SIZE: u32 = 256;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct PrefilterConstants {
+    roughness: f32,
+}
+
+#[derive(Clone)]
+pub struct Environment {
+    pub irradiance: Cubemap,
+    pub prefiltered: Cubemap,
+    pub brdf_lut: Texture,
+}
+
+impl Environment {
+    pub fn new(gpu: Arc<Gpu>, source: &Cubemap) -> Result<Self> {
+        let device = gpu.device().clone();
+        let irradiance_pipeline = gpu.create_compute_pipeline(
+            irradiance_cs::load(device.clone())?
+                .entry_point("main")
+                .unwrap(),
+        )?;
+        let prefilter_pipeline = gpu.create_compute_pipeline(
+            prefilter_cs::load(device.clone())?
+                .entry_point("main")
+                .unwrap(),
+        )?;
+        let brdf_lut_pipeline = gpu.create_compute_pipeline(
+            brdf_lut_cs::load(device.clone())?
+                .entry_point("main")
+                .unwrap(),
+        )?;
+
+        let sampler = Sampler::new(
+            device,
+            SamplerCreateInfo {
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..SamplerCreateInfo::simple_repeat_linear()
+            },
+        )?;
+        let usage = ImageUsage::STORAGE | ImageUsage::SAMPLED;
+        let irradiance = gpu.create_cubemap(CUBEMAP_FORMAT, IRRADIANCE_SIZE, 1, usage)?;
+        let prefiltered = gpu.create_cubemap(
+            CUBEMAP_FORMAT,
+            PREFILTERED_SIZE,
+            PREFILTERED_MIP_LEVELS,
+            usage,
+        )?;
+        let brdf_lut = gpu.create_image(
+            Format::R16G16B16A16_SFLOAT,
+            [BRDF_LUT_SIZE, BRDF_LUT_SIZE, 1],
+            1,
+            usage,
+        )?;
+
+        let mut builder = gpu.create_command_buffer_builder(QueueKind::Graphics)?;
+        let mut dispatch_faces = |pipeline: &Arc<ComputePipeline>,
+                                  target: &Arc<Image>,
+                                  mip_level: u32,
+                                  roughness: Option<f32>|
+         -> Result<()> {
+            let layout = pipeline.layout().clone();
+            let set = gpu.create_descriptor_set(
+                layout.set_layouts()[0].clone(),
+                [
+                    WriteDescriptorSet::image_view_sampler(0, source.image_view(), sampler.clone()),
+                    WriteDescriptorSet::image_view(1, face_array_view(target, mip_level)?),
+                ],
+            )?;
+            builder
+                .bind_pipeline_compute(pipeline.clone())?
+                .bind_descriptor_sets(PipelineBindPoint::Compute, layout.clone(), 0, set)?;
+            if let Some(roughness) = roughness {
+                builder.push_constants(layout, 0, PrefilterConstants { roughness })?;
+            }
+            let groups = (target.extent()[0] >> mip_level)
+                .max(1)
+                .div_ceil(WORKGROUP_SIZE);
+            unsafe { builder.dispatch([groups, groups, 6]) }?;
+            Ok(())
+        };
+
+        dispatch_faces(&irradiance_pipeline, &irradiance, 0, None)?;
+        for mip_level in 0..PREFILTERED_MIP_LEVELS {
+            let roughness = mip_level as f32 / (PREFILTERED_MIP_LEVELS - 1) as f32;
+            dispatch_faces(
+                &prefilter_pipeline,
+                &prefiltered,
+                mip_level,
+                Some(roughness),
+            )?;
+        }
+
+        let brdf_lut_view = ImageView::new_default(brdf_lut)?;
+        let set = gpu.create_descriptor_set(
+            brdf_lut_pipeline.layout().set_layouts()[0].clone(),
+            [WriteDescriptorSet::image_view(0, brdf_lut_view.clone())],
+        )?;
+        builder
+            .bind_pipeline_compute(brdf_lut_pipeline.clone())?
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute,
+                brdf_lut_pipeline.layout().clone(),
+                0,
+                set,
+            )?;
+        let groups = BRDF_LUT_SIZE.div_ceil(WORKGROUP_SIZE);
+        unsafe { builder.dispatch([groups, groups, 1]) }?;
+
+        gpu.submit_and_wait(QueueKind::Graphics, builder.build()?)?;
+
+        Ok(Self {
+            irradiance: Cubemap::from_image(irradiance)?,
+            prefiltered: Cubemap::from_image(prefiltered)?,
+            brdf_lut: Texture::from_image_view(brdf_lut_view),
+        })
+    }
+
+    pub(crate) fn empty(gpu: Arc<Gpu>) -> Result<Self> {
+        let black = [0u8; 8 * 6];
+        Ok(Self {
+            irradiance: Cubemap::from_faces(gpu.clone(), 1, CUBEMAP_FORMAT, &black)?,
+            prefiltered: Cubemap::from_faces(gpu.clone(), 1, CUBEMAP_FORMAT, &black)?,
+            brdf_lut: Texture::from_pixels(gpu, [1, 1], Format::R16G16B16A16_SFLOAT, &black[..8])?,
+        })
+    }
+}
+
+fn face_array_view(image: &Arc<Image>, mip_level: u32) -> Result<Arc<ImageView>> {
+    Ok(ImageView::new(
+        image.clone(),
+        ImageViewCreateInfo {
+            view_type: ImageViewType::Dim2dArray,
+            subresource_range: ImageSubresourceRange {
+                aspects: ImageAspects::COLOR,
+                mip_levels: mip_level..mip_level + 1,
+                array_layers: 0..6,
+            },
+            ..ImageViewCreateInfo::from_image(image)
+        },
+    )?)
+}
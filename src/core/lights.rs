@@ -0,0 +1,174 @@
+use crate::core::error::{EngineError, Result};
+use glam::Vec3;
+use vulkano::buffer::BufferContents;
+
+pub const MAX_LIGHTS: usize = 64;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Light {
+    Directional {
+        direction: Vec3,
+        color: Vec3,
+        intensity: f32,
+    },
+    Point {
+        position: Vec3,
+        color: Vec3,
+        intensity: f32,
+        range: f32,
+    },
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        color: Vec3,
+        intensity: f32,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Lights {
+    lights: Vec<Option<Light>>,
+    pub ambient: Vec3,
+}
+
+impl Default for Lights {
+    fn default() -> Self {
+        Self {
+            lights: Vec::new(),
+            ambient: Vec3::splat(0.03),
+        }
+    }
+}
+
+impl Lights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, light: Light) -> Result<usize> {
+        if self.len() == MAX_LIGHTS {
+            return Err(EngineError::InvalidArgument(format!(
+                "cannot add more than {MAX_LIGHTS} lights"
+            )));
+        }
+        if let Some(index) = self.lights.iter().position(Option::is_none) {
+            self.lights[index] = Some(light);
+            return Ok(index);
+        }
+        self.lights.push(Some(light));
+        Ok(self.lights.len() - 1)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Light> {
+        self.lights.get(index)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Light> {
+        self.lights.get_mut(index)?.as_mut()
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Light> {
+        let light = self.lights.get_mut(index)?.take();
+        while self.lights.last().is_some_and(Option::is_none) {
+            self.lights.pop();
+        }
+        light
+    }
+
+    pub fn clear(&mut self) {
+        self.lights.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Light> {
+        self.lights.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    pub(crate) fn uniform(&self) -> LightsUniform {
+        let mut lights = [GpuLight::default(); MAX_LIGHTS];
+        for (gpu_light, light) in lights.iter_mut().zip(self.iter()) {
+            *gpu_light = GpuLight::from(light);
+        }
+        LightsUniform {
+            ambient: self.ambient.extend(0.0).to_array(),
+            count: [self.len() as u32, 0, 0, 0],
+            lights,
+        }
+    }
+}
+
+const DIRECTIONAL: f32 = 0.0;
+const POINT: f32 = 1.0;
+const SPOT: f32 = 2.0;
+
+#[derive(BufferContents, Clone, Copy, Default)]
+#[repr(C)]
+pub(crate) struct GpuLight {
+    position_range: [f32; 4],
+    direction_kind: [f32; 4],
+    color_intensity: [f32; 4],
+    cone: [f32; 4],
+}
+
+impl From<&Light> for GpuLight {
+    fn from(light: &Light) -> Self {
+        match *light {
+            Light::Directional {
+                direction,
+                color,
+                intensity,
+            } => Self {
+                position_range: [0.0; 4],
+                direction_kind: direction
+                    .normalize_or(Vec3::NEG_Z)
+                    .extend(DIRECTIONAL)
+                    .to_array(),
+                color_intensity: color.extend(intensity).to_array(),
+                cone: [0.0; 4],
+            },
+            Light::Point {
+                position,
+                color,
+                intensity,
+                range,
+            } => Self {
+                position_range: position.extend(range).to_array(),
+                direction_kind: [0.0, 0.0, 0.0, POINT],
+                color_intensity: color.extend(intensity).to_array(),
+                cone: [0.0; 4],
+            },
+            Light::Spot {
+                position,
+                direction,
+                color,
+                intensity,
+                range,
+                inner_angle,
+                outer_angle,
+            } => Self {
+                position_range: position.extend(range).to_array(),
+                direction_kind: direction.normalize_or(Vec3::NEG_Z).extend(SPOT).to_array(),
+                color_intensity: color.extend(intensity).to_array(),
+                cone: [inner_angle.cos(), outer_angle.cos(), 0.0, 0.0],
+            },
+        }
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct LightsUniform {
+    ambient: [f32; 4],
+    count: [u32; 4],
+    lights: [GpuLight; MAX_LIGHTS],
+}
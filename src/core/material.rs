@@ -0,0 +1,26 @@
+use crate::core::texture::Texture;
+
+#[derive(Clone)]
+pub struct Material {
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub base_color_map: Option<Texture>,
+    pub metallic_roughness_map: Option<Texture>,
+    pub normal_map: Option<Texture>,
+    pub normal_scale: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            base_color: [1.0; 4],
+            metallic: 0.0,
+            roughness: 0.5,
+            base_color_map: None,
+            metallic_roughness_map: None,
+            normal_map: None,
+            normal_scale: 1.0,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use ash::vk;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use vulkano::device::Device;
+use vulkano::{DeviceSize, Version, VulkanObject};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug)]
+pub struct HeapBudget {
+    pub heap_index: u32,
+    pub size: DeviceSize,
+    pub device_local: bool,
+    pub usage: DeviceSize,
+    pub budget: DeviceSize,
+}
+
+impl HeapBudget {
+    pub fn usage_ratio(&self) -> f32 {
+        if self.budget == 0 {
+            return 0.0;
+        }
+        self.usage as f32 / self.budget as f32
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MemoryReport {
+    pub heaps: Vec<HeapBudget>,
+}
+
+impl MemoryReport {
+    pub(crate) fn query(device: &Device) -> Option<Self> {
+        let physical_device = device.physical_device();
+        let instance = physical_device.instance();
+        if !device.enabled_extensions().ext_memory_budget || instance.api_version() < Version::V1_1
+        {
+            return None;
+        }
+        let mut budget = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+        let mut properties = vk::PhysicalDeviceMemoryProperties2::default().push_next(&mut budget);
+        unsafe {
+            (instance.fns().v1_1.get_physical_device_memory_properties2)(
+                physical_device.handle(),
+                &mut properties,
+            )
+        };
+        let memory_properties = properties.memory_properties;
+        let heaps = (0..memory_properties.memory_heap_count as usize)
+            .map(|i| {
+                let heap = memory_properties.memory_heaps[i];
+                HeapBudget {
+                    heap_index: i as u32,
+                    size: heap.size,
+                    device_local: heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL),
+                    usage: budget.heap_usage[i],
+                    budget: budget.heap_budget[i],
+                }
+            })
+            .collect();
+        Some(Self { heaps })
+    }
+
+    pub fn device_local_usage(&self) -> DeviceSize {
+        self.heaps
+            .iter()
+            .filter(|heap| heap.device_local)
+            .map(|heap| heap.usage)
+            .sum()
+    }
+
+    pub fn device_local_budget(&self) -> DeviceSize {
+        self.heaps
+            .iter()
+            .filter(|heap| heap.device_local)
+            .map(|heap| heap.budget)
+            .sum()
+    }
+
+    pub fn exceeds(&self, threshold: f32) -> bool {
+        self.heaps
+            .iter()
+            .any(|heap| heap.usage_ratio() >= threshold)
+    }
+}
+
+type BudgetCallback = Box<dyn Fn(&MemoryReport) + Send + Sync>;
+
+struct BudgetWatch {
+    threshold: f32,
+    callback: BudgetCallback,
+    last_poll: Option<Instant>,
+}
+
+#[derive(Default)]
+pub(crate) struct MemoryBudgetWatcher {
+    watch: Mutex<Option<BudgetWatch>>,
+}
+
+impl MemoryBudgetWatcher {
+    pub(crate) fn set(&self, threshold: f32, callback: BudgetCallback) {
+        *self.watch.lock().unwrap() = Some(BudgetWatch {
+            threshold,
+            callback,
+            last_poll: None,
+        });
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.watch.lock().unwrap() = None;
+    }
+
+    pub(crate) fn poll(&self, device: &Device) {
+        let mut watch = self.watch.lock().unwrap();
+        let Some(watch) = watch.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        if watch
+            .last_poll
+            .is_some_and(|last_poll| now - last_poll < POLL_INTERVAL)
+        {
+            return;
+        }
+        watch.last_poll = Some(now);
+        if let Some(report) = MemoryReport::query(device)
+            && report.exceeds(watch.threshold)
+        {
+            (watch.callback)(&report);
+        }
+    }
+}
@@ -1,4 +1,60 @@
+pub mod animation;
+pub mod anti_aliasing;
+pub mod atlas;
+pub(crate) mod bcn;
+pub mod buffer_arena;
+pub mod camera;
+pub(crate) mod capture;
+pub mod config;
+pub mod cubemap;
+pub mod culling;
+pub mod debug_draw;
+pub(crate) mod descriptor_allocator;
+pub mod device_selector;
 pub mod driver;
+pub mod error;
+pub mod external_memory;
+pub mod external_semaphore;
+#[cfg(feature = "renderdoc")]
+pub mod frame_capture;
+pub(crate) mod frame_pool;
+pub mod frame_stats;
+pub mod frame_uploader;
+pub(crate) mod glyphs;
+pub mod golden;
 pub mod gpu;
+pub mod hdr;
+pub mod ibl;
+pub mod lights;
+pub mod material;
+pub mod memory_budget;
+pub mod morph;
+pub mod occlusion;
+pub mod offscreen_target;
+pub mod overlay;
+pub mod picking;
+#[cfg(feature = "shaderc")]
+pub mod pipeline_registry;
+pub mod profiler;
+pub mod readback;
+pub mod render_graph;
 pub mod renderer;
+pub mod resource_tracker;
+#[cfg(feature = "shaderc")]
+pub mod shader_compiler;
+pub mod shader_preprocessor;
+pub mod shader_reflection;
+pub(crate) mod shaders;
+pub mod shading_rate;
+pub(crate) mod skybox;
+pub mod sprite_animation;
+pub mod ssao;
+pub mod storage_image;
+pub mod submission;
 pub mod swapchain_target;
+pub mod texture;
+pub(crate) mod timeline;
+pub mod transform;
+pub(crate) mod upload_queue;
+pub mod upscaling;
+pub mod vertex;
@@ -0,0 +1,160 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::renderer::Mesh;
+use crate::core::vertex::Vertex3D;
+use std::sync::Arc;
+use vulkano::buffer::{BufferContents, BufferUsage, IndexBuffer, Subbuffer};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer};
+use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::pipeline::{ComputePipeline, Pipeline, PipelineBindPoint};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct MorphDelta {
+    position: [f32; 4],
+    normal: [f32; 4],
+    tangent: [f32; 4],
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct MorphConstants {
+    vertex_count: u32,
+    target_count: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MorphTarget {
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub tangents: Vec<[f32; 3]>,
+}
+
+#[derive(Clone)]
+pub struct MorphedMesh {
+    base: Subbuffer<[Vertex3D]>,
+    deltas: Subbuffer<[MorphDelta]>,
+    names: Vec<String>,
+    weights: Vec<f32>,
+    mesh: Mesh<Vertex3D>,
+}
+
+impl MorphedMesh {
+    pub fn new<Index>(
+        gpu: Arc<Gpu>,
+        vertices: Vec<Vertex3D>,
+        indices: Vec<Index>,
+        targets: Vec<MorphTarget>,
+    ) -> Result<Self>
+    where
+        Index: BufferContents,
+        Subbuffer<[Index]>: Into<IndexBuffer>,
+    {
+        let vertex_count = vertices.len();
+        if targets.is_empty() {
+            return Err(EngineError::InvalidArgument(
+                "a morphed mesh needs at least one morph target".into(),
+            ));
+        }
+        let mut deltas = Vec::with_capacity(vertex_count * targets.len());
+        for target in &targets {
+            if target.positions.len() != vertex_count
+                || !target.normals.is_empty() && target.normals.len() != vertex_count
+                || !target.tangents.is_empty() && target.tangents.len() != vertex_count
+            {
+                return Err(EngineError::InvalidArgument(format!(
+                    "morph target `{}` does not match the mesh's {vertex_count} vertices",
+                    target.name
+                )));
+            }
+            let delta = |values: &[[f32; 3]], i: usize| {
+                let [x, y, z] = values.get(i).copied().unwrap_or_default();
+                [x, y, z, 0.0]
+            };
+            deltas.extend((0..vertex_count).map(|i| MorphDelta {
+                position: delta(&target.positions, i),
+                normal: delta(&target.normals, i),
+                tangent: delta(&target.tangents, i),
+            }));
+        }
+
+        let base = gpu.create_buffer(vertices.iter().copied(), BufferUsage::STORAGE_BUFFER)?;
+        let deltas = gpu.create_buffer(deltas, BufferUsage::STORAGE_BUFFER)?;
+        let output = gpu.create_buffer(
+            vertices,
+            BufferUsage::STORAGE_BUFFER | BufferUsage::VERTEX_BUFFER,
+        )?;
+        let index_buffer = gpu.create_buffer(indices, BufferUsage::INDEX_BUFFER)?;
+        Ok(Self {
+            base,
+            deltas,
+            weights: vec![0.0; targets.len()],
+            names: targets.into_iter().map(|target| target.name).collect(),
+            mesh: Mesh::from_buffers(output, index_buffer.into()),
+        })
+    }
+
+    pub fn mesh(&self) -> Mesh<Vertex3D> {
+        self.mesh.clone()
+    }
+
+    pub fn target_count(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    pub fn set_weight(&mut self, target: usize, weight: f32) {
+        if let Some(w) = self.weights.get_mut(target) {
+            *w = weight;
+        }
+    }
+
+    pub fn set_weights(&mut self, weights: &[f32]) {
+        for (w, &weight) in self.weights.iter_mut().zip(weights) {
+            *w = weight;
+        }
+    }
+
+    pub(crate) fn record_blend(
+        &self,
+        gpu: &Gpu,
+        pipeline: &Arc<ComputePipeline>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<()> {
+        let weights = gpu.frame_staging(self.weights.iter().copied())?;
+        let layout = pipeline.layout().clone();
+        let set = gpu.create_frame_descriptor_set(
+            layout.set_layouts()[0].clone(),
+            [
+                WriteDescriptorSet::buffer(0, self.base.clone()),
+                WriteDescriptorSet::buffer(1, self.deltas.clone()),
+                WriteDescriptorSet::buffer(2, weights),
+                WriteDescriptorSet::buffer(3, self.mesh.vertex_buffer().clone()),
+            ],
+        )?;
+        let vertex_count = self.base.len() as u32;
+        builder
+            .bind_pipeline_compute(pipeline.clone())?
+            .bind_descriptor_sets(PipelineBindPoint::Compute, layout.clone(), 0, set)?
+            .push_constants(
+                layout,
+                0,
+                MorphConstants {
+                    vertex_count,
+                    target_count: self.weights.len() as u32,
+                },
+            )?;
+        unsafe { builder.dispatch([vertex_count.div_ceil(WORKGROUP_SIZE), 1, 1]) }?;
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use std::sync::Arc;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer};
+use vulkano::query::{
+    QueryControlFlags, QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType,
+};
+
+struct OcclusionFrame {
+    query_pool: Arc<QueryPool>,
+    used: Vec<u32>,
+}
+
+pub struct OcclusionQueries {
+    frames: Vec<OcclusionFrame>,
+    current: usize,
+    results: Vec<Option<u64>>,
+    flags: QueryControlFlags,
+}
+
+impl OcclusionQueries {
+    pub fn new(gpu: &Gpu, capacity: u32, frames_in_flight: usize) -> Result<Self> {
+        if capacity == 0 {
+            return Err(EngineError::InvalidArgument(
+                "occlusion queries need a capacity of at least one".into(),
+            ));
+        }
+        let device = gpu.device();
+        let frames = (0..frames_in_flight.max(1))
+            .map(|_| {
+                let query_pool = QueryPool::new(
+                    device.clone(),
+                    QueryPoolCreateInfo {
+                        query_count: capacity,
+                        ..QueryPoolCreateInfo::query_type(QueryType::Occlusion)
+                    },
+                )?;
+                Ok(OcclusionFrame {
+                    query_pool,
+                    used: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        let flags = if device.enabled_features().occlusion_query_precise {
+            QueryControlFlags::PRECISE
+        } else {
+            QueryControlFlags::empty()
+        };
+        Ok(Self {
+            frames,
+            current: 0,
+            results: vec![None; capacity as usize],
+            flags,
+        })
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.results.len() as u32
+    }
+
+    pub fn result(&self, query: u32) -> Option<u64> {
+        self.results.get(query as usize).copied().flatten()
+    }
+
+    pub fn is_visible(&self, query: u32) -> Option<bool> {
+        self.result(query).map(|samples| samples > 0)
+    }
+
+    pub(crate) fn begin_frame(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<()> {
+        self.current = (self.current + 1) % self.frames.len();
+        self.resolve()?;
+        let capacity = self.capacity();
+        let frame = &mut self.frames[self.current];
+        frame.used.clear();
+        unsafe { builder.reset_query_pool(frame.query_pool.clone(), 0..capacity) }?;
+        Ok(())
+    }
+
+    pub(crate) fn begin(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        query: u32,
+    ) -> Result<bool> {
+        if query >= self.capacity() {
+            return Err(EngineError::InvalidArgument(format!(
+                "occlusion query {query} is out of range for a capacity of {}",
+                self.capacity()
+            )));
+        }
+        let frame = &mut self.frames[self.current];
+        if frame.used.contains(&query) {
+            return Ok(false);
+        }
+        frame.used.push(query);
+        unsafe { builder.begin_query(frame.query_pool.clone(), query, self.flags) }?;
+        Ok(true)
+    }
+
+    pub(crate) fn end(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        query: u32,
+    ) -> Result<()> {
+        let frame = &self.frames[self.current];
+        builder.end_query(frame.query_pool.clone(), query)?;
+        Ok(())
+    }
+
+    fn resolve(&mut self) -> Result<()> {
+        let frame = &self.frames[self.current];
+        for &query in &frame.used {
+            let mut samples = [0u64];
+            let available = frame.query_pool.get_results(
+                query..query + 1,
+                &mut samples,
+                QueryResultFlags::empty(),
+            )?;
+            if available {
+                self.results[query as usize] = Some(samples[0]);
+            }
+        }
+        Ok(())
+    }
+}
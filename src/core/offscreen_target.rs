@@ -0,0 +1,102 @@
+use crate::core::capture::PendingCapture;
+use crate::core::error::Result;
+use crate::core::gpu::{Gpu, QueueKind};
+use crate::core::picking::ObjectId;
+use crate::core::render_graph::RenderGraph;
+use crate::core::renderer::{RenderParams, Renderer};
+use crate::core::texture::Texture;
+use image::RgbaImage;
+use std::sync::Arc;
+use vulkano::command_buffer::PrimaryAutoCommandBuffer;
+use vulkano::format::Format;
+use vulkano::image::view::ImageView;
+use vulkano::image::ImageUsage;
+
+pub struct OffscreenTarget {
+    image_view: Arc<ImageView>,
+    gpu: Arc<Gpu>,
+}
+
+impl OffscreenTarget {
+    pub fn new(gpu: Arc<Gpu>, extent: [u32; 2], format: Format) -> Result<Self> {
+        Self::layered(gpu, extent, format, 1)
+    }
+
+    pub fn layered(gpu: Arc<Gpu>, extent: [u32; 2], format: Format, layers: u32) -> Result<Self> {
+        let image = gpu.create_layered_image(
+            format,
+            extent,
+            layers,
+            ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_SRC | ImageUsage::SAMPLED,
+        )?;
+        Ok(Self {
+            image_view: ImageView::new_default(image)?,
+            gpu,
+        })
+    }
+
+    pub fn render<Vertex>(
+        &self,
+        renderer: &Renderer,
+        render_params: RenderParams<Vertex>,
+    ) -> Result<()> {
+        self.submit(renderer.render(self.image_view.clone(), render_params)?)?;
+        self.gpu.poll_memory_budget();
+        Ok(())
+    }
+
+    pub fn capture<Vertex>(
+        &self,
+        renderer: &Renderer,
+        render_params: RenderParams<Vertex>,
+    ) -> Result<RgbaImage> {
+        let mut graph = RenderGraph::new();
+        let target = graph.import(self.image_view.clone());
+        renderer.add_passes(&mut graph, target, render_params)?;
+        let capture = PendingCapture::record(
+            &self.gpu,
+            &mut graph,
+            target,
+            self.image_format(),
+            self.image_view.usage(),
+        )?;
+        self.submit(renderer.execute(graph)?)?;
+        capture.into_image()
+    }
+
+    pub fn pick<Vertex>(
+        &self,
+        renderer: &Renderer,
+        render_params: &RenderParams<Vertex>,
+        position: [u32; 2],
+    ) -> Result<Option<ObjectId>> {
+        let mut graph = RenderGraph::new();
+        let target = graph.import(self.image_view.clone());
+        let pick = renderer.add_picking_pass(&mut graph, target, render_params, position)?;
+        self.submit(renderer.execute(graph)?)?;
+        Ok(pick.try_resolve().flatten())
+    }
+
+    fn submit(&self, command_buffer: Arc<PrimaryAutoCommandBuffer>) -> Result<()> {
+        self.gpu
+            .submit_and_wait(QueueKind::Graphics, command_buffer)?;
+        self.gpu.end_frame(None)
+    }
+
+    pub fn texture(&self) -> Texture {
+        Texture::from_image_view(self.image_view.clone())
+    }
+
+    pub fn image_format(&self) -> Format {
+        self.image_view.format()
+    }
+
+    pub fn extent(&self) -> [u32; 2] {
+        let [width, height, _] = self.image_view.image().extent();
+        [width, height]
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.image_view.image().array_layers()
+    }
+}
@@ -0,0 +1,214 @@
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::renderer::Mesh;
+use crate::core::resource_tracker::GpuResourceKind;
+use crate::core::shaders::{overlay_fs, overlay_vs};
+use crate::core::texture::Texture;
+use crate::core::vertex::OverlayVertex;
+use std::sync::Arc;
+use vulkano::buffer::BufferContents;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer};
+use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::format::{Format, NumericFormat};
+use vulkano::image::sampler::{
+    Filter, Sampler, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode,
+};
+use vulkano::pipeline::graphics::color_blend::{
+    AttachmentBlend, BlendFactor, BlendOp, ColorBlendAttachmentState, ColorBlendState,
+};
+use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::multisample::MultisampleState;
+use vulkano::pipeline::graphics::rasterization::RasterizationState;
+use vulkano::pipeline::graphics::subpass::PipelineRenderingCreateInfo;
+use vulkano::pipeline::graphics::vertex_input::{Vertex as VertexTrait, VertexDefinition};
+use vulkano::pipeline::graphics::viewport::{Scissor, Viewport, ViewportState};
+use vulkano::pipeline::graphics::GraphicsPipelineCreateInfo;
+use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
+use vulkano::pipeline::{
+    DynamicState, GraphicsPipeline, Pipeline, PipelineBindPoint, PipelineLayout,
+    PipelineShaderStageCreateInfo,
+};
+
+pub struct OverlayDraw {
+    pub mesh: Mesh<OverlayVertex>,
+    pub texture: Texture,
+    pub filter: Filter,
+    pub clip_rect: [f32; 4],
+}
+
+pub struct Overlay {
+    pub draws: Vec<OverlayDraw>,
+    pub pixels_per_point: f32,
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self {
+            draws: Vec::new(),
+            pixels_per_point: 1.0,
+        }
+    }
+}
+
+impl Overlay {
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct OverlayConstants {
+    screen_size: [f32; 2],
+    gamma_output: u32,
+}
+
+pub(crate) struct OverlayPipeline {
+    linear_sampler: Arc<Sampler>,
+    nearest_sampler: Arc<Sampler>,
+    gamma_output: bool,
+    pipeline: Arc<GraphicsPipeline>,
+    gpu: Arc<Gpu>,
+}
+
+impl OverlayPipeline {
+    pub(crate) fn new(gpu: Arc<Gpu>, image_format: Format) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = overlay_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = overlay_fs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let vertex_input_state = OverlayVertex::per_vertex().definition(&vs)?;
+        let stages = [
+            PipelineShaderStageCreateInfo::new(vs),
+            PipelineShaderStageCreateInfo::new(fs),
+        ];
+        let layout = PipelineLayout::new(
+            device.clone(),
+            PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+                .into_pipeline_layout_create_info(device.clone())
+                .unwrap(),
+        )?;
+        let subpass = PipelineRenderingCreateInfo {
+            color_attachment_formats: vec![Some(image_format)],
+            ..Default::default()
+        };
+        let pipeline = GraphicsPipeline::new(
+            device.clone(),
+            None,
+            GraphicsPipelineCreateInfo {
+                stages: stages.into_iter().collect(),
+                vertex_input_state: Some(vertex_input_state),
+                input_assembly_state: Some(InputAssemblyState::default()),
+                viewport_state: Some(ViewportState::default()),
+                rasterization_state: Some(RasterizationState::default()),
+                multisample_state: Some(MultisampleState::default()),
+                color_blend_state: Some(ColorBlendState::with_attachment_states(
+                    1,
+                    ColorBlendAttachmentState {
+                        blend: Some(AttachmentBlend {
+                            src_color_blend_factor: BlendFactor::One,
+                            dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+                            color_blend_op: BlendOp::Add,
+                            src_alpha_blend_factor: BlendFactor::OneMinusDstAlpha,
+                            dst_alpha_blend_factor: BlendFactor::One,
+                            alpha_blend_op: BlendOp::Add,
+                        }),
+                        ..Default::default()
+                    },
+                )),
+                dynamic_state: [DynamicState::Viewport, DynamicState::Scissor]
+                    .into_iter()
+                    .collect(),
+                subpass: Some(subpass.into()),
+                ..GraphicsPipelineCreateInfo::layout(layout)
+            },
+        )?;
+        gpu.track_resource(GpuResourceKind::Pipeline, &pipeline, "overlay", 0);
+        let sampler = |filter| {
+            Sampler::new(
+                device.clone(),
+                SamplerCreateInfo {
+                    mag_filter: filter,
+                    min_filter: filter,
+                    mipmap_mode: SamplerMipmapMode::Nearest,
+                    address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                    ..Default::default()
+                },
+            )
+        };
+        Ok(Self {
+            linear_sampler: sampler(Filter::Linear)?,
+            nearest_sampler: sampler(Filter::Nearest)?,
+            gamma_output: !matches!(
+                image_format.numeric_format_color(),
+                Some(NumericFormat::SRGB | NumericFormat::SFLOAT)
+            ),
+            pipeline,
+            gpu,
+        })
+    }
+
+    pub(crate) fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        viewport: Viewport,
+        overlay: Overlay,
+    ) -> Result<()> {
+        let layout = self.pipeline.layout().clone();
+        let [width, height] = viewport.extent;
+        let pixels_per_point = overlay.pixels_per_point;
+        builder
+            .set_viewport(0, [viewport].into_iter().collect())?
+            .bind_pipeline_graphics(self.pipeline.clone())?
+            .push_constants(
+                layout.clone(),
+                0,
+                OverlayConstants {
+                    screen_size: [width / pixels_per_point, height / pixels_per_point],
+                    gamma_output: self.gamma_output as u32,
+                },
+            )?;
+        for draw in overlay.draws {
+            let [min_x, min_y, max_x, max_y] = draw
+                .clip_rect
+                .map(|value| (value * pixels_per_point).round());
+            let min = [min_x.clamp(0.0, width), min_y.clamp(0.0, height)];
+            let max = [max_x.clamp(min[0], width), max_y.clamp(min[1], height)];
+            if max[0] <= min[0] || max[1] <= min[1] {
+                continue;
+            }
+            let sampler = match draw.filter {
+                Filter::Nearest => self.nearest_sampler.clone(),
+                _ => self.linear_sampler.clone(),
+            };
+            let image_view = draw.texture.image_view();
+            let set = self.gpu.cached_descriptor_set(
+                layout.set_layouts()[0].clone(),
+                &[
+                    Arc::as_ptr(&image_view) as usize,
+                    Arc::as_ptr(&sampler) as usize,
+                ],
+                || {
+                    [WriteDescriptorSet::image_view_sampler(
+                        0, image_view, sampler,
+                    )]
+                },
+            )?;
+            let scissor = Scissor {
+                offset: [min[0] as u32, min[1] as u32],
+                extent: [(max[0] - min[0]) as u32, (max[1] - min[1]) as u32],
+            };
+            let index_count = draw.mesh.index_buffer().len() as u32;
+            builder
+                .set_scissor(0, [scissor].into_iter().collect())?
+                .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 0, set)?
+                .bind_vertex_buffers(0, draw.mesh.vertex_buffer().clone())?
+                .bind_index_buffer(draw.mesh.index_buffer().clone())?;
+            unsafe { builder.draw_indexed(index_count, 1, 0, 0, 0) }?;
+        }
+        Ok(())
+    }
+}
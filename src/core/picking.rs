@@ -0,0 +1,41 @@
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::render_graph::{RenderGraph, ResourceId};
+use vulkano::buffer::Subbuffer;
+use vulkano::command_buffer::CopyImageToBufferInfo;
+
+pub(crate) const NO_OBJECT: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObjectId(pub u32);
+
+pub(crate) struct PendingPick {
+    buffer: Subbuffer<[u8]>,
+}
+
+impl PendingPick {
+    pub(crate) fn record(
+        gpu: &Gpu,
+        graph: &mut RenderGraph,
+        ids: ResourceId,
+        position: [u32; 2],
+    ) -> Result<Self> {
+        let buffer = gpu.create_readback_buffer(size_of::<u32>() as u64)?;
+        let destination = buffer.clone();
+        graph.add_pass("pick").transfer_src(ids).record(move |ctx| {
+            let image = ctx.image_view(ids).image().clone();
+            let mut copy = CopyImageToBufferInfo::image_buffer(image, destination);
+            copy.regions[0].image_offset = [position[0], position[1], 0];
+            copy.regions[0].image_extent = [1, 1, 1];
+            ctx.builder.copy_image_to_buffer(copy)?;
+            Ok(())
+        });
+        Ok(Self { buffer })
+    }
+
+    pub(crate) fn try_resolve(&self) -> Option<Option<ObjectId>> {
+        let data = self.buffer.read().ok()?;
+        let id = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        Some((id != NO_OBJECT).then_some(ObjectId(id)))
+    }
+}
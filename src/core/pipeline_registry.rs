@@ -0,0 +1,163 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::shader_compiler::{ShaderCompiler, ShaderStage};
+use crate::core::shader_preprocessor::ShaderPreprocessor;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use vulkano::shader::ShaderModule;
+
+pub struct PipelineHandle<P> {
+    current: Arc<Mutex<Arc<P>>>,
+}
+
+impl<P> Clone for PipelineHandle<P> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<P> PipelineHandle<P> {
+    pub fn get(&self) -> Arc<P> {
+        self.current.lock().unwrap().clone()
+    }
+}
+
+type ShaderBuilder<P> = dyn Fn(&[Arc<ShaderModule>]) -> Result<Arc<P>> + Send;
+type Reload = Box<dyn FnMut(&Gpu, &ShaderCompiler) -> Result<()> + Send>;
+
+struct WatchedPipeline {
+    shaders: Vec<PathBuf>,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    reload: Reload,
+}
+
+pub struct PipelineRegistry {
+    compiler: ShaderCompiler,
+    pipelines: Vec<WatchedPipeline>,
+    gpu: Arc<Gpu>,
+}
+
+impl PipelineRegistry {
+    pub fn new(gpu: Arc<Gpu>, compiler: ShaderCompiler) -> Self {
+        Self {
+            compiler,
+            pipelines: Vec::new(),
+            gpu,
+        }
+    }
+
+    pub fn compiler(&self) -> &ShaderCompiler {
+        &self.compiler
+    }
+
+    pub fn register<P: Send + Sync + 'static>(
+        &mut self,
+        shaders: impl IntoIterator<Item = impl Into<PathBuf>>,
+        build: impl Fn(&[Arc<ShaderModule>]) -> Result<Arc<P>> + Send + 'static,
+    ) -> Result<PipelineHandle<P>> {
+        let mut sources = Vec::new();
+        for path in shaders {
+            let path = path.into();
+            let stage = ShaderStage::from_path(&path).ok_or_else(|| {
+                EngineError::InvalidArgument(format!(
+                    "cannot infer the shader stage of {}",
+                    path.display()
+                ))
+            })?;
+            sources.push((path, stage));
+        }
+        let build: Box<ShaderBuilder<P>> = Box::new(build);
+        let pipeline = compile_and_build(&self.gpu, &self.compiler, &sources, &build)?;
+        let handle = PipelineHandle {
+            current: Arc::new(Mutex::new(pipeline)),
+        };
+        let shaders: Vec<_> = sources.iter().map(|(path, _)| path.clone()).collect();
+        let files = watched_files(&self.compiler, &shaders);
+        let current = handle.current.clone();
+        self.pipelines.push(WatchedPipeline {
+            shaders,
+            files,
+            reload: Box::new(move |gpu, compiler| {
+                let pipeline = compile_and_build(gpu, compiler, &sources, &build)?;
+                *current.lock().unwrap() = pipeline;
+                Ok(())
+            }),
+        });
+        Ok(handle)
+    }
+
+    pub fn poll(&mut self) -> Vec<EngineError> {
+        let mut errors = Vec::new();
+        for pipeline in &mut self.pipelines {
+            let mut changed = false;
+            for (path, last_modified) in &mut pipeline.files {
+                let modified = modified(path);
+                if modified != *last_modified {
+                    *last_modified = modified;
+                    changed = true;
+                }
+            }
+            if !changed {
+                continue;
+            }
+            if let Err(e) = (pipeline.reload)(&self.gpu, &self.compiler) {
+                errors.push(e);
+            }
+            pipeline.files = watched_files(&self.compiler, &pipeline.shaders);
+        }
+        errors
+    }
+}
+
+fn watched_files(
+    compiler: &ShaderCompiler,
+    shaders: &[PathBuf],
+) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut files = shaders.to_vec();
+    for path in shaders {
+        let Ok(preprocessed) = compiler.preprocessor().process_file(path) else {
+            continue;
+        };
+        for file in preprocessed.files() {
+            let builtin = ShaderPreprocessor::library().any(|name| Path::new(name) == file);
+            if !builtin && !files.contains(file) {
+                files.push(file.clone());
+            }
+        }
+    }
+    files
+        .into_iter()
+        .map(|file| {
+            let modified = modified(&file);
+            (file, modified)
+        })
+        .collect()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn compile_and_build<P>(
+    gpu: &Gpu,
+    compiler: &ShaderCompiler,
+    sources: &[(PathBuf, ShaderStage)],
+    build: &ShaderBuilder<P>,
+) -> Result<Arc<P>> {
+    let modules = sources
+        .iter()
+        .map(|(path, stage)| {
+            compiler
+                .compile_file(gpu, path, Some(*stage))
+                .map_err(|e| EngineError::Shader {
+                    path: path.clone(),
+                    source: Box::new(e),
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    build(&modules)
+}
@@ -0,0 +1,240 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::{Gpu, QueueKind};
+use std::sync::Arc;
+use std::time::Duration;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer};
+use vulkano::query::{
+    QueryControlFlags, QueryPipelineStatisticFlags, QueryPool, QueryPoolCreateInfo,
+    QueryResultFlags, QueryType,
+};
+use vulkano::sync::PipelineStage;
+
+const MAX_PASSES: u32 = 64;
+
+const STATISTICS: QueryPipelineStatisticFlags =
+    QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES
+        .union(QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES)
+        .union(QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS)
+        .union(QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS)
+        .union(QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES)
+        .union(QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS)
+        .union(QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS);
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PipelineStatistics {
+    pub input_vertices: u64,
+    pub input_primitives: u64,
+    pub vertex_invocations: u64,
+    pub clipping_invocations: u64,
+    pub clipping_primitives: u64,
+    pub fragment_invocations: u64,
+    pub compute_invocations: u64,
+}
+
+impl PipelineStatistics {
+    fn from_results(results: &[u64]) -> Self {
+        Self {
+            input_vertices: results[0],
+            input_primitives: results[1],
+            vertex_invocations: results[2],
+            clipping_invocations: results[3],
+            clipping_primitives: results[4],
+            fragment_invocations: results[5],
+            compute_invocations: results[6],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PassTiming {
+    pub name: String,
+    pub duration: Duration,
+    pub statistics: Option<PipelineStatistics>,
+}
+
+struct ProfilerFrame {
+    query_pool: Arc<QueryPool>,
+    statistics_pool: Option<Arc<QueryPool>>,
+    names: Vec<String>,
+}
+
+pub struct GpuProfiler {
+    frames: Vec<ProfilerFrame>,
+    current: usize,
+    timings: Vec<PassTiming>,
+    timestamp_period: f64,
+    valid_mask: u64,
+}
+
+impl GpuProfiler {
+    pub fn new(gpu: &Gpu, frames_in_flight: usize) -> Result<Self> {
+        let device = gpu.device();
+        let physical_device = device.physical_device();
+        let valid_bits = physical_device.queue_family_properties()
+            [gpu.queue(QueueKind::Graphics).queue_family_index() as usize]
+            .timestamp_valid_bits
+            .ok_or_else(|| {
+                EngineError::Unsupported(
+                    "the graphics queue does not support timestamp queries".into(),
+                )
+            })?;
+        let statistics = device.enabled_features().pipeline_statistics_query;
+        let frames = (0..frames_in_flight.max(1))
+            .map(|_| {
+                let query_pool = QueryPool::new(
+                    device.clone(),
+                    QueryPoolCreateInfo {
+                        query_count: MAX_PASSES * 2,
+                        ..QueryPoolCreateInfo::query_type(QueryType::Timestamp)
+                    },
+                )?;
+                let statistics_pool = statistics
+                    .then(|| {
+                        QueryPool::new(
+                            device.clone(),
+                            QueryPoolCreateInfo {
+                                query_count: MAX_PASSES,
+                                pipeline_statistics: STATISTICS,
+                                ..QueryPoolCreateInfo::query_type(QueryType::PipelineStatistics)
+                            },
+                        )
+                    })
+                    .transpose()?;
+                Ok(ProfilerFrame {
+                    query_pool,
+                    statistics_pool,
+                    names: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            frames,
+            current: 0,
+            timings: Vec::new(),
+            timestamp_period: physical_device.properties().timestamp_period as f64,
+            valid_mask: u64::MAX >> (64 - valid_bits.min(64)),
+        })
+    }
+
+    pub fn timings(&self) -> &[PassTiming] {
+        &self.timings
+    }
+
+    pub fn total(&self) -> Duration {
+        self.timings.iter().map(|timing| timing.duration).sum()
+    }
+
+    pub fn has_statistics(&self) -> bool {
+        self.frames[0].statistics_pool.is_some()
+    }
+
+    pub(crate) fn begin_frame(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<()> {
+        self.current = (self.current + 1) % self.frames.len();
+        self.resolve()?;
+        let frame = &mut self.frames[self.current];
+        frame.names.clear();
+        unsafe { builder.reset_query_pool(frame.query_pool.clone(), 0..MAX_PASSES * 2) }?;
+        if let Some(statistics_pool) = &frame.statistics_pool {
+            unsafe { builder.reset_query_pool(statistics_pool.clone(), 0..MAX_PASSES) }?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn begin_pass(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        name: &str,
+    ) -> Result<Option<u32>> {
+        let frame = &mut self.frames[self.current];
+        let index = frame.names.len() as u32;
+        if index >= MAX_PASSES {
+            return Ok(None);
+        }
+        frame.names.push(name.to_owned());
+        unsafe {
+            builder.write_timestamp(
+                frame.query_pool.clone(),
+                index * 2,
+                PipelineStage::TopOfPipe,
+            )
+        }?;
+        if let Some(statistics_pool) = &frame.statistics_pool {
+            unsafe {
+                builder.begin_query(statistics_pool.clone(), index, QueryControlFlags::empty())
+            }?;
+        }
+        Ok(Some(index))
+    }
+
+    pub(crate) fn end_pass(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        index: u32,
+    ) -> Result<()> {
+        let frame = &self.frames[self.current];
+        if let Some(statistics_pool) = &frame.statistics_pool {
+            builder.end_query(statistics_pool.clone(), index)?;
+        }
+        unsafe {
+            builder.write_timestamp(
+                frame.query_pool.clone(),
+                index * 2 + 1,
+                PipelineStage::BottomOfPipe,
+            )
+        }?;
+        Ok(())
+    }
+
+    fn resolve(&mut self) -> Result<()> {
+        let frame = &self.frames[self.current];
+        if frame.names.is_empty() {
+            return Ok(());
+        }
+        let count = frame.names.len() as u32 * 2;
+        let mut results = vec![0u64; count as usize];
+        let available =
+            frame
+                .query_pool
+                .get_results(0..count, &mut results, QueryResultFlags::empty())?;
+        if !available {
+            return Ok(());
+        }
+        let statistics: Option<Vec<PipelineStatistics>> = match &frame.statistics_pool {
+            Some(statistics_pool) => {
+                let passes = frame.names.len() as u32;
+                let stride = STATISTICS.count() as usize;
+                let mut results = vec![0u64; passes as usize * stride];
+                statistics_pool
+                    .get_results(0..passes, &mut results, QueryResultFlags::empty())?
+                    .then_some(results)
+                    .map(|results| {
+                        results
+                            .chunks_exact(stride)
+                            .map(PipelineStatistics::from_results)
+                            .collect()
+                    })
+            }
+            None => None,
+        };
+        self.timings = frame
+            .names
+            .iter()
+            .zip(results.chunks_exact(2))
+            .enumerate()
+            .map(|(index, (name, timestamps))| {
+                let ticks = (timestamps[1] & self.valid_mask)
+                    .wrapping_sub(timestamps[0] & self.valid_mask)
+                    & self.valid_mask;
+                PassTiming {
+                    name: name.clone(),
+                    duration: Duration::from_nanos((ticks as f64 * self.timestamp_period) as u64),
+                    statistics: statistics.as_ref().map(|statistics| statistics[index]),
+                }
+            })
+            .collect();
+        Ok(())
+    }
+}
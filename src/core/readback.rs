@@ -0,0 +1,45 @@
+use crate::core::error::Result;
+use vulkano::buffer::{BufferContents, Subbuffer};
+use vulkano::sync::future::FenceSignalFuture;
+use vulkano::sync::GpuFuture;
+
+pub struct Readback<T> {
+    buffer: Subbuffer<[T]>,
+    future: FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>,
+}
+
+impl<T: BufferContents + Copy> Readback<T> {
+    pub(crate) fn new(
+        buffer: Subbuffer<[T]>,
+        future: FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>,
+    ) -> Self {
+        Self { buffer, future }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.len() == 0
+    }
+
+    pub fn is_ready(&self) -> Result<bool> {
+        Ok(self.future.is_signaled()?)
+    }
+
+    pub fn poll(&mut self) -> Result<Option<Vec<T>>> {
+        if !self.is_ready()? {
+            return Ok(None);
+        }
+        self.future.cleanup_finished();
+        Ok(Some(self.buffer.read()?.to_vec()))
+    }
+
+    pub fn wait(self) -> Result<Vec<T>> {
+        let Self { buffer, future } = self;
+        future.wait(None)?;
+        drop(future);
+        Ok(buffer.read()?.to_vec())
+    }
+}
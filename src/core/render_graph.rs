@@ -0,0 +1,504 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::frame_pool::FRAMES_IN_FLIGHT;
+use crate::core::gpu::Gpu;
+use crate::core::profiler::GpuProfiler;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Arc;
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilder, PrimaryAutoCommandBuffer, RenderingAttachmentInfo, RenderingInfo,
+};
+use vulkano::device::DeviceOwned;
+use vulkano::format::{ClearValue, Format};
+use vulkano::image::view::ImageView;
+use vulkano::image::ImageUsage;
+use vulkano::instance::debug::DebugUtilsLabel;
+use vulkano::pipeline::graphics::viewport::Viewport;
+use vulkano::render_pass::{AttachmentLoadOp, AttachmentStoreOp};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ResourceId(usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TransientImage {
+    pub format: Format,
+    pub extent: [u32; 2],
+    pub layers: u32,
+}
+
+impl TransientImage {
+    pub fn new(format: Format, extent: [u32; 2]) -> Self {
+        Self {
+            format,
+            extent,
+            layers: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Attachment {
+    pub resource: ResourceId,
+    pub load_op: AttachmentLoadOp,
+    pub store_op: AttachmentStoreOp,
+    pub clear_value: Option<ClearValue>,
+}
+
+impl Attachment {
+    pub fn clear(resource: ResourceId, clear_value: impl Into<ClearValue>) -> Self {
+        Self {
+            resource,
+            load_op: AttachmentLoadOp::Clear,
+            store_op: AttachmentStoreOp::Store,
+            clear_value: Some(clear_value.into()),
+        }
+    }
+
+    pub fn load(resource: ResourceId) -> Self {
+        Self {
+            resource,
+            load_op: AttachmentLoadOp::Load,
+            store_op: AttachmentStoreOp::Store,
+            clear_value: None,
+        }
+    }
+}
+
+enum Resource {
+    Imported(Arc<ImageView>),
+    Transient(TransientImage, ImageUsage),
+}
+
+type RecordFn<'a> = Box<dyn FnOnce(&mut PassContext) -> Result<()> + 'a>;
+
+struct Pass<'a> {
+    name: String,
+    color_attachments: Vec<Attachment>,
+    depth_attachment: Option<Attachment>,
+    sampled: Vec<ResourceId>,
+    storage: Vec<ResourceId>,
+    transfer_src: Vec<ResourceId>,
+    view_mask: u32,
+    record: RecordFn<'a>,
+}
+
+impl Pass<'_> {
+    fn is_graphics(&self) -> bool {
+        !self.color_attachments.is_empty() || self.depth_attachment.is_some()
+    }
+
+    fn attachments(&self) -> impl Iterator<Item = &Attachment> {
+        self.color_attachments.iter().chain(&self.depth_attachment)
+    }
+
+    fn writes(&self) -> impl Iterator<Item = ResourceId> + '_ {
+        self.attachments()
+            .map(|a| a.resource)
+            .chain(self.storage.iter().copied())
+    }
+
+    fn reads(&self) -> impl Iterator<Item = ResourceId> + '_ {
+        self.attachments()
+            .filter(|a| a.load_op == AttachmentLoadOp::Load)
+            .map(|a| a.resource)
+            .chain(self.sampled.iter().copied())
+            .chain(self.storage.iter().copied())
+            .chain(self.transfer_src.iter().copied())
+    }
+}
+
+pub struct PassContext<'b> {
+    pub builder: &'b mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    views: &'b [Arc<ImageView>],
+    extent: [u32; 2],
+}
+
+impl PassContext<'_> {
+    pub fn image_view(&self, id: ResourceId) -> Arc<ImageView> {
+        self.views[id.0].clone()
+    }
+
+    pub fn extent(&self) -> [u32; 2] {
+        self.extent
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        Viewport {
+            offset: [0.0, 0.0],
+            extent: [self.extent[0] as f32, self.extent[1] as f32],
+            depth_range: 0.0..=1.0,
+        }
+    }
+}
+
+pub struct PassBuilder<'g, 'a> {
+    graph: &'g mut RenderGraph<'a>,
+    name: String,
+    color_attachments: Vec<Attachment>,
+    depth_attachment: Option<Attachment>,
+    sampled: Vec<ResourceId>,
+    storage: Vec<ResourceId>,
+    transfer_src: Vec<ResourceId>,
+    view_mask: u32,
+}
+
+impl<'a> PassBuilder<'_, 'a> {
+    pub fn color_attachment(mut self, attachment: Attachment) -> Self {
+        self.graph
+            .add_usage(attachment.resource, ImageUsage::COLOR_ATTACHMENT);
+        self.color_attachments.push(attachment);
+        self
+    }
+
+    pub fn depth_attachment(mut self, attachment: Attachment) -> Self {
+        self.graph
+            .add_usage(attachment.resource, ImageUsage::DEPTH_STENCIL_ATTACHMENT);
+        self.depth_attachment = Some(attachment);
+        self
+    }
+
+    pub fn sample(mut self, resource: ResourceId) -> Self {
+        self.graph.add_usage(resource, ImageUsage::SAMPLED);
+        self.sampled.push(resource);
+        self
+    }
+
+    pub fn storage(mut self, resource: ResourceId) -> Self {
+        self.graph.add_usage(resource, ImageUsage::STORAGE);
+        self.storage.push(resource);
+        self
+    }
+
+    pub fn transfer_src(mut self, resource: ResourceId) -> Self {
+        self.graph.add_usage(resource, ImageUsage::TRANSFER_SRC);
+        self.transfer_src.push(resource);
+        self
+    }
+
+    pub fn view_mask(mut self, view_mask: u32) -> Self {
+        self.view_mask = view_mask;
+        self
+    }
+
+    pub fn record(self, record: impl FnOnce(&mut PassContext) -> Result<()> + 'a) {
+        self.graph.passes.push(Pass {
+            name: self.name,
+            color_attachments: self.color_attachments,
+            depth_attachment: self.depth_attachment,
+            sampled: self.sampled,
+            storage: self.storage,
+            transfer_src: self.transfer_src,
+            view_mask: self.view_mask,
+            record: Box::new(record),
+        });
+    }
+}
+
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<Resource>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn import(&mut self, image_view: Arc<ImageView>) -> ResourceId {
+        self.resources.push(Resource::Imported(image_view));
+        ResourceId(self.resources.len() - 1)
+    }
+
+    pub fn transient(&mut self, image: TransientImage) -> ResourceId {
+        self.resources
+            .push(Resource::Transient(image, ImageUsage::empty()));
+        ResourceId(self.resources.len() - 1)
+    }
+
+    pub fn extent(&self, resource: ResourceId) -> [u32; 2] {
+        match &self.resources[resource.0] {
+            Resource::Imported(view) => {
+                let extent = view.image().extent();
+                [extent[0], extent[1]]
+            }
+            Resource::Transient(image, _) => image.extent,
+        }
+    }
+
+    pub fn add_pass(&mut self, name: impl Into<String>) -> PassBuilder<'_, 'a> {
+        PassBuilder {
+            graph: self,
+            name: name.into(),
+            color_attachments: Vec::new(),
+            depth_attachment: None,
+            sampled: Vec::new(),
+            storage: Vec::new(),
+            transfer_src: Vec::new(),
+            view_mask: 0,
+        }
+    }
+
+    pub fn format(&self, resource: ResourceId) -> Format {
+        match &self.resources[resource.0] {
+            Resource::Imported(view) => view.format(),
+            Resource::Transient(image, _) => image.format,
+        }
+    }
+
+    pub fn layers(&self, resource: ResourceId) -> u32 {
+        match &self.resources[resource.0] {
+            Resource::Imported(view) => view.subresource_range().array_layers.len() as u32,
+            Resource::Transient(image, _) => image.layers,
+        }
+    }
+
+    fn add_usage(&mut self, resource: ResourceId, usage: ImageUsage) {
+        if let Resource::Transient(_, u) = &mut self.resources[resource.0] {
+            *u |= usage;
+        }
+    }
+
+    fn execution_order(&self) -> Result<Vec<usize>> {
+        let mut writers: HashMap<ResourceId, Vec<usize>> = HashMap::new();
+        for (i, pass) in self.passes.iter().enumerate() {
+            for resource in pass.writes() {
+                writers.entry(resource).or_default().push(i);
+            }
+        }
+
+        let mut dependents = vec![Vec::new(); self.passes.len()];
+        let mut in_degree = vec![0usize; self.passes.len()];
+        for (i, pass) in self.passes.iter().enumerate() {
+            let writes: Vec<_> = pass.writes().collect();
+            let mut dependencies: Vec<usize> = Vec::new();
+            for resource in pass.reads().chain(writes.iter().copied()) {
+                let Some(resource_writers) = writers.get(&resource) else {
+                    continue;
+                };
+                if writes.contains(&resource) {
+                    dependencies.extend(resource_writers.iter().filter(|&&w| w < i));
+                } else {
+                    dependencies.extend(resource_writers.iter().filter(|&&w| w != i));
+                }
+            }
+            dependencies.sort_unstable();
+            dependencies.dedup();
+            for dependency in dependencies {
+                dependents[dependency].push(i);
+                in_degree[i] += 1;
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.passes.len());
+        let mut ready: BinaryHeap<_> = (0..self.passes.len())
+            .filter(|&i| in_degree[i] == 0)
+            .map(Reverse)
+            .collect();
+        while let Some(Reverse(i)) = ready.pop() {
+            order.push(i);
+            for &dependent in &dependents[i] {
+                in_degree[dependent] -= 1;
+                if in_degree[dependent] == 0 {
+                    ready.push(Reverse(dependent));
+                }
+            }
+        }
+
+        if order.len() != self.passes.len() {
+            let cyclic: Vec<_> = (0..self.passes.len())
+                .filter(|i| !order.contains(i))
+                .map(|i| self.passes[i].name.as_str())
+                .collect();
+            return Err(EngineError::InvalidArgument(format!(
+                "render graph contains a cycle between passes {cyclic:?}"
+            )));
+        }
+        Ok(order)
+    }
+
+    pub fn execute(
+        self,
+        transients: &mut TransientPool,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<()> {
+        self.execute_profiled(transients, builder, None)
+    }
+
+    pub fn execute_profiled(
+        self,
+        transients: &mut TransientPool,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        mut profiler: Option<&mut GpuProfiler>,
+    ) -> Result<()> {
+        let order = self.execution_order()?;
+
+        let mut acquired = Vec::new();
+        let views = self
+            .resources
+            .iter()
+            .map(|resource| match resource {
+                Resource::Imported(view) => Ok(view.clone()),
+                Resource::Transient(image, usage) => {
+                    if usage.is_empty() {
+                        return Err(EngineError::InvalidArgument(format!(
+                            "transient image {image:?} is never used by any pass"
+                        )));
+                    }
+                    let view = transients.acquire(*image, *usage)?;
+                    acquired.push((*image, *usage, view.clone()));
+                    Ok(view)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(profiler) = profiler.as_deref_mut() {
+            profiler.begin_frame(builder)?;
+        }
+        let labels = builder
+            .device()
+            .instance()
+            .enabled_extensions()
+            .ext_debug_utils;
+        let mut passes: Vec<_> = self.passes.into_iter().map(Some).collect();
+        let result = order.into_iter().try_for_each(|i| {
+            let pass = passes[i].take().unwrap();
+            let query = match profiler.as_deref_mut() {
+                Some(profiler) => profiler.begin_pass(builder, &pass.name)?,
+                None => None,
+            };
+            if labels {
+                builder.begin_debug_utils_label(DebugUtilsLabel {
+                    label_name: pass.name.clone(),
+                    ..Default::default()
+                })?;
+            }
+            Self::execute_pass(pass, &views, builder)?;
+            if labels {
+                unsafe { builder.end_debug_utils_label() }?;
+            }
+            if let (Some(profiler), Some(query)) = (profiler.as_deref_mut(), query) {
+                profiler.end_pass(builder, query)?;
+            }
+            Ok(())
+        });
+
+        for (image, usage, view) in acquired {
+            transients.release(image, usage, view);
+        }
+        result
+    }
+
+    fn execute_pass(
+        pass: Pass,
+        views: &[Arc<ImageView>],
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<()> {
+        let rendering_info = |attachment: &Attachment| RenderingAttachmentInfo {
+            load_op: attachment.load_op,
+            store_op: attachment.store_op,
+            clear_value: attachment.clear_value,
+            ..RenderingAttachmentInfo::image_view(views[attachment.resource.0].clone())
+        };
+
+        let graphics = pass.is_graphics();
+        let extent = pass
+            .attachments()
+            .next()
+            .map(|a| {
+                let extent = views[a.resource.0].image().extent();
+                [extent[0], extent[1]]
+            })
+            .unwrap_or_default();
+
+        if graphics {
+            builder.begin_rendering(RenderingInfo {
+                color_attachments: pass
+                    .color_attachments
+                    .iter()
+                    .map(|a| Some(rendering_info(a)))
+                    .collect(),
+                depth_attachment: pass.depth_attachment.as_ref().map(rendering_info),
+                view_mask: pass.view_mask,
+                ..Default::default()
+            })?;
+        }
+
+        let name = pass.name;
+        let mut context = PassContext {
+            builder,
+            views,
+            extent,
+        };
+        (pass.record)(&mut context).map_err(|e| EngineError::Pass {
+            name: name.clone(),
+            source: Box::new(e),
+        })?;
+
+        if graphics {
+            builder.end_rendering()?;
+        }
+        Ok(())
+    }
+}
+
+type TransientKey = (TransientImage, ImageUsage);
+
+pub struct TransientPool {
+    free: HashMap<TransientKey, Vec<Arc<ImageView>>>,
+    in_flight: Vec<(u64, TransientKey, Arc<ImageView>)>,
+    gpu: Arc<Gpu>,
+}
+
+impl TransientPool {
+    pub fn new(gpu: Arc<Gpu>) -> Self {
+        Self {
+            free: HashMap::new(),
+            in_flight: Vec::new(),
+            gpu,
+        }
+    }
+
+    /// Returns images to the free list once the frame that released them has retired. Until
+    /// then they may still be in use by a previous frame or by another window's command buffer.
+    fn recycle(&mut self) {
+        let frame = self.gpu.frame_index();
+        let (retired, in_flight) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition(|(released, ..)| released + FRAMES_IN_FLIGHT as u64 <= frame);
+        self.in_flight = in_flight;
+        for (_, key, view) in retired {
+            self.free.entry(key).or_default().push(view);
+        }
+    }
+
+    fn acquire(&mut self, image: TransientImage, usage: ImageUsage) -> Result<Arc<ImageView>> {
+        self.recycle();
+        if let Some(view) = self.free.get_mut(&(image, usage)).and_then(Vec::pop) {
+            return Ok(view);
+        }
+        let TransientImage {
+            format,
+            extent,
+            layers,
+        } = image;
+        let image = self
+            .gpu
+            .create_layered_image(format, extent, layers, usage)?;
+        self.gpu.set_debug_name(
+            &*image,
+            &format!("transient {format:?} {}x{}", extent[0], extent[1]),
+        )?;
+        Ok(ImageView::new_default(image)?)
+    }
+
+    fn release(&mut self, image: TransientImage, usage: ImageUsage, view: Arc<ImageView>) {
+        self.in_flight
+            .push((self.gpu.frame_index(), (image, usage), view));
+    }
+
+    pub fn clear(&mut self) {
+        self.free.clear();
+        self.in_flight.clear();
+    }
+}
@@ -1,47 +1,400 @@
-use crate::core::gpu::Gpu;
-use std::sync::Arc;
-use vulkano::buffer::{BufferContents, BufferUsage, Subbuffer};
-use vulkano::command_buffer::{PrimaryAutoCommandBuffer, RenderingAttachmentInfo, RenderingInfo};
-use vulkano::format::Format;
+use crate::core::animation::JointBuffer;
+use crate::core::anti_aliasing::{AntiAliasing, FxaaPipeline, TaaPipeline};
+use crate::core::buffer_arena::{ArenaRange, BufferArena};
+use crate::core::camera::{Camera, MultiviewCameraUniform, MAX_VIEWS};
+use crate::core::cubemap::Cubemap;
+use crate::core::culling::CulledBatch;
+use crate::core::debug_draw::{DebugDraw, DebugDrawPipeline};
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::{Gpu, QueueKind};
+use crate::core::hdr::{HdrOutputPipeline, HdrSettings, OutputColorSpace, HDR_SCENE_FORMAT};
+use crate::core::ibl::Environment;
+use crate::core::lights::Lights;
+use crate::core::material::Material;
+use crate::core::morph::MorphedMesh;
+use crate::core::occlusion::OcclusionQueries;
+use crate::core::overlay::{Overlay, OverlayPipeline};
+use crate::core::picking::{ObjectId, PendingPick, NO_OBJECT};
+use crate::core::profiler::{GpuProfiler, PassTiming};
+use crate::core::render_graph::{
+    Attachment, RenderGraph, ResourceId, TransientImage, TransientPool,
+};
+use crate::core::resource_tracker::GpuResourceKind;
+use crate::core::shader_reflection::{ShaderParameters, ShaderReflection};
+use crate::core::shaders::{
+    cull_cs, culled_vs, deferred_resolve_fs, forward_fs, forward_multiview_fs,
+    forward_multiview_vs, forward_vs, fullscreen_vs, gbuffer_fs, morph_cs, picking_fs, skinned_vs,
+    vector_fs, vector_vs,
+};
+use crate::core::skybox::SkyboxPipeline;
+use crate::core::ssao::{SsaoPipeline, SsaoSettings};
+use crate::core::texture::Texture;
+use crate::core::upscaling::{RenderScale, UpscalePipeline};
+use crate::core::vertex::{SkinnedVertex3D, VectorVertex, Vertex3D};
+use glam::Mat4;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use vulkano::buffer::{BufferContents, BufferUsage, IndexBuffer, Subbuffer};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer};
+use vulkano::descriptor_set::{DescriptorSet, WriteDescriptorSet};
+use vulkano::format::{ClearValue, Format};
+use vulkano::image::sampler::{Sampler, SamplerAddressMode, SamplerCreateInfo};
 use vulkano::image::view::ImageView;
-use vulkano::pipeline::graphics::color_blend::{ColorBlendAttachmentState, ColorBlendState};
+use vulkano::pipeline::graphics::color_blend::{
+    AttachmentBlend, ColorBlendAttachmentState, ColorBlendState,
+};
+use vulkano::pipeline::graphics::depth_stencil::{CompareOp, DepthState, DepthStencilState};
+use vulkano::pipeline::graphics::fragment_shading_rate::{
+    FragmentShadingRateCombinerOp, FragmentShadingRateState,
+};
 use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
 use vulkano::pipeline::graphics::multisample::MultisampleState;
 use vulkano::pipeline::graphics::rasterization::{PolygonMode, RasterizationState};
 use vulkano::pipeline::graphics::subpass::PipelineRenderingCreateInfo;
-use vulkano::pipeline::graphics::vertex_input::{Vertex as VertexTrait, VertexDefinition};
-use vulkano::pipeline::graphics::viewport::{Viewport, ViewportState};
+use vulkano::pipeline::graphics::vertex_input::{
+    Vertex as VertexTrait, VertexDefinition, VertexInputState,
+};
+use vulkano::pipeline::graphics::viewport::ViewportState;
 use vulkano::pipeline::graphics::GraphicsPipelineCreateInfo;
 use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
 use vulkano::pipeline::{
-    DynamicState, GraphicsPipeline, PipelineLayout, PipelineShaderStageCreateInfo,
+    ComputePipeline, DynamicState, GraphicsPipeline, Pipeline, PipelineBindPoint, PipelineLayout,
+    PipelineShaderStageCreateInfo,
 };
-use vulkano::render_pass::{AttachmentLoadOp, AttachmentStoreOp};
 use vulkano::shader::EntryPoint;
 
+const DEPTH_FORMAT: Format = Format::D32_SFLOAT;
+
 pub struct RenderParams<Vertex> {
     pub clear_color: [f32; 4],
-    pub mesh: Option<Mesh<Vertex>>,
+    pub draws: Vec<Draw<Vertex>>,
+    pub skinned_draws: Vec<Draw<SkinnedVertex3D>>,
+    pub morphs: Vec<MorphedMesh>,
+    pub culled: Vec<CulledBatch>,
+    pub camera: Camera,
+    pub views: Vec<Camera>,
+    pub lights: Lights,
+    pub skybox: Option<Cubemap>,
+    pub environment: Option<Environment>,
+    pub debug_draw: DebugDraw,
+    pub overlay: Overlay,
+}
+
+impl<Vertex> Default for RenderParams<Vertex> {
+    fn default() -> Self {
+        Self {
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            draws: Vec::new(),
+            skinned_draws: Vec::new(),
+            morphs: Vec::new(),
+            culled: Vec::new(),
+            camera: Camera::default(),
+            views: Vec::new(),
+            lights: Lights::default(),
+            skybox: None,
+            environment: None,
+            debug_draw: DebugDraw::default(),
+            overlay: Overlay::default(),
+        }
+    }
+}
+
+pub struct Draw<Vertex> {
+    pub mesh: Mesh<Vertex>,
+    pub layer: i32,
+    pub transform: Mat4,
+    pub material: Material,
+    pub joints: Option<JointBuffer>,
+    pub occlusion_query: Option<u32>,
+    pub shading_rate: Option<[u32; 2]>,
+    pub object_id: Option<ObjectId>,
+    pub parameters: Option<Arc<ShaderParameters>>,
+}
+
+impl<Vertex> Draw<Vertex> {
+    pub fn new(mesh: Mesh<Vertex>, layer: i32) -> Self {
+        Self {
+            mesh,
+            layer,
+            transform: Mat4::IDENTITY,
+            material: Material::default(),
+            joints: None,
+            occlusion_query: None,
+            shading_rate: None,
+            object_id: None,
+            parameters: None,
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Mat4) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_parameters(mut self, parameters: Arc<ShaderParameters>) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+
+    pub fn with_joints(mut self, joints: JointBuffer) -> Self {
+        self.joints = Some(joints);
+        self
+    }
+
+    pub fn with_occlusion_query(mut self, query: u32) -> Self {
+        self.occlusion_query = Some(query);
+        self
+    }
+
+    pub fn with_shading_rate(mut self, fragment_size: [u32; 2]) -> Self {
+        self.shading_rate = Some(fragment_size);
+        self
+    }
+
+    pub fn with_object_id(mut self, object_id: ObjectId) -> Self {
+        self.object_id = Some(object_id);
+        self
+    }
+
+    fn depth_only(&self) -> Self {
+        Self {
+            mesh: self.mesh.clone(),
+            layer: self.layer,
+            transform: self.transform,
+            material: self.material.clone(),
+            joints: self.joints.clone(),
+            occlusion_query: None,
+            shading_rate: None,
+            object_id: None,
+            parameters: None,
+        }
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct DrawConstants {
+    model: [[f32; 4]; 4],
+    base_color: [f32; 4],
+    material: [f32; 4],
+}
+
+impl DrawConstants {
+    fn new(transform: Mat4, material: &Material) -> Self {
+        Self {
+            model: transform.to_cols_array_2d(),
+            base_color: material.base_color,
+            material: [
+                material.metallic,
+                material.roughness,
+                material.normal_scale,
+                0.0,
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderPath {
+    Forward,
+    Deferred,
+}
+
+const GBUFFER_FORMATS: [Format; 3] = [
+    Format::R8G8B8A8_UNORM,
+    Format::R16G16B16A16_SFLOAT,
+    Format::R8G8B8A8_UNORM,
+];
+
+struct DeferredResolve {
+    sampler: Arc<Sampler>,
+    pipeline: Arc<GraphicsPipeline>,
+}
+
+struct DepthPrepass {
+    pipeline: Arc<GraphicsPipeline>,
+    skinned_pipeline: Arc<GraphicsPipeline>,
+    culled_pipeline: Arc<GraphicsPipeline>,
 }
 
 pub struct Renderer {
+    transients: Mutex<TransientPool>,
+    profiler: Mutex<Option<GpuProfiler>>,
+    occlusion: Mutex<Option<OcclusionQueries>>,
+    deferred: Option<DeferredResolve>,
+    depth_prepass: Option<DepthPrepass>,
+    depth_prepass_enabled: AtomicBool,
+    ssao: Option<SsaoPipeline>,
+    ssao_settings: Mutex<Option<SsaoSettings>>,
+    fxaa: Option<FxaaPipeline>,
+    taa: Option<TaaPipeline>,
+    anti_aliasing: Mutex<AntiAliasing>,
+    upscale: Option<UpscalePipeline>,
+    render_scale: Mutex<Option<RenderScale>>,
+    hdr_output: Option<HdrOutputPipeline>,
+    hdr_settings: Mutex<HdrSettings>,
+    skybox: Option<SkyboxPipeline>,
+    debug_draw: Option<DebugDrawPipeline>,
+    overlay: Option<OverlayPipeline>,
+    lit_defaults: Option<LitDefaults>,
+    path: Option<RenderPath>,
+    view_count: u32,
+    skinned_pipeline: Option<Arc<GraphicsPipeline>>,
+    morph_pipeline: Option<Arc<ComputePipeline>>,
+    cull_pipeline: Option<Arc<ComputePipeline>>,
+    culled_pipeline: Option<Arc<GraphicsPipeline>>,
+    picking_pipeline: Option<Arc<GraphicsPipeline>>,
     pipeline: Arc<GraphicsPipeline>,
     gpu: Arc<Gpu>,
 }
 
-#[derive(Clone)]
 pub struct Mesh<Vertex> {
     vertex_buffer: Subbuffer<[Vertex]>,
-    index_buffer: Subbuffer<[u16]>,
+    index_buffer: IndexBuffer,
+    arena_ranges: Vec<Arc<ArenaRange>>,
+}
+
+impl<Vertex> Clone for Mesh<Vertex> {
+    fn clone(&self) -> Self {
+        Self {
+            vertex_buffer: self.vertex_buffer.clone(),
+            index_buffer: self.index_buffer.clone(),
+            arena_ranges: self.arena_ranges.clone(),
+        }
+    }
 }
 
 impl<Vertex: BufferContents> Mesh<Vertex> {
-    pub fn new(gpu: Arc<Gpu>, vertices: Vec<Vertex>, indices: Vec<u16>) -> anyhow::Result<Self> {
+    pub fn new<Index>(gpu: Arc<Gpu>, vertices: Vec<Vertex>, indices: Vec<Index>) -> Result<Self>
+    where
+        Index: BufferContents,
+        Subbuffer<[Index]>: Into<IndexBuffer>,
+    {
+        let vertex_buffer = gpu.create_device_local_buffer(vertices, BufferUsage::VERTEX_BUFFER)?;
+        let index_buffer = gpu.create_device_local_buffer(indices, BufferUsage::INDEX_BUFFER)?;
+        Ok(Self::from_buffers(vertex_buffer, index_buffer.into()))
+    }
+
+    pub fn dynamic<Index>(gpu: Arc<Gpu>, vertices: Vec<Vertex>, indices: Vec<Index>) -> Result<Self>
+    where
+        Index: BufferContents,
+        Subbuffer<[Index]>: Into<IndexBuffer>,
+    {
         let vertex_buffer = gpu.create_buffer(vertices, BufferUsage::VERTEX_BUFFER)?;
         let index_buffer = gpu.create_buffer(indices, BufferUsage::INDEX_BUFFER)?;
+        Ok(Self::from_buffers(vertex_buffer, index_buffer.into()))
+    }
+
+    pub fn from_arena<Index>(
+        arena: &BufferArena,
+        vertices: Vec<Vertex>,
+        indices: Vec<Index>,
+    ) -> Result<Self>
+    where
+        Index: BufferContents,
+        Subbuffer<[Index]>: Into<IndexBuffer>,
+    {
+        let (vertex_buffer, vertex_range) = arena.allocate(vertices)?.into_parts();
+        let (index_buffer, index_range) = arena.allocate(indices)?.into_parts();
         Ok(Self {
+            vertex_buffer,
+            index_buffer: index_buffer.into(),
+            arena_ranges: vec![vertex_range, index_range],
+        })
+    }
+
+    pub(crate) fn from_buffers(
+        vertex_buffer: Subbuffer<[Vertex]>,
+        index_buffer: IndexBuffer,
+    ) -> Self {
+        Self {
             vertex_buffer,
             index_buffer,
+            arena_ranges: Vec::new(),
+        }
+    }
+
+    pub fn set_debug_name(&self, gpu: &Gpu, name: &str) -> Result<()> {
+        gpu.set_debug_name(&**self.vertex_buffer.buffer(), &format!("{name} vertices"))?;
+        gpu.set_debug_name(
+            &**self.index_buffer.as_bytes().buffer(),
+            &format!("{name} indices"),
+        )
+    }
+
+    pub(crate) fn vertex_buffer(&self) -> &Subbuffer<[Vertex]> {
+        &self.vertex_buffer
+    }
+
+    pub(crate) fn index_buffer(&self) -> &IndexBuffer {
+        &self.index_buffer
+    }
+}
+
+impl Mesh<Vertex3D> {
+    pub fn with_tangents<Index>(
+        gpu: Arc<Gpu>,
+        mut vertices: Vec<Vertex3D>,
+        indices: Vec<Index>,
+    ) -> Result<Self>
+    where
+        Index: BufferContents + Copy + Into<u32>,
+        Subbuffer<[Index]>: Into<IndexBuffer>,
+    {
+        if !Vertex3D::generate_tangents(&mut vertices, &indices) {
+            return Err(EngineError::InvalidArgument(
+                "cannot generate tangents for this mesh".into(),
+            ));
+        }
+        Self::new(gpu, vertices, indices)
+    }
+}
+
+struct LitDefaults {
+    sampler: Arc<Sampler>,
+    clamp_sampler: Arc<Sampler>,
+    normal_map: Texture,
+    white: Texture,
+    environment: Environment,
+}
+
+impl LitDefaults {
+    fn new(gpu: Arc<Gpu>) -> Result<Self> {
+        let sampler = Sampler::new(
+            gpu.device().clone(),
+            SamplerCreateInfo::simple_repeat_linear_no_mipmap(),
+        )?;
+        let clamp_sampler = Sampler::new(
+            gpu.device().clone(),
+            SamplerCreateInfo {
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..SamplerCreateInfo::simple_repeat_linear()
+            },
+        )?;
+        let normal_map = Texture::from_pixels(
+            gpu.clone(),
+            [1, 1],
+            Format::R8G8B8A8_UNORM,
+            &[128, 128, 255, 255],
+        )?;
+        let white = Texture::from_pixels(
+            gpu.clone(),
+            [1, 1],
+            Format::R8G8B8A8_UNORM,
+            &[255, 255, 255, 255],
+        )?;
+        let environment = Environment::empty(gpu)?;
+        Ok(Self {
+            sampler,
+            clamp_sampler,
+            normal_map,
+            white,
+            environment,
         })
     }
 }
@@ -52,86 +405,1259 @@ impl Renderer {
         image_format: Format,
         vs: EntryPoint,
         fs: EntryPoint,
-    ) -> anyhow::Result<Self> {
-        let pipeline = {
-            let vertex_input_state = Vertex::per_vertex().definition(&vs)?;
-
-            let stages = [
-                PipelineShaderStageCreateInfo::new(vs),
-                PipelineShaderStageCreateInfo::new(fs),
-            ];
-
-            let layout = PipelineLayout::new(
-                gpu.queue.device().clone(),
-                PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
-                    .into_pipeline_layout_create_info(gpu.queue.device().clone())
-                    .unwrap(),
-            )?;
+    ) -> Result<Self> {
+        let vertex_input_state = Vertex::per_vertex().definition(&vs)?;
+        let pipeline = create_pipeline(
+            &gpu,
+            vs,
+            Some(fs),
+            vertex_input_state,
+            rendering_info(&[image_format], None),
+            None,
+        )?;
+        let debug_draw = DebugDrawPipeline::new(gpu.clone(), image_format, None)?;
+        let overlay = OverlayPipeline::new(gpu.clone(), image_format)?;
+        let fxaa = FxaaPipeline::new(gpu.clone(), image_format)?;
+        let upscale = UpscalePipeline::new(gpu.clone(), image_format)?;
+        let mut renderer = Self::from_pipeline(gpu, pipeline, None, None);
+        renderer.debug_draw = Some(debug_draw);
+        renderer.overlay = Some(overlay);
+        renderer.fxaa = Some(fxaa);
+        renderer.upscale = Some(upscale);
+        Ok(renderer)
+    }
 
-            let subpass = PipelineRenderingCreateInfo {
-                color_attachment_formats: vec![Some(image_format)],
-                ..Default::default()
-            };
+    pub fn vector(gpu: Arc<Gpu>, image_format: Format) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = vector_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = vector_fs::load(device)?.entry_point("main").unwrap();
+        let vertex_input_state = VectorVertex::per_vertex().definition(&vs)?;
+        let pipeline = create_pipeline(
+            &gpu,
+            vs,
+            Some(fs),
+            vertex_input_state,
+            rendering_info(&[image_format], None),
+            Some(AttachmentBlend::alpha()),
+        )?;
+        let lit_defaults = LitDefaults::new(gpu.clone())?;
+        let debug_draw = DebugDrawPipeline::new(gpu.clone(), image_format, None)?;
+        let overlay = OverlayPipeline::new(gpu.clone(), image_format)?;
+        let fxaa = FxaaPipeline::new(gpu.clone(), image_format)?;
+        let upscale = UpscalePipeline::new(gpu.clone(), image_format)?;
+        let mut renderer = Self::from_pipeline(gpu, pipeline, None, Some(lit_defaults));
+        renderer.debug_draw = Some(debug_draw);
+        renderer.overlay = Some(overlay);
+        renderer.fxaa = Some(fxaa);
+        renderer.upscale = Some(upscale);
+        Ok(renderer)
+    }
+
+    pub fn lit(gpu: Arc<Gpu>, image_format: Format, path: RenderPath) -> Result<Self> {
+        Self::lit_with_output(gpu, image_format, OutputColorSpace::Srgb, path)
+    }
 
-            GraphicsPipeline::new(
-                gpu.queue.device().clone(),
+    pub fn lit_with_output(
+        gpu: Arc<Gpu>,
+        image_format: Format,
+        output: OutputColorSpace,
+        path: RenderPath,
+    ) -> Result<Self> {
+        let hdr_output = if output.is_hdr() {
+            Some(HdrOutputPipeline::new(gpu.clone(), image_format, output)?)
+        } else {
+            None
+        };
+        let image_format = if output.is_hdr() {
+            HDR_SCENE_FORMAT
+        } else {
+            image_format
+        };
+        let device = gpu.device().clone();
+        let vs = forward_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let vertex_input_state = Vertex3D::per_vertex().definition(&vs)?;
+        let (fs, color_formats) = match path {
+            RenderPath::Forward => (forward_fs::load(device.clone())?, vec![image_format]),
+            RenderPath::Deferred => (gbuffer_fs::load(device.clone())?, GBUFFER_FORMATS.to_vec()),
+        };
+        let fs = fs.entry_point("main").unwrap();
+        let pipeline = create_pipeline(
+            &gpu,
+            vs.clone(),
+            Some(fs.clone()),
+            vertex_input_state.clone(),
+            rendering_info(&color_formats, Some(DEPTH_FORMAT)),
+            None,
+        )?;
+        let skinned_vs = skinned_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let skinned_vertex_input_state = SkinnedVertex3D::per_vertex().definition(&skinned_vs)?;
+        let skinned_pipeline = create_pipeline(
+            &gpu,
+            skinned_vs.clone(),
+            Some(fs.clone()),
+            skinned_vertex_input_state.clone(),
+            rendering_info(&color_formats, Some(DEPTH_FORMAT)),
+            None,
+        )?;
+        let culled_vs = culled_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let culled_vertex_input_state = Vertex3D::per_vertex().definition(&culled_vs)?;
+        let culled_pipeline = create_pipeline(
+            &gpu,
+            culled_vs.clone(),
+            Some(fs),
+            culled_vertex_input_state.clone(),
+            rendering_info(&color_formats, Some(DEPTH_FORMAT)),
+            None,
+        )?;
+        let depth_prepass = DepthPrepass {
+            pipeline: create_pipeline(
+                &gpu,
+                vs,
                 None,
-                GraphicsPipelineCreateInfo {
-                    stages: stages.into_iter().collect(),
-                    vertex_input_state: Some(vertex_input_state),
-                    input_assembly_state: Some(InputAssemblyState::default()),
-                    viewport_state: Some(ViewportState::default()),
-                    rasterization_state: Some(RasterizationState {
-                        polygon_mode: PolygonMode::Fill,
-                        ..Default::default()
-                    }),
-                    multisample_state: Some(MultisampleState::default()),
-                    color_blend_state: Some(ColorBlendState::with_attachment_states(
-                        subpass.color_attachment_formats.len() as u32,
-                        ColorBlendAttachmentState::default(),
-                    )),
-                    dynamic_state: [DynamicState::Viewport].into_iter().collect(),
-                    subpass: Some(subpass.into()),
-                    ..GraphicsPipelineCreateInfo::layout(layout)
-                },
-            )?
+                vertex_input_state,
+                rendering_info(&[], Some(DEPTH_FORMAT)),
+                None,
+            )?,
+            skinned_pipeline: create_pipeline(
+                &gpu,
+                skinned_vs,
+                None,
+                skinned_vertex_input_state,
+                rendering_info(&[], Some(DEPTH_FORMAT)),
+                None,
+            )?,
+            culled_pipeline: create_pipeline(
+                &gpu,
+                culled_vs,
+                None,
+                culled_vertex_input_state,
+                rendering_info(&[], Some(DEPTH_FORMAT)),
+                None,
+            )?,
+        };
+        let picking_vs = forward_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let picking_fs = picking_fs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let picking_vertex_input_state = Vertex3D::per_vertex().definition(&picking_vs)?;
+        let picking_pipeline = create_pipeline(
+            &gpu,
+            picking_vs,
+            Some(picking_fs),
+            picking_vertex_input_state,
+            rendering_info(&[Format::R32_UINT], Some(DEPTH_FORMAT)),
+            None,
+        )?;
+
+        let morph_pipeline = gpu.create_compute_pipeline(
+            morph_cs::load(device.clone())?.entry_point("main").unwrap(),
+        )?;
+        let cull_pipeline = gpu
+            .create_compute_pipeline(cull_cs::load(device.clone())?.entry_point("main").unwrap())?;
+
+        let ssao = match path {
+            RenderPath::Forward => None,
+            RenderPath::Deferred => Some(SsaoPipeline::new(gpu.clone())?),
+        };
+        let deferred = match path {
+            RenderPath::Forward => None,
+            RenderPath::Deferred => {
+                let vs = fullscreen_vs::load(device.clone())?
+                    .entry_point("main")
+                    .unwrap();
+                let fs = deferred_resolve_fs::load(device.clone())?
+                    .entry_point("main")
+                    .unwrap();
+                let pipeline = create_pipeline(
+                    &gpu,
+                    vs,
+                    Some(fs),
+                    VertexInputState::new(),
+                    rendering_info(&[image_format], None),
+                    None,
+                )?;
+                let sampler = Sampler::new(device, SamplerCreateInfo::default())?;
+                Some(DeferredResolve { sampler, pipeline })
+            }
+        };
+
+        let lit_defaults = LitDefaults::new(gpu.clone())?;
+        let skybox = SkyboxPipeline::new(gpu.clone(), image_format, DEPTH_FORMAT)?;
+        let debug_draw = DebugDrawPipeline::new(gpu.clone(), image_format, Some(DEPTH_FORMAT))?;
+        let overlay = OverlayPipeline::new(gpu.clone(), image_format)?;
+        let fxaa = FxaaPipeline::new(gpu.clone(), image_format)?;
+        let taa = TaaPipeline::new(gpu.clone(), image_format)?;
+        let upscale = UpscalePipeline::new(gpu.clone(), image_format)?;
+        let mut renderer = Self::from_pipeline(gpu, pipeline, Some(path), Some(lit_defaults));
+        renderer.deferred = deferred;
+        renderer.ssao = ssao;
+        renderer.fxaa = Some(fxaa);
+        renderer.taa = Some(taa);
+        renderer.upscale = Some(upscale);
+        renderer.hdr_output = hdr_output;
+        renderer.skybox = Some(skybox);
+        renderer.debug_draw = Some(debug_draw);
+        renderer.overlay = Some(overlay);
+        renderer.skinned_pipeline = Some(skinned_pipeline);
+        renderer.morph_pipeline = Some(morph_pipeline);
+        renderer.cull_pipeline = Some(cull_pipeline);
+        renderer.culled_pipeline = Some(culled_pipeline);
+        renderer.picking_pipeline = Some(picking_pipeline);
+        renderer.depth_prepass = Some(depth_prepass);
+        renderer.name_pipelines()?;
+        Ok(renderer)
+    }
+
+    fn name_pipelines(&self) -> Result<()> {
+        let gpu = &self.gpu;
+        gpu.set_debug_name(&*self.pipeline, "lit")?;
+        let graphics = [
+            ("skinned", &self.skinned_pipeline),
+            ("culled", &self.culled_pipeline),
+            ("picking", &self.picking_pipeline),
+        ];
+        for (name, pipeline) in graphics {
+            if let Some(pipeline) = pipeline {
+                gpu.set_debug_name(&**pipeline, name)?;
+            }
+        }
+        let compute = [
+            ("morph", &self.morph_pipeline),
+            ("cull", &self.cull_pipeline),
+        ];
+        for (name, pipeline) in compute {
+            if let Some(pipeline) = pipeline {
+                gpu.set_debug_name(&**pipeline, name)?;
+            }
+        }
+        if let Some(depth_prepass) = &self.depth_prepass {
+            gpu.set_debug_name(&*depth_prepass.pipeline, "depth prepass")?;
+            gpu.set_debug_name(&*depth_prepass.skinned_pipeline, "skinned depth prepass")?;
+            gpu.set_debug_name(&*depth_prepass.culled_pipeline, "culled depth prepass")?;
+        }
+        if let Some(deferred) = &self.deferred {
+            gpu.set_debug_name(&*deferred.pipeline, "deferred resolve")?;
+        }
+        Ok(())
+    }
+
+    pub fn multiview(gpu: Arc<Gpu>, image_format: Format, view_count: u32) -> Result<Self> {
+        let device = gpu.device().clone();
+        if !device.enabled_features().multiview {
+            return Err(EngineError::Unsupported(
+                "the device does not support multiview rendering".into(),
+            ));
+        }
+        if !(2..=MAX_VIEWS as u32).contains(&view_count) {
+            return Err(EngineError::InvalidArgument(format!(
+                "multiview rendering supports between 2 and {MAX_VIEWS} views, got {view_count}"
+            )));
+        }
+        let vs = forward_multiview_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = forward_multiview_fs::load(device)?
+            .entry_point("main")
+            .unwrap();
+        let vertex_input_state = Vertex3D::per_vertex().definition(&vs)?;
+        let pipeline = create_pipeline(
+            &gpu,
+            vs,
+            Some(fs),
+            vertex_input_state,
+            PipelineRenderingCreateInfo {
+                view_mask: view_mask(view_count),
+                ..rendering_info(&[image_format], Some(DEPTH_FORMAT))
+            },
+            None,
+        )?;
+        let lit_defaults = LitDefaults::new(gpu.clone())?;
+        let mut renderer =
+            Self::from_pipeline(gpu, pipeline, Some(RenderPath::Forward), Some(lit_defaults));
+        renderer.view_count = view_count;
+        Ok(renderer)
+    }
+
+    pub fn view_count(&self) -> u32 {
+        self.view_count
+    }
+
+    pub fn shader_parameters(
+        &self,
+        reflection: Arc<ShaderReflection>,
+        set: u32,
+    ) -> Result<ShaderParameters> {
+        ShaderParameters::new(reflection, self.pipeline.layout(), set)
+    }
+
+    fn from_pipeline(
+        gpu: Arc<Gpu>,
+        pipeline: Arc<GraphicsPipeline>,
+        path: Option<RenderPath>,
+        lit_defaults: Option<LitDefaults>,
+    ) -> Self {
+        let transients = Mutex::new(TransientPool::new(gpu.clone()));
+        Self {
+            transients,
+            profiler: Mutex::new(None),
+            occlusion: Mutex::new(None),
+            deferred: None,
+            depth_prepass: None,
+            depth_prepass_enabled: AtomicBool::new(false),
+            ssao: None,
+            ssao_settings: Mutex::new(None),
+            fxaa: None,
+            taa: None,
+            anti_aliasing: Mutex::new(AntiAliasing::None),
+            upscale: None,
+            render_scale: Mutex::new(None),
+            hdr_output: None,
+            hdr_settings: Mutex::new(HdrSettings::default()),
+            skybox: None,
+            debug_draw: None,
+            overlay: None,
+            lit_defaults,
+            path,
+            view_count: 1,
+            skinned_pipeline: None,
+            morph_pipeline: None,
+            cull_pipeline: None,
+            culled_pipeline: None,
+            picking_pipeline: None,
+            pipeline,
+            gpu,
+        }
+    }
+
+    pub fn set_profiling(&self, frames_in_flight: Option<usize>) -> Result<()> {
+        *self.profiler.lock().unwrap() = match frames_in_flight {
+            Some(frames_in_flight) => Some(GpuProfiler::new(&self.gpu, frames_in_flight)?),
+            None => None,
         };
+        Ok(())
+    }
+
+    pub fn gpu_timings(&self) -> Vec<PassTiming> {
+        self.profiler
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|profiler| profiler.timings().to_vec())
+            .unwrap_or_default()
+    }
 
-        Ok(Self { pipeline, gpu })
+    pub fn set_depth_prepass(&self, enabled: bool) -> Result<()> {
+        if enabled && self.depth_prepass.is_none() {
+            return Err(EngineError::InvalidArgument(
+                "a depth prepass requires a lit renderer".into(),
+            ));
+        }
+        self.depth_prepass_enabled.store(enabled, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn depth_prepass(&self) -> bool {
+        self.depth_prepass_enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_ssao(&self, settings: Option<SsaoSettings>) -> Result<()> {
+        if settings.is_some() && self.ssao.is_none() {
+            return Err(EngineError::InvalidArgument(
+                "SSAO requires the deferred render path".into(),
+            ));
+        }
+        *self.ssao_settings.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    pub fn ssao(&self) -> Option<SsaoSettings> {
+        *self.ssao_settings.lock().unwrap()
+    }
+
+    pub fn set_anti_aliasing(&self, anti_aliasing: AntiAliasing) -> Result<()> {
+        match anti_aliasing {
+            AntiAliasing::Fxaa if self.fxaa.is_none() => {
+                return Err(EngineError::InvalidArgument(
+                    "FXAA is not supported by a multiview renderer".into(),
+                ))
+            }
+            AntiAliasing::Taa if self.taa.is_none() => {
+                return Err(EngineError::InvalidArgument(
+                    "TAA requires a lit renderer".into(),
+                ))
+            }
+            _ => {}
+        }
+        if let Some(taa) = &self.taa {
+            taa.reset();
+        }
+        *self.anti_aliasing.lock().unwrap() = anti_aliasing;
+        Ok(())
+    }
+
+    pub fn anti_aliasing(&self) -> AntiAliasing {
+        *self.anti_aliasing.lock().unwrap()
+    }
+
+    pub fn set_render_scale(&self, render_scale: Option<RenderScale>) -> Result<()> {
+        if let Some(render_scale) = render_scale {
+            if self.upscale.is_none() {
+                return Err(EngineError::InvalidArgument(
+                    "render scaling is not supported by a multiview renderer".into(),
+                ));
+            }
+            if !(0.25..=1.0).contains(&render_scale.scale) {
+                return Err(EngineError::InvalidArgument(format!(
+                    "render scale must be between 0.25 and 1.0, got {}",
+                    render_scale.scale
+                )));
+            }
+        }
+        *self.render_scale.lock().unwrap() = render_scale;
+        Ok(())
+    }
+
+    pub fn render_scale(&self) -> Option<RenderScale> {
+        *self.render_scale.lock().unwrap()
+    }
+
+    pub fn gpu(&self) -> &Arc<Gpu> {
+        &self.gpu
+    }
+
+    pub fn output_color_space(&self) -> OutputColorSpace {
+        self.hdr_output
+            .as_ref()
+            .map_or(OutputColorSpace::Srgb, HdrOutputPipeline::color_space)
+    }
+
+    pub fn set_hdr_settings(&self, settings: HdrSettings) {
+        *self.hdr_settings.lock().unwrap() = settings;
+    }
+
+    pub fn hdr_settings(&self) -> HdrSettings {
+        *self.hdr_settings.lock().unwrap()
+    }
+
+    pub fn set_occlusion_queries(&self, queries: Option<OcclusionQueries>) {
+        *self.occlusion.lock().unwrap() = queries;
+    }
+
+    pub fn occlusion_result(&self, query: u32) -> Option<u64> {
+        self.occlusion
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|queries| queries.result(query))
     }
 
     pub fn render<Vertex>(
         &self,
         image_view: Arc<ImageView>,
         render_params: RenderParams<Vertex>,
-    ) -> anyhow::Result<Arc<PrimaryAutoCommandBuffer>> {
-        let extent = image_view.image().extent();
-        let viewport = Viewport {
-            offset: [0.0, 0.0],
-            extent: [extent[0] as f32, extent[1] as f32],
-            depth_range: 0.0..=1.0,
-        };
-        let mut builder = self.gpu.create_command_buffer_builder()?;
-        builder
-            .begin_rendering(RenderingInfo {
-                color_attachments: vec![Some(RenderingAttachmentInfo {
-                    load_op: AttachmentLoadOp::Clear,
-                    store_op: AttachmentStoreOp::Store,
-                    clear_value: Some(render_params.clear_color.into()),
-                    ..RenderingAttachmentInfo::image_view(image_view)
-                })],
-                ..Default::default()
-            })?
-            .set_viewport(0, [viewport].into_iter().collect())?
-            .bind_pipeline_graphics(self.pipeline.clone())?;
-        if let Some(mesh) = render_params.mesh {
-            let index_count = mesh.index_buffer.len();
-            builder.bind_vertex_buffers(0, mesh.vertex_buffer)?;
-            builder.bind_index_buffer(mesh.index_buffer)?;
+    ) -> Result<Arc<PrimaryAutoCommandBuffer>> {
+        let mut graph = RenderGraph::new();
+        let target = graph.import(image_view);
+        self.add_passes(&mut graph, target, render_params)?;
+        self.execute(graph)
+    }
+
+    pub fn add_passes<'a, Vertex: 'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        target: ResourceId,
+        mut render_params: RenderParams<Vertex>,
+    ) -> Result<()> {
+        let morphs = std::mem::take(&mut render_params.morphs);
+        let overlay = std::mem::take(&mut render_params.overlay);
+        let hdr = self.hdr_output.as_ref().map(|hdr_output| {
+            let scene =
+                graph.transient(TransientImage::new(HDR_SCENE_FORMAT, graph.extent(target)));
+            (hdr_output, scene, target)
+        });
+        let target = hdr.map_or(target, |(_, scene, _)| scene);
+        let upscale = match (&self.upscale, self.render_scale()) {
+            (Some(upscale), Some(render_scale)) if render_scale.scale < 1.0 => {
+                let extent = render_scale.extent(graph.extent(target));
+                let output = graph.transient(TransientImage::new(graph.format(target), extent));
+                Some((upscale, output, render_scale.sharpness))
+            }
+            _ => None,
+        };
+        let output = upscale.map_or(target, |(_, output, _)| output);
+        let anti_aliasing = self.anti_aliasing();
+        let taa = match (&self.taa, anti_aliasing) {
+            (Some(taa), AntiAliasing::Taa) => {
+                let frame = taa.prepare(&mut render_params.camera, graph.extent(output))?;
+                let frame_set = self.create_frame_set(taa.layout(), &render_params)?;
+                Some((taa, frame, frame_set))
+            }
+            _ => None,
+        };
+        let scene = match anti_aliasing {
+            AntiAliasing::None => output,
+            AntiAliasing::Fxaa | AntiAliasing::Taa => graph.transient(TransientImage::new(
+                graph.format(output),
+                graph.extent(output),
+            )),
+        };
+        if !morphs.is_empty() {
+            let Some(pipeline) = &self.morph_pipeline else {
+                return Err(EngineError::InvalidArgument(
+                    "morphed meshes require a lit renderer".into(),
+                ));
+            };
+            graph.add_pass("morph").record(move |ctx| {
+                for morph in &morphs {
+                    morph.record_blend(&self.gpu, pipeline, ctx.builder)?;
+                }
+                Ok(())
+            });
+        }
+        if !render_params.culled.is_empty() {
+            let Some(pipeline) = &self.cull_pipeline else {
+                return Err(EngineError::InvalidArgument(
+                    "culled batches require a lit renderer".into(),
+                ));
+            };
+            let batches = render_params.culled.clone();
+            let camera = render_params.camera;
+            graph.add_pass("cull").record(move |ctx| {
+                for batch in &batches {
+                    batch.record_cull(&self.gpu, pipeline, &camera, ctx.builder)?;
+                }
+                Ok(())
+            });
+        }
+
+        let depth = match self.path {
+            None => {
+                graph
+                    .add_pass("main")
+                    .color_attachment(Attachment::clear(scene, render_params.clear_color))
+                    .record(move |ctx| {
+                        let viewport = ctx.viewport();
+                        ctx.builder
+                            .set_viewport(0, [viewport].into_iter().collect())?;
+                        self.record_draws(ctx.builder, &self.pipeline, None, render_params.draws)?;
+                        self.record_debug_draw(
+                            ctx.builder,
+                            &render_params.camera,
+                            render_params.debug_draw,
+                        )
+                    });
+                None
+            }
+            Some(RenderPath::Forward) if self.view_count > 1 => {
+                self.add_multiview_passes(graph, scene, render_params)?;
+                None
+            }
+            Some(RenderPath::Forward) => {
+                Some(self.add_forward_passes(graph, scene, render_params)?)
+            }
+            Some(RenderPath::Deferred) => {
+                Some(self.add_deferred_passes(graph, scene, render_params)?)
+            }
+        };
+
+        match (anti_aliasing, taa, depth) {
+            (AntiAliasing::Fxaa, _, _) => {
+                if let Some(fxaa) = &self.fxaa {
+                    fxaa.add_pass(graph, scene, output);
+                }
+            }
+            (AntiAliasing::Taa, Some((taa, frame, frame_set)), Some(depth)) => {
+                taa.add_pass(graph, frame, frame_set, scene, depth, output);
+            }
+            _ => {}
+        }
+        if let Some((upscale, output, sharpness)) = upscale {
+            upscale.add_pass(graph, output, target, sharpness);
+        }
+
+        if let (Some(pipeline), false) = (&self.overlay, overlay.is_empty()) {
+            graph
+                .add_pass("overlay")
+                .color_attachment(Attachment::load(target))
+                .record(move |ctx| pipeline.draw(ctx.builder, ctx.viewport(), overlay));
+        }
+        if let Some((hdr_output, scene, target)) = hdr {
+            hdr_output.add_pass(graph, scene, target, self.hdr_settings());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn add_picking_pass<'a, Vertex>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        target: ResourceId,
+        render_params: &RenderParams<Vertex>,
+        position: [u32; 2],
+    ) -> Result<PendingPick> {
+        let Some(pipeline) = &self.picking_pipeline else {
+            return Err(EngineError::InvalidArgument(
+                "picking requires a lit renderer".into(),
+            ));
+        };
+        let frame_set = self.create_frame_set(pipeline.layout(), render_params)?;
+        let draws: Vec<_> = render_params
+            .draws
+            .iter()
+            .filter_map(|draw| {
+                let object_id = draw.object_id?;
+                let vertex_buffer = draw.mesh.vertex_buffer.clone().into_bytes();
+                let index_buffer = draw.mesh.index_buffer.clone();
+                Some((vertex_buffer, index_buffer, draw.transform, object_id))
+            })
+            .collect();
+        let extent = graph.extent(target);
+        let position = [
+            position[0].min(extent[0] - 1),
+            position[1].min(extent[1] - 1),
+        ];
+        let ids = graph.transient(TransientImage::new(Format::R32_UINT, extent));
+        let depth = graph.transient(TransientImage::new(DEPTH_FORMAT, extent));
+        graph
+            .add_pass("picking")
+            .color_attachment(Attachment::clear(
+                ids,
+                ClearValue::Uint([NO_OBJECT, 0, 0, 0]),
+            ))
+            .depth_attachment(Attachment::clear(depth, ClearValue::Depth(1.0)))
+            .record(move |ctx| {
+                let viewport = ctx.viewport();
+                let layout = pipeline.layout().clone();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?
+                    .bind_pipeline_graphics(pipeline.clone())?
+                    .bind_descriptor_sets(
+                        PipelineBindPoint::Graphics,
+                        layout.clone(),
+                        0,
+                        frame_set,
+                    )?;
+                for (vertex_buffer, index_buffer, transform, object_id) in draws {
+                    let mut constants = DrawConstants::new(transform, &Material::default());
+                    constants.material[3] = f32::from_bits(object_id.0);
+                    let index_count = index_buffer.len();
+                    ctx.builder
+                        .push_constants(layout.clone(), 0, constants)?
+                        .bind_vertex_buffers(0, vertex_buffer)?
+                        .bind_index_buffer(index_buffer)?;
+                    unsafe { ctx.builder.draw_indexed(index_count as u32, 1, 0, 0, 0) }?;
+                }
+                Ok(())
+            });
+        PendingPick::record(&self.gpu, graph, ids, position)
+    }
+
+    fn add_forward_passes<'a, Vertex: 'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        target: ResourceId,
+        render_params: RenderParams<Vertex>,
+    ) -> Result<ResourceId> {
+        let frame_set = self.create_frame_set(self.pipeline.layout(), &render_params)?;
+        let skinned_pipeline = self.skinned_pipeline.as_ref().unwrap();
+        let skinned_frame_set = self.create_frame_set(skinned_pipeline.layout(), &render_params)?;
+        let culled_pipeline = self.culled_pipeline.as_ref().unwrap();
+        let culled_frame_set = self.create_frame_set(culled_pipeline.layout(), &render_params)?;
+        let depth = graph.transient(TransientImage::new(DEPTH_FORMAT, graph.extent(target)));
+        let depth_attachment = self.add_depth_prepass(graph, depth, &render_params)?;
+        graph
+            .add_pass("forward")
+            .color_attachment(Attachment::clear(target, render_params.clear_color))
+            .depth_attachment(depth_attachment)
+            .record(move |ctx| {
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?;
+                self.record_draws(
+                    ctx.builder,
+                    &self.pipeline,
+                    Some(frame_set),
+                    render_params.draws,
+                )?;
+                self.record_draws(
+                    ctx.builder,
+                    skinned_pipeline,
+                    Some(skinned_frame_set),
+                    render_params.skinned_draws,
+                )?;
+                self.record_culled(
+                    ctx.builder,
+                    culled_pipeline,
+                    culled_frame_set,
+                    render_params.culled,
+                )?;
+                if let (Some(pipeline), Some(cubemap)) = (&self.skybox, &render_params.skybox) {
+                    pipeline.draw(ctx.builder, &render_params.camera, cubemap)?;
+                }
+                self.record_debug_draw(ctx.builder, &render_params.camera, render_params.debug_draw)
+            });
+        Ok(depth)
+    }
+
+    fn add_multiview_passes<'a, Vertex: 'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        target: ResourceId,
+        render_params: RenderParams<Vertex>,
+    ) -> Result<()> {
+        if !render_params.skinned_draws.is_empty()
+            || render_params.skybox.is_some()
+            || !render_params.debug_draw.is_empty()
+        {
+            return Err(EngineError::InvalidArgument(
+                "multiview rendering only supports static draws".into(),
+            ));
+        }
+        let layers = graph.layers(target);
+        if layers < self.view_count {
+            return Err(EngineError::InvalidArgument(format!(
+                "multiview rendering needs {} target layers, got {layers}",
+                self.view_count
+            )));
+        }
+        let frame_set = self.create_frame_set(self.pipeline.layout(), &render_params)?;
+        let depth = graph.transient(TransientImage {
+            layers: self.view_count,
+            ..TransientImage::new(DEPTH_FORMAT, graph.extent(target))
+        });
+        graph
+            .add_pass("multiview")
+            .color_attachment(Attachment::clear(target, render_params.clear_color))
+            .depth_attachment(Attachment::clear(depth, ClearValue::Depth(1.0)))
+            .view_mask(view_mask(self.view_count))
+            .record(move |ctx| {
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?;
+                self.record_draws(
+                    ctx.builder,
+                    &self.pipeline,
+                    Some(frame_set),
+                    render_params.draws,
+                )
+            });
+        Ok(())
+    }
+
+    fn add_depth_prepass<'a, Vertex: 'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        depth: ResourceId,
+        render_params: &RenderParams<Vertex>,
+    ) -> Result<Attachment> {
+        let clear = Attachment::clear(depth, ClearValue::Depth(1.0));
+        let Some(prepass) = self.depth_prepass.as_ref().filter(|_| self.depth_prepass()) else {
+            return Ok(clear);
+        };
+        let frame_set = self.create_frame_set(prepass.pipeline.layout(), render_params)?;
+        let skinned_frame_set =
+            self.create_frame_set(prepass.skinned_pipeline.layout(), render_params)?;
+        let culled_frame_set =
+            self.create_frame_set(prepass.culled_pipeline.layout(), render_params)?;
+        let draws = render_params.draws.iter().map(Draw::depth_only).collect();
+        let skinned_draws = render_params
+            .skinned_draws
+            .iter()
+            .map(Draw::depth_only)
+            .collect();
+        let culled = render_params.culled.clone();
+        graph
+            .add_pass("depth prepass")
+            .depth_attachment(clear)
+            .record(move |ctx| {
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?;
+                self.record_draws(ctx.builder, &prepass.pipeline, Some(frame_set), draws)?;
+                self.record_draws(
+                    ctx.builder,
+                    &prepass.skinned_pipeline,
+                    Some(skinned_frame_set),
+                    skinned_draws,
+                )?;
+                self.record_culled(
+                    ctx.builder,
+                    &prepass.culled_pipeline,
+                    culled_frame_set,
+                    culled,
+                )
+            });
+        Ok(Attachment::load(depth))
+    }
+
+    fn add_deferred_passes<'a, Vertex: 'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        target: ResourceId,
+        render_params: RenderParams<Vertex>,
+    ) -> Result<ResourceId> {
+        let resolve = self.deferred.as_ref().unwrap();
+        let geometry_set = self.create_frame_set(self.pipeline.layout(), &render_params)?;
+        let skinned_pipeline = self.skinned_pipeline.as_ref().unwrap();
+        let skinned_geometry_set =
+            self.create_frame_set(skinned_pipeline.layout(), &render_params)?;
+        let culled_pipeline = self.culled_pipeline.as_ref().unwrap();
+        let culled_geometry_set =
+            self.create_frame_set(culled_pipeline.layout(), &render_params)?;
+        let resolve_set = self.create_frame_set(resolve.pipeline.layout(), &render_params)?;
+
+        let extent = graph.extent(target);
+        let [albedo, normal, material] =
+            GBUFFER_FORMATS.map(|format| graph.transient(TransientImage::new(format, extent)));
+        let depth = graph.transient(TransientImage::new(DEPTH_FORMAT, extent));
+        let depth_attachment = self.add_depth_prepass(graph, depth, &render_params)?;
+        let ssao = match (&self.ssao, self.ssao()) {
+            (Some(ssao), Some(settings)) => {
+                let frame_set = self.create_frame_set(ssao.layout(), &render_params)?;
+                Some((ssao, frame_set, settings))
+            }
+            _ => None,
+        };
+
+        let RenderParams {
+            clear_color,
+            draws,
+            skinned_draws,
+            culled,
+            camera,
+            skybox,
+            debug_draw,
+            ..
+        } = render_params;
+
+        graph
+            .add_pass("gbuffer")
+            .color_attachment(Attachment::clear(albedo, [0.0; 4]))
+            .color_attachment(Attachment::clear(normal, [0.0; 4]))
+            .color_attachment(Attachment::clear(material, [0.0; 4]))
+            .depth_attachment(depth_attachment)
+            .record(move |ctx| {
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?;
+                self.record_draws(ctx.builder, &self.pipeline, Some(geometry_set), draws)?;
+                self.record_draws(
+                    ctx.builder,
+                    skinned_pipeline,
+                    Some(skinned_geometry_set),
+                    skinned_draws,
+                )?;
+                self.record_culled(ctx.builder, culled_pipeline, culled_geometry_set, culled)
+            });
+
+        let occlusion = ssao.map(|(ssao, frame_set, settings)| {
+            ssao.add_passes(graph, frame_set, normal, depth, settings)
+        });
+
+        let mut lighting = graph
+            .add_pass("lighting")
+            .color_attachment(Attachment::clear(target, clear_color))
+            .sample(albedo)
+            .sample(normal)
+            .sample(material)
+            .sample(depth);
+        if let Some(occlusion) = occlusion {
+            lighting = lighting.sample(occlusion);
+        }
+        lighting.record(move |ctx| {
+            let layout = resolve.pipeline.layout().clone();
+            let occlusion = match occlusion {
+                Some(occlusion) => ctx.image_view(occlusion),
+                None => self.lit_defaults.as_ref().unwrap().white.image_view(),
+            };
+            let gbuffer_set = self.gpu.create_frame_descriptor_set(
+                layout.set_layouts()[1].clone(),
+                [albedo, normal, material, depth]
+                    .map(|resource| ctx.image_view(resource))
+                    .into_iter()
+                    .chain([occlusion])
+                    .enumerate()
+                    .map(|(binding, image_view)| {
+                        WriteDescriptorSet::image_view_sampler(
+                            binding as u32,
+                            image_view,
+                            resolve.sampler.clone(),
+                        )
+                    }),
+            )?;
+            let viewport = ctx.viewport();
+            ctx.builder
+                .set_viewport(0, [viewport].into_iter().collect())?
+                .bind_pipeline_graphics(resolve.pipeline.clone())?
+                .bind_descriptor_sets(
+                    PipelineBindPoint::Graphics,
+                    layout,
+                    0,
+                    (resolve_set, gbuffer_set),
+                )?;
+            unsafe { ctx.builder.draw(3, 1, 0, 0) }?;
+            Ok(())
+        });
+
+        if let (Some(pipeline), Some(cubemap)) = (&self.skybox, skybox) {
+            graph
+                .add_pass("skybox")
+                .color_attachment(Attachment::load(target))
+                .depth_attachment(Attachment::load(depth))
+                .record(move |ctx| {
+                    let viewport = ctx.viewport();
+                    ctx.builder
+                        .set_viewport(0, [viewport].into_iter().collect())?;
+                    pipeline.draw(ctx.builder, &camera, &cubemap)
+                });
+        }
+
+        if !debug_draw.is_empty() {
+            graph
+                .add_pass("debug")
+                .color_attachment(Attachment::load(target))
+                .depth_attachment(Attachment::load(depth))
+                .record(move |ctx| {
+                    let viewport = ctx.viewport();
+                    ctx.builder
+                        .set_viewport(0, [viewport].into_iter().collect())?;
+                    self.record_debug_draw(ctx.builder, &camera, debug_draw)
+                });
+        }
+        Ok(depth)
+    }
+
+    fn record_draws<Vertex>(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        pipeline: &Arc<GraphicsPipeline>,
+        frame_set: Option<Arc<DescriptorSet>>,
+        mut draws: Vec<Draw<Vertex>>,
+    ) -> Result<()> {
+        if draws.is_empty() {
+            return Ok(());
+        }
+        let layout = pipeline.layout().clone();
+        builder.bind_pipeline_graphics(pipeline.clone())?;
+        if let Some(frame_set) = frame_set {
+            builder.bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                layout.clone(),
+                0,
+                frame_set,
+            )?;
+        }
+        let push_constants = layout
+            .push_constant_ranges()
+            .iter()
+            .any(|range| range.size as usize >= size_of::<DrawConstants>());
+        let skinned = layout.set_layouts().len() > 2;
+        let shading_rate = pipeline
+            .dynamic_state()
+            .contains(&DynamicState::FragmentShadingRate);
+
+        draws.sort_by_key(|draw| draw.layer);
+        for draw in draws {
+            if let Some(parameters) = &draw.parameters {
+                builder.bind_descriptor_sets(
+                    PipelineBindPoint::Graphics,
+                    layout.clone(),
+                    parameters.set_index(),
+                    parameters.descriptor_set(&self.gpu)?,
+                )?;
+            } else if let Some(material_set) = self.create_material_set(&layout, &draw.material)? {
+                builder.bind_descriptor_sets(
+                    PipelineBindPoint::Graphics,
+                    layout.clone(),
+                    1,
+                    material_set,
+                )?;
+            }
+            if skinned {
+                let Some(joints) = draw.joints else {
+                    continue;
+                };
+                let joints_set = self.gpu.create_frame_descriptor_set(
+                    layout.set_layouts()[2].clone(),
+                    [WriteDescriptorSet::buffer(0, joints)],
+                )?;
+                builder.bind_descriptor_sets(
+                    PipelineBindPoint::Graphics,
+                    layout.clone(),
+                    2,
+                    joints_set,
+                )?;
+            }
+            if push_constants {
+                builder.push_constants(
+                    layout.clone(),
+                    0,
+                    DrawConstants::new(draw.transform, &draw.material),
+                )?;
+            }
+            if shading_rate {
+                builder.set_fragment_shading_rate(
+                    draw.shading_rate.unwrap_or([1, 1]),
+                    [FragmentShadingRateCombinerOp::Keep; 2],
+                )?;
+            }
+            let index_count = draw.mesh.index_buffer.len();
+            builder.bind_vertex_buffers(0, draw.mesh.vertex_buffer)?;
+            builder.bind_index_buffer(draw.mesh.index_buffer)?;
+            let mut occlusion = self.occlusion.lock().unwrap();
+            let query = match (occlusion.as_mut(), draw.occlusion_query) {
+                (Some(queries), Some(query)) => queries.begin(builder, query)?.then_some(query),
+                _ => None,
+            };
             unsafe { builder.draw_indexed(index_count as u32, 1, 0, 0, 0) }?;
+            if let (Some(queries), Some(query)) = (occlusion.as_mut(), query) {
+                queries.end(builder, query)?;
+            }
         }
-        builder.end_rendering()?;
+        Ok(())
+    }
+
+    fn record_culled(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        pipeline: &Arc<GraphicsPipeline>,
+        frame_set: Arc<DescriptorSet>,
+        batches: Vec<CulledBatch>,
+    ) -> Result<()> {
+        if batches.is_empty() {
+            return Ok(());
+        }
+        let layout = pipeline.layout().clone();
+        builder
+            .bind_pipeline_graphics(pipeline.clone())?
+            .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 0, frame_set)?;
+        for batch in batches {
+            if let Some(material_set) = self.create_material_set(&layout, &batch.material)? {
+                builder.bind_descriptor_sets(
+                    PipelineBindPoint::Graphics,
+                    layout.clone(),
+                    1,
+                    material_set,
+                )?;
+            }
+            builder.push_constants(
+                layout.clone(),
+                0,
+                DrawConstants::new(Mat4::IDENTITY, &batch.material),
+            )?;
+            batch.record_draw(&self.gpu, &layout, builder)?;
+        }
+        Ok(())
+    }
+
+    fn record_debug_draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        camera: &Camera,
+        debug_draw: DebugDraw,
+    ) -> Result<()> {
+        match &self.debug_draw {
+            Some(pipeline) => pipeline.draw(builder, camera, debug_draw),
+            None => Ok(()),
+        }
+    }
+
+    fn create_frame_set<Vertex>(
+        &self,
+        layout: &Arc<PipelineLayout>,
+        render_params: &RenderParams<Vertex>,
+    ) -> Result<Arc<DescriptorSet>> {
+        let defaults = self.lit_defaults.as_ref().unwrap();
+        let environment = render_params
+            .environment
+            .as_ref()
+            .unwrap_or(&defaults.environment);
+        let set_layout = layout.set_layouts()[0].clone();
+        let camera = if self.view_count > 1 {
+            let uniform = MultiviewCameraUniform::new(&render_params.views, &render_params.camera);
+            self.gpu.frame_uniform(uniform)?.into_bytes()
+        } else {
+            self.gpu
+                .frame_uniform(render_params.camera.uniform())?
+                .into_bytes()
+        };
+        let mut writes = vec![WriteDescriptorSet::buffer(0, camera)];
+        if set_layout.bindings().contains_key(&1) {
+            writes.extend([
+                WriteDescriptorSet::buffer(
+                    1,
+                    self.gpu.frame_uniform(render_params.lights.uniform())?,
+                ),
+                WriteDescriptorSet::image_view_sampler(
+                    2,
+                    environment.irradiance.image_view(),
+                    defaults.clamp_sampler.clone(),
+                ),
+                WriteDescriptorSet::image_view_sampler(
+                    3,
+                    environment.prefiltered.image_view(),
+                    defaults.clamp_sampler.clone(),
+                ),
+                WriteDescriptorSet::image_view_sampler(
+                    4,
+                    environment.brdf_lut.image_view(),
+                    defaults.clamp_sampler.clone(),
+                ),
+            ]);
+        }
+        let set = self.gpu.create_frame_descriptor_set(set_layout, writes)?;
+        Ok(set)
+    }
+
+    fn create_material_set(
+        &self,
+        layout: &Arc<PipelineLayout>,
+        material: &Material,
+    ) -> Result<Option<Arc<DescriptorSet>>> {
+        let Some(defaults) = &self.lit_defaults else {
+            return Ok(None);
+        };
+        let normal_map = material.normal_map.as_ref().unwrap_or(&defaults.normal_map);
+        let base_color_map = material.base_color_map.as_ref().unwrap_or(&defaults.white);
+        let metallic_roughness_map = material
+            .metallic_roughness_map
+            .as_ref()
+            .unwrap_or(&defaults.white);
+        let Some(set_layout) = layout.set_layouts().get(1).cloned() else {
+            return Ok(None);
+        };
+        let bindings = set_layout.bindings();
+        if bindings.is_empty() {
+            return Ok(None);
+        }
+        let textures = [normal_map, base_color_map, metallic_roughness_map];
+        let image_views = textures.map(Texture::image_view);
+        let resources = image_views
+            .each_ref()
+            .map(|view| Arc::as_ptr(view) as usize);
+        let set = self
+            .gpu
+            .cached_descriptor_set(set_layout.clone(), &resources, || {
+                image_views
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(binding, _)| bindings.contains_key(&(*binding as u32)))
+                    .map(|(binding, image_view)| {
+                        WriteDescriptorSet::image_view_sampler(
+                            binding as u32,
+                            image_view,
+                            defaults.sampler.clone(),
+                        )
+                    })
+            })?;
+        Ok(Some(set))
+    }
+
+    #[tracing::instrument(name = "record", level = "trace", skip_all)]
+    pub fn execute(&self, graph: RenderGraph) -> Result<Arc<PrimaryAutoCommandBuffer>> {
+        let mut builder = self
+            .gpu
+            .create_frame_command_buffer_builder(QueueKind::Graphics)?;
+        if let Some(queries) = self.occlusion.lock().unwrap().as_mut() {
+            queries.begin_frame(&mut builder)?;
+        }
+        graph.execute_profiled(
+            &mut self.transients.lock().unwrap(),
+            &mut builder,
+            self.profiler.lock().unwrap().as_mut(),
+        )?;
         let command_buffer = builder.build()?;
         Ok(command_buffer)
     }
 }
+
+pub(crate) fn create_pipeline(
+    gpu: &Gpu,
+    vs: EntryPoint,
+    fs: Option<EntryPoint>,
+    vertex_input_state: VertexInputState,
+    subpass: PipelineRenderingCreateInfo,
+    blend: Option<AttachmentBlend>,
+) -> Result<Arc<GraphicsPipeline>> {
+    let stages: Vec<_> = [Some(vs), fs]
+        .into_iter()
+        .flatten()
+        .map(PipelineShaderStageCreateInfo::new)
+        .collect();
+
+    let layout = PipelineLayout::new(
+        gpu.device().clone(),
+        PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+            .into_pipeline_layout_create_info(gpu.device().clone())
+            .unwrap(),
+    )?;
+
+    let mut dynamic_state = vec![DynamicState::Viewport];
+    let shading_rate = gpu
+        .device()
+        .enabled_features()
+        .pipeline_fragment_shading_rate;
+    if shading_rate {
+        dynamic_state.push(DynamicState::FragmentShadingRate);
+    }
+
+    let pipeline = GraphicsPipeline::new(
+        gpu.device().clone(),
+        None,
+        GraphicsPipelineCreateInfo {
+            stages: stages.into(),
+            vertex_input_state: Some(vertex_input_state),
+            input_assembly_state: Some(InputAssemblyState::default()),
+            viewport_state: Some(ViewportState::default()),
+            rasterization_state: Some(RasterizationState {
+                polygon_mode: PolygonMode::Fill,
+                ..Default::default()
+            }),
+            depth_stencil_state: subpass.depth_attachment_format.map(|_| DepthStencilState {
+                depth: Some(DepthState {
+                    write_enable: true,
+                    compare_op: CompareOp::LessOrEqual,
+                }),
+                ..Default::default()
+            }),
+            multisample_state: Some(MultisampleState::default()),
+            color_blend_state: Some(ColorBlendState::with_attachment_states(
+                subpass.color_attachment_formats.len() as u32,
+                ColorBlendAttachmentState {
+                    blend,
+                    ..Default::default()
+                },
+            )),
+            fragment_shading_rate_state: shading_rate.then(FragmentShadingRateState::default),
+            dynamic_state: dynamic_state.into_iter().collect(),
+            subpass: Some(subpass.into()),
+            ..GraphicsPipelineCreateInfo::layout(layout)
+        },
+    )?;
+    gpu.track_resource(GpuResourceKind::Pipeline, &pipeline, "graphics pipeline", 0);
+    Ok(pipeline)
+}
+
+pub(crate) fn rendering_info(
+    color_formats: &[Format],
+    depth_format: Option<Format>,
+) -> PipelineRenderingCreateInfo {
+    PipelineRenderingCreateInfo {
+        color_attachment_formats: color_formats.iter().copied().map(Some).collect(),
+        depth_attachment_format: depth_format,
+        ..Default::default()
+    }
+}
+
+fn view_mask(view_count: u32) -> u32 {
+    (1 << view_count) - 1
+}
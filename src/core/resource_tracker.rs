@@ -0,0 +1,211 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use vulkano::DeviceSize;
+
+const TRACKING_ENV: &str = "CODOTAKU_TRACK_RESOURCES";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GpuResourceKind {
+    Buffer,
+    Image,
+    Pipeline,
+}
+
+#[derive(Clone, Debug)]
+pub struct TrackedResource {
+    pub kind: GpuResourceKind,
+    pub label: String,
+    pub size: DeviceSize,
+    pub age: Duration,
+    pub backtrace: Arc<Backtrace>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ResourceReport {
+    pub resources: Vec<TrackedResource>,
+}
+
+impl ResourceReport {
+    pub fn count(&self, kind: GpuResourceKind) -> usize {
+        self.resources.iter().filter(|r| r.kind == kind).count()
+    }
+
+    pub fn size(&self, kind: GpuResourceKind) -> DeviceSize {
+        self.resources
+            .iter()
+            .filter(|r| r.kind == kind)
+            .map(|r| r.size)
+            .sum()
+    }
+
+    pub fn total_size(&self) -> DeviceSize {
+        self.resources.iter().map(|r| r.size).sum()
+    }
+}
+
+impl fmt::Display for ResourceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} live resources, {} total",
+            self.resources.len(),
+            bytes(self.total_size())
+        )?;
+        for kind in [
+            GpuResourceKind::Buffer,
+            GpuResourceKind::Image,
+            GpuResourceKind::Pipeline,
+        ] {
+            writeln!(
+                f,
+                "  {kind:?}: {} ({})",
+                self.count(kind),
+                bytes(self.size(kind))
+            )?;
+        }
+        for resource in &self.resources {
+            writeln!(
+                f,
+                "{:?} `{}` {} alive for {:.1?}",
+                resource.kind,
+                resource.label,
+                bytes(resource.size),
+                resource.age
+            )?;
+            if f.alternate() {
+                writeln!(f, "{}", resource.backtrace)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn bytes(size: DeviceSize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+struct Entry {
+    kind: GpuResourceKind,
+    label: String,
+    size: DeviceSize,
+    created: Instant,
+    backtrace: Arc<Backtrace>,
+    resource: Weak<dyn Any + Send + Sync>,
+}
+
+pub(crate) struct ResourceTracker {
+    enabled: AtomicBool,
+    entries: Mutex<HashMap<usize, Entry>>,
+}
+
+impl Default for ResourceTracker {
+    fn default() -> Self {
+        Self {
+            enabled: AtomicBool::new(env::var(TRACKING_ENV).is_ok_and(|value| value != "0")),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl ResourceTracker {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.entries.lock().unwrap().clear();
+        }
+    }
+
+    pub(crate) fn track<T: Any + Send + Sync>(
+        &self,
+        kind: GpuResourceKind,
+        resource: &Arc<T>,
+        label: &str,
+        size: DeviceSize,
+    ) {
+        tracing::debug!(?kind, label, size, "created GPU resource");
+        if !self.is_enabled() {
+            return;
+        }
+        let resource: Weak<dyn Any + Send + Sync> = Arc::downgrade(resource) as Weak<T>;
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.resource.strong_count() > 0);
+        entries.insert(
+            resource.as_ptr() as *const () as usize,
+            Entry {
+                kind,
+                label: label.to_owned(),
+                size,
+                created: Instant::now(),
+                backtrace: Arc::new(Backtrace::force_capture()),
+                resource,
+            },
+        );
+    }
+
+    pub(crate) fn rename<T>(&self, object: &T, label: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+        let address = object as *const T as *const () as usize;
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(&address)
+            && entry.resource.strong_count() > 0
+        {
+            entry.label = label.to_owned();
+        }
+    }
+
+    pub(crate) fn report(&self) -> ResourceReport {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.resource.strong_count() > 0);
+        let mut resources: Vec<_> = entries
+            .values()
+            .map(|entry| TrackedResource {
+                kind: entry.kind,
+                label: entry.label.clone(),
+                size: entry.size,
+                age: entry.created.elapsed(),
+                backtrace: entry.backtrace.clone(),
+            })
+            .collect();
+        resources.sort_by(|a, b| b.size.cmp(&a.size).then(b.age.cmp(&a.age)));
+        ResourceReport { resources }
+    }
+}
+
+impl Drop for ResourceTracker {
+    fn drop(&mut self) {
+        if !self.is_enabled() {
+            return;
+        }
+        for resource in self.report().resources {
+            log::warn!(
+                "{:?} `{}` ({}) outlived the GPU, created at:\n{}",
+                resource.kind,
+                resource.label,
+                bytes(resource.size),
+                resource.backtrace
+            );
+        }
+    }
+}
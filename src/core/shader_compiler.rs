@@ -0,0 +1,168 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::shader_preprocessor::ShaderPreprocessor;
+use shaderc::{
+    CompileOptions, Compiler, EnvVersion, OptimizationLevel, ShaderKind, SourceLanguage, TargetEnv,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use vulkano::shader::{ShaderModule, ShaderModuleCreateInfo};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Compute,
+    Geometry,
+    TessControl,
+    TessEvaluation,
+}
+
+impl ShaderStage {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        Some(match extension {
+            "vert" => Self::Vertex,
+            "frag" => Self::Fragment,
+            "comp" => Self::Compute,
+            "geom" => Self::Geometry,
+            "tesc" => Self::TessControl,
+            "tese" => Self::TessEvaluation,
+            _ => return None,
+        })
+    }
+
+    fn kind(self) -> ShaderKind {
+        match self {
+            Self::Vertex => ShaderKind::Vertex,
+            Self::Fragment => ShaderKind::Fragment,
+            Self::Compute => ShaderKind::Compute,
+            Self::Geometry => ShaderKind::Geometry,
+            Self::TessControl => ShaderKind::TessControl,
+            Self::TessEvaluation => ShaderKind::TessEvaluation,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShaderLanguage {
+    #[default]
+    Glsl,
+    Hlsl,
+}
+
+pub struct ShaderCompiler {
+    compiler: Compiler,
+    preprocessor: ShaderPreprocessor,
+    optimize: bool,
+}
+
+impl ShaderCompiler {
+    pub fn new() -> Result<Self> {
+        let compiler = Compiler::new()
+            .ok_or_else(|| EngineError::Unsupported("failed to create shaderc compiler".into()))?;
+        Ok(Self {
+            compiler,
+            preprocessor: ShaderPreprocessor::new(),
+            optimize: true,
+        })
+    }
+
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.preprocessor = self.preprocessor.with_include_dir(dir);
+        self
+    }
+
+    pub fn with_define(mut self, name: impl Into<String>, value: Option<&str>) -> Self {
+        self.preprocessor = self.preprocessor.with_define(name, value);
+        self
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        &self.preprocessor
+    }
+
+    pub fn with_optimization(mut self, optimize: bool) -> Self {
+        self.optimize = optimize;
+        self
+    }
+
+    pub fn compile_to_spirv(
+        &self,
+        source: &str,
+        name: &str,
+        stage: ShaderStage,
+        language: ShaderLanguage,
+    ) -> Result<Vec<u32>> {
+        let mut options = CompileOptions::new()
+            .ok_or_else(|| EngineError::Unsupported("failed to create shaderc options".into()))?;
+        options.set_target_env(TargetEnv::Vulkan, EnvVersion::Vulkan1_3 as u32);
+        options.set_source_language(match language {
+            ShaderLanguage::Glsl => SourceLanguage::GLSL,
+            ShaderLanguage::Hlsl => SourceLanguage::HLSL,
+        });
+        options.set_optimization_level(if self.optimize {
+            OptimizationLevel::Performance
+        } else {
+            OptimizationLevel::Zero
+        });
+        let preprocessed = self.preprocessor.process(source, name)?;
+        let artifact = self
+            .compiler
+            .compile_into_spirv(
+                &preprocessed.source,
+                stage.kind(),
+                name,
+                "main",
+                Some(&options),
+            )
+            .map_err(|error| match error {
+                shaderc::Error::CompilationError(count, message) => {
+                    shaderc::Error::CompilationError(
+                        count,
+                        preprocessed.remap_errors(name, &message),
+                    )
+                }
+                error => error,
+            })?;
+        Ok(artifact.as_binary().to_vec())
+    }
+
+    pub fn compile(
+        &self,
+        gpu: &Gpu,
+        source: &str,
+        name: &str,
+        stage: ShaderStage,
+        language: ShaderLanguage,
+    ) -> Result<Arc<ShaderModule>> {
+        let words = self.compile_to_spirv(source, name, stage, language)?;
+        load_spirv(gpu, &words)
+    }
+
+    pub fn compile_file(
+        &self,
+        gpu: &Gpu,
+        path: impl AsRef<Path>,
+        stage: Option<ShaderStage>,
+    ) -> Result<Arc<ShaderModule>> {
+        let path = path.as_ref();
+        let Some(stage) = stage.or_else(|| ShaderStage::from_path(path)) else {
+            return Err(EngineError::InvalidArgument(format!(
+                "cannot infer the shader stage of {}",
+                path.display()
+            )));
+        };
+        let language = match path.extension().and_then(|e| e.to_str()) {
+            Some("hlsl") => ShaderLanguage::Hlsl,
+            _ => ShaderLanguage::Glsl,
+        };
+        let source = fs::read_to_string(path)?;
+        self.compile(gpu, &source, &path.to_string_lossy(), stage, language)
+    }
+}
+
+pub fn load_spirv(gpu: &Gpu, words: &[u32]) -> Result<Arc<ShaderModule>> {
+    Ok(unsafe { ShaderModule::new(gpu.device().clone(), ShaderModuleCreateInfo::new(words))? })
+}
@@ -0,0 +1,388 @@
+use crate::core::error::{EngineError, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LIBRARY: &[(&str, &str)] = &[
+    ("engine/camera.glsl", include_str!("../shaders/camera.glsl")),
+    ("engine/draw.glsl", include_str!("../shaders/draw.glsl")),
+    (
+        "engine/ibl_common.glsl",
+        include_str!("../shaders/ibl_common.glsl"),
+    ),
+    (
+        "engine/lighting.glsl",
+        include_str!("../shaders/lighting.glsl"),
+    ),
+    (
+        "engine/objects.glsl",
+        include_str!("../shaders/objects.glsl"),
+    ),
+    (
+        "engine/surface.glsl",
+        include_str!("../shaders/surface.glsl"),
+    ),
+    (
+        "engine/tonemapping.glsl",
+        include_str!("../shaders/tonemapping.glsl"),
+    ),
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct PreprocessedShader {
+    pub source: String,
+    files: Vec<PathBuf>,
+    lines: Vec<(usize, u32)>,
+}
+
+impl PreprocessedShader {
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn original_location(&self, line: u32) -> Option<(&Path, u32)> {
+        let (file, line) = *self.lines.get(line.checked_sub(1)? as usize)?;
+        Some((&self.files[file], line))
+    }
+
+    pub fn remap_errors(&self, name: &str, message: &str) -> String {
+        message
+            .lines()
+            .map(|line| {
+                let Some((number, rest)) = line
+                    .strip_prefix(name)
+                    .and_then(|rest| rest.strip_prefix(':'))
+                    .and_then(|rest| rest.split_once(':'))
+                else {
+                    return line.to_owned();
+                };
+                match number
+                    .parse()
+                    .ok()
+                    .and_then(|number| self.original_location(number))
+                {
+                    Some((file, number)) => format!("{}:{number}:{rest}", file.display()),
+                    None => line.to_owned(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn push(&mut self, line: &str, file: usize, number: u32) {
+        self.source.push_str(line);
+        self.source.push('\n');
+        self.lines.push((file, number));
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ShaderPreprocessor {
+    include_dirs: Vec<PathBuf>,
+    defines: Vec<(String, Option<String>)>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    pub fn with_define(mut self, name: impl Into<String>, value: Option<&str>) -> Self {
+        self.defines.push((name.into(), value.map(str::to_owned)));
+        self
+    }
+
+    pub fn library() -> impl Iterator<Item = &'static str> {
+        LIBRARY.iter().map(|(name, _)| *name)
+    }
+
+    pub fn process_file(&self, path: impl AsRef<Path>) -> Result<PreprocessedShader> {
+        let path = path.as_ref();
+        self.process(&fs::read_to_string(path)?, path)
+    }
+
+    pub fn process(&self, source: &str, name: impl AsRef<Path>) -> Result<PreprocessedShader> {
+        let mut expansion = Expansion {
+            preprocessor: self,
+            output: PreprocessedShader::default(),
+            stack: Vec::new(),
+            once: HashSet::new(),
+            defines_pending: true,
+        };
+        let name = name.as_ref();
+        let path = fs::canonicalize(name).unwrap_or_else(|_| name.to_owned());
+        expansion.expand(path, source)?;
+        Ok(expansion.output)
+    }
+
+    fn resolve(&self, requested: &str, from: &Path, relative: bool) -> Option<(PathBuf, String)> {
+        let relative = from
+            .parent()
+            .map(|dir| dir.join(requested))
+            .filter(|_| relative);
+        let candidates = relative
+            .into_iter()
+            .chain(self.include_dirs.iter().map(|dir| dir.join(requested)))
+            .chain([PathBuf::from(requested)]);
+        for candidate in candidates {
+            if let Some((name, content)) = LIBRARY
+                .iter()
+                .find(|(name, _)| Path::new(name) == candidate)
+            {
+                return Some((PathBuf::from(name), (*content).to_owned()));
+            }
+            if let Ok(content) = fs::read_to_string(&candidate) {
+                let path = fs::canonicalize(&candidate).unwrap_or(candidate);
+                return Some((path, content));
+            }
+        }
+        None
+    }
+}
+
+struct Expansion<'a> {
+    preprocessor: &'a ShaderPreprocessor,
+    output: PreprocessedShader,
+    stack: Vec<PathBuf>,
+    once: HashSet<PathBuf>,
+    defines_pending: bool,
+}
+
+impl Expansion<'_> {
+    fn expand(&mut self, path: PathBuf, source: &str) -> Result<()> {
+        if self.stack.contains(&path) {
+            let cycle: Vec<_> = self
+                .stack
+                .iter()
+                .skip_while(|file| **file != path)
+                .chain([&path])
+                .map(|file| file.display().to_string())
+                .collect();
+            return Err(EngineError::InvalidAsset(format!(
+                "include cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+        let file = match self.output.files.iter().position(|file| *file == path) {
+            Some(file) => file,
+            None => {
+                self.output.files.push(path.clone());
+                self.output.files.len() - 1
+            }
+        };
+        if self.defines_pending && !has_version(source) {
+            self.inject_defines(file, 1);
+        }
+        self.stack.push(path.clone());
+        for (number, line) in (1..).zip(source.lines()) {
+            let directive = line.trim_start();
+            if directive
+                .strip_prefix("#pragma")
+                .is_some_and(|rest| rest.trim() == "once")
+            {
+                self.once.insert(path.clone());
+                self.output.push("", file, number);
+                continue;
+            }
+            if let Some(rest) = directive.strip_prefix("#include") {
+                let Some((requested, relative)) = parse_include(rest) else {
+                    return Err(EngineError::InvalidAsset(format!(
+                        "{}:{number}: malformed #include",
+                        path.display()
+                    )));
+                };
+                let Some((included, content)) =
+                    self.preprocessor.resolve(requested, &path, relative)
+                else {
+                    return Err(EngineError::InvalidAsset(format!(
+                        "{}:{number}: cannot find include {requested:?}",
+                        path.display()
+                    )));
+                };
+                if !self.once.contains(&included) {
+                    self.expand(included, &content)?;
+                }
+                continue;
+            }
+            self.output.push(line, file, number);
+            if self.defines_pending && directive.starts_with("#version") {
+                self.inject_defines(file, number);
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn inject_defines(&mut self, file: usize, number: u32) {
+        self.defines_pending = false;
+        for (name, value) in &self.preprocessor.defines {
+            let define = match value {
+                Some(value) => format!("#define {name} {value}"),
+                None => format!("#define {name}"),
+            };
+            self.output.push(&define, file, number);
+        }
+    }
+}
+
+fn has_version(source: &str) -> bool {
+    source
+        .lines()
+        .any(|line| line.trim_start().starts_with("#version"))
+}
+
+fn parse_include(rest: &str) -> Option<(&str, bool)> {
+    let rest = rest.trim();
+    let (close, relative) = match rest.chars().next()? {
+        '"' => ('"', true),
+        '<' => ('>', false),
+        _ => return None,
+    };
+    let (requested, _) = rest[1..].split_once(close)?;
+    Some((requested, relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shader-pp-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        fs::canonicalize(dir).unwrap()
+    }
+
+    #[test]
+    fn includes_resolve_relative_include_dir_and_library() {
+        let dir = scratch(
+            "resolve",
+            &[
+                (
+                    "src/main.frag",
+                    "#version 450\n#include \"common.glsl\"\n#include <lib/util.glsl>\n#include <engine/tonemapping.glsl>\nvoid main() {}\n",
+                ),
+                ("src/common.glsl", "float common_value;\n"),
+                ("include/lib/util.glsl", "float util_value;\n"),
+            ],
+        );
+        let shader = ShaderPreprocessor::new()
+            .with_include_dir(dir.join("include"))
+            .process_file(dir.join("src/main.frag"))
+            .unwrap();
+        assert!(shader.source.contains("float common_value;"));
+        assert!(shader.source.contains("float util_value;"));
+        assert!(!shader.source.contains("#include"));
+        assert_eq!(
+            shader.files(),
+            [
+                dir.join("src/main.frag"),
+                dir.join("src/common.glsl"),
+                dir.join("include/lib/util.glsl"),
+                PathBuf::from("engine/tonemapping.glsl"),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_includes_are_reported() {
+        let error = ShaderPreprocessor::new()
+            .process("#include \"missing.glsl\"\n", "main.frag")
+            .unwrap_err();
+        assert!(error.to_string().contains("missing.glsl"));
+    }
+
+    #[test]
+    fn pragma_once_files_are_included_once() {
+        let dir = scratch(
+            "once",
+            &[
+                (
+                    "main.frag",
+                    "#include \"once.glsl\"\n#include \"once.glsl\"\n#include \"twice.glsl\"\n#include \"twice.glsl\"\n",
+                ),
+                ("once.glsl", "#pragma once\nfloat once_value;\n"),
+                ("twice.glsl", "float twice_value;\n"),
+            ],
+        );
+        let shader = ShaderPreprocessor::new()
+            .process_file(dir.join("main.frag"))
+            .unwrap();
+        assert_eq!(shader.source.matches("float once_value;").count(), 1);
+        assert_eq!(shader.source.matches("float twice_value;").count(), 2);
+    }
+
+    #[test]
+    fn include_cycles_are_rejected() {
+        let dir = scratch(
+            "cycle",
+            &[
+                ("main.frag", "#include \"a.glsl\"\n"),
+                ("a.glsl", "#include \"b.glsl\"\n"),
+                ("b.glsl", "#include \"a.glsl\"\n"),
+            ],
+        );
+        let error = ShaderPreprocessor::new()
+            .process_file(dir.join("main.frag"))
+            .unwrap_err();
+        let EngineError::InvalidAsset(message) = error else {
+            panic!("unexpected error: {error}");
+        };
+        assert!(message.starts_with("include cycle:"));
+        assert!(message.ends_with("a.glsl"));
+    }
+
+    #[test]
+    fn defines_follow_the_version_directive() {
+        let shader = ShaderPreprocessor::new()
+            .with_define("QUALITY", Some("2"))
+            .with_define("SHADOWS", None)
+            .process("#version 450\nvoid main() {}\n", "main.frag")
+            .unwrap();
+        assert_eq!(
+            shader.source,
+            "#version 450\n#define QUALITY 2\n#define SHADOWS\nvoid main() {}\n"
+        );
+        assert_eq!(
+            shader.original_location(3),
+            Some((Path::new("main.frag"), 1))
+        );
+    }
+
+    #[test]
+    fn errors_are_remapped_to_original_files() {
+        let dir = scratch(
+            "remap",
+            &[
+                (
+                    "main.frag",
+                    "#version 450\n#include \"common.glsl\"\nbroken\n",
+                ),
+                ("common.glsl", "float a;\nfloat b\n"),
+            ],
+        );
+        let main = dir.join("main.frag");
+        let shader = ShaderPreprocessor::new().process_file(&main).unwrap();
+        let name = "shader.frag";
+        let message = format!(
+            "{name}:3: error: 'b' : syntax error\n{name}:4: error: 'broken' : undeclared\n1 error generated"
+        );
+        assert_eq!(
+            shader.remap_errors(name, &message),
+            format!(
+                "{}:2: error: 'b' : syntax error\n{}:3: error: 'broken' : undeclared\n1 error generated",
+                dir.join("common.glsl").display(),
+                main.display()
+            )
+        );
+    }
+}
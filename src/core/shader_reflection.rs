@@ -0,0 +1,420 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::storage_image::StorageImage;
+use crate::core::texture::Texture;
+use bytemuck::Pod;
+use std::collections::HashMap;
+use std::sync::Arc;
+use vulkano::buffer::{BufferUsage, Subbuffer};
+use vulkano::descriptor_set::layout::DescriptorSetLayout;
+use vulkano::descriptor_set::{DescriptorSet, WriteDescriptorSet};
+use vulkano::image::sampler::Sampler;
+use vulkano::image::view::ImageView;
+use vulkano::pipeline::PipelineLayout;
+use vulkano::shader::spirv::{Decoration, Id, Instruction, Spirv, StorageClass};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceKind {
+    UniformBuffer,
+    StorageBuffer,
+    Image,
+    StorageImage,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReflectedMember {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReflectedBinding {
+    pub name: String,
+    pub set: u32,
+    pub binding: u32,
+    pub kind: ResourceKind,
+    pub size: u32,
+    pub members: Vec<ReflectedMember>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ShaderReflection {
+    pub bindings: Vec<ReflectedBinding>,
+    pub push_constant_size: u32,
+    pub push_constant_members: Vec<ReflectedMember>,
+}
+
+impl ShaderReflection {
+    pub fn from_spirv(words: &[u32]) -> Result<Self> {
+        let spirv = Spirv::new(words)?;
+        let mut reflection = Self::default();
+        for instruction in spirv.global_variables() {
+            let Instruction::Variable {
+                result_type_id,
+                result_id,
+                storage_class,
+                ..
+            } = instruction
+            else {
+                continue;
+            };
+            let Instruction::TypePointer { ty, .. } = spirv.id(*result_type_id).instruction()
+            else {
+                continue;
+            };
+            let members = struct_members(&spirv, *ty);
+            let size = block_size(&members);
+            let kind = match storage_class {
+                StorageClass::Uniform => ResourceKind::UniformBuffer,
+                StorageClass::StorageBuffer => ResourceKind::StorageBuffer,
+                StorageClass::UniformConstant if is_storage_image(&spirv, *ty) => {
+                    ResourceKind::StorageImage
+                }
+                StorageClass::UniformConstant => ResourceKind::Image,
+                StorageClass::PushConstant => {
+                    reflection.push_constant_size = size;
+                    reflection.push_constant_members = members;
+                    continue;
+                }
+                _ => continue,
+            };
+            let mut name = debug_name(spirv.id(*result_id).names());
+            if name.is_empty() {
+                name = debug_name(spirv.id(*ty).names());
+            }
+            let (mut set, mut binding) = (0, 0);
+            for decoration in spirv.id(*result_id).decorations() {
+                match decoration {
+                    Instruction::Decorate {
+                        decoration: Decoration::DescriptorSet { descriptor_set },
+                        ..
+                    } => set = *descriptor_set,
+                    Instruction::Decorate {
+                        decoration: Decoration::Binding { binding_point },
+                        ..
+                    } => binding = *binding_point,
+                    _ => {}
+                }
+            }
+            reflection.bindings.push(ReflectedBinding {
+                name,
+                set,
+                binding,
+                kind,
+                size,
+                members,
+            });
+        }
+        Ok(reflection)
+    }
+
+    pub fn merge(mut self, other: Self) -> Self {
+        for binding in other.bindings {
+            if !self
+                .bindings
+                .iter()
+                .any(|b| b.set == binding.set && b.binding == binding.binding)
+            {
+                self.bindings.push(binding);
+            }
+        }
+        if other.push_constant_size > self.push_constant_size {
+            self.push_constant_size = other.push_constant_size;
+            self.push_constant_members = other.push_constant_members;
+        }
+        self
+    }
+
+    pub fn binding(&self, name: &str) -> Option<&ReflectedBinding> {
+        self.bindings.iter().find(|binding| binding.name == name)
+    }
+
+    pub fn member(&self, name: &str) -> Option<(&ReflectedBinding, &ReflectedMember)> {
+        self.bindings.iter().find_map(|binding| {
+            binding
+                .members
+                .iter()
+                .find(|member| member.name == name)
+                .map(|member| (binding, member))
+        })
+    }
+
+    pub fn push_constant(&self, name: &str) -> Option<&ReflectedMember> {
+        self.push_constant_members
+            .iter()
+            .find(|member| member.name == name)
+    }
+}
+
+fn debug_name(names: &[Instruction]) -> String {
+    names
+        .iter()
+        .find_map(|instruction| match instruction {
+            Instruction::Name { name, .. } | Instruction::MemberName { name, .. } => {
+                Some(name.clone())
+            }
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn is_storage_image(spirv: &Spirv, id: Id) -> bool {
+    match spirv.id(id).instruction() {
+        Instruction::TypeImage { sampled, .. } => *sampled == 2,
+        Instruction::TypeArray { element_type, .. }
+        | Instruction::TypeRuntimeArray { element_type, .. } => {
+            is_storage_image(spirv, *element_type)
+        }
+        _ => false,
+    }
+}
+
+fn block_size(members: &[ReflectedMember]) -> u32 {
+    members
+        .iter()
+        .map(|member| member.offset + member.size)
+        .max()
+        .unwrap_or(0)
+        .next_multiple_of(16)
+}
+
+fn struct_members(spirv: &Spirv, id: Id) -> Vec<ReflectedMember> {
+    let info = spirv.id(id);
+    let Instruction::TypeStruct { member_types, .. } = info.instruction() else {
+        return Vec::new();
+    };
+    member_types
+        .iter()
+        .zip(info.members())
+        .map(|(ty, member)| {
+            let (mut offset, mut matrix_stride) = (0, None);
+            for decoration in member.decorations() {
+                match decoration {
+                    Instruction::MemberDecorate {
+                        decoration: Decoration::Offset { byte_offset },
+                        ..
+                    } => offset = *byte_offset,
+                    Instruction::MemberDecorate {
+                        decoration:
+                            Decoration::MatrixStride {
+                                matrix_stride: stride,
+                            },
+                        ..
+                    } => matrix_stride = Some(*stride),
+                    _ => {}
+                }
+            }
+            ReflectedMember {
+                name: debug_name(member.names()),
+                offset,
+                size: type_size(spirv, *ty, matrix_stride),
+            }
+        })
+        .collect()
+}
+
+fn type_size(spirv: &Spirv, id: Id, matrix_stride: Option<u32>) -> u32 {
+    let info = spirv.id(id);
+    match info.instruction() {
+        Instruction::TypeBool { .. } => 4,
+        Instruction::TypeInt { width, .. } | Instruction::TypeFloat { width, .. } => width / 8,
+        Instruction::TypeVector {
+            component_type,
+            component_count,
+            ..
+        } => type_size(spirv, *component_type, None) * component_count,
+        Instruction::TypeMatrix {
+            column_type,
+            column_count,
+            ..
+        } => matrix_stride.unwrap_or_else(|| type_size(spirv, *column_type, None)) * column_count,
+        Instruction::TypeArray {
+            element_type,
+            length,
+            ..
+        } => {
+            let length = match spirv.id(*length).instruction() {
+                Instruction::Constant { value, .. } => value[0],
+                _ => 0,
+            };
+            let stride = info
+                .decorations()
+                .iter()
+                .find_map(|decoration| match decoration {
+                    Instruction::Decorate {
+                        decoration: Decoration::ArrayStride { array_stride },
+                        ..
+                    } => Some(*array_stride),
+                    _ => None,
+                })
+                .unwrap_or_else(|| type_size(spirv, *element_type, matrix_stride));
+            stride * length
+        }
+        Instruction::TypeStruct { .. } => block_size(&struct_members(spirv, id)),
+        _ => 0,
+    }
+}
+
+pub struct ShaderParameters {
+    reflection: Arc<ShaderReflection>,
+    set: u32,
+    layout: Arc<DescriptorSetLayout>,
+    uniforms: HashMap<u32, Vec<u8>>,
+    images: HashMap<u32, (Arc<ImageView>, Arc<Sampler>)>,
+    storage_images: HashMap<u32, Arc<ImageView>>,
+    buffers: HashMap<u32, Subbuffer<[u8]>>,
+}
+
+impl ShaderParameters {
+    pub fn new(
+        reflection: Arc<ShaderReflection>,
+        layout: &Arc<PipelineLayout>,
+        set: u32,
+    ) -> Result<Self> {
+        let Some(set_layout) = layout.set_layouts().get(set as usize).cloned() else {
+            return Err(EngineError::InvalidArgument(format!(
+                "the pipeline layout has no descriptor set {set}"
+            )));
+        };
+        let uniforms = reflection
+            .bindings
+            .iter()
+            .filter(|b| b.set == set && b.kind == ResourceKind::UniformBuffer)
+            .map(|b| (b.binding, vec![0; b.size as usize]))
+            .collect();
+        Ok(Self {
+            reflection,
+            set,
+            layout: set_layout,
+            uniforms,
+            images: HashMap::new(),
+            storage_images: HashMap::new(),
+            buffers: HashMap::new(),
+        })
+    }
+
+    pub fn set_index(&self) -> u32 {
+        self.set
+    }
+
+    pub fn set<T: Pod>(&mut self, name: &str, value: T) -> Result<()> {
+        let bytes = bytemuck::bytes_of(&value);
+        let (binding, offset, size) = match self.reflection.member(name) {
+            Some((binding, member)) if binding.set == self.set => {
+                (binding.binding, member.offset, member.size)
+            }
+            _ => match self.reflection.binding(name) {
+                Some(binding)
+                    if binding.set == self.set && binding.kind == ResourceKind::UniformBuffer =>
+                {
+                    (binding.binding, 0, binding.size)
+                }
+                _ => {
+                    return Err(EngineError::InvalidArgument(format!(
+                        "no uniform named {name:?} in descriptor set {}",
+                        self.set
+                    )))
+                }
+            },
+        };
+        if bytes.len() > size as usize {
+            return Err(EngineError::InvalidArgument(format!(
+                "value of {} bytes does not fit uniform {name:?} of {size} bytes",
+                bytes.len()
+            )));
+        }
+        let data = self.uniforms.get_mut(&binding).unwrap();
+        data[offset as usize..offset as usize + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    pub fn set_texture(
+        &mut self,
+        name: &str,
+        texture: &Texture,
+        sampler: Arc<Sampler>,
+    ) -> Result<()> {
+        let binding = self.resource(name, ResourceKind::Image)?;
+        self.images.insert(binding, (texture.image_view(), sampler));
+        Ok(())
+    }
+
+    pub fn set_storage_image(&mut self, name: &str, image: &StorageImage) -> Result<()> {
+        let binding = self.resource(name, ResourceKind::StorageImage)?;
+        self.storage_images.insert(binding, image.image_view());
+        Ok(())
+    }
+
+    pub fn set_storage_buffer(&mut self, name: &str, buffer: Subbuffer<[u8]>) -> Result<()> {
+        let binding = self.resource(name, ResourceKind::StorageBuffer)?;
+        self.buffers.insert(binding, buffer);
+        Ok(())
+    }
+
+    fn resource(&self, name: &str, kind: ResourceKind) -> Result<u32> {
+        self.reflection
+            .binding(name)
+            .filter(|binding| binding.set == self.set && binding.kind == kind)
+            .map(|binding| binding.binding)
+            .ok_or_else(|| {
+                EngineError::InvalidArgument(format!(
+                    "no {kind:?} named {name:?} in descriptor set {}",
+                    self.set
+                ))
+            })
+    }
+
+    pub fn descriptor_set(&self, gpu: &Gpu) -> Result<Arc<DescriptorSet>> {
+        let mut writes = Vec::new();
+        for binding in self
+            .reflection
+            .bindings
+            .iter()
+            .filter(|b| b.set == self.set)
+        {
+            let index = binding.binding;
+            writes.push(match binding.kind {
+                ResourceKind::UniformBuffer => WriteDescriptorSet::buffer(
+                    index,
+                    gpu.create_buffer(
+                        self.uniforms[&index].iter().copied(),
+                        BufferUsage::UNIFORM_BUFFER,
+                    )?,
+                ),
+                ResourceKind::StorageBuffer => {
+                    let Some(buffer) = self.buffers.get(&index) else {
+                        return Err(EngineError::InvalidArgument(format!(
+                            "storage buffer {:?} was not set",
+                            binding.name
+                        )));
+                    };
+                    WriteDescriptorSet::buffer(index, buffer.clone())
+                }
+                ResourceKind::Image => {
+                    let Some((image_view, sampler)) = self.images.get(&index) else {
+                        return Err(EngineError::InvalidArgument(format!(
+                            "texture {:?} was not set",
+                            binding.name
+                        )));
+                    };
+                    WriteDescriptorSet::image_view_sampler(
+                        index,
+                        image_view.clone(),
+                        sampler.clone(),
+                    )
+                }
+                ResourceKind::StorageImage => {
+                    let Some(image_view) = self.storage_images.get(&index) else {
+                        return Err(EngineError::InvalidArgument(format!(
+                            "storage image {:?} was not set",
+                            binding.name
+                        )));
+                    };
+                    WriteDescriptorSet::image_view(index, image_view.clone())
+                }
+            });
+        }
+        gpu.create_descriptor_set(self.layout.clone(), writes)
+    }
+}
@@ -0,0 +1,204 @@
+pub(crate) mod forward_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/forward.vert",
+    }
+}
+
+pub(crate) mod forward_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/forward.frag",
+    }
+}
+
+pub(crate) mod forward_multiview_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/forward.vert",
+        define: [("MULTIVIEW", "1")],
+    }
+}
+
+pub(crate) mod forward_multiview_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/forward.frag",
+        define: [("MULTIVIEW", "1")],
+    }
+}
+
+pub(crate) mod picking_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/picking.frag",
+    }
+}
+
+pub(crate) mod ssao_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/ssao.frag",
+    }
+}
+
+pub(crate) mod ssao_blur_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/ssao_blur.frag",
+    }
+}
+
+pub(crate) mod fxaa_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/fxaa.frag",
+    }
+}
+
+pub(crate) mod taa_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/taa.frag",
+    }
+}
+
+pub(crate) mod upscale_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/upscale.frag",
+    }
+}
+
+pub(crate) mod hdr_output_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/hdr_output.frag",
+    }
+}
+
+pub(crate) mod skybox_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/skybox.vert",
+    }
+}
+
+pub(crate) mod skybox_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/skybox.frag",
+    }
+}
+
+pub(crate) mod irradiance_cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "src/shaders/irradiance.comp",
+    }
+}
+
+pub(crate) mod prefilter_cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "src/shaders/prefilter.comp",
+    }
+}
+
+pub(crate) mod brdf_lut_cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "src/shaders/brdf_lut.comp",
+    }
+}
+
+pub(crate) mod gbuffer_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/gbuffer.frag",
+    }
+}
+
+pub(crate) mod fullscreen_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/fullscreen.vert",
+    }
+}
+
+pub(crate) mod deferred_resolve_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/deferred_resolve.frag",
+    }
+}
+
+pub(crate) mod skinned_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/skinned.vert",
+    }
+}
+
+pub(crate) mod morph_cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "src/shaders/morph.comp",
+    }
+}
+
+pub(crate) mod vector_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/vector.vert",
+    }
+}
+
+pub(crate) mod vector_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/vector.frag",
+    }
+}
+
+pub(crate) mod debug_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/debug.vert",
+    }
+}
+
+pub(crate) mod debug_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/debug.frag",
+    }
+}
+
+pub(crate) mod overlay_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/overlay.vert",
+    }
+}
+
+pub(crate) mod overlay_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/overlay.frag",
+    }
+}
+
+pub(crate) mod cull_cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "src/shaders/cull.comp",
+    }
+}
+
+pub(crate) mod culled_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/culled.vert",
+    }
+}
@@ -0,0 +1,57 @@
+use crate::core::gpu::Gpu;
+use glam::Vec2;
+
+pub fn is_supported(gpu: &Gpu) -> bool {
+    gpu.device()
+        .enabled_features()
+        .pipeline_fragment_shading_rate
+}
+
+pub fn max_fragment_size(gpu: &Gpu) -> [u32; 2] {
+    gpu.device()
+        .physical_device()
+        .properties()
+        .max_fragment_size
+        .unwrap_or([1, 1])
+}
+
+pub fn foveated_rate(position: Vec2, center: Vec2, radii: [f32; 2], aspect: f32) -> [u32; 2] {
+    let distance = ((position - center) * Vec2::new(aspect, 1.0)).length();
+    let size = if distance <= radii[0] {
+        1
+    } else if distance <= radii[1] {
+        2
+    } else {
+        4
+    };
+    [size, size]
+}
+
+pub fn encode(fragment_size: [u32; 2]) -> u8 {
+    let [width, height] = fragment_size.map(|size| size.clamp(1, 4).ilog2() as u8);
+    (width << 2) | height
+}
+
+pub fn foveated_rates(
+    extent: [u32; 2],
+    center: Vec2,
+    radii: [f32; 2],
+    max_fragment_size: [u32; 2],
+) -> Vec<u8> {
+    let aspect = extent[0] as f32 / extent[1].max(1) as f32;
+    let mut rates = Vec::with_capacity((extent[0] * extent[1]) as usize);
+    for y in 0..extent[1] {
+        for x in 0..extent[0] {
+            let uv = Vec2::new(
+                (x as f32 + 0.5) / extent[0] as f32,
+                (y as f32 + 0.5) / extent[1] as f32,
+            );
+            let [width, height] = foveated_rate(uv, center, radii, aspect);
+            rates.push(encode([
+                width.min(max_fragment_size[0]),
+                height.min(max_fragment_size[1]),
+            ]));
+        }
+    }
+    rates
+}
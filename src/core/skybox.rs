@@ -0,0 +1,140 @@
+use crate::core::camera::Camera;
+use crate::core::cubemap::Cubemap;
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::resource_tracker::GpuResourceKind;
+use crate::core::shaders::{skybox_fs, skybox_vs};
+use glam::{Mat3, Mat4};
+use std::sync::Arc;
+use vulkano::buffer::BufferContents;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer};
+use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::format::Format;
+use vulkano::image::sampler::{Sampler, SamplerAddressMode, SamplerCreateInfo};
+use vulkano::pipeline::graphics::color_blend::{ColorBlendAttachmentState, ColorBlendState};
+use vulkano::pipeline::graphics::depth_stencil::{CompareOp, DepthState, DepthStencilState};
+use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::multisample::MultisampleState;
+use vulkano::pipeline::graphics::rasterization::RasterizationState;
+use vulkano::pipeline::graphics::subpass::PipelineRenderingCreateInfo;
+use vulkano::pipeline::graphics::vertex_input::VertexInputState;
+use vulkano::pipeline::graphics::viewport::ViewportState;
+use vulkano::pipeline::graphics::GraphicsPipelineCreateInfo;
+use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
+use vulkano::pipeline::{
+    DynamicState, GraphicsPipeline, Pipeline, PipelineBindPoint, PipelineLayout,
+    PipelineShaderStageCreateInfo,
+};
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct SkyboxConstants {
+    inverse_view_projection: [[f32; 4]; 4],
+}
+
+pub(crate) struct SkyboxPipeline {
+    sampler: Arc<Sampler>,
+    pipeline: Arc<GraphicsPipeline>,
+    gpu: Arc<Gpu>,
+}
+
+impl SkyboxPipeline {
+    pub(crate) fn new(gpu: Arc<Gpu>, image_format: Format, depth_format: Format) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = skybox_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = skybox_fs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let stages = [
+            PipelineShaderStageCreateInfo::new(vs),
+            PipelineShaderStageCreateInfo::new(fs),
+        ];
+        let layout = PipelineLayout::new(
+            device.clone(),
+            PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+                .into_pipeline_layout_create_info(device.clone())
+                .unwrap(),
+        )?;
+        let subpass = PipelineRenderingCreateInfo {
+            color_attachment_formats: vec![Some(image_format)],
+            depth_attachment_format: Some(depth_format),
+            ..Default::default()
+        };
+        let pipeline = GraphicsPipeline::new(
+            device.clone(),
+            None,
+            GraphicsPipelineCreateInfo {
+                stages: stages.into_iter().collect(),
+                vertex_input_state: Some(VertexInputState::new()),
+                input_assembly_state: Some(InputAssemblyState::default()),
+                viewport_state: Some(ViewportState::default()),
+                rasterization_state: Some(RasterizationState::default()),
+                depth_stencil_state: Some(DepthStencilState {
+                    depth: Some(DepthState {
+                        write_enable: false,
+                        compare_op: CompareOp::LessOrEqual,
+                    }),
+                    ..Default::default()
+                }),
+                multisample_state: Some(MultisampleState::default()),
+                color_blend_state: Some(ColorBlendState::with_attachment_states(
+                    1,
+                    ColorBlendAttachmentState::default(),
+                )),
+                dynamic_state: [DynamicState::Viewport].into_iter().collect(),
+                subpass: Some(subpass.into()),
+                ..GraphicsPipelineCreateInfo::layout(layout)
+            },
+        )?;
+        gpu.track_resource(GpuResourceKind::Pipeline, &pipeline, "skybox", 0);
+        let sampler = Sampler::new(
+            device,
+            SamplerCreateInfo {
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..SamplerCreateInfo::simple_repeat_linear()
+            },
+        )?;
+        Ok(Self {
+            sampler,
+            pipeline,
+            gpu,
+        })
+    }
+
+    pub(crate) fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        camera: &Camera,
+        cubemap: &Cubemap,
+    ) -> Result<()> {
+        let rotation = Mat4::from_mat3(Mat3::from_mat4(camera.view));
+        let inverse_view_projection = (camera.projection * rotation).inverse();
+        let layout = self.pipeline.layout().clone();
+        let image_view = cubemap.image_view();
+        let set = self.gpu.cached_descriptor_set(
+            layout.set_layouts()[0].clone(),
+            &[Arc::as_ptr(&image_view) as usize],
+            || {
+                [WriteDescriptorSet::image_view_sampler(
+                    0,
+                    image_view,
+                    self.sampler.clone(),
+                )]
+            },
+        )?;
+        builder
+            .bind_pipeline_graphics(self.pipeline.clone())?
+            .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 0, set)?
+            .push_constants(
+                layout,
+                0,
+                SkyboxConstants {
+                    inverse_view_projection: inverse_view_projection.to_cols_array_2d(),
+                },
+            )?;
+        unsafe { builder.draw(3, 1, 0, 0) }?;
+        Ok(())
+    }
+}
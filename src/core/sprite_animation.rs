@@ -0,0 +1,174 @@
+use crate::core::atlas::{AtlasRegion, UvRect};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoopMode {
+    Once,
+    Loop,
+    PingPong,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SpriteFrame {
+    pub uv: UvRect,
+    pub duration: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpriteAnimation {
+    frames: Vec<SpriteFrame>,
+    frame: usize,
+    elapsed: f32,
+    reversed: bool,
+    finished: bool,
+    pub loop_mode: LoopMode,
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl SpriteAnimation {
+    pub fn new(frames: Vec<SpriteFrame>, loop_mode: LoopMode) -> Self {
+        Self {
+            frames,
+            frame: 0,
+            elapsed: 0.0,
+            reversed: false,
+            finished: false,
+            loop_mode,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn from_grid(
+        uv: UvRect,
+        columns: u32,
+        rows: u32,
+        frame_count: u32,
+        frame_duration: f32,
+        loop_mode: LoopMode,
+    ) -> Self {
+        let cell = [
+            (uv.max[0] - uv.min[0]) / columns as f32,
+            (uv.max[1] - uv.min[1]) / rows as f32,
+        ];
+        let frames = (0..frame_count.min(columns * rows))
+            .map(|index| {
+                let min = [
+                    uv.min[0] + (index % columns) as f32 * cell[0],
+                    uv.min[1] + (index / columns) as f32 * cell[1],
+                ];
+                SpriteFrame {
+                    uv: UvRect {
+                        min,
+                        max: [min[0] + cell[0], min[1] + cell[1]],
+                    },
+                    duration: frame_duration,
+                }
+            })
+            .collect();
+        Self::new(frames, loop_mode)
+    }
+
+    pub fn from_regions(regions: &[AtlasRegion], frame_duration: f32, loop_mode: LoopMode) -> Self {
+        let frames = regions
+            .iter()
+            .map(|region| SpriteFrame {
+                uv: region.uv,
+                duration: frame_duration,
+            })
+            .collect();
+        Self::new(frames, loop_mode)
+    }
+
+    pub fn frames(&self) -> &[SpriteFrame] {
+        &self.frames
+    }
+
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    pub fn current_frame(&self) -> Option<&SpriteFrame> {
+        self.frames.get(self.frame)
+    }
+
+    pub fn uv(&self) -> UvRect {
+        self.current_frame().map_or(UvRect::FULL, |frame| frame.uv)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.elapsed = 0.0;
+        self.reversed = false;
+        self.finished = false;
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        if self.paused || self.finished || !self.frames.iter().any(|frame| frame.duration > 0.0) {
+            return;
+        }
+        self.elapsed += delta_time * self.speed;
+        loop {
+            let duration = self.frames[self.frame].duration.max(0.0);
+            let forward = self.elapsed >= duration;
+            if !forward && self.elapsed >= 0.0 {
+                break;
+            }
+            if forward {
+                self.elapsed -= duration;
+            }
+            if !self.step(forward) {
+                self.elapsed = 0.0;
+                self.finished = true;
+                break;
+            }
+            if !forward {
+                self.elapsed += self.frames[self.frame].duration.max(0.0);
+            }
+        }
+    }
+
+    fn step(&mut self, forward: bool) -> bool {
+        let last = self.frames.len() - 1;
+        match self.loop_mode {
+            LoopMode::Once => {
+                if forward && self.frame == last || !forward && self.frame == 0 {
+                    return false;
+                }
+                if forward {
+                    self.frame += 1;
+                } else {
+                    self.frame -= 1;
+                }
+            }
+            LoopMode::Loop => {
+                self.frame = match (forward, self.frame) {
+                    (true, frame) if frame == last => 0,
+                    (true, frame) => frame + 1,
+                    (false, 0) => last,
+                    (false, frame) => frame - 1,
+                }
+            }
+            LoopMode::PingPong => {
+                if last == 0 {
+                    return true;
+                }
+                let mut ascending = forward != self.reversed;
+                if ascending && self.frame == last || !ascending && self.frame == 0 {
+                    self.reversed = !self.reversed;
+                    ascending = !ascending;
+                }
+                if ascending {
+                    self.frame += 1;
+                } else {
+                    self.frame -= 1;
+                }
+            }
+        }
+        true
+    }
+}
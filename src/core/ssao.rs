@@ -0,0 +1,235 @@
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::render_graph::{Attachment, RenderGraph, ResourceId, TransientImage};
+use crate::core::renderer::{create_pipeline, rendering_info};
+use crate::core::shaders::{fullscreen_vs, ssao_blur_fs, ssao_fs};
+use glam::Vec3;
+use std::sync::Arc;
+use vulkano::buffer::{BufferContents, Subbuffer};
+use vulkano::descriptor_set::{DescriptorSet, WriteDescriptorSet};
+use vulkano::format::Format;
+use vulkano::image::sampler::{Sampler, SamplerAddressMode, SamplerCreateInfo};
+use vulkano::pipeline::graphics::vertex_input::VertexInputState;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint, PipelineLayout};
+
+const SSAO_FORMAT: Format = Format::R8_UNORM;
+const MAX_SAMPLES: usize = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SsaoQuality {
+    Low,
+    Medium,
+    High,
+}
+
+impl SsaoQuality {
+    fn sample_count(self) -> u32 {
+        match self {
+            SsaoQuality::Low => 8,
+            SsaoQuality::Medium => 16,
+            SsaoQuality::High => 32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SsaoSettings {
+    pub quality: SsaoQuality,
+    pub radius: f32,
+    pub bias: f32,
+    pub intensity: f32,
+}
+
+impl SsaoSettings {
+    pub fn preset(quality: SsaoQuality) -> Self {
+        Self {
+            quality,
+            radius: 0.5,
+            bias: 0.025,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self::preset(SsaoQuality::Medium)
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct SsaoKernel {
+    samples: [[f32; 4]; MAX_SAMPLES],
+}
+
+impl SsaoKernel {
+    fn hemisphere() -> Self {
+        let mut state = 0x9e37_79b9u32;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32
+        };
+        Self {
+            samples: std::array::from_fn(|_| {
+                let direction = Vec3::new(random() * 2.0 - 1.0, random() * 2.0 - 1.0, random());
+                (direction.normalize_or(Vec3::Z) * random())
+                    .extend(0.0)
+                    .to_array()
+            }),
+        }
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct SsaoConstants {
+    radius: f32,
+    bias: f32,
+    intensity: f32,
+    sample_count: u32,
+}
+
+pub(crate) struct SsaoPipeline {
+    sampler: Arc<Sampler>,
+    kernel: Subbuffer<SsaoKernel>,
+    pipeline: Arc<GraphicsPipeline>,
+    blur_pipeline: Arc<GraphicsPipeline>,
+    gpu: Arc<Gpu>,
+}
+
+impl SsaoPipeline {
+    pub(crate) fn new(gpu: Arc<Gpu>) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = fullscreen_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = ssao_fs::load(device.clone())?.entry_point("main").unwrap();
+        let pipeline = create_pipeline(
+            &gpu,
+            vs.clone(),
+            Some(fs),
+            VertexInputState::new(),
+            rendering_info(&[SSAO_FORMAT], None),
+            None,
+        )?;
+        gpu.set_debug_name(&*pipeline, "ssao")?;
+        let blur_fs = ssao_blur_fs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let blur_pipeline = create_pipeline(
+            &gpu,
+            vs,
+            Some(blur_fs),
+            VertexInputState::new(),
+            rendering_info(&[SSAO_FORMAT], None),
+            None,
+        )?;
+        gpu.set_debug_name(&*blur_pipeline, "ssao blur")?;
+        let sampler = Sampler::new(
+            device,
+            SamplerCreateInfo {
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )?;
+        let kernel = gpu.create_uniform_buffer(SsaoKernel::hemisphere())?;
+        Ok(Self {
+            sampler,
+            kernel,
+            pipeline,
+            blur_pipeline,
+            gpu,
+        })
+    }
+
+    pub(crate) fn layout(&self) -> &Arc<PipelineLayout> {
+        self.pipeline.layout()
+    }
+
+    pub(crate) fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        frame_set: Arc<DescriptorSet>,
+        normal: ResourceId,
+        depth: ResourceId,
+        settings: SsaoSettings,
+    ) -> ResourceId {
+        let extent = graph.extent(normal);
+        let occlusion = graph.transient(TransientImage::new(SSAO_FORMAT, extent));
+        let blurred = graph.transient(TransientImage::new(SSAO_FORMAT, extent));
+
+        graph
+            .add_pass("ssao")
+            .color_attachment(Attachment::clear(occlusion, [1.0; 4]))
+            .sample(normal)
+            .sample(depth)
+            .record(move |ctx| {
+                let layout = self.pipeline.layout().clone();
+                let inputs_set = self.gpu.create_frame_descriptor_set(
+                    layout.set_layouts()[1].clone(),
+                    [
+                        WriteDescriptorSet::image_view_sampler(
+                            0,
+                            ctx.image_view(normal),
+                            self.sampler.clone(),
+                        ),
+                        WriteDescriptorSet::image_view_sampler(
+                            1,
+                            ctx.image_view(depth),
+                            self.sampler.clone(),
+                        ),
+                        WriteDescriptorSet::buffer(2, self.kernel.clone()),
+                    ],
+                )?;
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?
+                    .bind_pipeline_graphics(self.pipeline.clone())?
+                    .bind_descriptor_sets(
+                        PipelineBindPoint::Graphics,
+                        layout.clone(),
+                        0,
+                        (frame_set, inputs_set),
+                    )?
+                    .push_constants(
+                        layout,
+                        0,
+                        SsaoConstants {
+                            radius: settings.radius,
+                            bias: settings.bias,
+                            intensity: settings.intensity,
+                            sample_count: settings.quality.sample_count(),
+                        },
+                    )?;
+                unsafe { ctx.builder.draw(3, 1, 0, 0) }?;
+                Ok(())
+            });
+
+        graph
+            .add_pass("ssao blur")
+            .color_attachment(Attachment::clear(blurred, [1.0; 4]))
+            .sample(occlusion)
+            .record(move |ctx| {
+                let layout = self.blur_pipeline.layout().clone();
+                let set = self.gpu.create_frame_descriptor_set(
+                    layout.set_layouts()[0].clone(),
+                    [WriteDescriptorSet::image_view_sampler(
+                        0,
+                        ctx.image_view(occlusion),
+                        self.sampler.clone(),
+                    )],
+                )?;
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?
+                    .bind_pipeline_graphics(self.blur_pipeline.clone())?
+                    .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, set)?;
+                unsafe { ctx.builder.draw(3, 1, 0, 0) }?;
+                Ok(())
+            });
+        blurred
+    }
+}
@@ -0,0 +1,60 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::texture::Texture;
+use std::sync::Arc;
+use vulkano::format::{Format, FormatFeatures};
+use vulkano::image::view::ImageView;
+use vulkano::image::ImageUsage;
+
+#[derive(Clone)]
+pub struct StorageImage {
+    image_view: Arc<ImageView>,
+}
+
+impl StorageImage {
+    pub fn new(gpu: Arc<Gpu>, extent: [u32; 2], format: Format) -> Result<Self> {
+        let features = gpu
+            .device()
+            .physical_device()
+            .format_properties(format)?
+            .optimal_tiling_features;
+        if !features.contains(FormatFeatures::STORAGE_IMAGE | FormatFeatures::SAMPLED_IMAGE) {
+            return Err(EngineError::Unsupported(format!(
+                "{format:?} cannot be used as a sampled storage image"
+            )));
+        }
+        let image = gpu.create_image(
+            format,
+            [extent[0], extent[1], 1],
+            1,
+            ImageUsage::STORAGE
+                | ImageUsage::SAMPLED
+                | ImageUsage::TRANSFER_SRC
+                | ImageUsage::TRANSFER_DST,
+        )?;
+        Ok(Self {
+            image_view: ImageView::new_default(image)?,
+        })
+    }
+
+    pub fn image_view(&self) -> Arc<ImageView> {
+        self.image_view.clone()
+    }
+
+    pub fn texture(&self) -> Texture {
+        Texture::from_image_view(self.image_view.clone())
+    }
+
+    pub fn extent(&self) -> [u32; 2] {
+        let [width, height, _] = self.image_view.image().extent();
+        [width, height]
+    }
+
+    pub fn format(&self) -> Format {
+        self.image_view.format()
+    }
+
+    pub fn set_debug_name(&self, gpu: &Gpu, name: &str) -> Result<()> {
+        gpu.set_debug_name(&**self.image_view.image(), name)
+    }
+}
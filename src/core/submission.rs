@@ -0,0 +1,26 @@
+use crate::core::error::Result;
+use std::time::Duration;
+use vulkano::sync::future::FenceSignalFuture;
+use vulkano::sync::GpuFuture;
+
+pub struct Submission {
+    future: FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>,
+}
+
+impl Submission {
+    pub(crate) fn new(future: FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>) -> Self {
+        Self { future }
+    }
+
+    pub fn is_done(&self) -> Result<bool> {
+        Ok(self.future.is_signaled()?)
+    }
+
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<()> {
+        Ok(self.future.wait(Some(timeout))?)
+    }
+
+    pub fn wait(self) -> Result<()> {
+        Ok(self.future.wait(None)?)
+    }
+}
@@ -1,20 +1,49 @@
-use crate::core::gpu::Gpu;
-use anyhow::anyhow;
+use crate::core::capture::PendingCapture;
+use crate::core::error::{EngineError, Result};
+use crate::core::external_semaphore::{self, SemaphoreWaitFuture};
+use crate::core::frame_stats::{FrameStats, FrameStatsTracker};
+use crate::core::gpu::{Gpu, QueueKind};
+use crate::core::hdr::OutputColorSpace;
+use crate::core::picking::{ObjectId, PendingPick};
 use std::any::Any;
 use std::sync::Arc;
+use std::time::Instant;
 use vulkano::command_buffer::PrimaryCommandBufferAbstract;
-use vulkano::device::DeviceOwned;
 use vulkano::format::Format;
 use vulkano::image::view::ImageView;
 use vulkano::image::{Image, ImageUsage};
 use vulkano::swapchain::{
-    acquire_next_image, Swapchain, SwapchainAcquireFuture, SwapchainCreateInfo,
-    SwapchainPresentInfo,
+    acquire_next_image, ColorSpace, CompositeAlpha, CompositeAlphas, PresentMode, Surface,
+    SurfaceInfo, Swapchain, SwapchainAcquireFuture, SwapchainCreateInfo, SwapchainPresentInfo,
 };
+use vulkano::sync::semaphore::Semaphore;
 use vulkano::sync::GpuFuture;
-use vulkano::{sync, Validated, VulkanError};
+use vulkano::{Validated, VulkanError};
 use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SwapchainSettings {
+    pub(crate) present_mode: PresentMode,
+    pub(crate) color_space: OutputColorSpace,
+    pub(crate) transparent: bool,
+    pub(crate) composite_alpha: Option<CompositeAlpha>,
+    pub(crate) image_count: Option<u32>,
+    pub(crate) clear_color: Option<[f32; 4]>,
+}
+
+impl Default for SwapchainSettings {
+    fn default() -> Self {
+        Self {
+            present_mode: PresentMode::Fifo,
+            color_space: OutputColorSpace::Srgb,
+            transparent: false,
+            composite_alpha: None,
+            image_count: None,
+            clear_color: None,
+        }
+    }
+}
+
 pub struct Acquired {
     pub(crate) image: Arc<Image>,
     pub(crate) image_view: Arc<ImageView>,
@@ -24,7 +53,20 @@ pub struct Acquired {
 
 pub(crate) struct SwapchainTarget {
     recreate_swapchain: bool,
-    previous_frame_end: Option<Box<dyn GpuFuture>>,
+    present_mode: PresentMode,
+    color_space: OutputColorSpace,
+    surface_format: (Format, ColorSpace),
+    composite_alpha: CompositeAlpha,
+    image_count: u32,
+    frame_stats: FrameStatsTracker,
+    pub(crate) capture_requested: bool,
+    pub(crate) pending_capture: Option<PendingCapture>,
+    pub(crate) pick_requested: Option<[u32; 2]>,
+    pub(crate) pending_pick: Option<PendingPick>,
+    pub(crate) picked: Option<ObjectId>,
+    pub(crate) wait_semaphores: Vec<Arc<Semaphore>>,
+    pub(crate) signal_semaphores: Vec<Arc<Semaphore>>,
+    previous_frame_end: Option<Box<dyn GpuFuture + Send + Sync>>,
     swapchain_images: Vec<Arc<Image>>,
     swapchain_image_views: Vec<Arc<ImageView>>,
     swapchain: Arc<Swapchain>,
@@ -32,14 +74,66 @@ pub(crate) struct SwapchainTarget {
 }
 
 impl SwapchainTarget {
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(?extent, present_mode = ?settings.present_mode)
+    )]
     pub(crate) fn new(
         gpu: Arc<Gpu>,
         window: Arc<impl HasWindowHandle + HasDisplayHandle + Any + Send + Sync>,
         extent: [u32; 2],
-    ) -> anyhow::Result<Self> {
+        settings: SwapchainSettings,
+    ) -> Result<Self> {
         let surface = gpu.create_surface(window)?;
-        let (swapchain, swapchain_images) =
-            gpu.create_swapchain(surface, extent, ImageUsage::COLOR_ATTACHMENT)?;
+        let queue_family_index = gpu.queue(QueueKind::Graphics).queue_family_index();
+        if !gpu
+            .device()
+            .physical_device()
+            .surface_support(queue_family_index, &surface)?
+        {
+            return Err(EngineError::Unsupported(format!(
+                "{} cannot present to this window",
+                gpu.adapter_info().name
+            )));
+        }
+        let SwapchainSettings {
+            present_mode,
+            color_space,
+            ..
+        } = settings;
+        let present_modes = gpu
+            .device()
+            .physical_device()
+            .surface_present_modes(&surface, SurfaceInfo::default())?;
+        if !present_modes.contains(&present_mode) {
+            return Err(EngineError::Unsupported(format!(
+                "the surface does not support the {present_mode:?} present mode"
+            )));
+        }
+        let surface_format = choose_surface_format(&gpu, &surface, color_space)?;
+        let composite_alpha = choose_composite_alpha(&gpu, &surface, settings)?;
+        let image_count = choose_image_count(&gpu, &surface, settings.image_count)?;
+        let image_usage = choose_image_usage(&gpu, &surface)?;
+        let (swapchain, swapchain_images) = gpu.create_swapchain(
+            surface,
+            SwapchainCreateInfo {
+                min_image_count: image_count,
+                image_format: surface_format.0,
+                image_color_space: surface_format.1,
+                image_extent: extent,
+                image_usage,
+                composite_alpha,
+                present_mode,
+                ..Default::default()
+            },
+        )?;
+        tracing::debug!(
+            format = ?surface_format.0,
+            color_space = ?surface_format.1,
+            images = swapchain_images.len(),
+            "created swapchain"
+        );
         let swapchain_image_views = swapchain_images
             .iter()
             .map(|image| ImageView::new_default(image.clone()).unwrap())
@@ -47,6 +141,19 @@ impl SwapchainTarget {
         let previous_frame_end = Some(gpu.now());
         Ok(Self {
             recreate_swapchain: false,
+            present_mode,
+            color_space,
+            surface_format,
+            composite_alpha,
+            image_count,
+            frame_stats: FrameStatsTracker::default(),
+            capture_requested: false,
+            pending_capture: None,
+            pick_requested: None,
+            pending_pick: None,
+            picked: None,
+            wait_semaphores: Vec::new(),
+            signal_semaphores: Vec::new(),
             gpu,
             swapchain,
             swapchain_images,
@@ -55,10 +162,8 @@ impl SwapchainTarget {
         })
     }
 
-    pub(crate) fn try_acquire_image(
-        &mut self,
-        window_size: [u32; 2],
-    ) -> anyhow::Result<Option<Acquired>> {
+    #[tracing::instrument(name = "acquire", level = "trace", skip(self))]
+    pub(crate) fn try_acquire_image(&mut self, window_size: [u32; 2]) -> Result<Option<Acquired>> {
         if window_size[0] == 0 || window_size[1] == 0 {
             return Ok(None);
         }
@@ -66,8 +171,14 @@ impl SwapchainTarget {
         self.previous_frame_end.as_mut().unwrap().cleanup_finished();
 
         if self.recreate_swapchain {
+            tracing::debug!(?window_size, "recreating swapchain");
             let (new_swapchain, new_images) = self.swapchain.recreate(SwapchainCreateInfo {
                 image_extent: window_size.into(),
+                present_mode: self.present_mode,
+                image_format: self.surface_format.0,
+                image_color_space: self.surface_format.1,
+                composite_alpha: self.composite_alpha,
+                min_image_count: self.image_count,
                 ..self.swapchain.create_info()
             })?;
 
@@ -79,18 +190,30 @@ impl SwapchainTarget {
                 .map(|image| ImageView::new_default(image.clone()).unwrap())
                 .collect();
             self.recreate_swapchain = false;
+            self.frame_stats.record_recreation();
         }
 
+        let acquire_start = Instant::now();
         let (image_index, suboptimal, acquire_future) =
             match acquire_next_image(self.swapchain.clone(), None).map_err(Validated::unwrap) {
                 Ok(r) => r,
                 Err(VulkanError::OutOfDate) => {
+                    tracing::debug!("swapchain is out of date, skipping the frame");
                     self.recreate_swapchain = true;
                     return Ok(None);
                 }
-                Err(e) => return Err(anyhow!(e)),
+                Err(e) => return Err(e.into()),
             };
 
+        let acquire_wait = acquire_start.elapsed();
+        tracing::trace!(
+            image_index,
+            suboptimal,
+            ?acquire_wait,
+            "acquired swapchain image"
+        );
+        self.frame_stats.record_acquire(acquire_wait);
+
         if suboptimal {
             self.recreate_swapchain = true;
         }
@@ -103,49 +226,361 @@ impl SwapchainTarget {
         }))
     }
 
+    #[tracing::instrument(level = "trace", skip_all, fields(image_index = acquired.image_index))]
     pub(crate) fn present(
         &mut self,
         acquired: Acquired,
         command_buffer: Arc<impl PrimaryCommandBufferAbstract + 'static>,
-    ) -> anyhow::Result<bool> {
+    ) -> Result<bool> {
+        let submitted = Instant::now();
+        let external_waits = SemaphoreWaitFuture::new(
+            self.gpu.device().clone(),
+            std::mem::take(&mut self.wait_semaphores),
+        );
+        let submit = tracing::trace_span!("submit").entered();
         let future = self
             .previous_frame_end
             .take()
             .unwrap()
             .join(acquired.acquire_future)
-            .then_execute(self.gpu.queue.clone(), command_buffer)?
+            .join(external_waits)
+            .then_execute(self.gpu.queue(QueueKind::Graphics).clone(), command_buffer)?
             .then_swapchain_present(
-                self.gpu.queue.clone(),
+                self.gpu.queue(QueueKind::Graphics).clone(),
                 SwapchainPresentInfo::swapchain_image_index(
                     self.swapchain.clone(),
                     acquired.image_index,
                 ),
             )
+            .boxed_send_sync()
             .then_signal_fence_and_flush();
+        let present_wait = submitted.elapsed();
+        drop(submit);
+        tracing::trace!(?present_wait, "submitted frame");
+        self.gpu.poll_memory_budget();
 
-        match future.map_err(Validated::unwrap) {
+        let presented = match future.map_err(Validated::unwrap) {
             Ok(future) => {
-                self.previous_frame_end = Some(future.boxed());
-                Ok(true)
+                let future = Arc::new(future);
+                self.frame_stats
+                    .record_present(Some(future.clone()), submitted, present_wait);
+                self.previous_frame_end = Some(Box::new(future.clone()));
+                self.gpu.end_frame(Some(future)).map(|_| true)
             }
             Err(VulkanError::OutOfDate) => {
+                tracing::debug!("swapchain went out of date during present");
                 self.recreate_swapchain = true;
-                self.previous_frame_end = Some(sync::now(self.gpu.queue.device().clone()).boxed());
+                self.previous_frame_end = Some(self.gpu.now());
                 Ok(true)
             }
             Err(e) => {
-                println!("failed to flush future: {e}");
-                self.previous_frame_end = Some(sync::now(self.gpu.queue.device().clone()).boxed());
-                Err(anyhow!(e))
+                tracing::error!(error = %e, "failed to flush frame");
+                self.previous_frame_end = Some(self.gpu.now());
+                Err(e.into())
+            }
+        };
+        external_semaphore::signal(
+            self.gpu.queue(QueueKind::Graphics),
+            std::mem::take(&mut self.signal_semaphores),
+        )?;
+        presented
+    }
+
+    #[tracing::instrument(level = "trace", skip_all, fields(windows = batch.len()))]
+    pub(crate) fn present_all(
+        gpu: &Arc<Gpu>,
+        batch: Vec<(
+            &mut SwapchainTarget,
+            Acquired,
+            Arc<impl PrimaryCommandBufferAbstract + 'static>,
+        )>,
+    ) -> Result<()> {
+        let submitted = Instant::now();
+        let submit = tracing::trace_span!("submit").entered();
+        let queue = gpu.queue(QueueKind::Graphics).clone();
+        let mut targets = Vec::with_capacity(batch.len());
+        let mut command_buffers = Vec::with_capacity(batch.len());
+        let mut future = gpu.now();
+        for (target, acquired, command_buffer) in batch {
+            let external_waits = SemaphoreWaitFuture::new(
+                gpu.device().clone(),
+                std::mem::take(&mut target.wait_semaphores),
+            );
+            future = future
+                .join(target.previous_frame_end.take().unwrap())
+                .join(acquired.acquire_future)
+                .join(external_waits)
+                .boxed_send_sync();
+            targets.push((target, acquired.image_index));
+            command_buffers.push(command_buffer);
+        }
+        for command_buffer in command_buffers {
+            future = future
+                .then_execute(queue.clone(), command_buffer)?
+                .boxed_send_sync();
+        }
+        for (target, image_index) in &targets {
+            tracing::trace!(image_index, "queueing present");
+            future = future
+                .then_swapchain_present(
+                    queue.clone(),
+                    SwapchainPresentInfo::swapchain_image_index(
+                        target.swapchain.clone(),
+                        *image_index,
+                    ),
+                )
+                .boxed_send_sync();
+        }
+        let future = future.then_signal_fence_and_flush();
+        let present_wait = submitted.elapsed();
+        drop(submit);
+        tracing::trace!(?present_wait, "submitted frames");
+        gpu.poll_memory_budget();
+
+        let presented = match future.map_err(Validated::unwrap) {
+            Ok(future) => {
+                let future = Arc::new(future);
+                for (target, _) in &mut targets {
+                    target.frame_stats.record_present(
+                        Some(future.clone()),
+                        submitted,
+                        present_wait,
+                    );
+                    target.previous_frame_end = Some(Box::new(future.clone()));
+                }
+                gpu.end_frame(Some(future))
+            }
+            Err(VulkanError::OutOfDate) => {
+                tracing::debug!("a swapchain went out of date during present");
+                for (target, _) in &mut targets {
+                    target.recreate_swapchain = true;
+                    target.previous_frame_end = Some(gpu.now());
+                }
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "failed to flush frames");
+                for (target, _) in &mut targets {
+                    target.previous_frame_end = Some(gpu.now());
+                }
+                Err(e.into())
             }
+        };
+        for (target, _) in targets {
+            external_semaphore::signal(
+                gpu.queue(QueueKind::Graphics),
+                std::mem::take(&mut target.signal_semaphores),
+            )?;
+        }
+        presented
+    }
+
+    pub(crate) fn wait(&mut self) -> Result<()> {
+        if let Some(previous_frame_end) = self.previous_frame_end.take() {
+            previous_frame_end
+                .then_signal_fence_and_flush()?
+                .wait(None)?;
         }
+        self.previous_frame_end = Some(self.gpu.now());
+        Ok(())
+    }
+
+    pub(crate) fn image_usage(&self) -> ImageUsage {
+        self.swapchain.image_usage()
     }
 
     pub(crate) fn image_format(&self) -> Format {
-        self.swapchain.image_format()
+        self.surface_format.0
+    }
+
+    pub(crate) fn present_modes(&self) -> Result<Vec<PresentMode>> {
+        Ok(self
+            .gpu
+            .device()
+            .physical_device()
+            .surface_present_modes(self.swapchain.surface(), SurfaceInfo::default())?)
+    }
+
+    pub(crate) fn set_present_mode(&mut self, present_mode: PresentMode) -> Result<()> {
+        if !self.present_modes()?.contains(&present_mode) {
+            return Err(EngineError::Unsupported(format!(
+                "the surface does not support the {present_mode:?} present mode"
+            )));
+        }
+        if present_mode != self.present_mode {
+            self.present_mode = present_mode;
+            self.recreate_swapchain = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn output_color_spaces(&self) -> Result<Vec<OutputColorSpace>> {
+        let mut color_spaces = Vec::new();
+        for (format, color_space) in self
+            .gpu
+            .device()
+            .physical_device()
+            .surface_formats(self.swapchain.surface(), SurfaceInfo::default())?
+        {
+            if let Some(color_space) = OutputColorSpace::from_surface_format(format, color_space)
+                && !color_spaces.contains(&color_space)
+            {
+                color_spaces.push(color_space);
+            }
+        }
+        Ok(color_spaces)
+    }
+
+    pub(crate) fn set_output_color_space(&mut self, color_space: OutputColorSpace) -> Result<()> {
+        if color_space != self.color_space {
+            self.surface_format =
+                choose_surface_format(&self.gpu, self.swapchain.surface(), color_space)?;
+            self.color_space = color_space;
+            self.recreate_swapchain = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn supported_composite_alpha(&self) -> Result<CompositeAlphas> {
+        Ok(self
+            .gpu
+            .device()
+            .physical_device()
+            .surface_capabilities(self.swapchain.surface(), SurfaceInfo::default())?
+            .supported_composite_alpha)
+    }
+
+    pub(crate) fn composite_alpha(&self) -> CompositeAlpha {
+        self.composite_alpha
+    }
+
+    pub(crate) fn set_composite_alpha(&mut self, composite_alpha: CompositeAlpha) -> Result<()> {
+        if !self
+            .supported_composite_alpha()?
+            .contains_enum(composite_alpha)
+        {
+            return Err(EngineError::Unsupported(format!(
+                "the surface does not support {composite_alpha:?} composite alpha"
+            )));
+        }
+        if composite_alpha != self.composite_alpha {
+            self.composite_alpha = composite_alpha;
+            self.recreate_swapchain = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn image_count(&self) -> u32 {
+        self.image_count
+    }
+
+    pub(crate) fn set_image_count(&mut self, image_count: Option<u32>) -> Result<()> {
+        let image_count = choose_image_count(&self.gpu, self.swapchain.surface(), image_count)?;
+        if image_count != self.image_count {
+            self.image_count = image_count;
+            self.recreate_swapchain = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn frame_stats(&self) -> FrameStats {
+        self.frame_stats.stats()
     }
 
     pub(crate) fn resize(&mut self) {
         self.recreate_swapchain = true;
     }
 }
+
+fn choose_surface_format(
+    gpu: &Gpu,
+    surface: &Surface,
+    color_space: OutputColorSpace,
+) -> Result<(Format, ColorSpace)> {
+    let formats = gpu
+        .device()
+        .physical_device()
+        .surface_formats(surface, SurfaceInfo::default())?;
+    match color_space.surface_format() {
+        Some(format) if formats.contains(&format) => Ok(format),
+        Some(_) => Err(EngineError::Unsupported(format!(
+            "the surface does not support {color_space:?} output"
+        ))),
+        None => Ok(formats
+            .iter()
+            .copied()
+            .find(|&(_, color_space)| color_space == ColorSpace::SrgbNonLinear)
+            .unwrap_or(formats[0])),
+    }
+}
+
+fn choose_composite_alpha(
+    gpu: &Gpu,
+    surface: &Surface,
+    settings: SwapchainSettings,
+) -> Result<CompositeAlpha> {
+    let supported = gpu
+        .device()
+        .physical_device()
+        .surface_capabilities(surface, SurfaceInfo::default())?
+        .supported_composite_alpha;
+    if let Some(composite_alpha) = settings.composite_alpha {
+        if !supported.contains_enum(composite_alpha) {
+            return Err(EngineError::Unsupported(format!(
+                "the surface does not support {composite_alpha:?} composite alpha"
+            )));
+        }
+        return Ok(composite_alpha);
+    }
+    let preferred: &[CompositeAlpha] = if settings.transparent {
+        &[
+            CompositeAlpha::PreMultiplied,
+            CompositeAlpha::PostMultiplied,
+            CompositeAlpha::Inherit,
+        ]
+    } else {
+        &[CompositeAlpha::Opaque]
+    };
+    let composite_alpha = preferred
+        .iter()
+        .copied()
+        .find(|&composite_alpha| supported.contains_enum(composite_alpha));
+    if composite_alpha.is_none() && settings.transparent {
+        log::warn!("the surface does not support transparent composition");
+    }
+    Ok(composite_alpha.unwrap_or_else(|| supported.into_iter().next().unwrap()))
+}
+
+fn choose_image_usage(gpu: &Gpu, surface: &Surface) -> Result<ImageUsage> {
+    let supported = gpu
+        .device()
+        .physical_device()
+        .surface_capabilities(surface, SurfaceInfo::default())?
+        .supported_usage_flags;
+    if !supported.intersects(ImageUsage::COLOR_ATTACHMENT) {
+        return Err(EngineError::Unsupported(
+            "the surface does not support color attachment images".into(),
+        ));
+    }
+    if !supported.intersects(ImageUsage::TRANSFER_SRC) {
+        log::warn!("the surface does not support transfer source images, captures are disabled");
+    }
+    Ok(supported & (ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_SRC))
+}
+
+fn choose_image_count(gpu: &Gpu, surface: &Surface, image_count: Option<u32>) -> Result<u32> {
+    let capabilities = gpu
+        .device()
+        .physical_device()
+        .surface_capabilities(surface, SurfaceInfo::default())?;
+    let max_image_count = capabilities.max_image_count.unwrap_or(u32::MAX);
+    let Some(image_count) = image_count else {
+        return Ok(capabilities.min_image_count.max(2).min(max_image_count));
+    };
+    if !(capabilities.min_image_count..=max_image_count).contains(&image_count) {
+        return Err(EngineError::Unsupported(format!(
+            "the surface does not support {image_count} swapchain images"
+        )));
+    }
+    Ok(image_count)
+}
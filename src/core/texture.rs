@@ -0,0 +1,216 @@
+use crate::core::bcn;
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use ash::vk;
+use std::path::Path;
+use std::sync::Arc;
+use vulkano::command_buffer::{BufferImageCopy, CopyBufferToImageInfo};
+use vulkano::format::{Format, FormatFeatures, NumericFormat};
+use vulkano::image::view::ImageView;
+use vulkano::image::{ImageSubresourceLayers, ImageUsage};
+use vulkano::DeviceSize;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+impl ColorSpace {
+    pub fn rgba8_format(self) -> Format {
+        match self {
+            ColorSpace::Srgb => Format::R8G8B8A8_SRGB,
+            ColorSpace::Linear => Format::R8G8B8A8_UNORM,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Texture {
+    image_view: Arc<ImageView>,
+}
+
+impl Texture {
+    pub fn from_pixels(
+        gpu: Arc<Gpu>,
+        extent: [u32; 2],
+        format: Format,
+        pixels: &[u8],
+    ) -> Result<Self> {
+        Self::from_mip_levels(gpu, extent, format, &[pixels])
+    }
+
+    pub fn from_mip_levels(
+        gpu: Arc<Gpu>,
+        extent: [u32; 2],
+        format: Format,
+        levels: &[&[u8]],
+    ) -> Result<Self> {
+        let image = gpu.create_image(
+            format,
+            [extent[0], extent[1], 1],
+            levels.len() as u32,
+            ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+        )?;
+        let staging = gpu.create_staging_buffer(levels.concat())?;
+        let mut buffer_offset = 0;
+        let regions = levels
+            .iter()
+            .enumerate()
+            .map(|(mip_level, level)| {
+                let region = BufferImageCopy {
+                    buffer_offset,
+                    image_subresource: ImageSubresourceLayers {
+                        mip_level: mip_level as u32,
+                        ..image.subresource_layers()
+                    },
+                    image_extent: mip_extent(extent, mip_level as u32),
+                    ..Default::default()
+                };
+                buffer_offset += level.len() as DeviceSize;
+                region
+            })
+            .collect();
+        gpu.immediate(|builder| {
+            builder.copy_buffer_to_image(CopyBufferToImageInfo {
+                regions,
+                ..CopyBufferToImageInfo::buffer_image(staging, image.clone())
+            })?;
+            Ok(())
+        })?;
+        Ok(Self {
+            image_view: ImageView::new_default(image)?,
+        })
+    }
+
+    pub fn from_ktx2(gpu: Arc<Gpu>, bytes: &[u8]) -> Result<Self> {
+        let reader = ktx2::Reader::new(bytes)?;
+        let header = reader.header();
+        if header.supercompression_scheme.is_some() {
+            return Err(EngineError::Unsupported(
+                "supercompressed KTX2 textures are not supported".into(),
+            ));
+        }
+        if header.face_count > 1 || header.layer_count > 1 || header.pixel_depth > 1 {
+            return Err(EngineError::Unsupported(
+                "only 2D KTX2 textures are supported".into(),
+            ));
+        }
+        let Some(format) = header
+            .format
+            .and_then(|format| Format::try_from(vk::Format::from_raw(format.value() as i32)).ok())
+        else {
+            return Err(EngineError::Unsupported(format!(
+                "unsupported KTX2 format {:?}",
+                header.format
+            )));
+        };
+        let extent = [header.pixel_width, header.pixel_height];
+        let levels: Vec<&[u8]> = reader.levels().map(|level| level.data).collect();
+
+        let supported = gpu
+            .device()
+            .physical_device()
+            .format_properties(format)?
+            .optimal_tiling_features
+            .contains(FormatFeatures::SAMPLED_IMAGE);
+        if supported {
+            return Self::from_mip_levels(gpu, extent, format, &levels);
+        }
+
+        let decompressed = levels
+            .iter()
+            .enumerate()
+            .map(|(mip_level, level)| {
+                let [width, height, _] = mip_extent(extent, mip_level as u32);
+                bcn::decompress(format, [width, height], level)
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(decompressed) = decompressed else {
+            return Err(EngineError::Unsupported(format!(
+                "{format:?} is not supported by the device and has no CPU fallback (BC1-BC5 and BC7 only)"
+            )));
+        };
+        let color_space = if format.numeric_format_color() == Some(NumericFormat::SRGB) {
+            ColorSpace::Srgb
+        } else {
+            ColorSpace::Linear
+        };
+        let levels: Vec<&[u8]> = decompressed.iter().map(Vec::as_slice).collect();
+        Self::from_mip_levels(gpu, extent, color_space.rgba8_format(), &levels)
+    }
+
+    pub fn from_path(
+        gpu: Arc<Gpu>,
+        path: impl AsRef<Path>,
+        color_space: ColorSpace,
+    ) -> Result<Self> {
+        Self::from_image(gpu, image::open(path)?, color_space)
+    }
+
+    pub fn from_ktx2_path(gpu: Arc<Gpu>, path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_ktx2(gpu, &std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(gpu: Arc<Gpu>, bytes: &[u8], color_space: ColorSpace) -> Result<Self> {
+        Self::from_image(gpu, image::load_from_memory(bytes)?, color_space)
+    }
+
+    pub fn from_image(
+        gpu: Arc<Gpu>,
+        image: image::DynamicImage,
+        color_space: ColorSpace,
+    ) -> Result<Self> {
+        let image = image.into_rgba8();
+        Self::from_pixels(
+            gpu,
+            [image.width(), image.height()],
+            color_space.rgba8_format(),
+            image.as_raw(),
+        )
+    }
+
+    pub fn write(
+        &self,
+        gpu: Arc<Gpu>,
+        offset: [u32; 2],
+        extent: [u32; 2],
+        pixels: &[u8],
+    ) -> Result<()> {
+        let image = self.image_view.image().clone();
+        let staging = gpu.create_staging_buffer(pixels.iter().copied())?;
+        gpu.immediate(|builder| {
+            builder.copy_buffer_to_image(CopyBufferToImageInfo {
+                regions: [BufferImageCopy {
+                    image_subresource: image.subresource_layers(),
+                    image_offset: [offset[0], offset[1], 0],
+                    image_extent: [extent[0], extent[1], 1],
+                    ..Default::default()
+                }]
+                .into(),
+                ..CopyBufferToImageInfo::buffer_image(staging, image)
+            })?;
+            Ok(())
+        })
+    }
+
+    pub(crate) fn from_image_view(image_view: Arc<ImageView>) -> Self {
+        Self { image_view }
+    }
+
+    pub fn image_view(&self) -> Arc<ImageView> {
+        self.image_view.clone()
+    }
+
+    pub fn set_debug_name(&self, gpu: &Gpu, name: &str) -> Result<()> {
+        gpu.set_debug_name(&**self.image_view.image(), name)
+    }
+}
+
+fn mip_extent(extent: [u32; 2], mip_level: u32) -> [u32; 3] {
+    [
+        (extent[0] >> mip_level).max(1),
+        (extent[1] >> mip_level).max(1),
+        1,
+    ]
+}
@@ -0,0 +1,112 @@
+use crate::core::error::Result;
+use ash::vk;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use vulkano::command_buffer::{CommandBuffer, SemaphoreSubmitInfo, SubmitInfo};
+use vulkano::device::{DeviceOwned, Queue};
+use vulkano::sync::semaphore::{Semaphore, SemaphoreCreateInfo, SemaphoreType, SemaphoreWaitInfo};
+use vulkano::{VulkanError, VulkanObject};
+
+pub(crate) struct Timeline {
+    semaphore: Arc<Semaphore>,
+    value: AtomicU64,
+}
+
+impl Timeline {
+    pub(crate) fn new(queue: &Queue) -> Result<Option<Self>> {
+        let device = queue.device();
+        if !device.enabled_features().timeline_semaphore {
+            return Ok(None);
+        }
+        let semaphore = Semaphore::new(
+            device.clone(),
+            SemaphoreCreateInfo {
+                semaphore_type: SemaphoreType::Timeline,
+                ..Default::default()
+            },
+        )?;
+        Ok(Some(Self {
+            semaphore: Arc::new(semaphore),
+            value: AtomicU64::new(0),
+        }))
+    }
+
+    pub(crate) fn signal(&self, queue: &Arc<Queue>) -> Result<u64> {
+        queue.with(|mut guard| {
+            let value = self.value.fetch_add(1, Ordering::Relaxed) + 1;
+            let submit_info = SubmitInfo {
+                signal_semaphores: vec![SemaphoreSubmitInfo {
+                    value,
+                    ..SemaphoreSubmitInfo::new(self.semaphore.clone())
+                }],
+                ..Default::default()
+            };
+            unsafe { guard.submit(&[submit_info], None) }?;
+            Ok(value)
+        })
+    }
+
+    /// Submits a raw command buffer that signals the next value, optionally after `wait`.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer and every resource it uses must outlive the returned value.
+    pub(crate) unsafe fn submit(
+        &self,
+        queue: &Arc<Queue>,
+        command_buffer: &CommandBuffer,
+        wait: Option<(&Timeline, u64)>,
+    ) -> Result<u64> {
+        queue.with(|_guard| {
+            let value = self.value.fetch_add(1, Ordering::Relaxed) + 1;
+            let command_buffers = [command_buffer.handle()];
+            let signal_semaphores = [self.semaphore.handle()];
+            let signal_values = [value];
+            let (wait_semaphores, wait_values) = match wait {
+                Some((timeline, value)) => (vec![timeline.semaphore.handle()], vec![value]),
+                None => (Vec::new(), Vec::new()),
+            };
+            let wait_stages = vec![vk::PipelineStageFlags::ALL_COMMANDS; wait_semaphores.len()];
+            let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::default()
+                .wait_semaphore_values(&wait_values)
+                .signal_semaphore_values(&signal_values);
+            let submit_info = vk::SubmitInfo::default()
+                .wait_semaphores(&wait_semaphores)
+                .wait_dst_stage_mask(&wait_stages)
+                .command_buffers(&command_buffers)
+                .signal_semaphores(&signal_semaphores)
+                .push_next(&mut timeline_info);
+            unsafe {
+                (queue.device().fns().v1_0.queue_submit)(
+                    queue.handle(),
+                    1,
+                    &submit_info,
+                    vk::Fence::null(),
+                )
+            }
+            .result()
+            .map_err(VulkanError::from)?;
+            Ok(value)
+        })
+    }
+
+    pub(crate) fn wait(&self, value: u64, timeout: Option<Duration>) -> Result<()> {
+        self.semaphore.wait(
+            SemaphoreWaitInfo {
+                value,
+                ..Default::default()
+            },
+            timeout,
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn signaled(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn completed(&self) -> Result<u64> {
+        Ok(self.semaphore.counter_value()?)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::core::error::Result;
+use crate::core::timeline::Timeline;
+use std::sync::{Arc, Mutex};
+use vulkano::buffer::Buffer;
+use vulkano::command_buffer::CommandBuffer;
+
+struct PendingUpload {
+    value: u64,
+    _buffers: [Arc<Buffer>; 2],
+    _command_buffers: [CommandBuffer; 2],
+}
+
+#[derive(Default)]
+pub(crate) struct UploadQueue {
+    pending: Mutex<Vec<PendingUpload>>,
+}
+
+impl UploadQueue {
+    /// Keeps the staging buffer, destination and command buffers of an upload alive until the
+    /// graphics timeline reaches `value`.
+    pub(crate) fn push(
+        &self,
+        value: u64,
+        buffers: [Arc<Buffer>; 2],
+        command_buffers: [CommandBuffer; 2],
+    ) {
+        self.pending.lock().unwrap().push(PendingUpload {
+            value,
+            _buffers: buffers,
+            _command_buffers: command_buffers,
+        });
+    }
+
+    pub(crate) fn retire(&self, timeline: &Timeline) -> Result<()> {
+        let completed = timeline.completed()?;
+        self.pending
+            .lock()
+            .unwrap()
+            .retain(|upload| upload.value > completed);
+        Ok(())
+    }
+
+    pub(crate) fn drain(&self, timeline: &Timeline) -> Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(value) = pending.iter().map(|upload| upload.value).max() {
+            timeline.wait(value, None)?;
+        }
+        pending.clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::render_graph::{Attachment, RenderGraph, ResourceId};
+use crate::core::renderer::{create_pipeline, rendering_info};
+use crate::core::shaders::{fullscreen_vs, upscale_fs};
+use std::sync::Arc;
+use vulkano::buffer::BufferContents;
+use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::format::Format;
+use vulkano::image::sampler::{Sampler, SamplerAddressMode, SamplerCreateInfo};
+use vulkano::pipeline::graphics::vertex_input::VertexInputState;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint};
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderScale {
+    pub scale: f32,
+    pub sharpness: f32,
+}
+
+impl RenderScale {
+    pub fn new(scale: f32) -> Self {
+        Self {
+            scale,
+            sharpness: 0.5,
+        }
+    }
+
+    pub fn extent(&self, extent: [u32; 2]) -> [u32; 2] {
+        extent.map(|size| ((size as f32 * self.scale).round() as u32).clamp(1, size.max(1)))
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct UpscaleConstants {
+    sharpness: f32,
+}
+
+pub(crate) struct UpscalePipeline {
+    sampler: Arc<Sampler>,
+    pipeline: Arc<GraphicsPipeline>,
+    gpu: Arc<Gpu>,
+}
+
+impl UpscalePipeline {
+    pub(crate) fn new(gpu: Arc<Gpu>, image_format: Format) -> Result<Self> {
+        let device = gpu.device().clone();
+        let vs = fullscreen_vs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let fs = upscale_fs::load(device.clone())?
+            .entry_point("main")
+            .unwrap();
+        let pipeline = create_pipeline(
+            &gpu,
+            vs,
+            Some(fs),
+            VertexInputState::new(),
+            rendering_info(&[image_format], None),
+            None,
+        )?;
+        gpu.set_debug_name(&*pipeline, "upscale")?;
+        let sampler = Sampler::new(
+            device,
+            SamplerCreateInfo {
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..SamplerCreateInfo::simple_repeat_linear_no_mipmap()
+            },
+        )?;
+        Ok(Self {
+            sampler,
+            pipeline,
+            gpu,
+        })
+    }
+
+    pub(crate) fn add_pass<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        source: ResourceId,
+        target: ResourceId,
+        sharpness: f32,
+    ) {
+        graph
+            .add_pass("upscale")
+            .color_attachment(Attachment::clear(target, [0.0; 4]))
+            .sample(source)
+            .record(move |ctx| {
+                let layout = self.pipeline.layout().clone();
+                let set = self.gpu.create_frame_descriptor_set(
+                    layout.set_layouts()[0].clone(),
+                    [WriteDescriptorSet::image_view_sampler(
+                        0,
+                        ctx.image_view(source),
+                        self.sampler.clone(),
+                    )],
+                )?;
+                let viewport = ctx.viewport();
+                ctx.builder
+                    .set_viewport(0, [viewport].into_iter().collect())?
+                    .bind_pipeline_graphics(self.pipeline.clone())?
+                    .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 0, set)?
+                    .push_constants(
+                        layout,
+                        0,
+                        UpscaleConstants {
+                            sharpness: sharpness.clamp(0.0, 1.0),
+                        },
+                    )?;
+                unsafe { ctx.builder.draw(3, 1, 0, 0) }?;
+                Ok(())
+            });
+    }
+}
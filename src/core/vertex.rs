@@ -0,0 +1,119 @@
+use vulkano::buffer::BufferContents;
+use vulkano::pipeline::graphics::vertex_input::Vertex as VertexTrait;
+
+#[derive(BufferContents, VertexTrait, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct Vertex2D {
+    #[format(R32G32_SFLOAT)]
+    pub position: [f32; 2],
+}
+
+#[derive(BufferContents, VertexTrait, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct VectorVertex {
+    #[format(R32G32_SFLOAT)]
+    pub position: [f32; 2],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub color: [f32; 4],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub gradient: [f32; 4],
+    #[format(R32G32_SFLOAT)]
+    pub paint: [f32; 2],
+}
+
+#[derive(BufferContents, VertexTrait, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct DebugVertex {
+    #[format(R32G32B32_SFLOAT)]
+    pub position: [f32; 3],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub color: [f32; 4],
+}
+
+#[derive(BufferContents, VertexTrait, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct OverlayVertex {
+    #[format(R32G32_SFLOAT)]
+    pub position: [f32; 2],
+    #[format(R32G32_SFLOAT)]
+    pub uv: [f32; 2],
+    #[format(R8G8B8A8_UNORM)]
+    pub color: [u8; 4],
+}
+
+#[derive(BufferContents, VertexTrait, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct Vertex3D {
+    #[format(R32G32B32_SFLOAT)]
+    pub position: [f32; 3],
+    #[format(R32G32B32_SFLOAT)]
+    pub normal: [f32; 3],
+    #[format(R32G32_SFLOAT)]
+    pub uv: [f32; 2],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub tangent: [f32; 4],
+}
+
+impl Vertex3D {
+    pub fn generate_tangents<I: Copy + Into<u32>>(
+        vertices: &mut [Vertex3D],
+        indices: &[I],
+    ) -> bool {
+        bevy_mikktspace::generate_tangents(&mut TangentGeometry { vertices, indices })
+    }
+}
+
+struct TangentGeometry<'a, I> {
+    vertices: &'a mut [Vertex3D],
+    indices: &'a [I],
+}
+
+impl<I: Copy + Into<u32>> TangentGeometry<'_, I> {
+    fn vertex(&self, face: usize, vert: usize) -> &Vertex3D {
+        &self.vertices[self.indices[face * 3 + vert].into() as usize]
+    }
+}
+
+impl<I: Copy + Into<u32>> bevy_mikktspace::Geometry for TangentGeometry<'_, I> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).position
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).normal
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.vertex(face, vert).uv
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        let index = self.indices[face * 3 + vert].into() as usize;
+        self.vertices[index].tangent = tangent;
+    }
+}
+
+#[derive(BufferContents, VertexTrait, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct SkinnedVertex3D {
+    #[format(R32G32B32_SFLOAT)]
+    pub position: [f32; 3],
+    #[format(R32G32B32_SFLOAT)]
+    pub normal: [f32; 3],
+    #[format(R32G32_SFLOAT)]
+    pub uv: [f32; 2],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub tangent: [f32; 4],
+    #[format(R32G32B32A32_UINT)]
+    pub joints: [u32; 4],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub weights: [f32; 4],
+}
@@ -0,0 +1,63 @@
+use crate::core::camera::Camera;
+use crate::core::material::Material;
+use crate::core::renderer::{Draw, Mesh, RenderParams};
+use crate::core::transform::Transform;
+use bevy_ecs::component::Component;
+use bevy_ecs::world::World;
+use glam::Mat4;
+
+#[derive(Component)]
+pub struct MeshHandle<Vertex: Send + Sync + 'static>(pub Mesh<Vertex>);
+
+#[derive(Component, Clone, Default)]
+pub struct MaterialHandle(pub Material);
+
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderLayer(pub i32);
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct ActiveCamera;
+
+pub fn extract_draws<Vertex: Send + Sync + 'static>(world: &mut World) -> Vec<Draw<Vertex>> {
+    let mut query = world.query::<(
+        &Transform,
+        &MeshHandle<Vertex>,
+        Option<&MaterialHandle>,
+        Option<&RenderLayer>,
+    )>();
+    query
+        .iter(world)
+        .map(|(transform, mesh, material, layer)| {
+            let mut draw = Draw::new(mesh.0.clone(), layer.map_or(0, |layer| layer.0));
+            draw.transform = transform.matrix();
+            if let Some(material) = material {
+                draw.material = material.0.clone();
+            }
+            draw
+        })
+        .collect()
+}
+
+pub fn extract_camera(world: &mut World) -> Option<Camera> {
+    let mut query = world.query::<(&Camera, Option<&Transform>, Option<&ActiveCamera>)>();
+    let mut cameras: Vec<_> = query.iter(world).collect();
+    cameras.sort_by_key(|(_, _, active)| active.is_none());
+    let (camera, transform, _) = cameras.first()?;
+    let mut camera = **camera;
+    if let Some(transform) = transform {
+        camera.view =
+            Mat4::from_rotation_translation(transform.rotation, transform.translation).inverse();
+        camera.position = transform.translation;
+    }
+    Some(camera)
+}
+
+pub fn extract<Vertex: Send + Sync + 'static>(
+    world: &mut World,
+    render_params: &mut RenderParams<Vertex>,
+) {
+    render_params.draws.extend(extract_draws(world));
+    if let Some(camera) = extract_camera(world) {
+        render_params.camera = camera;
+    }
+}
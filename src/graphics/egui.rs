@@ -0,0 +1,165 @@
+use crate::core::buffer_arena::BufferArena;
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::overlay::{Overlay, OverlayDraw};
+use crate::core::renderer::Mesh;
+use crate::core::texture::{ColorSpace, Texture};
+use crate::core::vertex::OverlayVertex;
+use ::egui::epaint::{ImageData, ImageDelta, Primitive};
+use ::egui::{ClippedPrimitive, Context, TextureFilter, TextureId, ViewportId};
+use egui_winit::{EventResponse, State};
+use std::collections::HashMap;
+use std::sync::Arc;
+use vulkano::buffer::BufferUsage;
+use vulkano::image::sampler::Filter;
+use winit::event::WindowEvent;
+use winit::window::Window;
+
+pub struct Egui {
+    state: State,
+    textures: HashMap<TextureId, (Texture, Filter)>,
+    freed: Vec<TextureId>,
+    primitives: Vec<ClippedPrimitive>,
+    pixels_per_point: f32,
+    next_user_texture: u64,
+    arena: BufferArena,
+    gpu: Arc<Gpu>,
+}
+
+impl Egui {
+    pub fn new(gpu: Arc<Gpu>, window: &Window) -> Self {
+        let max_texture_side = gpu
+            .device()
+            .physical_device()
+            .properties()
+            .max_image_dimension2_d as usize;
+        let state = State::new(
+            Context::default(),
+            ViewportId::ROOT,
+            window,
+            Some(window.scale_factor() as f32),
+            window.theme(),
+            Some(max_texture_side),
+        );
+        Self {
+            state,
+            textures: HashMap::new(),
+            freed: Vec::new(),
+            primitives: Vec::new(),
+            pixels_per_point: window.scale_factor() as f32,
+            next_user_texture: 0,
+            arena: BufferArena::new(
+                gpu.clone(),
+                BufferUsage::VERTEX_BUFFER | BufferUsage::INDEX_BUFFER,
+            ),
+            gpu,
+        }
+    }
+
+    pub fn context(&self) -> &Context {
+        self.state.egui_ctx()
+    }
+
+    pub fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> EventResponse {
+        self.state.on_window_event(window, event)
+    }
+
+    pub fn register_texture(&mut self, texture: Texture, filter: Filter) -> TextureId {
+        let id = TextureId::User(self.next_user_texture);
+        self.next_user_texture += 1;
+        self.textures.insert(id, (texture, filter));
+        id
+    }
+
+    pub fn unregister_texture(&mut self, id: TextureId) {
+        self.textures.remove(&id);
+    }
+
+    pub fn run(&mut self, window: &Window, run_ui: impl FnMut(&Context)) -> Result<()> {
+        for id in self.freed.drain(..) {
+            self.textures.remove(&id);
+        }
+        let input = self.state.take_egui_input(window);
+        let context = self.state.egui_ctx().clone();
+        let output = context.run(input, run_ui);
+        self.state
+            .handle_platform_output(window, output.platform_output);
+        for (id, delta) in output.textures_delta.set {
+            self.update_texture(id, delta)?;
+        }
+        self.freed = output.textures_delta.free;
+        self.primitives = context.tessellate(output.shapes, output.pixels_per_point);
+        self.pixels_per_point = output.pixels_per_point;
+        Ok(())
+    }
+
+    pub fn overlay(&self) -> Result<Overlay> {
+        let mut draws = Vec::new();
+        for primitive in &self.primitives {
+            let Primitive::Mesh(mesh) = &primitive.primitive else {
+                continue;
+            };
+            let Some((texture, filter)) = self.textures.get(&mesh.texture_id) else {
+                continue;
+            };
+            if mesh.indices.is_empty() {
+                continue;
+            }
+            let vertices = mesh
+                .vertices
+                .iter()
+                .map(|vertex| OverlayVertex {
+                    position: [vertex.pos.x, vertex.pos.y],
+                    uv: [vertex.uv.x, vertex.uv.y],
+                    color: vertex.color.to_array(),
+                })
+                .collect();
+            let clip_rect = primitive.clip_rect;
+            draws.push(OverlayDraw {
+                mesh: Mesh::from_arena(&self.arena, vertices, mesh.indices.clone())?,
+                texture: texture.clone(),
+                filter: *filter,
+                clip_rect: [
+                    clip_rect.min.x,
+                    clip_rect.min.y,
+                    clip_rect.max.x,
+                    clip_rect.max.y,
+                ],
+            });
+        }
+        Ok(Overlay {
+            draws,
+            pixels_per_point: self.pixels_per_point,
+        })
+    }
+
+    fn update_texture(&mut self, id: TextureId, delta: ImageDelta) -> Result<()> {
+        let ImageData::Color(image) = &delta.image;
+        let pixels: Vec<u8> = image.pixels.iter().flat_map(|c| c.to_array()).collect();
+        let extent = [image.size[0] as u32, image.size[1] as u32];
+        let filter = match delta.options.magnification {
+            TextureFilter::Nearest => Filter::Nearest,
+            TextureFilter::Linear => Filter::Linear,
+        };
+        match (delta.pos, self.textures.get(&id)) {
+            (Some(pos), Some((texture, _))) => {
+                texture.write(
+                    self.gpu.clone(),
+                    [pos[0] as u32, pos[1] as u32],
+                    extent,
+                    &pixels,
+                )?;
+            }
+            _ => {
+                let texture = Texture::from_pixels(
+                    self.gpu.clone(),
+                    extent,
+                    ColorSpace::Srgb.rgba8_format(),
+                    &pixels,
+                )?;
+                self.textures.insert(id, (texture, filter));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::gpu::Gpu;
+use crate::core::overlay::{Overlay, OverlayDraw};
+use crate::core::renderer::Mesh;
+use crate::core::texture::{ColorSpace, Texture};
+use crate::core::vertex::OverlayVertex;
+use ::imgui::{Context, DrawCmd, Key, MouseButton, SuspendedContext, Textures, Ui};
+use std::sync::Arc;
+use vulkano::image::sampler::Filter;
+use winit::dpi::PhysicalSize;
+use winit::event::{
+    ElementState, Ime, MouseButton as WinitMouseButton, MouseScrollDelta, WindowEvent,
+};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::Window;
+
+pub struct Imgui {
+    context: Option<SuspendedContext>,
+    textures: Textures<Texture>,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+    gpu: Arc<Gpu>,
+}
+
+impl Imgui {
+    pub fn new(gpu: Arc<Gpu>, window: &Window) -> Result<Self> {
+        let mut context = SuspendedContext::create().activate().map_err(|_| {
+            EngineError::InvalidArgument("another imgui context is already active".into())
+        })?;
+        context.set_ini_filename(None);
+        context.set_platform_name(Some("codotaku-engine-rs".to_string()));
+        context.set_renderer_name(Some("codotaku-engine-rs".to_string()));
+
+        let mut textures = Textures::new();
+        let fonts = context.fonts();
+        let atlas = fonts.build_rgba32_texture();
+        let pixels: Vec<u8> = atlas
+            .data
+            .chunks_exact(4)
+            .flat_map(|pixel| premultiply([pixel[0], pixel[1], pixel[2], pixel[3]]))
+            .collect();
+        let font_texture = Texture::from_pixels(
+            gpu.clone(),
+            [atlas.width, atlas.height],
+            ColorSpace::Srgb.rgba8_format(),
+            &pixels,
+        )?;
+        fonts.tex_id = textures.insert(font_texture);
+
+        Ok(Self {
+            context: Some(context.suspend()),
+            textures,
+            size: window.inner_size(),
+            scale_factor: window.scale_factor(),
+            gpu,
+        })
+    }
+
+    pub fn textures(&mut self) -> &mut Textures<Texture> {
+        &mut self.textures
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Result<bool> {
+        match event {
+            WindowEvent::Resized(size) => self.size = *size,
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.scale_factor = *scale_factor
+            }
+            _ => {}
+        }
+        let scale_factor = self.scale_factor;
+        self.with_context(|context, _| {
+            let io = context.io_mut();
+            match event {
+                WindowEvent::CursorMoved { position, .. } => {
+                    let position = position.to_logical::<f32>(scale_factor);
+                    io.add_mouse_pos_event([position.x, position.y]);
+                    io.want_capture_mouse
+                }
+                WindowEvent::CursorLeft { .. } => {
+                    io.add_mouse_pos_event([f32::MAX, f32::MAX]);
+                    false
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    if let Some(button) = mouse_button(*button) {
+                        io.add_mouse_button_event(button, state.is_pressed());
+                    }
+                    io.want_capture_mouse
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let [x, y] = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => [*x, *y],
+                        MouseScrollDelta::PixelDelta(delta) => {
+                            let delta = delta.to_logical::<f32>(scale_factor);
+                            [delta.x / 20.0, delta.y / 20.0]
+                        }
+                    };
+                    io.add_mouse_wheel_event([x, y]);
+                    io.want_capture_mouse
+                }
+                WindowEvent::ModifiersChanged(modifiers) => {
+                    let state = modifiers.state();
+                    io.add_key_event(Key::ModCtrl, state.control_key());
+                    io.add_key_event(Key::ModShift, state.shift_key());
+                    io.add_key_event(Key::ModAlt, state.alt_key());
+                    io.add_key_event(Key::ModSuper, state.super_key());
+                    io.want_capture_keyboard
+                }
+                WindowEvent::KeyboardInput { event, .. } => {
+                    let pressed = event.state == ElementState::Pressed;
+                    if let PhysicalKey::Code(code) = event.physical_key
+                        && let Some(key) = key(code)
+                    {
+                        io.add_key_event(key, pressed);
+                    }
+                    if pressed && let Some(text) = &event.text {
+                        for character in text.chars().filter(|c| !c.is_control()) {
+                            io.add_input_character(character);
+                        }
+                    }
+                    io.want_capture_keyboard
+                }
+                WindowEvent::Ime(Ime::Commit(text)) => {
+                    for character in text.chars().filter(|c| !c.is_control()) {
+                        io.add_input_character(character);
+                    }
+                    io.want_text_input
+                }
+                _ => false,
+            }
+        })
+    }
+
+    pub fn frame(&mut self, delta_time: f32, build: impl FnOnce(&mut Ui)) -> Result<Overlay> {
+        let size = self.size.to_logical::<f32>(self.scale_factor);
+        let scale_factor = self.scale_factor as f32;
+        let gpu = self.gpu.clone();
+        self.with_context(|context, textures| {
+            let io = context.io_mut();
+            io.display_size = [size.width, size.height];
+            io.display_framebuffer_scale = [scale_factor; 2];
+            io.delta_time = delta_time.max(f32::EPSILON);
+            build(context.new_frame());
+            let draw_data = context.render();
+
+            let mut draws = Vec::new();
+            for draw_list in draw_data.draw_lists() {
+                if draw_list.idx_buffer().is_empty() {
+                    continue;
+                }
+                let [x, y] = draw_data.display_pos;
+                let vertices = draw_list.vtx_buffer().iter().map(|vertex| OverlayVertex {
+                    position: [vertex.pos[0] - x, vertex.pos[1] - y],
+                    uv: vertex.uv,
+                    color: premultiply(vertex.col),
+                });
+                let vertex_buffer = gpu.frame_staging(vertices)?;
+                let index_buffer = gpu.frame_staging(draw_list.idx_buffer().iter().copied())?;
+                for command in draw_list.commands() {
+                    let DrawCmd::Elements { count, cmd_params } = command else {
+                        continue;
+                    };
+                    let Some(texture) = textures.get(cmd_params.texture_id) else {
+                        continue;
+                    };
+                    let [min_x, min_y, max_x, max_y] = cmd_params.clip_rect;
+                    let start = cmd_params.idx_offset as u64;
+                    let indices = index_buffer.clone().slice(start..start + count as u64);
+                    draws.push(OverlayDraw {
+                        mesh: Mesh::from_buffers(vertex_buffer.clone(), indices.into()),
+                        texture: texture.clone(),
+                        filter: Filter::Linear,
+                        clip_rect: [min_x - x, min_y - y, max_x - x, max_y - y],
+                    });
+                }
+            }
+            Ok(Overlay {
+                draws,
+                pixels_per_point: scale_factor,
+            })
+        })?
+    }
+
+    fn with_context<R>(
+        &mut self,
+        f: impl FnOnce(&mut Context, &Textures<Texture>) -> R,
+    ) -> Result<R> {
+        let suspended = self.context.take().unwrap();
+        let mut context = match suspended.activate() {
+            Ok(context) => context,
+            Err(suspended) => {
+                self.context = Some(suspended);
+                return Err(EngineError::InvalidArgument(
+                    "another imgui context is already active".into(),
+                ));
+            }
+        };
+        let result = f(&mut context, &self.textures);
+        self.context = Some(context.suspend());
+        Ok(result)
+    }
+}
+
+fn premultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    let scale = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+    [scale(r), scale(g), scale(b), a]
+}
+
+fn mouse_button(button: WinitMouseButton) -> Option<MouseButton> {
+    match button {
+        WinitMouseButton::Left => Some(MouseButton::Left),
+        WinitMouseButton::Right => Some(MouseButton::Right),
+        WinitMouseButton::Middle => Some(MouseButton::Middle),
+        WinitMouseButton::Back => Some(MouseButton::Extra1),
+        WinitMouseButton::Forward => Some(MouseButton::Extra2),
+        WinitMouseButton::Other(_) => None,
+    }
+}
+
+fn key(code: KeyCode) -> Option<Key> {
+    Some(match code {
+        KeyCode::Tab => Key::Tab,
+        KeyCode::ArrowLeft => Key::LeftArrow,
+        KeyCode::ArrowRight => Key::RightArrow,
+        KeyCode::ArrowUp => Key::UpArrow,
+        KeyCode::ArrowDown => Key::DownArrow,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Space => Key::Space,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Escape => Key::Escape,
+        KeyCode::ControlLeft => Key::LeftCtrl,
+        KeyCode::ShiftLeft => Key::LeftShift,
+        KeyCode::AltLeft => Key::LeftAlt,
+        KeyCode::SuperLeft => Key::LeftSuper,
+        KeyCode::ControlRight => Key::RightCtrl,
+        KeyCode::ShiftRight => Key::RightShift,
+        KeyCode::AltRight => Key::RightAlt,
+        KeyCode::SuperRight => Key::RightSuper,
+        KeyCode::ContextMenu => Key::Menu,
+        KeyCode::Digit0 => Key::Alpha0,
+        KeyCode::Digit1 => Key::Alpha1,
+        KeyCode::Digit2 => Key::Alpha2,
+        KeyCode::Digit3 => Key::Alpha3,
+        KeyCode::Digit4 => Key::Alpha4,
+        KeyCode::Digit5 => Key::Alpha5,
+        KeyCode::Digit6 => Key::Alpha6,
+        KeyCode::Digit7 => Key::Alpha7,
+        KeyCode::Digit8 => Key::Alpha8,
+        KeyCode::Digit9 => Key::Alpha9,
+        KeyCode::KeyA => Key::A,
+        KeyCode::KeyB => Key::B,
+        KeyCode::KeyC => Key::C,
+        KeyCode::KeyD => Key::D,
+        KeyCode::KeyE => Key::E,
+        KeyCode::KeyF => Key::F,
+        KeyCode::KeyG => Key::G,
+        KeyCode::KeyH => Key::H,
+        KeyCode::KeyI => Key::I,
+        KeyCode::KeyJ => Key::J,
+        KeyCode::KeyK => Key::K,
+        KeyCode::KeyL => Key::L,
+        KeyCode::KeyM => Key::M,
+        KeyCode::KeyN => Key::N,
+        KeyCode::KeyO => Key::O,
+        KeyCode::KeyP => Key::P,
+        KeyCode::KeyQ => Key::Q,
+        KeyCode::KeyR => Key::R,
+        KeyCode::KeyS => Key::S,
+        KeyCode::KeyT => Key::T,
+        KeyCode::KeyU => Key::U,
+        KeyCode::KeyV => Key::V,
+        KeyCode::KeyW => Key::W,
+        KeyCode::KeyX => Key::X,
+        KeyCode::KeyY => Key::Y,
+        KeyCode::KeyZ => Key::Z,
+        KeyCode::F1 => Key::F1,
+        KeyCode::F2 => Key::F2,
+        KeyCode::F3 => Key::F3,
+        KeyCode::F4 => Key::F4,
+        KeyCode::F5 => Key::F5,
+        KeyCode::F6 => Key::F6,
+        KeyCode::F7 => Key::F7,
+        KeyCode::F8 => Key::F8,
+        KeyCode::F9 => Key::F9,
+        KeyCode::F10 => Key::F10,
+        KeyCode::F11 => Key::F11,
+        KeyCode::F12 => Key::F12,
+        KeyCode::Quote => Key::Apostrophe,
+        KeyCode::Comma => Key::Comma,
+        KeyCode::Minus => Key::Minus,
+        KeyCode::Period => Key::Period,
+        KeyCode::Slash => Key::Slash,
+        KeyCode::Semicolon => Key::Semicolon,
+        KeyCode::Equal => Key::Equal,
+        KeyCode::BracketLeft => Key::LeftBracket,
+        KeyCode::Backslash => Key::Backslash,
+        KeyCode::BracketRight => Key::RightBracket,
+        KeyCode::Backquote => Key::GraveAccent,
+        KeyCode::CapsLock => Key::CapsLock,
+        KeyCode::ScrollLock => Key::ScrollLock,
+        KeyCode::NumLock => Key::NumLock,
+        KeyCode::PrintScreen => Key::PrintScreen,
+        KeyCode::Pause => Key::Pause,
+        KeyCode::Numpad0 => Key::Keypad0,
+        KeyCode::Numpad1 => Key::Keypad1,
+        KeyCode::Numpad2 => Key::Keypad2,
+        KeyCode::Numpad3 => Key::Keypad3,
+        KeyCode::Numpad4 => Key::Keypad4,
+        KeyCode::Numpad5 => Key::Keypad5,
+        KeyCode::Numpad6 => Key::Keypad6,
+        KeyCode::Numpad7 => Key::Keypad7,
+        KeyCode::Numpad8 => Key::Keypad8,
+        KeyCode::Numpad9 => Key::Keypad9,
+        KeyCode::NumpadDecimal => Key::KeypadDecimal,
+        KeyCode::NumpadDivide => Key::KeypadDivide,
+        KeyCode::NumpadMultiply => Key::KeypadMultiply,
+        KeyCode::NumpadSubtract => Key::KeypadSubtract,
+        KeyCode::NumpadAdd => Key::KeypadAdd,
+        KeyCode::NumpadEnter => Key::KeypadEnter,
+        KeyCode::NumpadEqual => Key::KeypadEqual,
+        _ => return None,
+    })
+}
@@ -0,0 +1,122 @@
+use crate::core::error::Result;
+use crate::graphics::monitors::MonitorInfo;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::window::{Fullscreen, Window};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FullscreenLayout {
+    Borderless,
+    Exclusive {
+        size: [u32; 2],
+        bit_depth: u16,
+        refresh_rate_millihertz: u32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowState {
+    #[serde(default)]
+    pub title: String,
+    pub position: Option<[i32; 2]>,
+    pub size: [u32; 2],
+    pub monitor: Option<String>,
+    #[serde(default)]
+    pub maximized: bool,
+    pub fullscreen: Option<FullscreenLayout>,
+    pub parent: Option<usize>,
+}
+
+impl WindowState {
+    pub(crate) fn capture(window: &Window, parent: Option<usize>) -> Self {
+        let fullscreen = window.fullscreen().map(|fullscreen| match fullscreen {
+            Fullscreen::Borderless(_) => FullscreenLayout::Borderless,
+            Fullscreen::Exclusive(mode) => FullscreenLayout::Exclusive {
+                size: mode.size().into(),
+                bit_depth: mode.bit_depth(),
+                refresh_rate_millihertz: mode.refresh_rate_millihertz(),
+            },
+        });
+        Self {
+            title: window.title(),
+            position: window.outer_position().ok().map(Into::into),
+            size: window.inner_size().into(),
+            monitor: window.current_monitor().and_then(|monitor| monitor.name()),
+            maximized: window.is_maximized(),
+            fullscreen,
+            parent,
+        }
+    }
+
+    pub(crate) fn monitor<'a>(&self, monitors: &'a [MonitorInfo]) -> Option<&'a MonitorInfo> {
+        let position = self.position.map(PhysicalPosition::from);
+        self.monitor
+            .as_ref()
+            .and_then(|name| {
+                monitors
+                    .iter()
+                    .find(|monitor| monitor.name.as_ref() == Some(name))
+            })
+            .or_else(|| {
+                monitors
+                    .iter()
+                    .find(|monitor| position.is_some_and(|position| monitor.contains(position)))
+            })
+    }
+
+    pub(crate) fn position_on(&self, monitor: &MonitorInfo) -> PhysicalPosition<i32> {
+        match self.position.map(PhysicalPosition::from) {
+            Some(position) if monitor.contains(position) => position,
+            _ => monitor.centered(PhysicalSize::<u32>::from(self.size)),
+        }
+    }
+
+    pub(crate) fn fullscreen_on(&self, monitor: Option<&MonitorInfo>) -> Option<Fullscreen> {
+        let handle = monitor.map(|monitor| monitor.handle.clone());
+        match self.fullscreen? {
+            FullscreenLayout::Borderless => Some(Fullscreen::Borderless(handle)),
+            FullscreenLayout::Exclusive {
+                size,
+                bit_depth,
+                refresh_rate_millihertz,
+            } => {
+                let mode = handle.as_ref().and_then(|handle| {
+                    handle.video_modes().find(|mode| {
+                        <[u32; 2]>::from(mode.size()) == size
+                            && mode.bit_depth() == bit_depth
+                            && mode.refresh_rate_millihertz() == refresh_rate_millihertz
+                    })
+                });
+                Some(match mode {
+                    Some(mode) => Fullscreen::Exclusive(mode),
+                    None => Fullscreen::Borderless(handle),
+                })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowLayout {
+    #[serde(default)]
+    pub windows: Vec<WindowState>,
+}
+
+impl WindowLayout {
+    pub fn from_toml(source: &str) -> Result<Self> {
+        Ok(toml::from_str(source)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(std::fs::write(path, self.to_toml()?)?)
+    }
+}
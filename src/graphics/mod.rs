@@ -1 +1,13 @@
+#[cfg(feature = "ecs")]
+pub mod ecs;
+#[cfg(feature = "egui")]
+pub mod egui;
+#[cfg(feature = "imgui")]
+pub mod imgui;
+pub mod layout;
+pub mod monitors;
+pub mod splash;
+pub mod tessellation;
+pub mod ui;
+pub mod vector;
 pub mod windows;
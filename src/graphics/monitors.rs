@@ -0,0 +1,55 @@
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::monitor::MonitorHandle;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonitorMode {
+    pub size: PhysicalSize<u32>,
+    pub bit_depth: u16,
+    pub refresh_rate_millihertz: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    pub handle: MonitorHandle,
+    pub name: Option<String>,
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+    pub refresh_rate_millihertz: Option<u32>,
+    pub modes: Vec<MonitorMode>,
+}
+
+impl MonitorInfo {
+    pub fn new(handle: MonitorHandle) -> Self {
+        let modes = handle
+            .video_modes()
+            .map(|mode| MonitorMode {
+                size: mode.size(),
+                bit_depth: mode.bit_depth(),
+                refresh_rate_millihertz: mode.refresh_rate_millihertz(),
+            })
+            .collect();
+        Self {
+            name: handle.name(),
+            position: handle.position(),
+            size: handle.size(),
+            scale_factor: handle.scale_factor(),
+            refresh_rate_millihertz: handle.refresh_rate_millihertz(),
+            modes,
+            handle,
+        }
+    }
+
+    pub fn contains(&self, position: PhysicalPosition<i32>) -> bool {
+        let PhysicalPosition { x, y } = self.position;
+        (x..x + self.size.width as i32).contains(&position.x)
+            && (y..y + self.size.height as i32).contains(&position.y)
+    }
+
+    pub fn centered(&self, size: PhysicalSize<u32>) -> PhysicalPosition<i32> {
+        PhysicalPosition::new(
+            self.position.x + (self.size.width as i32 - size.width as i32) / 2,
+            self.position.y + (self.size.height as i32 - size.height as i32) / 2,
+        )
+    }
+}
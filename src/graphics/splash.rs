@@ -0,0 +1,140 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::overlay::{Overlay, OverlayDraw};
+use crate::core::renderer::{Mesh, RenderParams, Renderer};
+use crate::core::texture::{ColorSpace, Texture};
+use crate::core::vertex::{OverlayVertex, VectorVertex};
+use crate::graphics::windows::Windows;
+use image::RgbaImage;
+use std::thread::JoinHandle;
+use vulkano::image::sampler::Filter;
+use winit::dpi::{PhysicalSize, Size};
+use winit::event_loop::ActiveEventLoop;
+use winit::window::WindowId;
+
+pub enum SplashBackground {
+    Transparent,
+    Color([f32; 4]),
+    Image(RgbaImage),
+}
+
+pub struct Splash<T> {
+    window: WindowId,
+    renderer: Renderer,
+    clear_color: [f32; 4],
+    image: Option<Texture>,
+    quad: Option<(PhysicalSize<u32>, Mesh<OverlayVertex>)>,
+    loading: Option<JoinHandle<Result<T>>>,
+}
+
+impl<T: Send + 'static> Splash<T> {
+    pub fn new(
+        windows: &mut Windows,
+        event_loop: &ActiveEventLoop,
+        size: impl Into<Size>,
+        background: SplashBackground,
+        load: impl FnOnce() -> Result<T> + Send + 'static,
+    ) -> Result<Self> {
+        let transparent = matches!(background, SplashBackground::Transparent);
+        let window = windows.add_splash(event_loop, size, transparent)?;
+        let (Some(gpu), Some(image_format)) = (
+            windows.window_gpu(window).cloned(),
+            windows.image_format(window),
+        ) else {
+            return Err(EngineError::WindowGone(window));
+        };
+        let (clear_color, image) = match background {
+            SplashBackground::Transparent => ([0.0; 4], None),
+            SplashBackground::Color(color) => (color, None),
+            SplashBackground::Image(image) => {
+                let texture = Texture::from_pixels(
+                    gpu.clone(),
+                    [image.width(), image.height()],
+                    ColorSpace::Srgb.rgba8_format(),
+                    image.as_raw(),
+                )?;
+                ([0.0, 0.0, 0.0, 1.0], Some(texture))
+            }
+        };
+        Ok(Self {
+            window,
+            renderer: Renderer::vector(gpu, image_format)?,
+            clear_color,
+            image,
+            quad: None,
+            loading: Some(std::thread::spawn(load)),
+        })
+    }
+
+    pub fn window(&self) -> WindowId {
+        self.window
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loading
+            .as_ref()
+            .is_none_or(|loading| loading.is_finished())
+    }
+
+    pub fn redraw(&mut self, windows: &mut Windows) -> Result<()> {
+        let Some(size) = windows.window_size(self.window) else {
+            return Err(EngineError::WindowGone(self.window));
+        };
+        let mut overlay = Overlay::default();
+        if let Some(texture) = &self.image {
+            if self
+                .quad
+                .as_ref()
+                .is_none_or(|(quad_size, _)| *quad_size != size)
+            {
+                self.quad = Some((size, self.create_quad(size)?));
+            }
+            let (_, mesh) = self.quad.as_ref().unwrap();
+            overlay.draws.push(OverlayDraw {
+                mesh: mesh.clone(),
+                texture: texture.clone(),
+                filter: Filter::Linear,
+                clip_rect: [0.0, 0.0, size.width as f32, size.height as f32],
+            });
+        }
+        windows.redraw(
+            self.window,
+            &self.renderer,
+            RenderParams::<VectorVertex> {
+                clear_color: self.clear_color,
+                overlay,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn poll(&mut self, windows: &mut Windows) -> Option<Result<T>> {
+        if !self.is_loaded() {
+            return None;
+        }
+        let result = match self.loading.take()?.join() {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        };
+        windows.remove(self.window);
+        Some(result)
+    }
+
+    fn create_quad(&self, size: PhysicalSize<u32>) -> Result<Mesh<OverlayVertex>> {
+        let [width, height] = [size.width as f32, size.height as f32];
+        let vertex = |position, uv| OverlayVertex {
+            position,
+            uv,
+            color: [255; 4],
+        };
+        Mesh::new(
+            self.renderer.gpu().clone(),
+            vec![
+                vertex([0.0, 0.0], [0.0, 0.0]),
+                vertex([width, 0.0], [1.0, 0.0]),
+                vertex([width, height], [1.0, 1.0]),
+                vertex([0.0, height], [0.0, 1.0]),
+            ],
+            vec![0u16, 1, 2, 0, 2, 3],
+        )
+    }
+}
@@ -0,0 +1,46 @@
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::renderer::Mesh;
+use crate::core::vertex::Vertex2D;
+use lyon::path::Path;
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator,
+    StrokeVertex, VertexBuffers,
+};
+use std::sync::Arc;
+
+pub type Geometry2D = VertexBuffers<Vertex2D, u32>;
+
+pub fn fill_geometry(path: &Path, options: &FillOptions) -> Result<Geometry2D> {
+    let mut geometry = Geometry2D::new();
+    FillTessellator::new().tessellate_path(
+        path,
+        options,
+        &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| Vertex2D {
+            position: vertex.position().to_array(),
+        }),
+    )?;
+    Ok(geometry)
+}
+
+pub fn stroke_geometry(path: &Path, options: &StrokeOptions) -> Result<Geometry2D> {
+    let mut geometry = Geometry2D::new();
+    StrokeTessellator::new().tessellate_path(
+        path,
+        options,
+        &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| Vertex2D {
+            position: vertex.position().to_array(),
+        }),
+    )?;
+    Ok(geometry)
+}
+
+pub fn fill_mesh(gpu: Arc<Gpu>, path: &Path, options: &FillOptions) -> Result<Mesh<Vertex2D>> {
+    let geometry = fill_geometry(path, options)?;
+    Mesh::new(gpu, geometry.vertices, geometry.indices)
+}
+
+pub fn stroke_mesh(gpu: Arc<Gpu>, path: &Path, options: &StrokeOptions) -> Result<Mesh<Vertex2D>> {
+    let geometry = stroke_geometry(path, options)?;
+    Mesh::new(gpu, geometry.vertices, geometry.indices)
+}
@@ -0,0 +1,524 @@
+use crate::core::error::{EngineError, Result};
+use crate::core::glyphs;
+use crate::core::gpu::Gpu;
+use crate::core::renderer::Draw;
+use crate::core::vertex::VectorVertex;
+use crate::graphics::vector::{Shape, ShapeStyle, Stroke, VectorLayer};
+use glam::{Mat4, Vec3};
+use lyon::math::{point, Box2D};
+use lyon::path::{Path, Winding};
+use lyon::tessellation::{LineCap, LineJoin};
+use std::collections::HashMap;
+use std::sync::Arc;
+use taffy::prelude::*;
+use winit::event::{ElementState, MouseButton, Touch, TouchPhase, WindowEvent};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct WidgetId(NodeId);
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Widget {
+    Panel { color: Option<[f32; 4]> },
+    Label { text: String },
+    Button { text: String },
+    Slider { value: f32, min: f32, max: f32 },
+}
+
+impl Widget {
+    fn is_interactive(&self) -> bool {
+        matches!(self, Widget::Button { .. } | Widget::Slider { .. })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UiEvent {
+    Clicked(WidgetId),
+    ValueChanged(WidgetId, f32),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct UiTheme {
+    pub text: [f32; 4],
+    pub text_size: f32,
+    pub button: [f32; 4],
+    pub button_hovered: [f32; 4],
+    pub button_pressed: [f32; 4],
+    pub slider_track: [f32; 4],
+    pub slider_thumb: [f32; 4],
+    pub slider_size: [f32; 2],
+    pub padding: f32,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        Self {
+            text: [0.9, 0.9, 0.9, 1.0],
+            text_size: 12.0,
+            button: [0.25, 0.25, 0.3, 1.0],
+            button_hovered: [0.35, 0.35, 0.42, 1.0],
+            button_pressed: [0.18, 0.18, 0.22, 1.0],
+            slider_track: [0.2, 0.2, 0.24, 1.0],
+            slider_thumb: [0.55, 0.55, 0.65, 1.0],
+            slider_size: [160.0, 16.0],
+            padding: 6.0,
+        }
+    }
+}
+
+pub struct Ui {
+    tree: TaffyTree<Widget>,
+    root: NodeId,
+    rects: HashMap<NodeId, Box2D>,
+    order: Vec<NodeId>,
+    size: [f32; 2],
+    scale_factor: f32,
+    hovered: Option<NodeId>,
+    pressed: Option<NodeId>,
+    touch: Option<u64>,
+    events: Vec<UiEvent>,
+    layer: VectorLayer,
+    layout_dirty: bool,
+    shapes_dirty: bool,
+    pub theme: UiTheme,
+}
+
+impl Ui {
+    pub fn new(size: [f32; 2]) -> Result<Self> {
+        let mut tree = TaffyTree::new();
+        let root = tree.new_leaf_with_context(
+            Style {
+                flex_direction: FlexDirection::Column,
+                ..Default::default()
+            },
+            Widget::Panel { color: None },
+        )?;
+        let mut layer = VectorLayer::new();
+        layer.tolerance = 0.1;
+        Ok(Self {
+            tree,
+            root,
+            rects: HashMap::new(),
+            order: Vec::new(),
+            size,
+            scale_factor: 1.0,
+            hovered: None,
+            touch: None,
+            pressed: None,
+            events: Vec::new(),
+            layer,
+            layout_dirty: true,
+            shapes_dirty: true,
+            theme: UiTheme::default(),
+        })
+    }
+
+    pub fn root(&self) -> WidgetId {
+        WidgetId(self.root)
+    }
+
+    pub fn add(&mut self, parent: WidgetId, widget: Widget, style: Style) -> Result<WidgetId> {
+        let node = self.tree.new_leaf_with_context(style, widget)?;
+        self.tree.add_child(parent.0, node)?;
+        self.layout_dirty = true;
+        Ok(WidgetId(node))
+    }
+
+    pub fn panel(
+        &mut self,
+        parent: WidgetId,
+        style: Style,
+        color: Option<[f32; 4]>,
+    ) -> Result<WidgetId> {
+        self.add(parent, Widget::Panel { color }, style)
+    }
+
+    pub fn label(&mut self, parent: WidgetId, text: impl Into<String>) -> Result<WidgetId> {
+        let text = text.into();
+        self.add(parent, Widget::Label { text }, Style::default())
+    }
+
+    pub fn button(&mut self, parent: WidgetId, text: impl Into<String>) -> Result<WidgetId> {
+        let text = text.into();
+        let padding = LengthPercentage::length(self.theme.padding);
+        let style = Style {
+            padding: Rect {
+                left: padding,
+                right: padding,
+                top: padding,
+                bottom: padding,
+            },
+            ..Default::default()
+        };
+        self.add(parent, Widget::Button { text }, style)
+    }
+
+    pub fn slider(&mut self, parent: WidgetId, value: f32, min: f32, max: f32) -> Result<WidgetId> {
+        let value = value.clamp(min, max);
+        self.add(parent, Widget::Slider { value, min, max }, Style::default())
+    }
+
+    pub fn remove(&mut self, id: WidgetId) -> Result<()> {
+        if id.0 == self.root {
+            return Err(EngineError::InvalidArgument(
+                "the root widget cannot be removed".into(),
+            ));
+        }
+        let mut stack = vec![id.0];
+        while let Some(node) = stack.pop() {
+            stack.extend(self.tree.children(node)?);
+            self.tree.remove(node)?;
+        }
+        self.hovered = None;
+        self.pressed = None;
+        self.layout_dirty = true;
+        Ok(())
+    }
+
+    pub fn widget(&self, id: WidgetId) -> Option<&Widget> {
+        self.tree.get_node_context(id.0)
+    }
+
+    pub fn update(&mut self, id: WidgetId, update: impl FnOnce(&mut Widget)) {
+        if let Some(widget) = self.tree.get_node_context_mut(id.0) {
+            update(widget);
+            if let Widget::Slider { value, min, max } = widget {
+                *value = value.clamp(*min, *max);
+            }
+            let _ = self.tree.mark_dirty(id.0);
+            self.layout_dirty = true;
+        }
+    }
+
+    pub fn set_style(&mut self, id: WidgetId, style: Style) -> Result<()> {
+        self.tree.set_style(id.0, style)?;
+        self.layout_dirty = true;
+        Ok(())
+    }
+
+    pub fn resize(&mut self, size: [f32; 2]) {
+        if self.size != size {
+            self.size = size;
+            self.layout_dirty = true;
+        }
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        let scale_factor = scale_factor.max(f32::EPSILON);
+        if self.scale_factor != scale_factor {
+            let ratio = self.scale_factor / scale_factor;
+            self.size = self.size.map(|extent| extent * ratio);
+            self.scale_factor = scale_factor;
+            self.layer.set_zoom(scale_factor);
+            self.layout_dirty = true;
+        }
+    }
+
+    pub fn rect(&mut self, id: WidgetId) -> Result<Option<[f32; 4]>> {
+        self.update_layout()?;
+        Ok(self
+            .rects
+            .get(&id.0)
+            .map(|rect| [rect.min.x, rect.min.y, rect.max.x, rect.max.y]))
+    }
+
+    pub fn hit_test(&mut self, position: [f32; 2]) -> Result<Option<WidgetId>> {
+        self.update_layout()?;
+        let position = point(position[0], position[1]);
+        Ok(self
+            .order
+            .iter()
+            .rev()
+            .find(|node| self.rects[node].contains(position))
+            .map(|node| WidgetId(*node)))
+    }
+
+    pub fn events(&mut self) -> std::vec::Drain<'_, UiEvent> {
+        self.events.drain(..)
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Result<bool> {
+        match event {
+            WindowEvent::Resized(size) => {
+                self.resize([
+                    size.width as f32 / self.scale_factor,
+                    size.height as f32 / self.scale_factor,
+                ]);
+                Ok(false)
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.set_scale_factor(*scale_factor as f32);
+                Ok(false)
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = [
+                    position.x as f32 / self.scale_factor,
+                    position.y as f32 / self.scale_factor,
+                ];
+                let hovered = self
+                    .hit_test(position)?
+                    .map(|id| id.0)
+                    .filter(|node| *node != self.root);
+                if hovered != self.hovered {
+                    self.hovered = hovered;
+                    self.shapes_dirty = true;
+                }
+                if let Some(pressed) = self.pressed {
+                    self.drag(pressed, position[0]);
+                }
+                Ok(hovered.is_some() || self.pressed.is_some())
+            }
+            WindowEvent::CursorLeft { .. } => {
+                if self.hovered.take().is_some() {
+                    self.shapes_dirty = true;
+                }
+                Ok(false)
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let Some(hovered) = self.hovered else {
+                    self.pressed = None;
+                    return Ok(false);
+                };
+                let interactive = self
+                    .tree
+                    .get_node_context(hovered)
+                    .is_some_and(Widget::is_interactive);
+                match state {
+                    ElementState::Pressed if interactive => {
+                        self.pressed = Some(hovered);
+                        self.shapes_dirty = true;
+                    }
+                    ElementState::Released => {
+                        if let Some(pressed) = self.pressed.take() {
+                            let clicked = pressed == hovered
+                                && matches!(
+                                    self.tree.get_node_context(pressed),
+                                    Some(Widget::Button { .. })
+                                );
+                            if clicked {
+                                self.events.push(UiEvent::Clicked(WidgetId(pressed)));
+                            }
+                            self.shapes_dirty = true;
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(true)
+            }
+            WindowEvent::Touch(touch) => self.handle_touch(touch),
+            _ => Ok(false),
+        }
+    }
+
+    fn handle_touch(&mut self, touch: &Touch) -> Result<bool> {
+        if self.touch.is_some_and(|id| id != touch.id) {
+            return Ok(false);
+        }
+        let device_id = touch.device_id;
+        let moved = WindowEvent::CursorMoved {
+            device_id,
+            position: touch.location,
+        };
+        let button = |state| WindowEvent::MouseInput {
+            device_id,
+            state,
+            button: MouseButton::Left,
+        };
+        match touch.phase {
+            TouchPhase::Started => {
+                self.touch = Some(touch.id);
+                let hovered = self.handle_event(&moved)?;
+                Ok(self.handle_event(&button(ElementState::Pressed))? || hovered)
+            }
+            TouchPhase::Moved => self.handle_event(&moved),
+            TouchPhase::Ended => {
+                self.touch = None;
+                let released = self.handle_event(&button(ElementState::Released))?;
+                self.handle_event(&WindowEvent::CursorLeft { device_id })?;
+                Ok(released)
+            }
+            TouchPhase::Cancelled => {
+                self.touch = None;
+                self.pressed = None;
+                self.handle_event(&WindowEvent::CursorLeft { device_id })
+            }
+        }
+    }
+
+    pub fn draw(&mut self, gpu: Arc<Gpu>, layer: i32) -> Result<Option<Draw<VectorVertex>>> {
+        self.update_layout()?;
+        if self.shapes_dirty {
+            self.rebuild_shapes();
+        }
+        let [width, height] = self.size;
+        let transform = Mat4::from_translation(Vec3::new(-1.0, -1.0, 0.0))
+            * Mat4::from_scale(Vec3::new(2.0 / width.max(1.0), 2.0 / height.max(1.0), 1.0));
+        self.layer.draw(gpu, transform, layer)
+    }
+
+    fn drag(&mut self, node: NodeId, x: f32) {
+        let rect = self.rects[&node];
+        let Some(Widget::Slider { value, min, max }) = self.tree.get_node_context_mut(node) else {
+            return;
+        };
+        let t = ((x - rect.min.x) / rect.width().max(f32::EPSILON)).clamp(0.0, 1.0);
+        let new_value = *min + (*max - *min) * t;
+        if new_value != *value {
+            *value = new_value;
+            self.events
+                .push(UiEvent::ValueChanged(WidgetId(node), new_value));
+            self.shapes_dirty = true;
+        }
+    }
+
+    fn update_layout(&mut self) -> Result<()> {
+        if !self.layout_dirty {
+            return Ok(());
+        }
+        let mut root_style = self.tree.style(self.root)?.clone();
+        root_style.size = Size {
+            width: Dimension::length(self.size[0]),
+            height: Dimension::length(self.size[1]),
+        };
+        self.tree.set_style(self.root, root_style)?;
+        let theme = self.theme;
+        self.tree.compute_layout_with_measure(
+            self.root,
+            Size::MAX_CONTENT,
+            |known, _, _, widget, _| {
+                let [width, height] = match widget {
+                    Some(Widget::Label { text } | Widget::Button { text }) => {
+                        glyphs::extent(text, theme.text_size)
+                    }
+                    Some(Widget::Slider { .. }) => theme.slider_size,
+                    _ => [0.0, 0.0],
+                };
+                Size {
+                    width: known.width.unwrap_or(width),
+                    height: known.height.unwrap_or(height),
+                }
+            },
+        )?;
+
+        self.rects.clear();
+        self.order.clear();
+        let mut stack = vec![(self.root, [0.0, 0.0])];
+        while let Some((node, origin)) = stack.pop() {
+            let layout = self.tree.layout(node)?;
+            let min = [origin[0] + layout.location.x, origin[1] + layout.location.y];
+            self.rects.insert(
+                node,
+                Box2D::new(
+                    point(min[0], min[1]),
+                    point(min[0] + layout.size.width, min[1] + layout.size.height),
+                ),
+            );
+            self.order.push(node);
+            for child in self.tree.children(node)?.into_iter().rev() {
+                stack.push((child, min));
+            }
+        }
+        self.layout_dirty = false;
+        self.shapes_dirty = true;
+        Ok(())
+    }
+
+    fn rebuild_shapes(&mut self) {
+        self.layer.clear();
+        let theme = self.theme;
+        for node in &self.order {
+            let rect = self.rects[node];
+            let Some(widget) = self.tree.get_node_context(*node) else {
+                continue;
+            };
+            match widget {
+                Widget::Panel { color: Some(color) } => {
+                    self.layer.add(rect_shape(rect, *color));
+                }
+                Widget::Panel { color: None } => {}
+                Widget::Label { text } => {
+                    if let Some(shape) = text_shape(text, rect, &theme) {
+                        self.layer.add(shape);
+                    }
+                }
+                Widget::Button { text } => {
+                    let color = if self.pressed == Some(*node) {
+                        theme.button_pressed
+                    } else if self.hovered == Some(*node) {
+                        theme.button_hovered
+                    } else {
+                        theme.button
+                    };
+                    self.layer.add(rect_shape(rect, color));
+                    let padding = self.tree.layout(*node).map(|layout| layout.padding);
+                    let content = match padding {
+                        Ok(padding) => Box2D::new(
+                            point(rect.min.x + padding.left, rect.min.y + padding.top),
+                            point(rect.max.x - padding.right, rect.max.y - padding.bottom),
+                        ),
+                        Err(_) => rect,
+                    };
+                    if let Some(shape) = text_shape(text, content, &theme) {
+                        self.layer.add(shape);
+                    }
+                }
+                Widget::Slider { value, min, max } => {
+                    let t = if max > min {
+                        (value - min) / (max - min)
+                    } else {
+                        0.0
+                    };
+                    let track_height = rect.height() / 4.0;
+                    let center = rect.center().y;
+                    self.layer.add(rect_shape(
+                        Box2D::new(
+                            point(rect.min.x, center - track_height / 2.0),
+                            point(rect.max.x, center + track_height / 2.0),
+                        ),
+                        theme.slider_track,
+                    ));
+                    let thumb = rect.height() / 2.0;
+                    let x = rect.min.x + thumb + (rect.width() - thumb * 2.0).max(0.0) * t;
+                    self.layer.add(rect_shape(
+                        Box2D::new(point(x - thumb, rect.min.y), point(x + thumb, rect.max.y)),
+                        theme.slider_thumb,
+                    ));
+                }
+            }
+        }
+        self.shapes_dirty = false;
+    }
+}
+
+fn rect_shape(rect: Box2D, color: [f32; 4]) -> Shape {
+    let mut builder = Path::builder();
+    builder.add_rectangle(&rect, Winding::Positive);
+    Shape::new(builder.build(), ShapeStyle::fill(color))
+}
+
+fn text_shape(text: &str, rect: Box2D, theme: &UiTheme) -> Option<Shape> {
+    let segments = glyphs::segments(text, theme.text_size);
+    if segments.is_empty() {
+        return None;
+    }
+    let top = rect.min.y + theme.text_size;
+    let mut builder = Path::builder();
+    for [start, end] in segments {
+        builder.begin(point(rect.min.x + start[0], top - start[1]));
+        builder.line_to(point(rect.min.x + end[0], top - end[1]));
+        builder.end(false);
+    }
+    let stroke = Stroke {
+        line_join: LineJoin::Round,
+        line_cap: LineCap::Round,
+        ..Stroke::new(theme.text, theme.text_size / 8.0)
+    };
+    Some(Shape::new(builder.build(), ShapeStyle::stroke(stroke)))
+}
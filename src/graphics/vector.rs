@@ -0,0 +1,358 @@
+use crate::core::error::Result;
+use crate::core::gpu::Gpu;
+use crate::core::material::Material;
+use crate::core::renderer::{Draw, Mesh};
+use crate::core::texture::{ColorSpace, Texture};
+use crate::core::vertex::VectorVertex;
+use glam::Mat4;
+use lyon::path::Path;
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, LineCap, LineJoin, StrokeOptions,
+    StrokeTessellator, StrokeVertex, VertexBuffers,
+};
+use std::sync::Arc;
+
+const ZOOM_THRESHOLD: f32 = 2.0;
+const GRADIENT_WIDTH: u32 = 256;
+const PAINT_SOLID: f32 = 0.0;
+const PAINT_LINEAR: f32 = 1.0;
+const PAINT_RADIAL: f32 = 2.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ShapeId(usize);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GradientStop {
+    pub offset: f32,
+    pub color: [f32; 4],
+}
+
+impl GradientStop {
+    pub fn new(offset: f32, color: [f32; 4]) -> Self {
+        Self { offset, color }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Paint {
+    Solid([f32; 4]),
+    LinearGradient {
+        start: [f32; 2],
+        end: [f32; 2],
+        stops: Vec<GradientStop>,
+    },
+    RadialGradient {
+        center: [f32; 2],
+        radius: f32,
+        stops: Vec<GradientStop>,
+    },
+}
+
+impl From<[f32; 4]> for Paint {
+    fn from(color: [f32; 4]) -> Self {
+        Paint::Solid(color)
+    }
+}
+
+impl Paint {
+    fn stops(&self) -> Option<&[GradientStop]> {
+        match self {
+            Paint::Solid(_) => None,
+            Paint::LinearGradient { stops, .. } | Paint::RadialGradient { stops, .. } => {
+                Some(stops)
+            }
+        }
+    }
+
+    fn vertex(&self, position: [f32; 2], row: f32) -> VectorVertex {
+        let (color, gradient, kind) = match self {
+            Paint::Solid(color) => (*color, [0.0; 4], PAINT_SOLID),
+            Paint::LinearGradient { start, end, .. } => {
+                ([1.0; 4], [start[0], start[1], end[0], end[1]], PAINT_LINEAR)
+            }
+            Paint::RadialGradient { center, radius, .. } => {
+                ([1.0; 4], [center[0], center[1], *radius, 0.0], PAINT_RADIAL)
+            }
+        };
+        VectorVertex {
+            position,
+            color,
+            gradient,
+            paint: [kind, row],
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Stroke {
+    pub paint: Paint,
+    pub width: f32,
+    pub line_join: LineJoin,
+    pub line_cap: LineCap,
+}
+
+impl Stroke {
+    pub fn new(paint: impl Into<Paint>, width: f32) -> Self {
+        Self {
+            paint: paint.into(),
+            width,
+            line_join: LineJoin::Miter,
+            line_cap: LineCap::Butt,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ShapeStyle {
+    pub fill: Option<Paint>,
+    pub stroke: Option<Stroke>,
+}
+
+impl ShapeStyle {
+    pub fn fill(paint: impl Into<Paint>) -> Self {
+        Self {
+            fill: Some(paint.into()),
+            stroke: None,
+        }
+    }
+
+    pub fn stroke(stroke: Stroke) -> Self {
+        Self {
+            fill: None,
+            stroke: Some(stroke),
+        }
+    }
+
+    pub fn with_stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = Some(stroke);
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Shape {
+    pub path: Path,
+    pub style: ShapeStyle,
+}
+
+impl Shape {
+    pub fn new(path: Path, style: ShapeStyle) -> Self {
+        Self { path, style }
+    }
+}
+
+struct Entry {
+    shape: Shape,
+    geometry: Option<VertexBuffers<VectorVertex, u32>>,
+}
+
+pub struct VectorLayer {
+    entries: Vec<Option<Entry>>,
+    mesh: Option<Mesh<VectorVertex>>,
+    gradients: Option<Texture>,
+    dirty: bool,
+    zoom: f32,
+    tessellated_zoom: f32,
+    pub tolerance: f32,
+}
+
+impl Default for VectorLayer {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            mesh: None,
+            gradients: None,
+            dirty: false,
+            zoom: 1.0,
+            tessellated_zoom: 1.0,
+            tolerance: 0.01,
+        }
+    }
+}
+
+impl VectorLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, shape: Shape) -> ShapeId {
+        let entry = Some(Entry {
+            shape,
+            geometry: None,
+        });
+        self.dirty = true;
+        if let Some(index) = self.entries.iter().position(Option::is_none) {
+            self.entries[index] = entry;
+            ShapeId(index)
+        } else {
+            self.entries.push(entry);
+            ShapeId(self.entries.len() - 1)
+        }
+    }
+
+    pub fn get(&self, id: ShapeId) -> Option<&Shape> {
+        self.entries.get(id.0)?.as_ref().map(|entry| &entry.shape)
+    }
+
+    pub fn update(&mut self, id: ShapeId, update: impl FnOnce(&mut Shape)) {
+        if let Some(Some(entry)) = self.entries.get_mut(id.0) {
+            update(&mut entry.shape);
+            entry.geometry = None;
+            self.dirty = true;
+        }
+    }
+
+    pub fn remove(&mut self, id: ShapeId) -> Option<Shape> {
+        let entry = self.entries.get_mut(id.0)?.take()?;
+        self.dirty = true;
+        Some(entry.shape)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.mesh = None;
+        self.gradients = None;
+        self.dirty = false;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.max(f32::EPSILON);
+        let ratio = self.zoom / self.tessellated_zoom;
+        if !(1.0 / ZOOM_THRESHOLD..=ZOOM_THRESHOLD).contains(&ratio) {
+            self.tessellated_zoom = self.zoom;
+            for entry in self.entries.iter_mut().flatten() {
+                entry.geometry = None;
+            }
+            self.dirty = true;
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        gpu: Arc<Gpu>,
+        transform: Mat4,
+        layer: i32,
+    ) -> Result<Option<Draw<VectorVertex>>> {
+        if self.dirty {
+            self.rebuild(gpu)?;
+        }
+        let material = Material {
+            base_color_map: self.gradients.clone(),
+            ..Default::default()
+        };
+        Ok(self.mesh.clone().map(|mesh| {
+            Draw::new(mesh, layer)
+                .with_transform(transform)
+                .with_material(material)
+        }))
+    }
+
+    fn rebuild(&mut self, gpu: Arc<Gpu>) -> Result<()> {
+        let tolerance = self.tolerance / self.tessellated_zoom;
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut ramps = Vec::new();
+        for entry in self.entries.iter_mut().flatten() {
+            if entry.geometry.is_none() {
+                entry.geometry = Some(tessellate(&entry.shape, tolerance)?);
+            }
+            let geometry = entry.geometry.as_ref().unwrap();
+            let row = (ramps.len() / (GRADIENT_WIDTH as usize * 4)) as f32;
+            let style = &entry.shape.style;
+            let paints = [style.fill.as_ref(), style.stroke.as_ref().map(|s| &s.paint)];
+            for stops in paints.into_iter().flatten().filter_map(Paint::stops) {
+                ramps.extend(gradient_ramp(stops));
+            }
+            let base = vertices.len() as u32;
+            vertices.extend(geometry.vertices.iter().map(|vertex| {
+                let mut vertex = *vertex;
+                if vertex.paint[0] != PAINT_SOLID {
+                    vertex.paint[1] += row;
+                }
+                vertex
+            }));
+            indices.extend(geometry.indices.iter().map(|index| base + index));
+        }
+        self.gradients = if ramps.is_empty() {
+            None
+        } else {
+            let rows = (ramps.len() / (GRADIENT_WIDTH as usize * 4)) as u32;
+            Some(Texture::from_pixels(
+                gpu.clone(),
+                [GRADIENT_WIDTH, rows],
+                ColorSpace::Linear.rgba8_format(),
+                &ramps,
+            )?)
+        };
+        self.mesh = if indices.is_empty() {
+            None
+        } else {
+            Some(Mesh::new(gpu, vertices, indices)?)
+        };
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+fn tessellate(shape: &Shape, tolerance: f32) -> Result<VertexBuffers<VectorVertex, u32>> {
+    let mut geometry = VertexBuffers::new();
+    let mut row = 0.0;
+    if let Some(paint) = &shape.style.fill {
+        FillTessellator::new().tessellate_path(
+            &shape.path,
+            &FillOptions::default().with_tolerance(tolerance),
+            &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| {
+                paint.vertex(vertex.position().to_array(), row)
+            }),
+        )?;
+        if paint.stops().is_some() {
+            row += 1.0;
+        }
+    }
+    if let Some(stroke) = &shape.style.stroke {
+        StrokeTessellator::new().tessellate_path(
+            &shape.path,
+            &StrokeOptions::default()
+                .with_tolerance(tolerance)
+                .with_line_width(stroke.width)
+                .with_line_join(stroke.line_join)
+                .with_line_cap(stroke.line_cap),
+            &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| {
+                stroke.paint.vertex(vertex.position().to_array(), row)
+            }),
+        )?;
+    }
+    Ok(geometry)
+}
+
+fn gradient_ramp(stops: &[GradientStop]) -> Vec<u8> {
+    let mut stops = stops.to_vec();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    (0..GRADIENT_WIDTH)
+        .flat_map(|x| {
+            let t = x as f32 / (GRADIENT_WIDTH - 1) as f32;
+            let next = stops.partition_point(|stop| stop.offset <= t);
+            let color = match (stops.get(next.wrapping_sub(1)), stops.get(next)) {
+                (Some(a), Some(b)) => {
+                    let factor = (t - a.offset) / (b.offset - a.offset).max(f32::EPSILON);
+                    std::array::from_fn(|i| a.color[i] + (b.color[i] - a.color[i]) * factor)
+                }
+                (Some(stop), None) | (None, Some(stop)) => stop.color,
+                (None, None) => [0.0; 4],
+            };
+            color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+        })
+        .collect()
+}
@@ -1,56 +1,654 @@
+use crate::core::camera::Camera;
+use crate::core::capture::PendingCapture;
+use crate::core::config::EngineConfig;
+use crate::core::device_selector::DeviceSelector;
 use crate::core::driver::Driver;
+use crate::core::error::{EngineError, Result};
+use crate::core::external_semaphore::ExternalSemaphore;
+use crate::core::frame_stats::{CpuTimings, FrameStats};
 use crate::core::gpu::Gpu;
+use crate::core::hdr::OutputColorSpace;
+use crate::core::picking::{ObjectId, PendingPick};
+use crate::core::render_graph::RenderGraph;
 use crate::core::renderer::{RenderParams, Renderer};
-use crate::core::swapchain_target::SwapchainTarget;
-use std::collections::HashMap;
+use crate::core::swapchain_target::{Acquired, SwapchainSettings, SwapchainTarget};
+#[cfg(feature = "imgui")]
+use crate::graphics::imgui::Imgui;
+use crate::graphics::layout::{WindowLayout, WindowState};
+use crate::graphics::monitors::MonitorInfo;
+use crate::graphics::ui::Ui;
+use crate::input::text::TextInput;
+use image::RgbaImage;
+use std::any::{Any, TypeId};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
+use vulkano::command_buffer::PrimaryAutoCommandBuffer;
+use vulkano::device::DeviceOwned;
+use vulkano::swapchain::{CompositeAlpha, CompositeAlphas, PresentMode};
+use winit::dpi::{PhysicalPosition, PhysicalSize, Size};
+use winit::event::WindowEvent;
 use winit::event_loop::ActiveEventLoop;
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::window::{Fullscreen, Window, WindowAttributes, WindowId};
+
+#[derive(Clone, Copy, Debug)]
+pub struct WindowOptions {
+    pub present_mode: PresentMode,
+    pub image_count: Option<u32>,
+    pub clear_color: Option<[f32; 4]>,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            present_mode: PresentMode::Fifo,
+            image_count: None,
+            clear_color: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FocusEvent {
+    Gained(WindowId),
+    Lost(WindowId),
+}
+
+type WindowHook = Box<dyn FnMut(WindowId)>;
+
+#[derive(Default)]
+struct WindowHooks {
+    close_requested: Option<Box<dyn FnMut(WindowId) -> bool>>,
+    destroyed: Option<WindowHook>,
+    focus: Option<Box<dyn FnMut(WindowId, bool)>>,
+}
+
+pub trait WindowHandler {
+    fn redraw(&mut self, _windows: &mut Windows, _id: WindowId) -> Result<()> {
+        Ok(())
+    }
+
+    fn resized(&mut self, _windows: &mut Windows, _id: WindowId, _size: PhysicalSize<u32>) {}
+
+    fn input(&mut self, _windows: &mut Windows, _id: WindowId, _event: &WindowEvent) {}
+
+    fn close_requested(&mut self, _windows: &mut Windows, _id: WindowId) -> bool {
+        true
+    }
+
+    fn event(&mut self, _windows: &mut Windows, _id: WindowId, _event: &WindowEvent) {}
+}
 
 pub struct Windows {
     children: HashMap<WindowId, Vec<WindowId>>,
+    swapchain_settings: HashMap<WindowId, SwapchainSettings>,
     swapchain_targets: HashMap<WindowId, SwapchainTarget>,
     windows: HashMap<WindowId, Arc<Window>>,
+    order: Vec<WindowId>,
+    focused: Option<WindowId>,
+    activation: Vec<WindowId>,
+    focus_events: Vec<FocusEvent>,
+    #[cfg(feature = "imgui")]
+    imgui: HashMap<WindowId, Imgui>,
+    uis: HashMap<WindowId, Ui>,
+    text_inputs: HashMap<WindowId, TextInput>,
+    hooks: HashMap<WindowId, WindowHooks>,
+    handlers: HashMap<WindowId, Box<dyn WindowHandler>>,
+    user_data: HashMap<WindowId, HashMap<TypeId, Box<dyn Any>>>,
+    created_hook: Option<WindowHook>,
+    window_gpus: HashMap<WindowId, Arc<Gpu>>,
+    secondary_gpus: Vec<Arc<Gpu>>,
+    cpu_timings: CpuTimings,
+    default_options: WindowOptions,
+    vsync: bool,
     pub gpu: Arc<Gpu>,
 }
 
 impl Windows {
-    pub fn new(event_loop: &ActiveEventLoop) -> anyhow::Result<Self> {
-        let driver = Arc::new(Driver::new(event_loop)?);
-        let (physical_device, queue_family_index) = driver.request_device(event_loop).unwrap();
-        let gpu = Arc::new(Gpu::new(driver, physical_device, queue_family_index)?);
+    pub fn new(event_loop: &ActiveEventLoop) -> Result<Self> {
+        Self::with_device_selector(event_loop, &DeviceSelector::default())
+    }
+
+    pub fn with_device_selector(
+        event_loop: &ActiveEventLoop,
+        selector: &DeviceSelector,
+    ) -> Result<Self> {
+        Self::with_driver(event_loop, Arc::new(Driver::new(event_loop)?), selector)
+    }
+
+    pub fn with_config(event_loop: &ActiveEventLoop, config: &EngineConfig) -> Result<Self> {
+        let driver = Arc::new(Driver::with_config(event_loop, config.driver_config())?);
+        let mut windows = Self::with_driver(event_loop, driver, &config.device_selector())?;
+        windows.default_options.image_count = config.image_count();
+        windows.vsync = config.vsync;
+        Ok(windows)
+    }
+
+    fn with_driver(
+        event_loop: &ActiveEventLoop,
+        driver: Arc<Driver>,
+        selector: &DeviceSelector,
+    ) -> Result<Self> {
+        let Some((physical_device, queue_family_index)) =
+            driver.request_device_with(event_loop, selector)
+        else {
+            return Err(EngineError::Unsupported(format!(
+                "no physical device matches {selector:?}"
+            )));
+        };
+        let gpu = Arc::new(Gpu::with_selector(
+            driver,
+            physical_device,
+            queue_family_index,
+            selector,
+        )?);
         Self::from_gpu(gpu)
     }
 
-    pub fn from_gpu(gpu: Arc<Gpu>) -> anyhow::Result<Self> {
+    pub fn from_gpu(gpu: Arc<Gpu>) -> Result<Self> {
         let windows = HashMap::new();
         let swapchain_targets = HashMap::new();
         let children = HashMap::new();
         Ok(Self {
             children,
+            swapchain_settings: HashMap::new(),
             swapchain_targets,
             windows,
+            order: Vec::new(),
+            focused: None,
+            activation: Vec::new(),
+            focus_events: Vec::new(),
+            #[cfg(feature = "imgui")]
+            imgui: HashMap::new(),
+            uis: HashMap::new(),
+            text_inputs: HashMap::new(),
+            hooks: HashMap::new(),
+            handlers: HashMap::new(),
+            user_data: HashMap::new(),
+            created_hook: None,
+            window_gpus: HashMap::new(),
+            secondary_gpus: Vec::new(),
+            cpu_timings: CpuTimings::default(),
+            default_options: WindowOptions::default(),
+            vsync: true,
             gpu,
         })
     }
 
+    pub fn gpus(&self) -> impl Iterator<Item = &Arc<Gpu>> {
+        std::iter::once(&self.gpu).chain(&self.secondary_gpus)
+    }
+
+    pub fn request_gpu(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        selector: &DeviceSelector,
+    ) -> Result<Arc<Gpu>> {
+        let driver = self.gpu.driver().clone();
+        let Some((physical_device, queue_family_index)) =
+            driver.request_device_with(event_loop, selector)
+        else {
+            return Err(EngineError::Unsupported(format!(
+                "no physical device matches {selector:?}"
+            )));
+        };
+        if let Some(gpu) = self.gpus().find(|gpu| {
+            gpu.device().physical_device() == &physical_device
+                && gpu
+                    .device()
+                    .enabled_features()
+                    .contains(selector.required_features())
+        }) {
+            return Ok(gpu.clone());
+        }
+        let gpu = Arc::new(Gpu::with_selector(
+            driver,
+            physical_device,
+            queue_family_index,
+            selector,
+        )?);
+        self.secondary_gpus.push(gpu.clone());
+        Ok(gpu)
+    }
+
+    pub fn window_gpu(&self, id: WindowId) -> Option<&Arc<Gpu>> {
+        self.window_gpus.get(&id)
+    }
+
     pub fn add(
         &mut self,
         event_loop: &ActiveEventLoop,
         window_attributes: WindowAttributes,
-    ) -> anyhow::Result<WindowId> {
+    ) -> Result<WindowId> {
+        self.add_with_gpu(event_loop, window_attributes, self.gpu.clone())
+    }
+
+    pub fn add_with_gpu(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_attributes: WindowAttributes,
+        gpu: Arc<Gpu>,
+    ) -> Result<WindowId> {
+        let id = self.create_window(event_loop, window_attributes, self.default_options, gpu)?;
+        if !self.vsync {
+            self.set_vsync(id, false)?;
+        }
+        Ok(id)
+    }
+
+    pub fn add_with_present_mode(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_attributes: WindowAttributes,
+        present_mode: PresentMode,
+    ) -> Result<WindowId> {
+        let options = WindowOptions {
+            present_mode,
+            ..Default::default()
+        };
+        self.add_with_options(event_loop, window_attributes, options)
+    }
+
+    pub fn add_with_options(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_attributes: WindowAttributes,
+        options: WindowOptions,
+    ) -> Result<WindowId> {
+        self.create_window(event_loop, window_attributes, options, self.gpu.clone())
+    }
+
+    pub fn add_on_monitor(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_attributes: WindowAttributes,
+        monitor: &MonitorInfo,
+    ) -> Result<WindowId> {
+        let id = self.add(
+            event_loop,
+            window_attributes.with_position(monitor.position),
+        )?;
+        self.center_on(id, monitor)?;
+        Ok(id)
+    }
+
+    pub fn add_splash(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        size: impl Into<Size>,
+        transparent: bool,
+    ) -> Result<WindowId> {
+        let size = size.into();
+        let mut window_attributes = WindowAttributes::default()
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_transparent(transparent)
+            .with_inner_size(size);
+        if let Some(monitor) = self
+            .primary_monitor(event_loop)
+            .or_else(|| self.monitors(event_loop).into_iter().next())
+        {
+            let position = monitor.centered(size.to_physical(monitor.scale_factor));
+            window_attributes = window_attributes.with_position(position);
+        }
+        self.add(event_loop, window_attributes)
+    }
+
+    pub fn monitors(&self, event_loop: &ActiveEventLoop) -> Vec<MonitorInfo> {
+        event_loop
+            .available_monitors()
+            .map(MonitorInfo::new)
+            .collect()
+    }
+
+    pub fn primary_monitor(&self, event_loop: &ActiveEventLoop) -> Option<MonitorInfo> {
+        event_loop.primary_monitor().map(MonitorInfo::new)
+    }
+
+    pub fn current_monitor(&self, id: WindowId) -> Option<MonitorInfo> {
+        self.windows
+            .get(&id)?
+            .current_monitor()
+            .map(MonitorInfo::new)
+    }
+
+    pub fn center_on(&self, id: WindowId, monitor: &MonitorInfo) -> Result<()> {
+        let Some(window) = self.windows.get(&id) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        window.set_outer_position(monitor.centered(window.outer_size()));
+        Ok(())
+    }
+
+    pub fn layout(&self) -> WindowLayout {
+        let parents: HashMap<WindowId, WindowId> = self
+            .children
+            .iter()
+            .flat_map(|(&parent, children)| children.iter().map(move |&child| (child, parent)))
+            .collect();
+        let index = |id: &WindowId| self.order.iter().position(|other| other == id);
+        WindowLayout {
+            windows: self
+                .iter()
+                .map(|(id, window)| WindowState::capture(window, parents.get(&id).and_then(index)))
+                .collect(),
+        }
+    }
+
+    pub fn save_layout(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.layout().save(path)
+    }
+
+    pub fn restore_layout(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<WindowId>> {
+        self.apply_layout(event_loop, &WindowLayout::load(path)?)
+    }
+
+    pub fn apply_layout(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        layout: &WindowLayout,
+    ) -> Result<Vec<WindowId>> {
+        let monitors = self.monitors(event_loop);
+        let primary = self.primary_monitor(event_loop);
+        let mut ids = Vec::with_capacity(layout.windows.len());
+        for state in &layout.windows {
+            let monitor = state.monitor(&monitors).or(primary.as_ref());
+            let mut window_attributes = WindowAttributes::default()
+                .with_title(state.title.clone())
+                .with_inner_size(PhysicalSize::<u32>::from(state.size))
+                .with_maximized(state.maximized)
+                .with_fullscreen(state.fullscreen_on(monitor));
+            if let Some(monitor) = monitor {
+                window_attributes = window_attributes.with_position(state.position_on(monitor));
+            }
+            ids.push(self.add(event_loop, window_attributes)?);
+        }
+        for (state, &child) in layout.windows.iter().zip(&ids) {
+            if let Some(&parent) = state.parent.and_then(|parent| ids.get(parent)) {
+                self.add_child(parent, child);
+            }
+        }
+        Ok(ids)
+    }
+
+    fn create_window(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_attributes: WindowAttributes,
+        options: WindowOptions,
+        gpu: Arc<Gpu>,
+    ) -> Result<WindowId> {
+        let settings = SwapchainSettings {
+            present_mode: options.present_mode,
+            transparent: window_attributes.transparent,
+            image_count: options.image_count,
+            clear_color: options.clear_color,
+            ..Default::default()
+        };
+        self.create_window_with_settings(event_loop, window_attributes, settings, gpu)
+    }
+
+    fn create_window_with_settings(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_attributes: WindowAttributes,
+        settings: SwapchainSettings,
+        gpu: Arc<Gpu>,
+    ) -> Result<WindowId> {
         let window = Arc::new(event_loop.create_window(window_attributes)?);
-        let swapchain_target =
-            SwapchainTarget::new(self.gpu.clone(), window.clone(), window.inner_size().into())?;
+        let swapchain_target = SwapchainTarget::new(
+            gpu.clone(),
+            window.clone(),
+            window.inner_size().into(),
+            settings,
+        )?;
         let id = window.id();
         self.windows.insert(id, window);
+        self.order.push(id);
         self.swapchain_targets.insert(id, swapchain_target);
+        self.swapchain_settings.insert(id, settings);
+        self.window_gpus.insert(id, gpu);
+        if let Some(hook) = self.created_hook.as_mut() {
+            hook(id);
+        }
+        Ok(id)
+    }
+
+    pub fn detach<V: 'static>(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        from: WindowId,
+        window_attributes: WindowAttributes,
+        handler: impl WindowHandler + 'static,
+    ) -> Result<WindowId> {
+        let (Some(&settings), Some(gpu)) = (
+            self.swapchain_settings.get(&from),
+            self.window_gpus.get(&from),
+        ) else {
+            return Err(EngineError::WindowGone(from));
+        };
+        if self.data::<V>(from).is_none() {
+            return Err(EngineError::InvalidArgument(format!(
+                "window {from:?} has no {} to detach",
+                std::any::type_name::<V>()
+            )));
+        }
+        let settings = SwapchainSettings {
+            transparent: window_attributes.transparent,
+            ..settings
+        };
+        let id =
+            self.create_window_with_settings(event_loop, window_attributes, settings, gpu.clone())?;
+        if let Some(view) = self
+            .user_data
+            .get_mut(&from)
+            .and_then(|data| data.remove(&TypeId::of::<V>()))
+        {
+            self.user_data
+                .entry(id)
+                .or_default()
+                .insert(TypeId::of::<V>(), view);
+        }
+        self.handlers.insert(id, Box::new(handler));
         Ok(id)
     }
 
+    pub fn on_created(&mut self, hook: impl FnMut(WindowId) + 'static) {
+        self.created_hook = Some(Box::new(hook));
+    }
+
+    pub fn on_close_requested(
+        &mut self,
+        id: WindowId,
+        hook: impl FnMut(WindowId) -> bool + 'static,
+    ) -> Result<()> {
+        self.hooks_mut(id)?.close_requested = Some(Box::new(hook));
+        Ok(())
+    }
+
+    pub fn on_destroyed(
+        &mut self,
+        id: WindowId,
+        hook: impl FnMut(WindowId) + 'static,
+    ) -> Result<()> {
+        self.hooks_mut(id)?.destroyed = Some(Box::new(hook));
+        Ok(())
+    }
+
+    pub fn on_focus(
+        &mut self,
+        id: WindowId,
+        hook: impl FnMut(WindowId, bool) + 'static,
+    ) -> Result<()> {
+        self.hooks_mut(id)?.focus = Some(Box::new(hook));
+        Ok(())
+    }
+
+    fn hooks_mut(&mut self, id: WindowId) -> Result<&mut WindowHooks> {
+        if !self.contains(id) {
+            return Err(EngineError::WindowGone(id));
+        }
+        Ok(self.hooks.entry(id).or_default())
+    }
+
+    pub fn close_requested(&mut self, id: WindowId) -> bool {
+        if !self.contains(id) || !self.can_close(id) {
+            return false;
+        }
+        if let Some(hook) = self
+            .hooks
+            .get_mut(&id)
+            .and_then(|hooks| hooks.close_requested.as_mut())
+            && !hook(id)
+        {
+            return false;
+        }
+        self.remove(id);
+        true
+    }
+
+    pub fn set_handler(
+        &mut self,
+        id: WindowId,
+        handler: impl WindowHandler + 'static,
+    ) -> Result<()> {
+        if !self.contains(id) {
+            return Err(EngineError::WindowGone(id));
+        }
+        self.handlers.insert(id, Box::new(handler));
+        Ok(())
+    }
+
+    pub fn dispatch(&mut self, event: WindowEvent, id: WindowId) -> Result<()> {
+        if !self.contains(id) || self.handle_ui_event(id, &event)? {
+            return Ok(());
+        }
+        if event == WindowEvent::RedrawRequested && !self.swapchain_targets.contains_key(&id) {
+            return Ok(());
+        }
+        let mut handler = self.handlers.remove(&id);
+        let result = self.route(handler.as_mut(), id, &event);
+        if let Some(handler) = handler
+            && self.contains(id)
+        {
+            self.handlers.entry(id).or_insert(handler);
+        }
+        result
+    }
+
+    fn route(
+        &mut self,
+        handler: Option<&mut Box<dyn WindowHandler>>,
+        id: WindowId,
+        event: &WindowEvent,
+    ) -> Result<()> {
+        match event {
+            WindowEvent::RedrawRequested => {
+                if let Some(handler) = handler {
+                    handler.redraw(self, id)?;
+                }
+            }
+            WindowEvent::Resized(size) => {
+                self.resize(id);
+                if let Some(handler) = handler {
+                    handler.resized(self, id, *size);
+                }
+            }
+            WindowEvent::CloseRequested => {
+                if handler.is_none_or(|handler| handler.close_requested(self, id)) {
+                    self.close_requested(id);
+                }
+            }
+            WindowEvent::KeyboardInput { .. }
+            | WindowEvent::ModifiersChanged(_)
+            | WindowEvent::Ime(_)
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::CursorEntered { .. }
+            | WindowEvent::CursorLeft { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::Touch(_) => {
+                if let Some(handler) = handler {
+                    handler.input(self, id, event);
+                }
+            }
+            _ => {
+                if let &WindowEvent::Focused(focused) = event {
+                    self.focus_changed(id, focused);
+                }
+                if let Some(handler) = handler {
+                    handler.event(self, id, event);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn focus_changed(&mut self, id: WindowId, focused: bool) {
+        if !self.contains(id) {
+            return;
+        }
+        if focused {
+            self.focused = Some(id);
+            self.activation.retain(|&window| window != id);
+            self.activation.push(id);
+            self.focus_events.push(FocusEvent::Gained(id));
+        } else {
+            if self.focused == Some(id) {
+                self.focused = None;
+            }
+            self.focus_events.push(FocusEvent::Lost(id));
+        }
+        if let Some(hook) = self
+            .hooks
+            .get_mut(&id)
+            .and_then(|hooks| hooks.focus.as_mut())
+        {
+            hook(id, focused);
+        }
+    }
+
+    pub fn focused(&self) -> Option<WindowId> {
+        self.focused
+    }
+
+    pub fn last_active(&self) -> Option<WindowId> {
+        self.activation.last().copied()
+    }
+
+    pub fn activation_order(&self) -> impl Iterator<Item = WindowId> + '_ {
+        self.activation.iter().rev().copied()
+    }
+
+    pub fn drain_focus_events(&mut self) -> impl Iterator<Item = FocusEvent> + '_ {
+        self.focus_events.drain(..)
+    }
+
     pub fn remove(&mut self, id: WindowId) {
         self.windows.remove(&id);
+        self.order.retain(|&window| window != id);
+        self.activation.retain(|&window| window != id);
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
         self.swapchain_targets.remove(&id);
+        self.swapchain_settings.remove(&id);
+        self.window_gpus.remove(&id);
+        #[cfg(feature = "imgui")]
+        self.imgui.remove(&id);
+        self.uis.remove(&id);
+        self.text_inputs.remove(&id);
+        self.user_data.remove(&id);
+        self.handlers.remove(&id);
+        if let Some(mut destroyed) = self.hooks.remove(&id).and_then(|hooks| hooks.destroyed) {
+            destroyed(id);
+        }
         if let Some(children) = self.children.remove(&id) {
             for child in children {
                 self.remove(child);
@@ -79,6 +677,146 @@ impl Windows {
         self.windows.get(&id)
     }
 
+    pub fn contains(&self, id: WindowId) -> bool {
+        self.windows.contains_key(&id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = WindowId> + '_ {
+        self.order.iter().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (WindowId, &Arc<Window>)> {
+        self.order.iter().map(|&id| (id, &self.windows[&id]))
+    }
+
+    pub fn primary(&self) -> Option<WindowId> {
+        self.order.first().copied()
+    }
+
+    pub fn window_size(&self, id: WindowId) -> Option<PhysicalSize<u32>> {
+        self.windows.get(&id).map(|window| window.inner_size())
+    }
+
+    pub fn insert_data<T: 'static>(&mut self, id: WindowId, value: T) -> Result<Option<T>> {
+        if !self.contains(id) {
+            return Err(EngineError::WindowGone(id));
+        }
+        let previous = self
+            .user_data
+            .entry(id)
+            .or_default()
+            .insert(TypeId::of::<T>(), Box::new(value));
+        Ok(previous
+            .and_then(|data| data.downcast().ok())
+            .map(|data| *data))
+    }
+
+    pub fn data<T: 'static>(&self, id: WindowId) -> Option<&T> {
+        self.user_data
+            .get(&id)?
+            .get(&TypeId::of::<T>())?
+            .downcast_ref()
+    }
+
+    pub fn data_mut<T: 'static>(&mut self, id: WindowId) -> Option<&mut T> {
+        self.user_data
+            .get_mut(&id)?
+            .get_mut(&TypeId::of::<T>())?
+            .downcast_mut()
+    }
+
+    pub fn remove_data<T: 'static>(&mut self, id: WindowId) -> Option<T> {
+        let data = self.user_data.get_mut(&id)?.remove(&TypeId::of::<T>())?;
+        data.downcast().ok().map(|data| *data)
+    }
+
+    #[cfg(feature = "imgui")]
+    pub fn imgui(&mut self, id: WindowId) -> Result<Option<&mut Imgui>> {
+        let Some(window) = self.windows.get(&id) else {
+            return Ok(None);
+        };
+        let imgui = match self.imgui.entry(id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(Imgui::new(self.window_gpus[&id].clone(), window)?)
+            }
+        };
+        Ok(Some(imgui))
+    }
+
+    pub fn ui(&mut self, id: WindowId) -> Result<Option<&mut Ui>> {
+        let Some(window) = self.windows.get(&id) else {
+            return Ok(None);
+        };
+        let ui = match self.uis.entry(id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let size = window.inner_size();
+                let mut ui = Ui::new([size.width as f32, size.height as f32])?;
+                ui.set_scale_factor(window.scale_factor() as f32);
+                entry.insert(ui)
+            }
+        };
+        Ok(Some(ui))
+    }
+
+    pub fn handle_ui_event(&mut self, id: WindowId, event: &WindowEvent) -> Result<bool> {
+        if let WindowEvent::ScaleFactorChanged { .. } = event
+            && let Some(swapchain_target) = self.swapchain_targets.get_mut(&id)
+        {
+            swapchain_target.resize();
+        }
+        if let Some(text_input) = self.text_inputs.get_mut(&id)
+            && text_input.handle_event(event)
+        {
+            return Ok(true);
+        }
+        match self.uis.get_mut(&id) {
+            Some(ui) => ui.handle_event(event),
+            None => Ok(false),
+        }
+    }
+
+    pub fn start_text_input(&mut self, id: WindowId) -> Result<&mut TextInput> {
+        let Some(window) = self.windows.get(&id) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        let text_input = self.text_inputs.entry(id).or_default();
+        text_input.start(window);
+        Ok(text_input)
+    }
+
+    pub fn stop_text_input(&mut self, id: WindowId) {
+        if let (Some(window), Some(text_input)) =
+            (self.windows.get(&id), self.text_inputs.get_mut(&id))
+        {
+            text_input.stop(window);
+        }
+    }
+
+    pub fn text_input(&mut self, id: WindowId) -> Option<&mut TextInput> {
+        self.text_inputs.get_mut(&id)
+    }
+
+    pub fn set_ime_caret_area(&self, id: WindowId, position: [f32; 2], size: [f32; 2]) {
+        if let (Some(window), Some(text_input)) = (self.windows.get(&id), self.text_inputs.get(&id))
+        {
+            text_input.set_caret_area(window, position, size);
+        }
+    }
+
+    pub fn scale_factor(&self, id: WindowId) -> Option<f64> {
+        self.windows.get(&id).map(|window| window.scale_factor())
+    }
+
+    pub fn screen_camera(&self, id: WindowId) -> Option<Camera> {
+        let window = self.windows.get(&id)?;
+        Some(Camera::screen(
+            window.inner_size().into(),
+            window.scale_factor(),
+        ))
+    }
+
     pub fn can_close(&self, id: WindowId) -> bool {
         if let Some(children) = self.children.get(&id) {
             for &child in children {
@@ -95,31 +833,387 @@ impl Windows {
         id: WindowId,
         renderer: &Renderer,
         render_params: RenderParams<Vertex>,
-    ) -> anyhow::Result<()> {
-        let swapchain_target = self.swapchain_targets.get_mut(&id).unwrap();
-        let window = self.windows.get(&id).unwrap();
-        if let Some(acquired) = swapchain_target.try_acquire_image(window.inner_size().into())? {
-            let command_buffer = renderer.render(acquired.image_view.clone(), render_params)?;
-            swapchain_target.present(acquired, command_buffer)?;
+    ) -> Result<()> {
+        if let Some((acquired, command_buffer)) = self.record_frame(id, renderer, render_params)? {
+            let submit_start = Instant::now();
+            let presented = self
+                .swapchain_targets
+                .get_mut(&id)
+                .unwrap()
+                .present(acquired, command_buffer);
+            self.cpu_timings.submit += submit_start.elapsed();
+            presented?;
         }
         Ok(())
     }
 
+    pub fn render_all<Vertex>(
+        &mut self,
+        renderer: &Renderer,
+        frames: impl IntoIterator<Item = (WindowId, RenderParams<Vertex>)>,
+    ) -> Result<()> {
+        let frames: Vec<_> = frames.into_iter().collect();
+        let mut ids = HashSet::new();
+        if let Some((id, _)) = frames.iter().find(|(id, _)| !ids.insert(*id)) {
+            return Err(EngineError::InvalidArgument(format!(
+                "window {id:?} is rendered more than once in the same batch"
+            )));
+        }
+        let mut recorded = HashMap::new();
+        let mut result = Ok(());
+        for (id, render_params) in frames {
+            match self.record_frame(id, renderer, render_params) {
+                Ok(Some(frame)) => {
+                    recorded.insert(id, frame);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        if recorded.is_empty() {
+            return result;
+        }
+        let batch = self
+            .swapchain_targets
+            .iter_mut()
+            .filter_map(|(id, swapchain_target)| {
+                let (acquired, command_buffer) = recorded.remove(id)?;
+                Some((swapchain_target, acquired, command_buffer))
+            })
+            .collect();
+        let submit_start = Instant::now();
+        let presented = SwapchainTarget::present_all(renderer.gpu(), batch);
+        self.cpu_timings.submit += submit_start.elapsed();
+        result.and(presented)
+    }
+
+    #[tracing::instrument(name = "frame", level = "trace", skip(self, renderer, render_params))]
+    fn record_frame<Vertex>(
+        &mut self,
+        id: WindowId,
+        renderer: &Renderer,
+        mut render_params: RenderParams<Vertex>,
+    ) -> Result<Option<(Acquired, Arc<PrimaryAutoCommandBuffer>)>> {
+        let (Some(swapchain_target), Some(window), Some(gpu)) = (
+            self.swapchain_targets.get_mut(&id),
+            self.windows.get(&id),
+            self.window_gpus.get(&id),
+        ) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        if !Arc::ptr_eq(renderer.gpu().device(), gpu.device()) {
+            return Err(EngineError::InvalidArgument(format!(
+                "window {id:?} is presented by {}, but the renderer was created on {}",
+                gpu.adapter_info().name,
+                renderer.gpu().adapter_info().name
+            )));
+        }
+        if let Some(clear_color) = self.swapchain_settings[&id].clear_color {
+            render_params.clear_color = clear_color;
+        }
+        let acquire_start = Instant::now();
+        let acquired = swapchain_target.try_acquire_image(window.inner_size().into());
+        self.cpu_timings.present_wait += acquire_start.elapsed();
+        let Some(acquired) = acquired? else {
+            return Ok(None);
+        };
+        let record_start = Instant::now();
+        let mut graph = RenderGraph::new();
+        let target = graph.import(acquired.image_view.clone());
+        if let Some(position) = swapchain_target.pick_requested.take() {
+            swapchain_target.pending_pick =
+                Some(renderer.add_picking_pass(&mut graph, target, &render_params, position)?);
+        }
+        renderer.add_passes(&mut graph, target, render_params)?;
+        if std::mem::take(&mut swapchain_target.capture_requested) {
+            swapchain_target.pending_capture = Some(PendingCapture::record(
+                gpu,
+                &mut graph,
+                target,
+                swapchain_target.image_format(),
+                swapchain_target.image_usage(),
+            )?);
+        }
+        let command_buffer = renderer.execute(graph)?;
+        self.cpu_timings.record += record_start.elapsed();
+        Ok(Some((acquired, command_buffer)))
+    }
+
+    pub fn request_capture(&mut self, id: WindowId) {
+        if let Some(swapchain_target) = self.swapchain_targets.get_mut(&id) {
+            swapchain_target.capture_requested = true;
+        }
+    }
+
+    pub fn take_capture(&mut self, id: WindowId) -> Result<Option<RgbaImage>> {
+        let Some(swapchain_target) = self.swapchain_targets.get_mut(&id) else {
+            return Ok(None);
+        };
+        let Some(capture) = swapchain_target.pending_capture.take() else {
+            return Ok(None);
+        };
+        swapchain_target.wait()?;
+        Ok(Some(capture.into_image()?))
+    }
+
+    pub fn capture<Vertex>(
+        &mut self,
+        id: WindowId,
+        renderer: &Renderer,
+        render_params: RenderParams<Vertex>,
+    ) -> Result<Option<RgbaImage>> {
+        self.request_capture(id);
+        self.redraw(id, renderer, render_params)?;
+        self.take_capture(id)
+    }
+
+    pub fn pick(&mut self, id: WindowId, cursor_pos: PhysicalPosition<f64>) -> Option<ObjectId> {
+        let size = self.windows.get(&id)?.inner_size();
+        let swapchain_target = self.swapchain_targets.get_mut(&id)?;
+        if let Some(picked) = swapchain_target
+            .pending_pick
+            .as_ref()
+            .and_then(PendingPick::try_resolve)
+        {
+            swapchain_target.picked = picked;
+            swapchain_target.pending_pick = None;
+        }
+        let inside = (0.0..size.width as f64).contains(&cursor_pos.x)
+            && (0.0..size.height as f64).contains(&cursor_pos.y);
+        if inside {
+            swapchain_target.pick_requested = Some([cursor_pos.x as u32, cursor_pos.y as u32]);
+        } else {
+            swapchain_target.pick_requested = None;
+            swapchain_target.picked = None;
+        }
+        swapchain_target.picked
+    }
+
     pub fn request_redraw(&self) {
         for window in self.windows.values() {
             window.request_redraw();
         }
     }
 
+    pub fn present_mode(&self, id: WindowId) -> Option<PresentMode> {
+        self.swapchain_settings
+            .get(&id)
+            .map(|settings| settings.present_mode)
+    }
+
+    pub fn supported_present_modes(&self, id: WindowId) -> Result<Vec<PresentMode>> {
+        match self.swapchain_targets.get(&id) {
+            Some(swapchain_target) => swapchain_target.present_modes(),
+            None => Err(EngineError::WindowGone(id)),
+        }
+    }
+
+    pub fn set_present_mode(&mut self, id: WindowId, present_mode: PresentMode) -> Result<()> {
+        let Some(swapchain_target) = self.swapchain_targets.get_mut(&id) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        swapchain_target.set_present_mode(present_mode)?;
+        if let Some(settings) = self.swapchain_settings.get_mut(&id) {
+            settings.present_mode = present_mode;
+        }
+        Ok(())
+    }
+
+    pub fn set_vsync(&mut self, id: WindowId, vsync: bool) -> Result<()> {
+        let present_mode = if vsync {
+            PresentMode::Fifo
+        } else {
+            let supported = self.supported_present_modes(id)?;
+            [PresentMode::Mailbox, PresentMode::Immediate]
+                .into_iter()
+                .find(|mode| supported.contains(mode))
+                .unwrap_or(PresentMode::Fifo)
+        };
+        self.set_present_mode(id, present_mode)
+    }
+
+    pub fn image_count(&self, id: WindowId) -> Option<u32> {
+        self.swapchain_targets
+            .get(&id)
+            .map(SwapchainTarget::image_count)
+    }
+
+    pub fn set_image_count(&mut self, id: WindowId, image_count: Option<u32>) -> Result<()> {
+        let Some(swapchain_target) = self.swapchain_targets.get_mut(&id) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        swapchain_target.set_image_count(image_count)?;
+        if let Some(settings) = self.swapchain_settings.get_mut(&id) {
+            settings.image_count = image_count;
+        }
+        Ok(())
+    }
+
+    pub fn clear_color(&self, id: WindowId) -> Option<[f32; 4]> {
+        self.swapchain_settings
+            .get(&id)
+            .and_then(|settings| settings.clear_color)
+    }
+
+    pub fn set_clear_color(&mut self, id: WindowId, clear_color: Option<[f32; 4]>) -> Result<()> {
+        let Some(settings) = self.swapchain_settings.get_mut(&id) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        settings.clear_color = clear_color;
+        Ok(())
+    }
+
+    pub fn output_color_space(&self, id: WindowId) -> Option<OutputColorSpace> {
+        self.swapchain_settings
+            .get(&id)
+            .map(|settings| settings.color_space)
+    }
+
+    pub fn supported_output_color_spaces(&self, id: WindowId) -> Result<Vec<OutputColorSpace>> {
+        match self.swapchain_targets.get(&id) {
+            Some(swapchain_target) => swapchain_target.output_color_spaces(),
+            None => Err(EngineError::WindowGone(id)),
+        }
+    }
+
+    pub fn set_output_color_space(
+        &mut self,
+        id: WindowId,
+        color_space: OutputColorSpace,
+    ) -> Result<()> {
+        let Some(swapchain_target) = self.swapchain_targets.get_mut(&id) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        swapchain_target.set_output_color_space(color_space)?;
+        if let Some(settings) = self.swapchain_settings.get_mut(&id) {
+            settings.color_space = color_space;
+        }
+        Ok(())
+    }
+
+    pub fn composite_alpha(&self, id: WindowId) -> Option<CompositeAlpha> {
+        self.swapchain_targets
+            .get(&id)
+            .map(SwapchainTarget::composite_alpha)
+    }
+
+    pub fn supported_composite_alpha(&self, id: WindowId) -> Result<CompositeAlphas> {
+        match self.swapchain_targets.get(&id) {
+            Some(swapchain_target) => swapchain_target.supported_composite_alpha(),
+            None => Err(EngineError::WindowGone(id)),
+        }
+    }
+
+    pub fn set_composite_alpha(
+        &mut self,
+        id: WindowId,
+        composite_alpha: CompositeAlpha,
+    ) -> Result<()> {
+        let Some(swapchain_target) = self.swapchain_targets.get_mut(&id) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        swapchain_target.set_composite_alpha(composite_alpha)?;
+        if let Some(settings) = self.swapchain_settings.get_mut(&id) {
+            settings.composite_alpha = Some(composite_alpha);
+        }
+        Ok(())
+    }
+
+    fn external_semaphore_target(
+        &mut self,
+        id: WindowId,
+        semaphore: &ExternalSemaphore,
+    ) -> Result<&mut SwapchainTarget> {
+        let (Some(swapchain_target), Some(gpu)) = (
+            self.swapchain_targets.get_mut(&id),
+            self.window_gpus.get(&id),
+        ) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        if !Arc::ptr_eq(semaphore.semaphore().device(), gpu.device()) {
+            return Err(EngineError::InvalidArgument(format!(
+                "the semaphore belongs to a different device than window {id:?}"
+            )));
+        }
+        Ok(swapchain_target)
+    }
+
+    pub fn wait_semaphore(&mut self, id: WindowId, semaphore: &ExternalSemaphore) -> Result<()> {
+        let swapchain_target = self.external_semaphore_target(id, semaphore)?;
+        swapchain_target
+            .wait_semaphores
+            .push(semaphore.semaphore().clone());
+        Ok(())
+    }
+
+    pub fn signal_semaphore(&mut self, id: WindowId, semaphore: &ExternalSemaphore) -> Result<()> {
+        let swapchain_target = self.external_semaphore_target(id, semaphore)?;
+        swapchain_target
+            .signal_semaphores
+            .push(semaphore.semaphore().clone());
+        Ok(())
+    }
+
+    fn reconfigure(&mut self, id: WindowId, configure: impl FnOnce(&Window)) -> Result<()> {
+        let Some(window) = self.windows.get(&id) else {
+            return Err(EngineError::WindowGone(id));
+        };
+        configure(window);
+        if let Some(swapchain_target) = self.swapchain_targets.get_mut(&id) {
+            swapchain_target.resize();
+        }
+        Ok(())
+    }
+
+    pub fn fullscreen(&self, id: WindowId) -> Option<Fullscreen> {
+        self.windows.get(&id)?.fullscreen()
+    }
+
+    pub fn set_fullscreen(&mut self, id: WindowId, fullscreen: Option<Fullscreen>) -> Result<()> {
+        self.reconfigure(id, |window| window.set_fullscreen(fullscreen))
+    }
+
+    pub fn set_decorations(&mut self, id: WindowId, decorations: bool) -> Result<()> {
+        self.reconfigure(id, |window| window.set_decorations(decorations))
+    }
+
+    pub fn set_resizable(&mut self, id: WindowId, resizable: bool) -> Result<()> {
+        self.reconfigure(id, |window| window.set_resizable(resizable))
+    }
+
+    pub fn set_min_inner_size(&mut self, id: WindowId, min_size: Option<Size>) -> Result<()> {
+        self.reconfigure(id, |window| window.set_min_inner_size(min_size))
+    }
+
+    pub fn frame_stats(&self, id: WindowId) -> Option<FrameStats> {
+        self.swapchain_targets
+            .get(&id)
+            .map(SwapchainTarget::frame_stats)
+    }
+
+    pub(crate) fn take_cpu_timings(&mut self) -> CpuTimings {
+        std::mem::take(&mut self.cpu_timings)
+    }
+
     pub fn resize(&mut self, id: WindowId) {
-        self.swapchain_targets.get_mut(&id).unwrap().resize();
+        if let Some(swapchain_target) = self.swapchain_targets.get_mut(&id) {
+            swapchain_target.resize();
+        }
     }
 
-    pub fn resume(&mut self) -> anyhow::Result<()> {
+    pub fn resume(&mut self) -> Result<()> {
         for (id, window) in &self.windows {
             self.swapchain_targets.insert(
                 *id,
-                SwapchainTarget::new(self.gpu.clone(), window.clone(), window.inner_size().into())?,
+                SwapchainTarget::new(
+                    self.window_gpus[id].clone(),
+                    window.clone(),
+                    window.inner_size().into(),
+                    self.swapchain_settings[id],
+                )?,
             );
         }
         Ok(())
@@ -0,0 +1,203 @@
+use crate::core::error::Result;
+use crate::input::gamepad::{GamepadAxis, GamepadButton};
+use crate::input::manager::Input;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use winit::event::MouseButton;
+use winit::keyboard::{KeyCode, ModifiersState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    fn pressed(self, input: &Input) -> bool {
+        match self {
+            Binding::Key(key) => input.pressed(key),
+            Binding::Mouse(button) => input.mouse_pressed(button),
+            Binding::Gamepad(button) => input.gamepad_pressed(button),
+        }
+    }
+
+    fn just_pressed(self, input: &Input) -> bool {
+        match self {
+            Binding::Key(key) => input.just_pressed(key),
+            Binding::Mouse(button) => input.mouse_just_pressed(button),
+            Binding::Gamepad(button) => input.gamepad_just_pressed(button),
+        }
+    }
+
+    fn just_released(self, input: &Input) -> bool {
+        match self {
+            Binding::Key(key) => input.just_released(key),
+            Binding::Mouse(button) => input.mouse_just_released(button),
+            Binding::Gamepad(button) => input.gamepad_just_released(button),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionBinding {
+    pub input: Binding,
+    #[serde(default, skip_serializing_if = "ModifiersState::is_empty")]
+    pub modifiers: ModifiersState,
+}
+
+impl ActionBinding {
+    pub fn new(input: Binding) -> Self {
+        Self {
+            input,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    pub fn with_modifiers(mut self, modifiers: ModifiersState) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    fn modifiers_held(&self, input: &Input) -> bool {
+        input.modifiers().contains(self.modifiers)
+    }
+}
+
+impl From<Binding> for ActionBinding {
+    fn from(input: Binding) -> Self {
+        Self::new(input)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AxisBinding {
+    Buttons {
+        negative: Binding,
+        positive: Binding,
+    },
+    Gamepad {
+        axis: GamepadAxis,
+        #[serde(default)]
+        dead_zone: f32,
+    },
+    MouseMotion {
+        vertical: bool,
+        scale: f32,
+    },
+    Scroll {
+        vertical: bool,
+    },
+}
+
+impl AxisBinding {
+    fn value(self, input: &Input) -> f32 {
+        match self {
+            AxisBinding::Buttons { negative, positive } => {
+                positive.pressed(input) as i32 as f32 - negative.pressed(input) as i32 as f32
+            }
+            AxisBinding::Gamepad { axis, dead_zone } => {
+                let value = input.gamepad_axis(axis);
+                if value.abs() <= dead_zone {
+                    0.0
+                } else {
+                    value
+                }
+            }
+            AxisBinding::MouseMotion { vertical, scale } => {
+                input.mouse_delta()[vertical as usize] * scale
+            }
+            AxisBinding::Scroll { vertical } => input.scroll_delta()[vertical as usize],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActionMap {
+    #[serde(default)]
+    actions: BTreeMap<String, Vec<ActionBinding>>,
+    #[serde(default)]
+    axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self> {
+        Ok(toml::from_str(source)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(std::fs::write(path, self.to_toml()?)?)
+    }
+
+    pub fn bind(&mut self, action: impl Into<String>, binding: impl Into<ActionBinding>) {
+        self.actions
+            .entry(action.into())
+            .or_default()
+            .push(binding.into());
+    }
+
+    pub fn bind_axis(&mut self, axis: impl Into<String>, binding: AxisBinding) {
+        self.axes.entry(axis.into()).or_default().push(binding);
+    }
+
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+        self.axes.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[ActionBinding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn pressed(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.modifiers_held(input) && binding.input.pressed(input))
+    }
+
+    pub fn just_pressed(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.modifiers_held(input) && binding.input.just_pressed(input))
+    }
+
+    pub fn just_released(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.input.just_released(input))
+    }
+
+    pub fn axis(&self, input: &Input, axis: &str) -> f32 {
+        self.axis_bindings(axis)
+            .iter()
+            .map(|binding| binding.value(input))
+            .sum()
+    }
+
+    pub fn axis_pair(&self, input: &Input, x: &str, y: &str) -> [f32; 2] {
+        let [x, y] = [self.axis(input, x), self.axis(input, y)];
+        let length = (x * x + y * y).sqrt();
+        if length > 1.0 {
+            [x / length, y / length]
+        } else {
+            [x, y]
+        }
+    }
+}
@@ -0,0 +1,495 @@
+use crate::core::error::Result;
+use crate::input::gamepad::{GamepadAxis, GamepadButton};
+use crate::input::recording::{InputEvent, InputPlayback, InputRecorder, RecordedEvent};
+use crate::input::touch::{Gesture, TouchTracker};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use winit::event::{DeviceEvent, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{Key, KeyCode, ModifiersState, PhysicalKey};
+use winit::window::{CursorGrabMode, Window, WindowId};
+
+const PIXELS_PER_LINE: f32 = 20.0;
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+const DOUBLE_CLICK_DISTANCE: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug)]
+struct Click {
+    window: WindowId,
+    button: MouseButton,
+    position: [f32; 2],
+    time: Duration,
+}
+
+#[derive(Debug)]
+struct ButtonState<T> {
+    down: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+    pending_down: HashSet<T>,
+    pending_pressed: HashSet<T>,
+    pending_released: HashSet<T>,
+}
+
+impl<T> Default for ButtonState<T> {
+    fn default() -> Self {
+        Self {
+            down: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            pending_down: HashSet::new(),
+            pending_pressed: HashSet::new(),
+            pending_released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> ButtonState<T> {
+    fn press(&mut self, button: T) {
+        if self.pending_down.insert(button.clone()) {
+            self.pending_pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: T) {
+        if self.pending_down.remove(&button) {
+            self.pending_released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        for button in std::mem::take(&mut self.pending_down) {
+            self.pending_released.insert(button);
+        }
+    }
+
+    fn latch(&mut self) {
+        self.down.clone_from(&self.pending_down);
+        self.pressed = std::mem::take(&mut self.pending_pressed);
+        self.released = std::mem::take(&mut self.pending_released);
+    }
+}
+
+#[derive(Default)]
+pub struct Input {
+    keys: ButtonState<KeyCode>,
+    logical_keys: ButtonState<Key>,
+    held_logical_keys: HashMap<KeyCode, Key>,
+    pending_modifiers: ModifiersState,
+    modifiers: ModifiersState,
+    mouse_buttons: ButtonState<MouseButton>,
+    pending_cursor_positions: HashMap<WindowId, [f32; 2]>,
+    cursor_positions: HashMap<WindowId, [f32; 2]>,
+    pending_scroll: [f32; 2],
+    scroll: [f32; 2],
+    last_click: Option<Click>,
+    pending_double_clicks: HashSet<MouseButton>,
+    double_clicks: HashSet<MouseButton>,
+    touch: TouchTracker,
+    gestures: Vec<Gesture>,
+    gamepad_buttons: ButtonState<GamepadButton>,
+    pending_gamepad_axes: HashMap<GamepadAxis, f32>,
+    gamepad_axes: HashMap<GamepadAxis, f32>,
+    epoch: Option<Instant>,
+    frame: u64,
+    recorder: Option<InputRecorder>,
+    playback: Option<InputPlayback>,
+    playback_offset: u64,
+    relative_mouse: bool,
+    pending_mouse_delta: [f64; 2],
+    mouse_delta: [f32; 2],
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle_window_event(&mut self, id: WindowId, event: &WindowEvent) {
+        let window = u64::from(id);
+        let event = match event {
+            WindowEvent::KeyboardInput { event, .. } => InputEvent::Key {
+                code: match event.physical_key {
+                    PhysicalKey::Code(code) => Some(code),
+                    PhysicalKey::Unidentified(_) => None,
+                },
+                logical: event.logical_key.clone(),
+                pressed: event.state.is_pressed(),
+            },
+            WindowEvent::CursorMoved { position, .. } => InputEvent::CursorMoved {
+                window,
+                position: [position.x as f32, position.y as f32],
+            },
+            WindowEvent::CursorLeft { .. } => InputEvent::CursorLeft { window },
+            WindowEvent::MouseInput { state, button, .. } => InputEvent::MouseButton {
+                window,
+                button: *button,
+                pressed: state.is_pressed(),
+            },
+            WindowEvent::MouseWheel { delta, .. } => InputEvent::Scroll {
+                delta: match delta {
+                    MouseScrollDelta::LineDelta(x, y) => [*x, *y],
+                    MouseScrollDelta::PixelDelta(delta) => [
+                        delta.x as f32 / PIXELS_PER_LINE,
+                        delta.y as f32 / PIXELS_PER_LINE,
+                    ],
+                },
+            },
+            WindowEvent::Touch(touch) => InputEvent::Touch {
+                id: touch.id,
+                phase: touch.phase,
+                position: [touch.location.x as f32, touch.location.y as f32],
+            },
+            WindowEvent::ModifiersChanged(modifiers) => InputEvent::Modifiers(modifiers.state()),
+            WindowEvent::Focused(false) => InputEvent::FocusLost,
+            _ => return,
+        };
+        self.push_live(event);
+    }
+
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event
+            && self.relative_mouse
+        {
+            self.push_live(InputEvent::MouseMotion { delta: [*x, *y] });
+        }
+    }
+
+    pub fn set_gamepad_button(&mut self, button: GamepadButton, pressed: bool) {
+        self.push_live(InputEvent::GamepadButton { button, pressed });
+    }
+
+    pub fn set_gamepad_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.push_live(InputEvent::GamepadAxis { axis, value });
+    }
+
+    pub fn start_recording(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.stop_recording()?;
+        self.recorder = Some(InputRecorder::create(path)?);
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) -> Result<()> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn start_playback(
+        &mut self,
+        path: impl AsRef<Path>,
+        windows: impl IntoIterator<Item = WindowId>,
+    ) -> Result<()> {
+        let mut playback = InputPlayback::load(path, windows)?;
+        if let Some(first) = playback.next(u64::MAX) {
+            self.frame = first.frame;
+            self.playback_offset = first.time_us;
+            self.playback = Some(playback);
+            self.apply(first.event, Duration::ZERO);
+        }
+        Ok(())
+    }
+
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+    }
+
+    pub fn is_playing_back(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    fn now(&mut self) -> Duration {
+        self.epoch.get_or_insert_with(Instant::now).elapsed()
+    }
+
+    fn push_live(&mut self, event: InputEvent) {
+        if self.playback.is_some() {
+            return;
+        }
+        let time = self.now();
+        if let Some(recorder) = self.recorder.as_mut() {
+            let recorded = RecordedEvent {
+                frame: self.frame,
+                time_us: time.as_micros() as u64,
+                event: event.clone(),
+            };
+            if let Err(error) = recorder.write(&recorded) {
+                log::warn!("failed to record input event: {error}");
+                self.recorder = None;
+            }
+        }
+        self.apply(event, time);
+    }
+
+    fn apply(&mut self, event: InputEvent, time: Duration) {
+        match event {
+            InputEvent::Key {
+                code,
+                logical,
+                pressed,
+            } => self.apply_key(code, logical, pressed),
+            InputEvent::Modifiers(modifiers) => self.pending_modifiers = modifiers,
+            InputEvent::CursorMoved { window, position } => {
+                let window = self.window(window);
+                self.pending_cursor_positions.insert(window, position);
+            }
+            InputEvent::CursorLeft { window } => {
+                let window = self.window(window);
+                self.pending_cursor_positions.remove(&window);
+            }
+            InputEvent::MouseButton {
+                window,
+                button,
+                pressed,
+            } => {
+                if pressed {
+                    let window = self.window(window);
+                    self.mouse_buttons.press(button);
+                    self.register_click(window, button, time);
+                } else {
+                    self.mouse_buttons.release(button);
+                }
+            }
+            InputEvent::Scroll { delta } => {
+                self.pending_scroll[0] += delta[0];
+                self.pending_scroll[1] += delta[1];
+            }
+            InputEvent::Touch {
+                id,
+                phase,
+                position,
+            } => self.touch.handle(id, phase, position.into(), time),
+            InputEvent::FocusLost => {
+                self.keys.release_all();
+                self.logical_keys.release_all();
+                self.held_logical_keys.clear();
+                self.pending_modifiers = ModifiersState::empty();
+                self.mouse_buttons.release_all();
+                self.touch.clear();
+            }
+            InputEvent::MouseMotion { delta } => {
+                self.pending_mouse_delta[0] += delta[0];
+                self.pending_mouse_delta[1] += delta[1];
+            }
+            InputEvent::GamepadButton { button, pressed } => {
+                if pressed {
+                    self.gamepad_buttons.press(button);
+                } else {
+                    self.gamepad_buttons.release(button);
+                }
+            }
+            InputEvent::GamepadAxis { axis, value } => {
+                self.pending_gamepad_axes
+                    .insert(axis, value.clamp(-1.0, 1.0));
+            }
+        }
+    }
+
+    fn window(&mut self, recorded: u64) -> WindowId {
+        match self.playback.as_mut() {
+            Some(playback) => playback.window(recorded),
+            None => WindowId::from(recorded),
+        }
+    }
+
+    fn apply_key(&mut self, code: Option<KeyCode>, logical: Key, pressed: bool) {
+        if pressed {
+            if let Some(code) = code {
+                self.keys.press(code);
+                self.held_logical_keys.insert(code, logical.clone());
+            }
+            self.logical_keys.press(logical);
+        } else {
+            let logical = code
+                .and_then(|code| {
+                    self.keys.release(code);
+                    self.held_logical_keys.remove(&code)
+                })
+                .unwrap_or(logical);
+            self.logical_keys.release(logical);
+        }
+    }
+
+    fn register_click(&mut self, window: WindowId, button: MouseButton, time: Duration) {
+        let position = self
+            .pending_cursor_positions
+            .get(&window)
+            .copied()
+            .unwrap_or_default();
+        let double_click = self.last_click.is_some_and(|last| {
+            let [dx, dy] = [
+                position[0] - last.position[0],
+                position[1] - last.position[1],
+            ];
+            last.window == window
+                && last.button == button
+                && time.saturating_sub(last.time) <= DOUBLE_CLICK_TIME
+                && dx * dx + dy * dy <= DOUBLE_CLICK_DISTANCE * DOUBLE_CLICK_DISTANCE
+        });
+        if double_click {
+            self.pending_double_clicks.insert(button);
+            self.last_click = None;
+        } else {
+            self.last_click = Some(Click {
+                window,
+                button,
+                position,
+                time,
+            });
+        }
+    }
+
+    pub fn begin_frame(&mut self) {
+        let frame = self.frame;
+        while let Some(recorded) = self
+            .playback
+            .as_mut()
+            .and_then(|playback| playback.next(frame))
+        {
+            let time = Duration::from_micros(recorded.time_us.saturating_sub(self.playback_offset));
+            self.apply(recorded.event, time);
+        }
+        if self
+            .playback
+            .as_ref()
+            .is_some_and(InputPlayback::is_finished)
+        {
+            self.playback = None;
+        }
+        self.frame += 1;
+        self.keys.latch();
+        self.logical_keys.latch();
+        self.modifiers = self.pending_modifiers;
+        self.mouse_buttons.latch();
+        self.cursor_positions
+            .clone_from(&self.pending_cursor_positions);
+        self.scroll = std::mem::take(&mut self.pending_scroll);
+        self.double_clicks = std::mem::take(&mut self.pending_double_clicks);
+        self.gestures = self.touch.take_gestures();
+        self.gamepad_buttons.latch();
+        self.gamepad_axes.clone_from(&self.pending_gamepad_axes);
+        let [x, y] = std::mem::take(&mut self.pending_mouse_delta);
+        self.mouse_delta = [x as f32, y as f32];
+    }
+
+    pub fn pressed(&self, key: KeyCode) -> bool {
+        self.keys.down.contains(&key)
+    }
+
+    pub fn just_pressed(&self, key: KeyCode) -> bool {
+        self.keys.pressed.contains(&key)
+    }
+
+    pub fn just_released(&self, key: KeyCode) -> bool {
+        self.keys.released.contains(&key)
+    }
+
+    pub fn pressed_keys(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys.down.iter().copied()
+    }
+
+    pub fn just_pressed_keys(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys.pressed.iter().copied()
+    }
+
+    pub fn just_released_keys(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys.released.iter().copied()
+    }
+
+    pub fn logical_pressed(&self, key: &Key) -> bool {
+        self.logical_keys.down.contains(key)
+    }
+
+    pub fn logical_just_pressed(&self, key: &Key) -> bool {
+        self.logical_keys.pressed.contains(key)
+    }
+
+    pub fn logical_just_released(&self, key: &Key) -> bool {
+        self.logical_keys.released.contains(key)
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.down.contains(&button)
+    }
+
+    pub fn mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.pressed.contains(&button)
+    }
+
+    pub fn mouse_just_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.released.contains(&button)
+    }
+
+    pub fn double_clicked(&self, button: MouseButton) -> bool {
+        self.double_clicks.contains(&button)
+    }
+
+    pub fn cursor_position(&self, id: WindowId) -> Option<[f32; 2]> {
+        self.cursor_positions.get(&id).copied()
+    }
+
+    pub fn scroll_delta(&self) -> [f32; 2] {
+        self.scroll
+    }
+
+    pub fn touches(&self) -> impl Iterator<Item = (u64, [f32; 2])> + '_ {
+        self.touch.touches()
+    }
+
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    pub fn gamepad_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepad_buttons.down.contains(&button)
+    }
+
+    pub fn gamepad_just_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepad_buttons.pressed.contains(&button)
+    }
+
+    pub fn gamepad_just_released(&self, button: GamepadButton) -> bool {
+        self.gamepad_buttons.released.contains(&button)
+    }
+
+    pub fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepad_axes.get(&axis).copied().unwrap_or_default()
+    }
+
+    pub fn mouse_delta(&self) -> [f32; 2] {
+        self.mouse_delta
+    }
+
+    pub fn is_relative_mouse(&self) -> bool {
+        self.relative_mouse
+    }
+
+    pub fn set_relative_mouse(&mut self, window: &Window, enabled: bool) -> Result<()> {
+        if enabled {
+            window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))?;
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)?;
+        }
+        window.set_cursor_visible(!enabled);
+        self.relative_mouse = enabled;
+        self.pending_mouse_delta = [0.0; 2];
+        self.mouse_delta = [0.0; 2];
+        Ok(())
+    }
+}
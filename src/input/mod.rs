@@ -0,0 +1,6 @@
+pub mod actions;
+pub mod gamepad;
+pub mod manager;
+pub mod recording;
+pub mod text;
+pub mod touch;
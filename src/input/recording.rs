@@ -0,0 +1,125 @@
+use crate::core::error::Result;
+use crate::input::gamepad::{GamepadAxis, GamepadButton};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use winit::event::{MouseButton, TouchPhase};
+use winit::keyboard::{Key, KeyCode, ModifiersState};
+use winit::window::WindowId;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key {
+        code: Option<KeyCode>,
+        logical: Key,
+        pressed: bool,
+    },
+    Modifiers(ModifiersState),
+    CursorMoved {
+        window: u64,
+        position: [f32; 2],
+    },
+    CursorLeft {
+        window: u64,
+    },
+    MouseButton {
+        window: u64,
+        button: MouseButton,
+        pressed: bool,
+    },
+    Scroll {
+        delta: [f32; 2],
+    },
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        position: [f32; 2],
+    },
+    FocusLost,
+    MouseMotion {
+        delta: [f64; 2],
+    },
+    GamepadButton {
+        button: GamepadButton,
+        pressed: bool,
+    },
+    GamepadAxis {
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub frame: u64,
+    pub time_us: u64,
+    pub event: InputEvent,
+}
+
+pub(crate) struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub(crate) fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub(crate) fn write(&mut self, event: &RecordedEvent) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        Ok(self.writer.write_all(b"\n")?)
+    }
+
+    pub(crate) fn finish(mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+pub(crate) struct InputPlayback {
+    events: VecDeque<RecordedEvent>,
+    windows: HashMap<u64, WindowId>,
+    targets: VecDeque<WindowId>,
+}
+
+impl InputPlayback {
+    pub(crate) fn load(
+        path: impl AsRef<Path>,
+        windows: impl IntoIterator<Item = WindowId>,
+    ) -> Result<Self> {
+        let mut events = VecDeque::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                events.push_back(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Self {
+            events,
+            windows: HashMap::new(),
+            targets: windows.into_iter().collect(),
+        })
+    }
+
+    pub(crate) fn window(&mut self, recorded: u64) -> WindowId {
+        *self.windows.entry(recorded).or_insert_with(|| {
+            self.targets
+                .pop_front()
+                .unwrap_or_else(|| WindowId::from(recorded))
+        })
+    }
+
+    pub(crate) fn next(&mut self, frame: u64) -> Option<RecordedEvent> {
+        if self.events.front()?.frame > frame {
+            return None;
+        }
+        self.events.pop_front()
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
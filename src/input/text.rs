@@ -0,0 +1,130 @@
+use winit::dpi::{LogicalPosition, LogicalSize};
+use winit::event::{ElementState, Ime, WindowEvent};
+use winit::window::Window;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextEvent {
+    Preedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+    Commit(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Composition {
+    pub text: String,
+    pub cursor: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Default)]
+pub struct TextInput {
+    active: bool,
+    ime_enabled: bool,
+    composition: Option<Composition>,
+    events: Vec<TextEvent>,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&mut self, window: &Window) {
+        self.active = true;
+        window.set_ime_allowed(true);
+    }
+
+    pub fn stop(&mut self, window: &Window) {
+        self.active = false;
+        self.ime_enabled = false;
+        self.composition = None;
+        window.set_ime_allowed(false);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn is_ime_enabled(&self) -> bool {
+        self.ime_enabled
+    }
+
+    pub fn is_composing(&self) -> bool {
+        self.composition.is_some()
+    }
+
+    pub fn composition(&self) -> Option<&Composition> {
+        self.composition.as_ref()
+    }
+
+    pub fn set_caret_area(&self, window: &Window, position: [f32; 2], size: [f32; 2]) {
+        window.set_ime_cursor_area(
+            LogicalPosition::new(position[0], position[1]),
+            LogicalSize::new(size[0], size[1]),
+        );
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = TextEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    pub fn take_committed(&mut self) -> String {
+        let mut committed = String::new();
+        for event in self.events.drain(..) {
+            if let TextEvent::Commit(text) = event {
+                committed.push_str(&text);
+            }
+        }
+        committed
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        if !self.active {
+            return false;
+        }
+        match event {
+            WindowEvent::Ime(Ime::Enabled) => {
+                self.ime_enabled = true;
+                true
+            }
+            WindowEvent::Ime(Ime::Preedit(text, cursor)) => {
+                self.composition = (!text.is_empty()).then(|| Composition {
+                    text: text.clone(),
+                    cursor: *cursor,
+                });
+                self.events.push(TextEvent::Preedit {
+                    text: text.clone(),
+                    cursor: *cursor,
+                });
+                true
+            }
+            WindowEvent::Ime(Ime::Commit(text)) => {
+                self.composition = None;
+                self.events.push(TextEvent::Commit(text.clone()));
+                true
+            }
+            WindowEvent::Ime(Ime::Disabled) => {
+                self.ime_enabled = false;
+                self.composition = None;
+                true
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                if self.is_composing() {
+                    return true;
+                }
+                if event.state == ElementState::Pressed
+                    && let Some(text) = &event.text
+                {
+                    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+                    if !text.is_empty() {
+                        self.events.push(TextEvent::Commit(text));
+                        return true;
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::core::camera::Camera2D;
+use glam::Vec2;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use winit::event::TouchPhase;
+
+const TAP_TIME: Duration = Duration::from_millis(300);
+const TAP_SLOP: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    Tap { position: [f32; 2] },
+    Drag { position: [f32; 2], delta: [f32; 2] },
+    Pinch { center: [f32; 2], scale: f32 },
+    Pan { delta: [f32; 2] },
+}
+
+impl Gesture {
+    pub fn apply(&self, camera: &mut Camera2D) {
+        match *self {
+            Gesture::Drag { delta, .. } | Gesture::Pan { delta } => camera.pan(delta.into()),
+            Gesture::Pinch { center, scale } => camera.zoom_at(scale, center.into()),
+            Gesture::Tap { .. } => {}
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TouchPoint {
+    start: Vec2,
+    position: Vec2,
+    started: Duration,
+    moved: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct TouchTracker {
+    touches: BTreeMap<u64, TouchPoint>,
+    gestures: Vec<Gesture>,
+}
+
+impl TouchTracker {
+    pub(crate) fn handle(&mut self, id: u64, phase: TouchPhase, position: Vec2, time: Duration) {
+        match phase {
+            TouchPhase::Started => {
+                if !self.touches.is_empty() {
+                    for point in self.touches.values_mut() {
+                        point.moved = true;
+                    }
+                }
+                self.touches.insert(
+                    id,
+                    TouchPoint {
+                        start: position,
+                        position,
+                        started: time,
+                        moved: !self.touches.is_empty(),
+                    },
+                );
+            }
+            TouchPhase::Moved => self.moved(id, position),
+            TouchPhase::Ended => {
+                if let Some(point) = self.touches.remove(&id)
+                    && self.touches.is_empty()
+                    && !point.moved
+                    && time.saturating_sub(point.started) <= TAP_TIME
+                {
+                    self.gestures.push(Gesture::Tap {
+                        position: position.into(),
+                    });
+                }
+            }
+            TouchPhase::Cancelled => {
+                self.touches.remove(&id);
+            }
+        }
+    }
+
+    fn moved(&mut self, id: u64, position: Vec2) {
+        let before = self.pair();
+        let Some(point) = self.touches.get_mut(&id) else {
+            return;
+        };
+        let delta = position - point.position;
+        point.position = position;
+        if point.start.distance(position) > TAP_SLOP {
+            point.moved = true;
+        }
+        match (before, self.pair()) {
+            (Some((a, b)), Some((c, d))) => {
+                let (old_center, new_center) = ((a + b) / 2.0, (c + d) / 2.0);
+                let old_distance = a.distance(b);
+                if old_distance > f32::EPSILON {
+                    self.gestures.push(Gesture::Pinch {
+                        center: new_center.into(),
+                        scale: c.distance(d) / old_distance,
+                    });
+                }
+                self.gestures.push(Gesture::Pan {
+                    delta: (new_center - old_center).into(),
+                });
+            }
+            _ if self.touches.len() == 1 && self.touches[&id].moved => {
+                self.gestures.push(Gesture::Drag {
+                    position: position.into(),
+                    delta: delta.into(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn pair(&self) -> Option<(Vec2, Vec2)> {
+        if self.touches.len() != 2 {
+            return None;
+        }
+        let mut points = self.touches.values().map(|point| point.position);
+        Some((points.next()?, points.next()?))
+    }
+
+    pub(crate) fn touches(&self) -> impl Iterator<Item = (u64, [f32; 2])> + '_ {
+        self.touches
+            .iter()
+            .map(|(&id, point)| (id, point.position.into()))
+    }
+
+    pub(crate) fn take_gestures(&mut self) -> Vec<Gesture> {
+        std::mem::take(&mut self.gestures)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.touches.clear();
+    }
+}
@@ -1,2 +1,5 @@
+pub mod app;
+pub mod assets;
 pub mod core;
 pub mod graphics;
+pub mod input;
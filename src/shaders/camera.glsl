@@ -0,0 +1,22 @@
+#ifdef MULTIVIEW
+#extension GL_EXT_multiview : require
+#define MAX_VIEWS 4
+
+struct CameraView {
+    mat4 view_projection;
+    mat4 inverse_view_projection;
+    vec4 position;
+};
+
+layout(set = 0, binding = 0) uniform Cameras {
+    CameraView views[MAX_VIEWS];
+} cameras;
+
+#define camera cameras.views[gl_ViewIndex]
+#else
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 view_projection;
+    mat4 inverse_view_projection;
+    vec4 position;
+} camera;
+#endif
@@ -0,0 +1,29 @@
+#version 450
+#include "camera.glsl"
+#include "draw.glsl"
+#include "objects.glsl"
+
+layout(set = 2, binding = 0) readonly buffer Objects {
+    Object objects[];
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in vec2 uv;
+layout(location = 3) in vec4 tangent;
+
+layout(location = 0) out vec3 v_world_position;
+layout(location = 1) out vec3 v_normal;
+layout(location = 2) out vec2 v_uv;
+layout(location = 3) out vec4 v_tangent;
+
+void main() {
+    mat4 model = objects[gl_InstanceIndex].model;
+    vec4 world_position = model * vec4(position, 1.0);
+    mat3 normal_matrix = mat3(transpose(inverse(model)));
+    v_world_position = world_position.xyz;
+    v_normal = normal_matrix * normal;
+    v_uv = uv;
+    v_tangent = vec4(mat3(model) * tangent.xyz, tangent.w);
+    gl_Position = camera.view_projection * world_position;
+}
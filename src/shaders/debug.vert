@@ -0,0 +1,15 @@
+#version 450
+
+layout(push_constant) uniform Debug {
+    mat4 view_projection;
+} debug;
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec4 color;
+
+layout(location = 0) out vec4 v_color;
+
+void main() {
+    v_color = color;
+    gl_Position = debug.view_projection * vec4(position, 1.0);
+}
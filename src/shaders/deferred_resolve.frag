@@ -0,0 +1,29 @@
+#version 450
+#include "camera.glsl"
+#include "lighting.glsl"
+
+layout(set = 1, binding = 0) uniform sampler2D g_albedo;
+layout(set = 1, binding = 1) uniform sampler2D g_normal;
+layout(set = 1, binding = 2) uniform sampler2D g_material;
+layout(set = 1, binding = 3) uniform sampler2D g_depth;
+layout(set = 1, binding = 4) uniform sampler2D g_occlusion;
+
+layout(location = 0) in vec2 v_uv;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    float depth = texture(g_depth, v_uv).r;
+    if (depth >= 1.0) {
+        discard;
+    }
+
+    vec4 world_position = camera.inverse_view_projection * vec4(v_uv * 2.0 - 1.0, depth, 1.0);
+    world_position /= world_position.w;
+    vec3 albedo = texture(g_albedo, v_uv).rgb;
+    vec3 n = normalize(texture(g_normal, v_uv).xyz);
+    vec2 material = texture(g_material, v_uv).rg;
+    float occlusion = texture(g_occlusion, v_uv).r;
+    vec3 v = normalize(camera.position.xyz - world_position.xyz);
+    f_color = vec4(shade(albedo, material.x, material.y, n, v, world_position.xyz, occlusion), 1.0);
+}
@@ -0,0 +1,5 @@
+layout(push_constant) uniform DrawConstants {
+    mat4 model;
+    vec4 base_color;
+    vec4 material;
+} draw;
@@ -0,0 +1,27 @@
+#version 450
+#include "camera.glsl"
+#include "draw.glsl"
+#include "lighting.glsl"
+#include "surface.glsl"
+
+layout(set = 1, binding = 0) uniform sampler2D normal_map;
+layout(set = 1, binding = 1) uniform sampler2D base_color_map;
+layout(set = 1, binding = 2) uniform sampler2D metallic_roughness_map;
+
+layout(location = 0) in vec3 v_world_position;
+layout(location = 1) in vec3 v_normal;
+layout(location = 2) in vec2 v_uv;
+layout(location = 3) in vec4 v_tangent;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec4 base_color = draw.base_color * texture(base_color_map, v_uv);
+    vec4 metallic_roughness = texture(metallic_roughness_map, v_uv);
+    float metallic = draw.material.x * metallic_roughness.b;
+    float roughness = draw.material.y * metallic_roughness.g;
+    vec3 n = perturb_normal(v_normal, v_tangent, texture(normal_map, v_uv).xyz, draw.material.z);
+    vec3 v = normalize(camera.position.xyz - v_world_position);
+    vec3 color = shade(base_color.rgb, metallic, roughness, n, v, v_world_position, 1.0);
+    f_color = vec4(color, base_color.a);
+}
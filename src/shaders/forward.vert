@@ -0,0 +1,23 @@
+#version 450
+#include "camera.glsl"
+#include "draw.glsl"
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in vec2 uv;
+layout(location = 3) in vec4 tangent;
+
+layout(location = 0) out vec3 v_world_position;
+layout(location = 1) out vec3 v_normal;
+layout(location = 2) out vec2 v_uv;
+layout(location = 3) out vec4 v_tangent;
+
+void main() {
+    vec4 world_position = draw.model * vec4(position, 1.0);
+    mat3 normal_matrix = mat3(transpose(inverse(draw.model)));
+    v_world_position = world_position.xyz;
+    v_normal = normal_matrix * normal;
+    v_uv = uv;
+    v_tangent = vec4(mat3(draw.model) * tangent.xyz, tangent.w);
+    gl_Position = camera.view_projection * world_position;
+}
@@ -0,0 +1,53 @@
+#version 450
+
+#define EDGE_THRESHOLD_MIN 0.0312
+#define EDGE_THRESHOLD_MAX 0.125
+#define REDUCE_MIN (1.0 / 128.0)
+#define REDUCE_MUL (1.0 / 8.0)
+#define SPAN_MAX 8.0
+
+layout(set = 0, binding = 0) uniform sampler2D scene;
+
+layout(location = 0) in vec2 v_uv;
+
+layout(location = 0) out vec4 f_color;
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(scene, 0));
+    vec4 center = texture(scene, v_uv);
+    float luma_m = luma(center.rgb);
+    float luma_nw = luma(texture(scene, v_uv + vec2(-1.0, -1.0) * texel).rgb);
+    float luma_ne = luma(texture(scene, v_uv + vec2(1.0, -1.0) * texel).rgb);
+    float luma_sw = luma(texture(scene, v_uv + vec2(-1.0, 1.0) * texel).rgb);
+    float luma_se = luma(texture(scene, v_uv + vec2(1.0, 1.0) * texel).rgb);
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+    if (luma_max - luma_min < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX)) {
+        f_color = center;
+        return;
+    }
+
+    vec2 direction = vec2(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+    vec3 inner = 0.5 * (
+        texture(scene, v_uv + direction * (1.0 / 3.0 - 0.5)).rgb +
+        texture(scene, v_uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 outer = inner * 0.5 + 0.25 * (
+        texture(scene, v_uv - direction * 0.5).rgb +
+        texture(scene, v_uv + direction * 0.5).rgb
+    );
+    float luma_outer = luma(outer);
+    vec3 color = luma_outer < luma_min || luma_outer > luma_max ? inner : outer;
+    f_color = vec4(color, center.a);
+}
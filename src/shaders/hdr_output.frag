@@ -0,0 +1,30 @@
+#version 450
+#include "tonemapping.glsl"
+
+layout(set = 0, binding = 0) uniform sampler2D scene;
+
+layout(push_constant) uniform HdrOutput {
+    uint color_space;
+    float paper_white_nits;
+    float max_nits;
+} hdr;
+
+layout(location = 0) in vec2 v_uv;
+
+layout(location = 0) out vec4 f_color;
+
+const uint HDR10 = 0;
+const uint SCRGB = 1;
+
+void main() {
+    vec4 scene_color = texelFetch(scene, ivec2(gl_FragCoord.xy), 0);
+    vec3 color = max(scene_color.rgb, vec3(0.0));
+    float peak = hdr.max_nits / hdr.paper_white_nits;
+    if (hdr.color_space == HDR10) {
+        vec3 bt2020 = roll_off(BT709_TO_BT2020 * color, peak);
+        f_color = vec4(pq_oetf(bt2020 * hdr.paper_white_nits), 1.0);
+    } else {
+        vec3 nits = roll_off(color, peak) * hdr.paper_white_nits;
+        f_color = vec4(nits / 80.0, scene_color.a);
+    }
+}
@@ -0,0 +1,37 @@
+#define PI 3.14159265359
+
+vec3 cube_direction(int face, vec2 uv) {
+    vec3 direction;
+    if (face == 0) direction = vec3(1.0, -uv.y, -uv.x);
+    else if (face == 1) direction = vec3(-1.0, -uv.y, uv.x);
+    else if (face == 2) direction = vec3(uv.x, 1.0, uv.y);
+    else if (face == 3) direction = vec3(uv.x, -1.0, -uv.y);
+    else if (face == 4) direction = vec3(uv.x, -uv.y, 1.0);
+    else direction = vec3(-uv.x, -uv.y, -1.0);
+    return normalize(direction);
+}
+
+float radical_inverse(uint bits) {
+    bits = (bits << 16u) | (bits >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return float(bits) * 2.3283064365386963e-10;
+}
+
+vec2 hammersley(uint i, uint count) {
+    return vec2(float(i) / float(count), radical_inverse(i));
+}
+
+vec3 importance_sample_ggx(vec2 xi, vec3 n, float roughness) {
+    float a = roughness * roughness;
+    float phi = 2.0 * PI * xi.x;
+    float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    vec3 h = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+    vec3 up = abs(n.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, n));
+    vec3 bitangent = cross(n, tangent);
+    return normalize(tangent * h.x + bitangent * h.y + n * h.z);
+}
@@ -0,0 +1,99 @@
+#define MAX_LIGHTS 64
+#define LIGHT_DIRECTIONAL 0.0
+#define LIGHT_POINT 1.0
+#define LIGHT_SPOT 2.0
+#define PI 3.14159265359
+
+struct Light {
+    vec4 position_range;
+    vec4 direction_kind;
+    vec4 color_intensity;
+    vec4 cone;
+};
+
+layout(set = 0, binding = 1) uniform Lights {
+    vec4 ambient;
+    uvec4 count;
+    Light lights[MAX_LIGHTS];
+} lights;
+
+float distribution_ggx(float n_dot_h, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+float geometry_smith(float n_dot_v, float n_dot_l, float roughness) {
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    float gv = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    float gl = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return gv * gl;
+}
+
+vec3 fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+vec3 light_radiance(Light light, vec3 world_position, out vec3 l) {
+    vec3 radiance = light.color_intensity.rgb * light.color_intensity.a;
+    if (light.direction_kind.w == LIGHT_DIRECTIONAL) {
+        l = -light.direction_kind.xyz;
+        return radiance;
+    }
+
+    vec3 to_light = light.position_range.xyz - world_position;
+    float distance = length(to_light);
+    l = to_light / distance;
+    float range = light.position_range.w;
+    float falloff = clamp(1.0 - pow(distance / range, 4.0), 0.0, 1.0);
+    radiance *= falloff * falloff / (distance * distance + 1.0);
+
+    if (light.direction_kind.w == LIGHT_SPOT) {
+        float cos_angle = dot(-l, light.direction_kind.xyz);
+        radiance *= smoothstep(light.cone.y, light.cone.x, cos_angle);
+    }
+    return radiance;
+}
+
+layout(set = 0, binding = 2) uniform samplerCube irradiance_map;
+layout(set = 0, binding = 3) uniform samplerCube prefiltered_map;
+layout(set = 0, binding = 4) uniform sampler2D brdf_lut;
+
+vec3 fresnel_schlick_roughness(float cos_theta, vec3 f0, float roughness) {
+    return f0 + (max(vec3(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+vec3 ambient(vec3 albedo, float metallic, float roughness, vec3 n, vec3 v) {
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    float n_dot_v = max(dot(n, v), 1e-4);
+    vec3 f = fresnel_schlick_roughness(n_dot_v, f0, roughness);
+    vec3 kd = (1.0 - f) * (1.0 - metallic);
+    vec3 diffuse = texture(irradiance_map, n).rgb * albedo;
+    float lod = roughness * float(textureQueryLevels(prefiltered_map) - 1);
+    vec3 prefiltered = textureLod(prefiltered_map, reflect(-v, n), lod).rgb;
+    vec2 brdf = texture(brdf_lut, vec2(n_dot_v, roughness)).rg;
+    return lights.ambient.rgb * albedo + kd * diffuse + prefiltered * (f * brdf.x + brdf.y);
+}
+
+vec3 shade(vec3 albedo, float metallic, float roughness, vec3 n, vec3 v, vec3 world_position, float occlusion) {
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    float n_dot_v = max(dot(n, v), 1e-4);
+    vec3 color = ambient(albedo, metallic, roughness, n, v) * occlusion;
+    for (uint i = 0; i < lights.count.x; i++) {
+        vec3 l;
+        vec3 radiance = light_radiance(lights.lights[i], world_position, l);
+        vec3 h = normalize(v + l);
+        float n_dot_l = max(dot(n, l), 0.0);
+        if (n_dot_l <= 0.0) {
+            continue;
+        }
+        float d = distribution_ggx(max(dot(n, h), 0.0), roughness);
+        float g = geometry_smith(n_dot_v, n_dot_l, roughness);
+        vec3 f = fresnel_schlick(max(dot(h, v), 0.0), f0);
+        vec3 specular = d * g * f / (4.0 * n_dot_v * n_dot_l + 1e-4);
+        vec3 diffuse = (1.0 - f) * (1.0 - metallic) * albedo / PI;
+        color += (diffuse + specular) * radiance * n_dot_l;
+    }
+    return color;
+}
@@ -0,0 +1,5 @@
+struct Object {
+    mat4 model;
+    vec4 bounds;
+    uvec4 range;
+};
@@ -0,0 +1,25 @@
+#version 450
+
+layout(push_constant) uniform Overlay {
+    vec2 screen_size;
+    uint gamma_output;
+} overlay;
+
+layout(set = 0, binding = 0) uniform sampler2D overlay_texture;
+
+layout(location = 0) in vec2 v_uv;
+layout(location = 1) in vec4 v_color;
+
+layout(location = 0) out vec4 f_color;
+
+vec3 linear_to_srgb(vec3 linear) {
+    return mix(linear * 12.92, 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055, greaterThan(linear, vec3(0.0031308)));
+}
+
+void main() {
+    vec4 color = v_color * texture(overlay_texture, v_uv);
+    if (overlay.gamma_output != 0) {
+        color.rgb = linear_to_srgb(color.rgb);
+    }
+    f_color = color;
+}
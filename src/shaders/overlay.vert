@@ -0,0 +1,23 @@
+#version 450
+
+layout(push_constant) uniform Overlay {
+    vec2 screen_size;
+    uint gamma_output;
+} overlay;
+
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+
+layout(location = 0) out vec2 v_uv;
+layout(location = 1) out vec4 v_color;
+
+vec3 srgb_to_linear(vec3 srgb) {
+    return mix(srgb / 12.92, pow((srgb + 0.055) / 1.055, vec3(2.4)), greaterThan(srgb, vec3(0.04045)));
+}
+
+void main() {
+    v_uv = uv;
+    v_color = vec4(srgb_to_linear(color.rgb), color.a);
+    gl_Position = vec4(position / overlay.screen_size * 2.0 - 1.0, 0.0, 1.0);
+}
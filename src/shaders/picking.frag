@@ -0,0 +1,8 @@
+#version 450
+#include "draw.glsl"
+
+layout(location = 0) out uint object_id;
+
+void main() {
+    object_id = floatBitsToUint(draw.material.w);
+}
@@ -0,0 +1,34 @@
+#version 450
+#include "camera.glsl"
+#include "draw.glsl"
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in vec2 uv;
+layout(location = 3) in vec4 tangent;
+layout(location = 4) in uvec4 joints;
+layout(location = 5) in vec4 weights;
+
+layout(set = 2, binding = 0) readonly buffer Joints {
+    mat4 matrices[];
+} skin;
+
+layout(location = 0) out vec3 v_world_position;
+layout(location = 1) out vec3 v_normal;
+layout(location = 2) out vec2 v_uv;
+layout(location = 3) out vec4 v_tangent;
+
+void main() {
+    mat4 skin_matrix = weights.x * skin.matrices[joints.x]
+        + weights.y * skin.matrices[joints.y]
+        + weights.z * skin.matrices[joints.z]
+        + weights.w * skin.matrices[joints.w];
+    mat4 model = draw.model * skin_matrix;
+    vec4 world_position = model * vec4(position, 1.0);
+    mat3 normal_matrix = mat3(transpose(inverse(model)));
+    v_world_position = world_position.xyz;
+    v_normal = normal_matrix * normal;
+    v_uv = uv;
+    v_tangent = vec4(mat3(model) * tangent.xyz, tangent.w);
+    gl_Position = camera.view_projection * world_position;
+}
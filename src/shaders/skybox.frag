@@ -0,0 +1,11 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform samplerCube environment;
+
+layout(location = 0) in vec3 v_direction;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    f_color = vec4(texture(environment, normalize(v_direction)).rgb, 1.0);
+}
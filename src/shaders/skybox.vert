@@ -0,0 +1,14 @@
+#version 450
+
+layout(push_constant) uniform Skybox {
+    mat4 inverse_view_projection;
+} skybox;
+
+layout(location = 0) out vec3 v_direction;
+
+void main() {
+    vec2 ndc = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2) * 2.0 - 1.0;
+    vec4 world = skybox.inverse_view_projection * vec4(ndc, 1.0, 1.0);
+    v_direction = world.xyz / world.w;
+    gl_Position = vec4(ndc, 1.0, 1.0);
+}
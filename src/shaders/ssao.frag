@@ -0,0 +1,63 @@
+#version 450
+#include "camera.glsl"
+
+#define MAX_SAMPLES 32
+#define TAU 6.28318530718
+
+layout(set = 1, binding = 0) uniform sampler2D g_normal;
+layout(set = 1, binding = 1) uniform sampler2D g_depth;
+layout(set = 1, binding = 2) uniform Kernel {
+    vec4 samples[MAX_SAMPLES];
+} kernel;
+
+layout(push_constant) uniform SsaoConstants {
+    float radius;
+    float bias;
+    float intensity;
+    uint sample_count;
+} ssao;
+
+layout(location = 0) in vec2 v_uv;
+
+layout(location = 0) out float f_occlusion;
+
+vec3 world_position(vec2 uv, float depth) {
+    vec4 position = camera.inverse_view_projection * vec4(uv * 2.0 - 1.0, depth, 1.0);
+    return position.xyz / position.w;
+}
+
+void main() {
+    float depth = texture(g_depth, v_uv).r;
+    if (depth >= 1.0) {
+        f_occlusion = 1.0;
+        return;
+    }
+
+    vec3 position = world_position(v_uv, depth);
+    vec3 n = normalize(texture(g_normal, v_uv).xyz);
+    vec3 up = abs(n.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, n));
+    vec3 bitangent = cross(n, tangent);
+    float angle = TAU * fract(52.9829189 * fract(dot(gl_FragCoord.xy, vec2(0.06711056, 0.00583715))));
+    vec3 t = cos(angle) * tangent + sin(angle) * bitangent;
+    mat3 tbn = mat3(t, cross(n, t), n);
+
+    float center_distance = distance(camera.position.xyz, position);
+    float occlusion = 0.0;
+    for (uint i = 0; i < ssao.sample_count; i++) {
+        float scale = float(i) / float(ssao.sample_count);
+        scale = mix(0.1, 1.0, scale * scale);
+        vec3 sample_position = position + tbn * kernel.samples[i].xyz * ssao.radius * scale;
+        vec4 clip = camera.view_projection * vec4(sample_position, 1.0);
+        vec2 uv = clip.xy / clip.w * 0.5 + 0.5;
+        if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+            continue;
+        }
+        vec3 scene = world_position(uv, texture(g_depth, uv).r);
+        float sample_distance = distance(camera.position.xyz, sample_position);
+        float scene_distance = distance(camera.position.xyz, scene);
+        float range = smoothstep(0.0, 1.0, ssao.radius / abs(center_distance - scene_distance));
+        occlusion += (scene_distance <= sample_distance - ssao.bias ? 1.0 : 0.0) * range;
+    }
+    f_occlusion = pow(1.0 - occlusion / float(ssao.sample_count), ssao.intensity);
+}
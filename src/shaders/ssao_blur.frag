@@ -0,0 +1,18 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D occlusion;
+
+layout(location = 0) in vec2 v_uv;
+
+layout(location = 0) out float f_occlusion;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(occlusion, 0));
+    float sum = 0.0;
+    for (int x = -2; x < 2; x++) {
+        for (int y = -2; y < 2; y++) {
+            sum += texture(occlusion, v_uv + vec2(x, y) * texel).r;
+        }
+    }
+    f_occlusion = sum / 16.0;
+}
@@ -0,0 +1,11 @@
+vec3 perturb_normal(vec3 normal, vec4 tangent, vec3 sampled, float scale) {
+    vec3 n = normalize(normal);
+    if (dot(tangent.xyz, tangent.xyz) < 1e-8) {
+        return n;
+    }
+    vec3 t = normalize(tangent.xyz - n * dot(n, tangent.xyz));
+    vec3 b = cross(n, t) * tangent.w;
+    vec3 m = sampled * 2.0 - 1.0;
+    m.xy *= scale;
+    return normalize(mat3(t, b, n) * m);
+}
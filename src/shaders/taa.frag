@@ -0,0 +1,46 @@
+#version 450
+#include "camera.glsl"
+
+layout(set = 1, binding = 0) uniform sampler2D scene;
+layout(set = 1, binding = 1) uniform sampler2D scene_depth;
+layout(set = 1, binding = 2) uniform sampler2D history;
+
+layout(push_constant) uniform TaaConstants {
+    mat4 previous_view_projection;
+    float blend;
+    float history_valid;
+} taa;
+
+layout(location = 0) in vec2 v_uv;
+
+layout(location = 0) out vec4 f_color;
+layout(location = 1) out vec4 f_history;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(scene, 0));
+    vec3 current = texture(scene, v_uv).rgb;
+    vec3 minimum = current;
+    vec3 maximum = current;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec3 neighbour = texture(scene, v_uv + vec2(x, y) * texel).rgb;
+            minimum = min(minimum, neighbour);
+            maximum = max(maximum, neighbour);
+        }
+    }
+
+    float depth = texture(scene_depth, v_uv).r;
+    vec4 world_position = camera.inverse_view_projection * vec4(v_uv * 2.0 - 1.0, depth, 1.0);
+    world_position /= world_position.w;
+    vec4 previous = taa.previous_view_projection * world_position;
+    vec2 previous_uv = previous.xy / previous.w * 0.5 + 0.5;
+
+    vec3 color = current;
+    bool on_screen = all(greaterThanEqual(previous_uv, vec2(0.0))) && all(lessThanEqual(previous_uv, vec2(1.0)));
+    if (taa.history_valid > 0.0 && on_screen) {
+        vec3 previous_color = clamp(texture(history, previous_uv).rgb, minimum, maximum);
+        color = mix(previous_color, current, taa.blend);
+    }
+    f_color = vec4(color, 1.0);
+    f_history = vec4(color, 1.0);
+}
@@ -0,0 +1,26 @@
+const mat3 BT709_TO_BT2020 = mat3(
+    0.6274, 0.0691, 0.0164,
+    0.3293, 0.9195, 0.0880,
+    0.0433, 0.0114, 0.8956
+);
+
+vec3 roll_off(vec3 color, float peak) {
+    float m = max(color.r, max(color.g, color.b));
+    float knee = 0.75 * peak;
+    if (m <= knee) {
+        return color;
+    }
+    float shoulder = peak - knee;
+    float compressed = knee + shoulder * (1.0 - exp(-(m - knee) / shoulder));
+    return color * (compressed / m);
+}
+
+vec3 pq_oetf(vec3 nits) {
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+    vec3 y = pow(clamp(nits / 10000.0, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
@@ -0,0 +1,50 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform UpscaleConstants {
+    float sharpness;
+} upscale;
+
+layout(location = 0) in vec2 v_uv;
+
+layout(location = 0) out vec4 f_color;
+
+vec3 catmull_rom(vec2 uv, vec2 texel) {
+    vec2 position = uv / texel;
+    vec2 center = floor(position - 0.5) + 0.5;
+    vec2 f = position - center;
+    vec2 w0 = f * (-0.5 + f * (1.0 - 0.5 * f));
+    vec2 w1 = 1.0 + f * f * (-2.5 + 1.5 * f);
+    vec2 w2 = f * (0.5 + f * (2.0 - 1.5 * f));
+    vec2 w3 = f * f * (-0.5 + 0.5 * f);
+    vec2 w12 = w1 + w2;
+    vec2 tc0 = (center - 1.0) * texel;
+    vec2 tc3 = (center + 2.0) * texel;
+    vec2 tc12 = (center + w2 / w12) * texel;
+    vec3 result =
+        texture(source, vec2(tc12.x, tc0.y)).rgb * w12.x * w0.y +
+        texture(source, vec2(tc0.x, tc12.y)).rgb * w0.x * w12.y +
+        texture(source, tc12).rgb * w12.x * w12.y +
+        texture(source, vec2(tc3.x, tc12.y)).rgb * w3.x * w12.y +
+        texture(source, vec2(tc12.x, tc3.y)).rgb * w12.x * w3.y;
+    float weight = w12.x * w0.y + w0.x * w12.y + w12.x * w12.y + w3.x * w12.y + w12.x * w3.y;
+    return result / weight;
+}
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(source, 0));
+    vec3 center = texture(source, v_uv).rgb;
+    vec3 north = texture(source, v_uv + vec2(0.0, -1.0) * texel).rgb;
+    vec3 south = texture(source, v_uv + vec2(0.0, 1.0) * texel).rgb;
+    vec3 east = texture(source, v_uv + vec2(1.0, 0.0) * texel).rgb;
+    vec3 west = texture(source, v_uv + vec2(-1.0, 0.0) * texel).rgb;
+    vec3 minimum = min(center, min(min(north, south), min(east, west)));
+    vec3 maximum = max(center, max(max(north, south), max(east, west)));
+
+    vec3 color = clamp(catmull_rom(v_uv, texel), minimum, maximum);
+    vec3 amplitude = sqrt(clamp(min(minimum, 1.0 - maximum) / max(maximum, vec3(1e-4)), 0.0, 1.0));
+    vec3 weight = amplitude * (-1.0 / mix(8.0, 5.0, upscale.sharpness));
+    vec3 sharpened = (color + (north + south + east + west) * weight) / (1.0 + 4.0 * weight);
+    f_color = vec4(max(sharpened, vec3(0.0)), 1.0);
+}
@@ -0,0 +1,31 @@
+#version 450
+
+const float PAINT_LINEAR = 1.0;
+const float PAINT_RADIAL = 2.0;
+
+layout(set = 1, binding = 1) uniform sampler2D base_color_map;
+
+layout(location = 0) in vec4 v_color;
+layout(location = 1) in vec2 v_position;
+layout(location = 2) flat in vec4 v_gradient;
+layout(location = 3) flat in vec2 v_paint;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec4 color = v_color;
+    if (v_paint.x == PAINT_LINEAR || v_paint.x == PAINT_RADIAL) {
+        float t;
+        if (v_paint.x == PAINT_LINEAR) {
+            vec2 axis = v_gradient.zw - v_gradient.xy;
+            t = dot(v_position - v_gradient.xy, axis) / max(dot(axis, axis), 1e-8);
+        } else {
+            t = length(v_position - v_gradient.xy) / max(v_gradient.z, 1e-8);
+        }
+        vec2 size = vec2(textureSize(base_color_map, 0));
+        float u = (clamp(t, 0.0, 1.0) * (size.x - 1.0) + 0.5) / size.x;
+        float v = (v_paint.y + 0.5) / size.y;
+        color *= textureLod(base_color_map, vec2(u, v), 0.0);
+    }
+    f_color = color;
+}
@@ -0,0 +1,20 @@
+#version 450
+#include "draw.glsl"
+
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec4 color;
+layout(location = 2) in vec4 gradient;
+layout(location = 3) in vec2 paint;
+
+layout(location = 0) out vec4 v_color;
+layout(location = 1) out vec2 v_position;
+layout(location = 2) flat out vec4 v_gradient;
+layout(location = 3) flat out vec2 v_paint;
+
+void main() {
+    v_color = color * draw.base_color;
+    v_position = position;
+    v_gradient = gradient;
+    v_paint = paint;
+    gl_Position = draw.model * vec4(position, 0.0, 1.0);
+}